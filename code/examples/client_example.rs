@@ -12,9 +12,13 @@ async fn main() -> Result<()> {
 
     // 1. Create graphs
     println!("📊 Creating graphs...");
-    
+
     let graphs = vec![
-        ("transactions", GraphType::Transaction as i32, "Financial transactions"),
+        (
+            "transactions",
+            GraphType::Transaction as i32,
+            "Financial transactions",
+        ),
         ("identity", GraphType::Identity as i32, "User identity data"),
         ("assets", GraphType::Asset as i32, "Asset ownership"),
     ];
@@ -24,6 +28,11 @@ async fn main() -> Result<()> {
             graph_id: id.to_string(),
             graph_type,
             description: desc.to_string(),
+            consensus: None,
+            endorsement: None,
+            schema: String::new(),
+            default_schema: false,
+            governance: None,
         };
 
         match client.create_graph(request).await {
@@ -43,11 +52,12 @@ async fn main() -> Result<()> {
     println!("📋 Listing all graphs...");
     let list_response = client.list_graphs(ListGraphsRequest {}).await?;
     let graphs_list = list_response.into_inner().graphs;
-    
+
     for graph in &graphs_list {
-        println!("  • {} ({:?}) - {} blocks", 
-            graph.graph_id, 
-            GraphType::from_i32(graph.graph_type),
+        println!(
+            "  • {} ({:?}) - {} blocks",
+            graph.graph_id,
+            GraphType::try_from(graph.graph_type).ok(),
             graph.total_blocks
         );
     }
@@ -56,8 +66,8 @@ async fn main() -> Result<()> {
 
     // 3. Add blocks to transaction graph
     println!("📦 Adding blocks to 'transactions' graph...");
-    
-    let transactions = vec![
+
+    let transactions = [
         r#"{"from":"Alice","to":"Bob","amount":100.0}"#,
         r#"{"from":"Bob","to":"Charlie","amount":50.0}"#,
         r#"{"from":"Charlie","to":"Alice","amount":25.0}"#,
@@ -70,6 +80,7 @@ async fn main() -> Result<()> {
             graph_id: "transactions".to_string(),
             data: tx.to_string(),
             cross_references: vec![],
+            include_receipt: false,
         };
 
         let response = client.add_block(request).await?;
@@ -87,21 +98,20 @@ async fn main() -> Result<()> {
 
     // 4. Add blocks with cross-references
     println!("🔗 Adding blocks with cross-references...");
-    
+
     let identity_request = AddBlockRequest {
         graph_id: "identity".to_string(),
         data: r#"{"user":"Alice","verified":true}"#.to_string(),
         cross_references: vec![last_hash.clone()], // Reference transaction block
+        include_receipt: false,
     };
 
     let response = client.add_block(identity_request).await?;
     let res = response.into_inner();
 
-    if res.success {
-        if let Some(block) = res.block {
-            println!("  ✓ Identity block added with cross-reference");
-            println!("    References: {}", last_hash[..16].to_string());
-        }
+    if res.success && res.block.is_some() {
+        println!("  ✓ Identity block added with cross-reference");
+        println!("    References: {}", &last_hash[..16]);
     }
 
     println!();
@@ -136,7 +146,7 @@ async fn main() -> Result<()> {
 
     if info.success {
         println!("  Graph ID: {}", info.graph_id);
-        println!("  Type: {:?}", GraphType::from_i32(info.graph_type));
+        println!("  Type: {:?}", GraphType::try_from(info.graph_type).ok());
         println!("  Total Blocks: {}", info.total_blocks);
         println!("  Is Valid: {}", info.is_valid);
     }
@@ -147,6 +157,7 @@ async fn main() -> Result<()> {
     println!("🔍 Verifying graph integrity...");
     let verify_request = VerifyGraphRequest {
         graph_id: "transactions".to_string(),
+        full: false,
     };
 
     let response = client.verify_graph(verify_request).await?;
@@ -165,7 +176,7 @@ async fn main() -> Result<()> {
 
     // 8. Cross-validate all graphs
     println!("🌐 Cross-validating all graphs...");
-    let cross_validate_request = CrossValidateRequest {};
+    let cross_validate_request = CrossValidateRequest { incremental: false };
 
     let response = client.cross_validate_graphs(cross_validate_request).await?;
     let validation = response.into_inner();
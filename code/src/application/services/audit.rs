@@ -0,0 +1,227 @@
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::sync::Notify;
+
+use crate::infrastructure::grpc::blockchain::{
+    AddBlockResponse, CreateBackupResponse, CreateGraphResponse, CrossValidateResponse,
+    IndexResponse, ProposalResponse, VerifyGraphResponse,
+};
+
+/// Key of the event list in the data of an audit block,
+/// e.g. `{"audit_events": [{"action": "block_added", ...}]}`
+pub const AUDIT_EVENTS_KEY: &str = "audit_events";
+
+/// One operation recorded in the audit trail
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct AuditEvent {
+    /// Unix timestamp in milliseconds
+    pub timestamp_ms: i64,
+    /// What was done, e.g. `graph_created`, `block_added`, `verification_failed`
+    pub action: String,
+    /// Who asked: the `x-caller-id` metadata, else the peer address
+    pub caller: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub graph_id: String,
+    pub success: bool,
+    /// Block hash, proposal id or error message
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub details: String,
+}
+
+/// Block data carrying a batch of events
+pub fn audit_batch_data(events: &[AuditEvent]) -> String {
+    serde_json::json!({ AUDIT_EVENTS_KEY: events }).to_string()
+}
+
+/// Reads the events of an audit block
+pub fn parse_audit_batch(data: &str) -> Option<Vec<AuditEvent>> {
+    let value: Value = serde_json::from_str(data).ok()?;
+    serde_json::from_value(value.get(AUDIT_EVENTS_KEY)?.clone()).ok()
+}
+
+/// Events waiting to be appended to the audit graph. They are written in
+/// batches so a burst of operations costs one block instead of one each.
+#[derive(Debug)]
+pub struct AuditLog {
+    graph_id: String,
+    max_batch: usize,
+    pending: Mutex<VecDeque<AuditEvent>>,
+    full: Notify,
+}
+
+impl AuditLog {
+    pub fn new(graph_id: String, max_batch: usize) -> Self {
+        Self {
+            graph_id,
+            max_batch: max_batch.max(1),
+            pending: Mutex::new(VecDeque::new()),
+            full: Notify::new(),
+        }
+    }
+
+    /// Graph the events are appended to
+    pub fn graph_id(&self) -> &str {
+        &self.graph_id
+    }
+
+    /// Queues an event, waking the writer once a full batch is pending
+    pub fn record(
+        &self,
+        action: &str,
+        caller: &str,
+        graph_id: &str,
+        success: bool,
+        details: String,
+    ) {
+        let mut pending = self.lock();
+        pending.push_back(AuditEvent {
+            timestamp_ms: Utc::now().timestamp_millis(),
+            action: action.to_string(),
+            caller: caller.to_string(),
+            graph_id: graph_id.to_string(),
+            success,
+            details,
+        });
+        if pending.len() >= self.max_batch {
+            self.full.notify_one();
+        }
+    }
+
+    /// Waits until a full batch is pending
+    pub async fn batch_ready(&self) {
+        self.full.notified().await;
+    }
+
+    /// Removes and returns the oldest events, at most one batch
+    pub fn take_batch(&self) -> Vec<AuditEvent> {
+        let mut pending = self.lock();
+        let count = pending.len().min(self.max_batch);
+        pending.drain(..count).collect()
+    }
+
+    /// Puts back a batch that could not be written, ahead of newer events
+    pub fn requeue(&self, batch: Vec<AuditEvent>) {
+        let mut pending = self.lock();
+        for event in batch.into_iter().rev() {
+            pending.push_front(event);
+        }
+    }
+
+    pub fn pending(&self) -> usize {
+        self.lock().len()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, VecDeque<AuditEvent>> {
+        self.pending.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// What the audit trail records about a response: whether the operation
+/// succeeded and its details
+pub trait AuditOutcome {
+    fn outcome(&self) -> (bool, String);
+}
+
+impl AuditOutcome for AddBlockResponse {
+    fn outcome(&self) -> (bool, String) {
+        let details = match (&self.block, &self.proposal) {
+            (Some(block), _) if self.success => {
+                format!("block {} at height {}", block.hash, block.height)
+            }
+            (_, Some(proposal)) if self.success => format!("proposal {}", proposal.proposal_id),
+            _ => self.message.clone(),
+        };
+        (self.success, details)
+    }
+}
+
+impl AuditOutcome for CreateGraphResponse {
+    fn outcome(&self) -> (bool, String) {
+        (
+            self.success,
+            if self.success {
+                String::new()
+            } else {
+                self.message.clone()
+            },
+        )
+    }
+}
+
+impl AuditOutcome for CreateBackupResponse {
+    fn outcome(&self) -> (bool, String) {
+        match &self.backup {
+            Some(backup) if self.success => (true, format!("backup {}", backup.backup_id)),
+            _ => (self.success, self.message.clone()),
+        }
+    }
+}
+
+impl AuditOutcome for ProposalResponse {
+    fn outcome(&self) -> (bool, String) {
+        (self.success, self.message.clone())
+    }
+}
+
+impl AuditOutcome for IndexResponse {
+    fn outcome(&self) -> (bool, String) {
+        (self.success, self.message.clone())
+    }
+}
+
+impl AuditOutcome for VerifyGraphResponse {
+    fn outcome(&self) -> (bool, String) {
+        let mut details = vec![self.message.clone()];
+        details.extend(self.errors.iter().cloned());
+        (self.is_valid, details.join("; "))
+    }
+}
+
+impl AuditOutcome for CrossValidateResponse {
+    fn outcome(&self) -> (bool, String) {
+        let mut invalid: Vec<&str> = self
+            .graph_statuses
+            .iter()
+            .filter(|(_, valid)| !**valid)
+            .map(|(graph_id, _)| graph_id.as_str())
+            .collect();
+        invalid.sort_unstable();
+        let details = if invalid.is_empty() {
+            self.message.clone()
+        } else {
+            format!("{}: {}", self.message, invalid.join(", "))
+        };
+        (self.all_valid, details)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_batches_keep_order_across_requeues() {
+        let log = AuditLog::new("audit".to_string(), 2);
+        for action in ["graph_created", "block_added", "backup_created"] {
+            log.record(action, "alice", "people", true, String::new());
+        }
+
+        let first = log.take_batch();
+        assert_eq!(first.len(), 2);
+        log.requeue(first.clone());
+        assert_eq!(log.pending(), 3);
+        assert_eq!(log.take_batch(), first);
+
+        let last = log.take_batch();
+        assert_eq!(last[0].action, "backup_created");
+        assert!(log.take_batch().is_empty());
+
+        let data = audit_batch_data(&first);
+        assert_eq!(parse_audit_batch(&data), Some(first));
+        assert_eq!(parse_audit_batch(r#"{"name": "alice"}"#), None);
+    }
+}
@@ -1,11 +1,60 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
+use std::pin::Pin;
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use std::time::Duration;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::{broadcast, mpsc, Mutex, RwLock};
+use tokio_stream::wrappers::ReceiverStream;
+use tokio_stream::Stream;
 use tonic::{Response, Status};
 
-use crate::domain::traits::BlockchainRepository;
-use crate::domain::{Block, BlockchainGraph, GraphType};
-use crate::infrastructure::grpc::blockchain::*;
+use super::audit::{audit_batch_data, AuditLog, AuditOutcome};
+use super::chain_applier::ChainApplier;
+use super::cluster::ClusterCommand;
+use super::gossip::{self, GossipState};
+use super::raft::RaftNode;
+use super::replication::{
+    self, ChainEvent, ReplicationState, ReplicationStream, EVENT_BUFFER, HEARTBEAT_INTERVAL,
+};
+use super::scrubber::IntegrityScrubber;
+use super::shutdown_coordinator::ShutdownCoordinator;
+use crate::domain::traits::{BlockchainRepository, ContractRuntime};
+use ed25519_dalek::SigningKey;
+
+use crate::domain::anchor::{anchor_data, graph_tips, parse_anchors};
+use crate::domain::asset::{asset_key, asset_policy_data};
+use crate::domain::consensus::{authority_update_data, public_key_hex};
+use crate::domain::contract::contract_deploy_data;
+use crate::domain::identity::{identity_key, parse_signer};
+use crate::domain::schema::schema_update_data;
+use crate::domain::world_state::commit_changes;
+use crate::domain::{
+    AssetPolicy, AssetRecord, AuthorityRotation, AuthoritySet, Block, BlockEndorsements,
+    BlockchainGraph, Consensus, ContractDeployment, DataSchema, EndorsementPolicy, GraphType,
+    IdentityRecord, PayloadIndex, Proposal, ProposalStatus, Receipt, ReceiptAnchor, SearchQuery,
+    StateEntry, VerifiedCheckpoint,
+};
+use crate::infrastructure::contracts::{wasm_runtime, WasmContractRuntime};
+use crate::infrastructure::grpc::blockchain::{self as proto, *};
+use crate::infrastructure::grpc::cluster_client::{self, ClusterCredentials};
+use crate::infrastructure::persistence::backup::{BackupInfo as StoredBackup, BackupManager};
+
+/// How long a proposal waits for endorsements unless configured otherwise
+pub const DEFAULT_PROPOSAL_TTL: Duration = Duration::from_secs(3600);
+
+/// Most fuel a contract may be deployed with unless configured otherwise
+pub const DEFAULT_MAX_CONTRACT_FUEL: u64 = wasm_runtime::DEFAULT_MAX_FUEL;
+
+/// Page size of paged RPCs (RangeState, QueryBlocks, SearchBlocks) when the request
+/// leaves it at 0, and its cap
+const DEFAULT_PAGE_SIZE: usize = 100;
+const MAX_PAGE_SIZE: usize = 1000;
+
+/// Blocks streamed back by GetBlocksByTime, buffered up to this many
+const BLOCK_STREAM_BUFFER: usize = 64;
+
+/// Stream returned by the GetBlocksByTime RPC
+pub type BlockStream = Pin<Box<dyn Stream<Item = Result<proto::Block, Status>> + Send>>;
 
 /// Main blockchain service (Application Service Layer)
 /// Orchestrates business logic and coordinates between layers
@@ -13,14 +62,211 @@ use crate::infrastructure::grpc::blockchain::*;
 pub struct BlockchainServiceImpl {
     repository: Arc<dyn BlockchainRepository>,
     graphs: Arc<RwLock<HashMap<String, BlockchainGraph>>>,
+    shutdown: ShutdownCoordinator,
+    backups: Option<Arc<BackupManager>>,
+    default_difficulty: usize,
+    events: broadcast::Sender<ChainEvent>,
+    applier: ChainApplier,
+    replication: ReplicationState,
+    cluster: Option<Arc<RaftNode>>,
+    /// What this member presents to the others on the cluster listener
+    cluster_credentials: Option<ClusterCredentials>,
+    gossip: GossipState,
+    /// Key used to sign blocks of proof-of-authority graphs
+    authority_key: Option<SigningKey>,
+    /// Key used to sign notarization receipts
+    receipt_key: Option<SigningKey>,
+    /// How long a proposal waits for endorsements
+    proposal_ttl: Duration,
+    /// Serializes endorsements so a proposal is committed once
+    endorsements: Mutex<()>,
+    /// Executes the contracts bound to graphs
+    contracts: Arc<dyn ContractRuntime>,
+    /// Most fuel a contract may be deployed with
+    max_contract_fuel: u64,
+    /// Serializes clustered writes so each block is mined on the committed tip
+    cluster_writes: Mutex<()>,
+    /// Operations waiting to be appended to the audit graph
+    audit: Option<Arc<AuditLog>>,
+    /// Graph the tips of every other graph are anchored into
+    anchor_graph: Option<String>,
+    /// Background integrity scrubber of storage
+    scrubber: Option<Arc<IntegrityScrubber>>,
+    /// Height up to which each graph was last verified; checks resume from it
+    checkpoints: RwLock<HashMap<String, VerifiedCheckpoint>>,
 }
 
 impl BlockchainServiceImpl {
     pub fn new(repository: Arc<dyn BlockchainRepository>) -> Self {
+        let graphs = Arc::new(RwLock::new(HashMap::new()));
+        let events = broadcast::channel(EVENT_BUFFER).0;
+        let contracts: Arc<dyn ContractRuntime> = Arc::new(WasmContractRuntime::new());
+        let applier = ChainApplier::new(
+            repository.clone(),
+            graphs.clone(),
+            events.clone(),
+            contracts.clone(),
+        );
+
         Self {
             repository,
-            graphs: Arc::new(RwLock::new(HashMap::new())),
+            graphs,
+            shutdown: ShutdownCoordinator::new(),
+            backups: None,
+            default_difficulty: 2,
+            events,
+            applier,
+            replication: ReplicationState::leader(),
+            cluster: None,
+            cluster_credentials: None,
+            gossip: GossipState::disabled(),
+            authority_key: None,
+            receipt_key: None,
+            proposal_ttl: DEFAULT_PROPOSAL_TTL,
+            endorsements: Mutex::new(()),
+            contracts,
+            max_contract_fuel: DEFAULT_MAX_CONTRACT_FUEL,
+            cluster_writes: Mutex::new(()),
+            audit: None,
+            anchor_graph: None,
+            scrubber: None,
+            checkpoints: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Orders AddBlock/CreateGraph through a Raft cluster; `credentials`
+    /// authenticate this member to the others
+    pub fn with_cluster(mut self, cluster: Arc<RaftNode>, credentials: ClusterCredentials) -> Self {
+        self.cluster = Some(cluster);
+        self.cluster_credentials = Some(credentials);
+        self
+    }
+
+    /// Raft node of this member, in clustered mode
+    pub fn cluster(&self) -> Option<Arc<RaftNode>> {
+        self.cluster.clone()
+    }
+
+    /// Credentials of this member, in clustered mode
+    pub fn cluster_credentials(&self) -> Option<&ClusterCredentials> {
+        self.cluster_credentials.as_ref()
+    }
+
+    /// Applier sharing this service's cache and storage (used as the Raft state machine)
+    pub fn applier(&self) -> ChainApplier {
+        self.applier.clone()
+    }
+
+    /// Enables peer-to-peer gossip with a static list of peers
+    pub fn with_gossip(mut self, peers: &[String]) -> Self {
+        self.gossip = GossipState::new(peers);
+        self
+    }
+
+    /// Gossip progress and pending pull requests
+    pub fn gossip(&self) -> &GossipState {
+        &self.gossip
+    }
+
+    /// Signs blocks of proof-of-authority graphs with `key`
+    pub fn with_authority_key(mut self, key: SigningKey) -> Self {
+        self.authority_key = Some(key);
+        self
+    }
+
+    /// Signs notarization receipts with `key`
+    pub fn with_receipt_key(mut self, key: SigningKey) -> Self {
+        self.receipt_key = Some(key);
+        self
+    }
+
+    /// Sets how long proposals on endorsed graphs wait for endorsements
+    pub fn with_proposal_ttl(mut self, ttl: Duration) -> Self {
+        self.proposal_ttl = ttl;
+        self
+    }
+
+    /// Caps the fuel limit contracts may be deployed with, on every path
+    /// a deployment block can arrive by
+    pub fn with_max_contract_fuel(mut self, fuel: u64) -> Self {
+        self.max_contract_fuel = fuel;
+        self.contracts = Arc::new(WasmContractRuntime::with_max_fuel(fuel));
+        self.applier = ChainApplier::new(
+            self.repository.clone(),
+            self.graphs.clone(),
+            self.events.clone(),
+            self.contracts.clone(),
+        );
+        self
+    }
+
+    /// Hex public key this node signs with, if it holds an authority key
+    pub fn authority_public_key(&self) -> Option<String> {
+        self.authority_key.as_ref().map(public_key_hex)
+    }
+
+    /// Receives every graph and block committed on this node
+    pub fn subscribe_events(&self) -> broadcast::Receiver<ChainEvent> {
+        self.events.subscribe()
+    }
+
+    /// Runs this node as a read-only follower of `leader_address`
+    pub fn with_leader(mut self, leader_address: String) -> Self {
+        self.replication = ReplicationState::follower(leader_address);
+        self
+    }
+
+    /// Sets the proof-of-work difficulty used for new graphs
+    pub fn with_default_difficulty(mut self, difficulty: usize) -> Self {
+        self.default_difficulty = difficulty;
+        self
+    }
+
+    /// Enables the CreateBackup/ListBackups admin RPCs
+    pub fn with_backup_manager(mut self, backups: Arc<BackupManager>) -> Self {
+        self.backups = Some(backups);
+        self
+    }
+
+    /// Records graph creation, block additions, verification failures and
+    /// admin operations into the audit graph `graph_id`, `max_batch` events per block
+    pub fn with_audit(mut self, graph_id: &str, max_batch: usize) -> Self {
+        self.audit = Some(Arc::new(AuditLog::new(graph_id.to_string(), max_batch)));
+        self
+    }
+
+    /// Anchors the tips of every other graph into the audit graph `graph_id`
+    /// (see [`Self::write_anchor`])
+    pub fn with_anchoring(mut self, graph_id: &str) -> Self {
+        self.anchor_graph = Some(graph_id.to_string());
+        self
+    }
+
+    /// Enables the GetScrubStatus RPC and scrub metrics
+    pub fn with_scrubber(mut self, scrubber: Arc<IntegrityScrubber>) -> Self {
+        self.scrubber = Some(scrubber);
+        self
+    }
+
+    /// Integrity scrubber of this node, if enabled
+    pub fn scrubber(&self) -> Option<Arc<IntegrityScrubber>> {
+        self.scrubber.clone()
+    }
+
+    /// Stops accepting new writes (AddBlock, CreateGraph)
+    pub fn begin_shutdown(&self) {
+        tracing::info!("🛑 No longer accepting new writes");
+        self.shutdown.begin_shutdown();
+    }
+
+    /// Waits for in-flight block production to finish.
+    /// Returns false if writes were still running when the timeout elapsed.
+    pub async fn drain_in_flight(&self, timeout: Duration) -> bool {
+        let pending = self.shutdown.in_flight();
+        if pending > 0 {
+            tracing::info!("⏳ Waiting for {} in-flight write(s) to finish", pending);
         }
+        self.shutdown.wait_idle(timeout).await
     }
 
     /// Initializes the service by loading existing graphs from storage
@@ -47,78 +293,666 @@ impl BlockchainServiceImpl {
                     .await?;
                 graph.load_blocks(blocks);
             }
+            self.repository.index_block_times(&graph.id).await?;
+            if let Some(checkpoint) = self.repository.get_verified_checkpoint(&graph.id).await? {
+                self.checkpoints
+                    .write()
+                    .await
+                    .insert(graph.id.clone(), checkpoint);
+            }
+            graph.side_blocks = self
+                .repository
+                .get_side_blocks(&graph.id)
+                .await?
+                .into_iter()
+                .map(|b| (b.hash.clone(), b))
+                .collect();
 
             graph_map.insert(graph.id.clone(), graph);
         }
 
         tracing::info!("✅ Loaded {} graphs", graph_map.len());
+        drop(graph_map);
+
+        // Clustered members create the audit and anchor graphs on their
+        // first write, once the cluster has a leader
+        for (graph_id, description) in self.node_graphs() {
+            if self.cluster.is_none() && self.read_only_message().is_none() {
+                self.ensure_audit_graph(graph_id, description).await?;
+            } else if let Some(graph) = self.graphs.read().await.get(graph_id) {
+                if graph.graph_type != GraphType::Audit {
+                    return Err(format!("Graph '{}' is not an audit graph", graph_id).into());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Graphs only this node writes to (audit trail and anchors), with the
+    /// description they are created with
+    fn node_graphs(&self) -> Vec<(&str, &'static str)> {
+        let audit = self
+            .audit
+            .as_ref()
+            .map(|a| (a.graph_id(), "Audit trail of node operations"));
+        let anchors = self
+            .anchor_graph
+            .as_deref()
+            .map(|id| (id, "Anchors of graph tips"));
+        audit.into_iter().chain(anchors).collect()
+    }
+
+    /// Creates an audit graph unless it exists; an existing graph of
+    /// another type is an error
+    async fn ensure_audit_graph(&self, graph_id: &str, description: &str) -> Result<(), String> {
+        if let Some(graph) = self.graphs.read().await.get(graph_id) {
+            if graph.graph_type != GraphType::Audit {
+                return Err(format!("Graph '{}' is not an audit graph", graph_id));
+            }
+            return Ok(());
+        }
+        let response = self
+            .create_graph(
+                CreateGraphRequest {
+                    graph_id: graph_id.to_string(),
+                    graph_type: GraphType::Audit.to_i32(),
+                    description: description.to_string(),
+                    consensus: None,
+                    endorsement: None,
+                    schema: String::new(),
+                    default_schema: false,
+                    governance: None,
+                },
+                false,
+            )
+            .await
+            .map_err(|status| status.message().to_string())?
+            .into_inner();
+        if !response.success {
+            return Err(response.message);
+        }
+        tracing::info!("🧾 Created audit graph '{}' ({})", graph_id, description);
         Ok(())
     }
 
-    /// Handles adding a new block to a graph
+    /// Queues an audit event for an operation `caller` ran on `graph_id`,
+    /// when the audit trail is enabled
+    pub fn record_audit(
+        &self,
+        action: &str,
+        caller: &str,
+        graph_id: &str,
+        response: &impl AuditOutcome,
+    ) {
+        if let Some(audit) = &self.audit {
+            let (success, details) = response.outcome();
+            audit.record(action, caller, graph_id, success, details);
+        }
+    }
+
+    /// Appends the pending audit events to the audit graph, one block per
+    /// batch, and returns how many were written. Events of a batch that
+    /// fails stay queued for the next flush.
+    pub async fn flush_audit(&self) -> usize {
+        let audit = match &self.audit {
+            Some(audit) => audit,
+            None => return 0,
+        };
+        let mut written = 0;
+        loop {
+            let batch = audit.take_batch();
+            if batch.is_empty() {
+                break;
+            }
+            // Audit blocks bypass the shutdown gate so the final flush still lands
+            let committed = match self
+                .ensure_audit_graph(audit.graph_id(), "Audit trail of node operations")
+                .await
+            {
+                Ok(()) => {
+                    let request = AddBlockRequest {
+                        graph_id: audit.graph_id().to_string(),
+                        data: audit_batch_data(&batch),
+                        cross_references: vec![],
+                        include_receipt: false,
+                    };
+                    let response = self.commit_block(request, false, None).await;
+                    if response.success {
+                        Ok(())
+                    } else {
+                        Err(response.message)
+                    }
+                }
+                Err(e) => Err(e),
+            };
+            if let Err(e) = committed {
+                tracing::warn!("⚠️  Failed to write {} audit event(s): {}", batch.len(), e);
+                audit.requeue(batch);
+                break;
+            }
+            written += batch.len();
+        }
+        if written > 0 {
+            tracing::debug!(
+                "🧾 Wrote {} audit event(s) to '{}'",
+                written,
+                audit.graph_id()
+            );
+        }
+        written
+    }
+
+    /// Flushes the audit trail every `interval`, or as soon as a full batch
+    /// is pending, until the task is aborted
+    pub async fn run_audit_writer(self: Arc<Self>, interval: Duration) {
+        let audit = match self.audit.clone() {
+            Some(audit) => audit,
+            None => return,
+        };
+        loop {
+            tokio::select! {
+                _ = tokio::time::sleep(interval) => {}
+                _ = audit.batch_ready() => {}
+            }
+            self.flush_audit().await;
+        }
+    }
+
+    /// Writes an anchor block holding the tip hash and height of every other
+    /// graph, unless no tip moved since the last anchor. Returns the height
+    /// of the new anchor block. Clustered members leave anchoring to the leader.
+    pub async fn write_anchor(&self) -> Result<Option<u64>, String> {
+        let graph_id = match &self.anchor_graph {
+            Some(graph_id) => graph_id,
+            None => return Ok(None),
+        };
+        if self
+            .cluster
+            .as_ref()
+            .is_some_and(|cluster| !cluster.is_leader())
+        {
+            return Ok(None);
+        }
+        self.ensure_audit_graph(graph_id, "Anchors of graph tips")
+            .await?;
+
+        let tips = {
+            let graphs = self.graphs.read().await;
+            let tips = graph_tips(&graphs, graph_id);
+            let last = graphs.get(graph_id).and_then(|graph| {
+                graph
+                    .chain
+                    .iter()
+                    .rev()
+                    .find_map(|block| parse_anchors(&block.data))
+            });
+            if last.as_ref() == Some(&tips) {
+                return Ok(None);
+            }
+            tips
+        };
+
+        let request = AddBlockRequest {
+            graph_id: graph_id.clone(),
+            data: anchor_data(&tips),
+            cross_references: vec![],
+            include_receipt: false,
+        };
+        let response = self.commit_block(request, false, None).await;
+        match response.block {
+            Some(block) if response.success => {
+                tracing::info!(
+                    "⚓ Anchored {} graph tip(s) at height {} of '{}'",
+                    tips.len(),
+                    block.height,
+                    graph_id
+                );
+                Ok(Some(block.height))
+            }
+            _ => Err(response.message),
+        }
+    }
+
+    /// Writes an anchor every `interval` until the task is aborted
+    pub async fn run_anchor_writer(self: Arc<Self>, interval: Duration) {
+        loop {
+            tokio::time::sleep(interval).await;
+            if let Err(e) = self.write_anchor().await {
+                tracing::warn!("⚠️  Failed to write anchor: {}", e);
+            }
+        }
+    }
+
+    /// Verifies every graph above its checkpoint and moves the checkpoint
+    /// to the tip; a graph that fails loses its checkpoint, so the next
+    /// pass starts from genesis. Storage is written after the graphs lock
+    /// is released. Returns how many checkpoints moved.
+    pub async fn advance_checkpoints(&self) -> usize {
+        let results: Vec<_> = {
+            let graphs = self.graphs.read().await;
+            let checkpoints = self.checkpoints.read().await;
+            graphs
+                .values()
+                .map(|graph| {
+                    let checkpoint = checkpoints.get(&graph.id).cloned();
+                    let verified = graph.verify_since(checkpoint.as_ref());
+                    (graph.id.clone(), checkpoint, verified)
+                })
+                .collect()
+        };
+
+        let mut moved = 0;
+        for (graph_id, checkpoint, verified) in results {
+            let stored = match verified {
+                Ok(tip) if checkpoint.as_ref() == Some(&tip) => continue,
+                Ok(tip) => {
+                    moved += 1;
+                    let stored = self
+                        .repository
+                        .save_verified_checkpoint(&graph_id, &tip)
+                        .await
+                        .map_err(|e| e.to_string());
+                    self.checkpoints.write().await.insert(graph_id.clone(), tip);
+                    stored
+                }
+                Err(e) => {
+                    tracing::warn!("⚠️  Graph '{}' failed verification: {}", graph_id, e);
+                    if checkpoint.is_none() {
+                        continue;
+                    }
+                    self.checkpoints.write().await.remove(&graph_id);
+                    self.repository
+                        .delete_verified_checkpoint(&graph_id)
+                        .await
+                        .map_err(|e| e.to_string())
+                }
+            };
+            if let Err(e) = stored {
+                tracing::warn!(
+                    "⚠️  Failed to store the verified checkpoint of '{}': {}",
+                    graph_id,
+                    e
+                );
+            }
+        }
+        moved
+    }
+
+    /// Moves the verified checkpoints every `interval` until the task is aborted
+    pub async fn run_checkpoint_writer(self: Arc<Self>, interval: Duration) {
+        loop {
+            tokio::time::sleep(interval).await;
+            let moved = self.advance_checkpoints().await;
+            if moved > 0 {
+                tracing::debug!("✅ Moved the verified checkpoint of {} graph(s)", moved);
+            }
+        }
+    }
+
+    /// Handles adding a new block to a graph. Data that changes graph
+    /// settings is refused: it goes through its dedicated RPC.
     pub async fn handle_add_block(
         &self,
         request: AddBlockRequest,
     ) -> Result<Response<AddBlockResponse>, Status> {
-        let graph_id = request.graph_id.clone();
+        let control = match self.graphs.read().await.get(&request.graph_id) {
+            Some(graph) => graph.is_control_data(&request.data),
+            None => false,
+        };
+        if control {
+            return Ok(Response::new(AddBlockResponse {
+                success: false,
+                message: "Block data changing graph settings must be sent with UpdateAuthorities, \
+                          DeployContract, UpdateSchema or UpdateAssetPolicy"
+                    .to_string(),
+                block: None,
+                proposal: None,
+                receipt: None,
+            }));
+        }
+        self.add_block(request, false).await
+    }
 
-        // Get or create graph
-        let mut graphs = self.graphs.write().await;
-        let graph = match graphs.get_mut(&graph_id) {
-            Some(g) => g,
+    /// Handles a client AddBlock forwarded by another cluster member; it
+    /// goes through the same checks as one received directly
+    pub async fn handle_forwarded_add_block(
+        &self,
+        request: AddBlockRequest,
+    ) -> Result<Response<AddBlockResponse>, Status> {
+        self.add_block(request, true).await
+    }
+
+    /// Handles a block another cluster member wrote itself (audit batches,
+    /// endorsed proposals); the leader seals it and orders it through Raft
+    pub async fn handle_forwarded_commit(
+        &self,
+        request: ForwardCommitRequest,
+    ) -> Result<Response<AddBlockResponse>, Status> {
+        let _in_flight = match self.shutdown.try_enter() {
+            Some(guard) => guard,
             None => {
                 return Ok(Response::new(AddBlockResponse {
                     success: false,
-                    message: format!("Graph '{}' not found", graph_id),
+                    message: "Service is shutting down".to_string(),
                     block: None,
+                    proposal: None,
+                    receipt: None,
                 }));
             }
         };
+        let endorsements = request
+            .endorsements
+            .map(replication::endorsements_from_proto);
+        let block = request.block.unwrap_or_default();
+        Ok(Response::new(
+            self.commit_block(block, true, endorsements).await,
+        ))
+    }
 
-        // Get previous block info
-        let (previous_hash, height) = match graph.get_latest_block() {
-            Some(block) => (block.hash.clone(), block.height + 1),
-            None => ("0".to_string(), 0),
+    async fn add_block(
+        &self,
+        mut request: AddBlockRequest,
+        forwarded: bool,
+    ) -> Result<Response<AddBlockResponse>, Status> {
+        let failure = |message: String| AddBlockResponse {
+            success: false,
+            message,
+            block: None,
+            proposal: None,
+            receipt: None,
         };
+        let graph_id = request.graph_id.clone();
+        // The member the client called signs the receipt, not the leader it forwards to
+        let include_receipt = std::mem::take(&mut request.include_receipt);
 
-        // Create new block
-        let block = Block::new(
-            previous_hash,
-            request.data,
-            graph_id.clone(),
-            height,
-            request.cross_references,
-        );
+        if include_receipt && self.receipt_key.is_none() {
+            return Ok(Response::new(failure(
+                "This node has no key to sign receipts".to_string(),
+            )));
+        }
+
+        if let Some(message) = self.read_only_message() {
+            return Ok(Response::new(failure(message)));
+        }
+
+        let _in_flight = match self.shutdown.try_enter() {
+            Some(guard) => guard,
+            None => {
+                return Ok(Response::new(failure(
+                    "Service is shutting down".to_string(),
+                )));
+            }
+        };
+
+        let policy = match self.graphs.read().await.get(&graph_id) {
+            Some(graph) => {
+                graph.approval_policy(graph.current_authorities().as_ref(), &request.data)
+            }
+            None => Ok(None),
+        };
+        let policy = match policy {
+            Ok(policy) => policy,
+            Err(message) => return Ok(Response::new(failure(message))),
+        };
+        // The block is only committed once endorsed, so there is nothing to sign yet
+        if include_receipt && policy.is_some() {
+            return Ok(Response::new(failure(format!(
+                "Block for '{}' requires endorsement; receipts are only issued for blocks committed directly",
+                graph_id
+            ))));
+        }
+
+        // Other members hand the write to the leader, which runs the checks below
+        if let Some(cluster) = self.cluster.as_ref().filter(|cluster| !cluster.is_leader()) {
+            let mut response = match self.leader_for_forwarding(cluster, forwarded) {
+                Ok((leader, credentials)) => {
+                    cluster_client::forward_add_block(&leader, credentials, request)
+                        .await
+                        .unwrap_or_else(failure)
+                }
+                Err(message) => failure(message),
+            };
+            if include_receipt {
+                self.attach_receipt(&mut response).await;
+            }
+            return Ok(Response::new(response));
+        }
+
+        // Check the data against the graph's schema and the graphs it names
+        // before any work is spent on it
+        let checked = {
+            let graphs = self.graphs.read().await;
+            match graphs.get(&graph_id) {
+                // Only the node appends to its audit and anchor graphs
+                Some(_) if self.node_graphs().iter().any(|(id, _)| *id == graph_id) => {
+                    Err(format!("Graph '{}' is written by the node only", graph_id))
+                }
+                Some(graph) => {
+                    let next = Block::new(
+                        String::new(),
+                        request.data.clone(),
+                        graph_id.clone(),
+                        0,
+                        request.cross_references.clone(),
+                    );
+                    graph
+                        .check_block_schema(&graph.chain, &next)
+                        .and_then(|_| Self::check_block_references(&graphs, &next))
+                }
+                None => Ok(()),
+            }
+        };
+        if let Err(message) = checked {
+            return Ok(Response::new(failure(message)));
+        }
+
+        if let Some(policy) = policy {
+            return Ok(Response::new(self.propose_block(request, &policy).await));
+        }
+
+        let mut response = self.commit_block(request, forwarded, None).await;
+        if include_receipt {
+            self.attach_receipt(&mut response).await;
+        }
+        Ok(Response::new(response))
+    }
+
+    /// Adds a receipt for the block of a successful AddBlock response
+    async fn attach_receipt(&self, response: &mut AddBlockResponse) {
+        response.receipt = match &response.block {
+            Some(block) if response.success => Some(
+                self.issue_receipt(replication::block_from_proto(block.clone()))
+                    .await,
+            ),
+            _ => None,
+        };
+    }
+
+    /// Signs a notarization receipt for a committed block with the receipt key,
+    /// naming the latest anchor of the block's history when anchoring is on
+    async fn issue_receipt(&self, block: Block) -> proto::Receipt {
+        let anchor = match &self.anchor_graph {
+            Some(anchor_graph) => {
+                let graphs = self.graphs.read().await;
+                graphs.get(anchor_graph).and_then(|anchors| {
+                    anchors.chain.iter().rev().find_map(|anchor| {
+                        let anchored = parse_anchors(&anchor.data)?.into_iter().find(|tip| {
+                            tip.graph_id == block.graph_id && tip.height < block.height
+                        })?;
+                        Some(ReceiptAnchor {
+                            graph_id: anchor_graph.clone(),
+                            height: anchor.height,
+                            hash: anchor.hash.clone(),
+                            anchored_height: anchored.height,
+                            anchored_hash: anchored.hash,
+                        })
+                    })
+                })
+            }
+            None => None,
+        };
+        let key = self
+            .receipt_key
+            .as_ref()
+            .expect("checked before the block was added");
+        Self::receipt_to_proto(Receipt::issue(key, &block, anchor))
+    }
+
+    /// Checks what a new block names in other graphs (see
+    /// `BlockchainGraph::check_references`). Its signer must also be
+    /// registered and hold the signing key now, not only when the block
+    /// was created.
+    fn check_block_references(
+        graphs: &HashMap<String, BlockchainGraph>,
+        block: &Block,
+    ) -> Result<(), String> {
+        if let Some((signer, message)) = parse_signer(&block.graph_id, &block.data)? {
+            let identities = Self::graph_of_type(graphs, &signer.graph_id, GraphType::Identity)?;
+            let record = identities
+                .state
+                .get(&identity_key(&signer.subject_id))
+                .and_then(|entry| IdentityRecord::from_bytes(&entry.value))
+                .ok_or_else(|| {
+                    format!(
+                        "Signer '{}' is not registered in '{}'",
+                        signer.subject_id, signer.graph_id
+                    )
+                })?;
+            if !record.verify(&message, &signer.signature) {
+                return Err(format!(
+                    "Signature of '{}' does not match an active key of the identity",
+                    signer.subject_id
+                ));
+            }
+        }
+
+        let graph = graphs
+            .get(&block.graph_id)
+            .ok_or_else(|| format!("Graph '{}' not found", block.graph_id))?;
+        graph.check_references(&graph.chain, block, graphs)
+    }
+
+    /// Mines or signs the block and appends it (through Raft when clustered).
+    /// Blocks of endorsed graphs carry the endorsements of their proposal.
+    async fn commit_block(
+        &self,
+        request: AddBlockRequest,
+        forwarded: bool,
+        endorsements: Option<BlockEndorsements>,
+    ) -> AddBlockResponse {
+        let graph_id = request.graph_id.clone();
+
+        if let Some(cluster) = &self.cluster {
+            return self
+                .add_block_clustered(cluster, request, forwarded, endorsements)
+                .await;
+        }
+
+        // Create the new block on the tip
+        let mut graphs = self.graphs.write().await;
+        let block = match graphs.get(&graph_id) {
+            Some(graph) => {
+                let (previous_hash, height) = match graph.get_latest_block() {
+                    Some(block) => (block.hash.clone(), block.height + 1),
+                    None => ("0".to_string(), 0),
+                };
+                let block = Block::new(
+                    previous_hash,
+                    request.data,
+                    graph_id.clone(),
+                    height,
+                    request.cross_references,
+                );
+                match endorsements {
+                    Some(endorsements) => block.with_endorsements(endorsements),
+                    None => block,
+                }
+            }
+            None => {
+                return AddBlockResponse {
+                    success: false,
+                    message: format!("Graph '{}' not found", graph_id),
+                    block: None,
+                    proposal: None,
+                    receipt: None,
+                };
+            }
+        };
+
+        // The graphs the block names may have changed since the pre-check
+        if let Err(message) = Self::check_block_references(&graphs, &block) {
+            return AddBlockResponse {
+                success: false,
+                message,
+                block: None,
+                proposal: None,
+                receipt: None,
+            };
+        }
+        let graph = graphs
+            .get_mut(&graph_id)
+            .expect("looked up above under the same lock");
+
+        // Check read/write sets and run the contract before spending work on the block
+        let changes = match graph.next_state_changes(&block, self.contracts.as_ref()) {
+            Ok(changes) => changes,
+            Err(e) => {
+                return AddBlockResponse {
+                    success: false,
+                    message: e,
+                    block: None,
+                    proposal: None,
+                    receipt: None,
+                };
+            }
+        };
 
-        // Add block to graph and capture the mined block
-        let mined_block = match graph.add_block(block) {
+        // Mine or sign the block and add it to the graph
+        let mined_block = match graph.seal_block(block, self.authority_key.as_ref()) {
             Ok(b) => b,
             Err(e) => {
-                return Ok(Response::new(AddBlockResponse {
+                return AddBlockResponse {
                     success: false,
                     message: format!("Failed to add block: {}", e),
                     block: None,
-                }));
+                    proposal: None,
+                    receipt: None,
+                };
             }
         };
 
-        // Persist the mined block
-        if let Err(e) = self.repository.save_block(&graph_id, &mined_block).await {
-            return Ok(Response::new(AddBlockResponse {
+        // Persist the mined block together with its state writes
+        let writes = commit_changes(&changes, &mined_block);
+        if let Err(e) = self
+            .repository
+            .save_block_with_state(&graph_id, &mined_block, &writes)
+            .await
+        {
+            return AddBlockResponse {
                 success: false,
                 message: format!("Failed to persist block: {}", e),
                 block: None,
-            }));
+                proposal: None,
+                receipt: None,
+            };
         }
+        graph.apply_state_writes(&writes);
+
+        // Publish to followers while the graph lock still orders commits
+        let _ = self
+            .events
+            .send(ChainEvent::BlockCommitted(mined_block.clone()));
 
         // Convert to proto block
         let proto_block = self.block_to_proto(&mined_block);
 
-        Ok(Response::new(AddBlockResponse {
+        AddBlockResponse {
             success: true,
             message: "Block added successfully".to_string(),
             block: Some(proto_block),
-        }))
+            proposal: None,
+            receipt: None,
+        }
     }
 
     /// Handles getting a block by hash
@@ -186,6 +1020,14 @@ impl BlockchainServiceImpl {
                     .get_latest_block()
                     .map(|b| b.hash.clone())
                     .unwrap_or_default();
+                let checkpoint = self
+                    .checkpoints
+                    .read()
+                    .await
+                    .get(&graph.id)
+                    .filter(|c| c.resume_height(&graph.chain).is_some())
+                    .cloned();
+                let is_valid = graph.verify_since(checkpoint.as_ref()).is_ok();
 
                 Ok(Response::new(GetGraphInfoResponse {
                     success: true,
@@ -194,7 +1036,19 @@ impl BlockchainServiceImpl {
                     total_blocks: graph.get_chain_length(),
                     latest_hash,
                     created_at: graph.created_at,
-                    is_valid: graph.is_valid(),
+                    is_valid,
+                    consensus: Some(replication::consensus_to_proto(
+                        &graph.consensus,
+                        graph.current_authorities().as_ref(),
+                    )),
+                    endorsement: graph.endorsement.as_ref().map(replication::policy_to_proto),
+                    contract_hash: graph
+                        .current_contract()
+                        .map(|c| c.code_hash())
+                        .unwrap_or_default(),
+                    schema: graph.current_schema().map(String::from).unwrap_or_default(),
+                    verified_height: checkpoint.map(|c| c.height).unwrap_or_default(),
+                    governance: graph.governance.as_ref().map(replication::policy_to_proto),
                 }))
             }
             None => Ok(Response::new(GetGraphInfoResponse {
@@ -205,6 +1059,12 @@ impl BlockchainServiceImpl {
                 latest_hash: String::new(),
                 created_at: 0,
                 is_valid: false,
+                consensus: None,
+                endorsement: None,
+                contract_hash: String::new(),
+                schema: String::new(),
+                verified_height: 0,
+                governance: None,
             })),
         }
     }
@@ -218,8 +1078,8 @@ impl BlockchainServiceImpl {
 
         match graphs.get(&request.graph_id) {
             Some(graph) => {
-                let is_valid = graph.is_valid();
-                let message = if is_valid {
+                let verified = self.verify_from_checkpoint(graph, request.full).await;
+                let message = if verified.is_ok() {
                     "Graph is valid".to_string()
                 } else {
                     "Graph integrity check failed".to_string()
@@ -227,9 +1087,11 @@ impl BlockchainServiceImpl {
 
                 Ok(Response::new(VerifyGraphResponse {
                     success: true,
-                    is_valid,
+                    is_valid: verified.is_ok(),
                     message,
-                    errors: vec![],
+                    verified_height: verified.as_ref().map(|c| c.height).unwrap_or_default(),
+                    // The error names the offending block
+                    errors: verified.err().into_iter().collect(),
                 }))
             }
             None => Ok(Response::new(VerifyGraphResponse {
@@ -237,6 +1099,7 @@ impl BlockchainServiceImpl {
                 is_valid: false,
                 message: format!("Graph '{}' not found", request.graph_id),
                 errors: vec![],
+                verified_height: 0,
             })),
         }
     }
@@ -244,13 +1107,17 @@ impl BlockchainServiceImpl {
     /// Handles cross-validation of all graphs
     pub async fn handle_cross_validate(
         &self,
+        request: CrossValidateRequest,
     ) -> Result<Response<CrossValidateResponse>, Status> {
         let graphs = self.graphs.read().await;
         let mut statuses = HashMap::new();
         let mut all_valid = true;
 
         for (id, graph) in graphs.iter() {
-            let is_valid = graph.is_valid();
+            let is_valid = self
+                .verify_from_checkpoint(graph, !request.incremental)
+                .await
+                .is_ok();
             statuses.insert(id.clone(), is_valid);
 
             if !is_valid {
@@ -265,6 +1132,19 @@ impl BlockchainServiceImpl {
             }
         }
 
+        // A graph whose history no longer matches an anchor was rewritten
+        for graph in graphs.values() {
+            for broken in graph.broken_anchors(&graphs) {
+                tracing::warn!(
+                    "⚠️  Graph '{}' does not match an anchor in '{}'",
+                    broken,
+                    graph.id
+                );
+                statuses.insert(broken, false);
+                all_valid = false;
+            }
+        }
+
         Ok(Response::new(CrossValidateResponse {
             success: true,
             all_valid,
@@ -277,10 +1157,23 @@ impl BlockchainServiceImpl {
         }))
     }
 
-    /// Handles listing all graphs
-    pub async fn handle_list_graphs(
+    /// Verifies the blocks of `graph` added since its checkpoint, or every
+    /// block when `full`. Read-only: checkpoints only move in
+    /// `advance_checkpoints`.
+    async fn verify_from_checkpoint(
         &self,
-    ) -> Result<Response<ListGraphsResponse>, Status> {
+        graph: &BlockchainGraph,
+        full: bool,
+    ) -> Result<VerifiedCheckpoint, String> {
+        let checkpoint = match full {
+            true => None,
+            false => self.checkpoints.read().await.get(&graph.id).cloned(),
+        };
+        graph.verify_since(checkpoint.as_ref())
+    }
+
+    /// Handles listing all graphs
+    pub async fn handle_list_graphs(&self) -> Result<Response<ListGraphsResponse>, Status> {
         tracing::debug!("📋 Listing all graphs...");
 
         let graphs = self.graphs.read().await;
@@ -291,7 +1184,12 @@ impl BlockchainServiceImpl {
         let graph_infos: Vec<GraphInfo> = graphs
             .values()
             .map(|g| {
-                tracing::trace!("  - {} ({:?}): {} blocks", g.id, g.graph_type, g.get_chain_length());
+                tracing::trace!(
+                    "  - {} ({:?}): {} blocks",
+                    g.id,
+                    g.graph_type,
+                    g.get_chain_length()
+                );
                 GraphInfo {
                     graph_id: g.id.clone(),
                     graph_type: g.graph_type.to_i32(),
@@ -312,51 +1210,106 @@ impl BlockchainServiceImpl {
     pub async fn handle_create_graph(
         &self,
         request: CreateGraphRequest,
+    ) -> Result<Response<CreateGraphResponse>, Status> {
+        self.create_graph(request, false).await
+    }
+
+    /// Handles a client CreateGraph forwarded by another cluster member
+    pub async fn handle_forwarded_create_graph(
+        &self,
+        request: CreateGraphRequest,
+    ) -> Result<Response<CreateGraphResponse>, Status> {
+        self.create_graph(request, true).await
+    }
+
+    async fn create_graph(
+        &self,
+        request: CreateGraphRequest,
+        forwarded: bool,
     ) -> Result<Response<CreateGraphResponse>, Status> {
         tracing::info!("📝 Creating graph '{}'", request.graph_id);
 
         let graph_id = request.graph_id.clone();
 
-        // Check if graph already exists
-        match self.repository.graph_exists(&graph_id).await {
-            Ok(true) => {
-                tracing::warn!("❌ Graph '{}' already exists", graph_id);
+        if let Some(message) = self.read_only_message() {
+            return Ok(Response::new(CreateGraphResponse {
+                success: false,
+                message,
+                graph_info: None,
+            }));
+        }
+
+        let _in_flight = match self.shutdown.try_enter() {
+            Some(guard) => guard,
+            None => {
                 return Ok(Response::new(CreateGraphResponse {
                     success: false,
-                    message: format!("Graph '{}' already exists", graph_id),
+                    message: "Service is shutting down".to_string(),
                     graph_info: None,
                 }));
             }
-            Err(e) => {
-                tracing::error!("❌ Error checking if graph exists: {}", e);
+        };
+
+        let graph = match self.requested_graph(&request) {
+            Ok(graph) => graph,
+            Err(message) => {
                 return Ok(Response::new(CreateGraphResponse {
                     success: false,
-                    message: format!("Error checking graph existence: {}", e),
+                    message,
+                    graph_info: None,
+                }));
+            }
+        };
+
+        if let Some(cluster) = &self.cluster {
+            let response = self
+                .create_graph_clustered(cluster, request, graph, forwarded)
+                .await;
+            return Ok(Response::new(response));
+        }
+
+        // Check if graph already exists
+        match self.repository.graph_exists(&graph_id).await {
+            Ok(true) => {
+                tracing::warn!("❌ Graph '{}' already exists", graph_id);
+                return Ok(Response::new(CreateGraphResponse {
+                    success: false,
+                    message: format!("Graph '{}' already exists", graph_id),
+                    graph_info: None,
+                }));
+            }
+            Err(e) => {
+                tracing::error!("❌ Error checking if graph exists: {}", e);
+                return Ok(Response::new(CreateGraphResponse {
+                    success: false,
+                    message: format!("Error checking graph existence: {}", e),
                     graph_info: None,
                 }));
             }
             Ok(false) => {
                 // Graph doesn't exist, continue
-                tracing::debug!("✅ Graph '{}' does not exist, proceeding with creation", graph_id);
+                tracing::debug!(
+                    "✅ Graph '{}' does not exist, proceeding with creation",
+                    graph_id
+                );
             }
         }
 
-        // Create new graph
-        let graph_type = GraphType::from_i32(request.graph_type);
-        let graph = BlockchainGraph::new(
-            graph_id.clone(),
-            graph_type,
-            request.description.clone(),
-            2,
+        tracing::info!(
+            "📦 Created graph '{}' with type {:?}",
+            graph_id,
+            graph.graph_type
         );
 
-        tracing::info!("📦 Created graph '{}' with type {:?}", graph_id, graph_type);
-
         // Persist genesis block
         if let Some(genesis) = graph.get_latest_block() {
             tracing::debug!("💾 Persisting genesis block for graph '{}'...", graph_id);
             if let Err(e) = self.repository.save_block(&graph_id, genesis).await {
-                tracing::error!("❌ Failed to persist genesis block for '{}': {}", graph_id, e);
+                tracing::error!(
+                    "❌ Failed to persist genesis block for '{}': {}",
+                    graph_id,
+                    e
+                );
                 return Ok(Response::new(CreateGraphResponse {
                     success: false,
                     message: format!("Failed to persist genesis block: {}", e),
@@ -388,8 +1341,12 @@ impl BlockchainServiceImpl {
 
         // Add to in-memory cache (minimize lock time)
         {
-            tracing::debug!("🔒 Acquiring write lock to add graph '{}' to cache...", graph_id);
+            tracing::debug!(
+                "🔒 Acquiring write lock to add graph '{}' to cache...",
+                graph_id
+            );
             let mut graphs = self.graphs.write().await;
+            let _ = self.events.send(ChainEvent::GraphCreated(graph.clone()));
             graphs.insert(graph_id.clone(), graph);
             tracing::debug!("🔓 Released write lock for graph '{}'", graph_id);
         } // Lock is explicitly released here
@@ -429,17 +1386,2068 @@ impl BlockchainServiceImpl {
         }
     }
 
-    /// Converts domain Block to proto Block
-    fn block_to_proto(&self, block: &Block) -> crate::infrastructure::grpc::blockchain::Block {
-        crate::infrastructure::grpc::blockchain::Block {
-            hash: block.hash.clone(),
-            previous_hash: block.previous_hash.clone(),
-            timestamp: block.timestamp,
-            nonce: block.nonce,
-            data: block.data.clone(),
-            height: block.height,
-            graph_id: block.graph_id.clone(),
-            cross_references: block.cross_references.clone(),
+    /// Handles creating an online backup
+    pub async fn handle_create_backup(&self) -> Result<Response<CreateBackupResponse>, Status> {
+        let backups = match &self.backups {
+            Some(backups) => backups.clone(),
+            None => {
+                return Ok(Response::new(CreateBackupResponse {
+                    success: false,
+                    message: "Backups are not configured".to_string(),
+                    backup: None,
+                }));
+            }
+        };
+
+        // Checkpoints do blocking file I/O
+        let result =
+            tokio::task::spawn_blocking(move || backups.create_backup().map_err(|e| e.to_string()))
+                .await
+                .map_err(|e| Status::internal(e.to_string()))?;
+
+        match result {
+            Ok(backup) => Ok(Response::new(CreateBackupResponse {
+                success: true,
+                message: "Backup created successfully".to_string(),
+                backup: Some(Self::backup_to_proto(&backup)),
+            })),
+            Err(e) => {
+                tracing::error!("❌ Backup failed: {}", e);
+                Ok(Response::new(CreateBackupResponse {
+                    success: false,
+                    message: format!("Failed to create backup: {}", e),
+                    backup: None,
+                }))
+            }
+        }
+    }
+
+    /// Handles listing available backups
+    pub async fn handle_list_backups(&self) -> Result<Response<ListBackupsResponse>, Status> {
+        let listed = match &self.backups {
+            Some(backups) => backups.list_backups().map_err(|e| e.to_string()),
+            None => Err("Backups are not configured".to_string()),
+        };
+
+        match listed {
+            Ok(backups) => Ok(Response::new(ListBackupsResponse {
+                success: true,
+                message: format!("{} backup(s)", backups.len()),
+                backups: backups.iter().map(Self::backup_to_proto).collect(),
+            })),
+            Err(e) => Ok(Response::new(ListBackupsResponse {
+                success: false,
+                message: e,
+                backups: vec![],
+            })),
+        }
+    }
+
+    /// Converts a stored backup to proto BackupInfo
+    fn backup_to_proto(backup: &StoredBackup) -> BackupInfo {
+        BackupInfo {
+            backup_id: backup.backup_id.clone(),
+            path: backup.path.display().to_string(),
+            created_at_ms: backup.created_at_ms,
+        }
+    }
+
+    /// Handles a follower subscription: replays everything the follower is
+    /// missing (per its cursors), then streams live commits and heartbeats
+    pub async fn handle_subscribe_replication(
+        &self,
+        request: SubscribeReplicationRequest,
+    ) -> Result<Response<ReplicationStream>, Status> {
+        let cursors: HashMap<String, u64> = request
+            .cursors
+            .into_iter()
+            .map(|c| (c.graph_id, c.next_height))
+            .collect();
+
+        // Subscribe before the snapshot so no commit falls in between;
+        // followers skip blocks they already have. Graphs are interleaved by
+        // time so blocks arrive after the payments and identities they name.
+        let mut live = self.events.subscribe();
+        let backlog: Vec<ReplicationEvent> = {
+            let graphs = self.graphs.read().await;
+            let mut ids: Vec<&String> = graphs.keys().collect();
+            ids.sort();
+
+            let mut queues = Vec::new();
+            for id in ids {
+                let graph = &graphs[id];
+                let mut queue = VecDeque::new();
+                let start = match cursors.get(id) {
+                    Some(next_height) => *next_height as usize,
+                    None => {
+                        let mut created = graph.clone();
+                        created.chain.truncate(1);
+                        queue.push_back((
+                            graph.created_at,
+                            ChainEvent::GraphCreated(created).to_proto(),
+                        ));
+                        1
+                    }
+                };
+                for block in graph.chain.iter().skip(start) {
+                    queue.push_back((
+                        block.timestamp,
+                        ChainEvent::BlockCommitted(block.clone()).to_proto(),
+                    ));
+                }
+                queues.push(queue);
+            }
+            replication::interleave_by_time(queues)
+        };
+
+        tracing::info!(
+            "🔁 Follower subscribed ({} graph(s) known, {} event(s) to replay)",
+            cursors.len(),
+            backlog.len()
+        );
+
+        let (tx, rx) = mpsc::channel(EVENT_BUFFER);
+        let graphs = self.graphs.clone();
+        tokio::spawn(async move {
+            for event in backlog {
+                if tx.send(Ok(event)).await.is_err() {
+                    return;
+                }
+            }
+
+            let mut heartbeat = tokio::time::interval(HEARTBEAT_INTERVAL);
+            loop {
+                let message = tokio::select! {
+                    event = live.recv() => match event {
+                        Ok(event) => Ok(event.to_proto()),
+                        Err(RecvError::Lagged(missed)) => Err(Status::data_loss(format!(
+                            "Follower fell {} event(s) behind; resubscribe",
+                            missed
+                        ))),
+                        Err(RecvError::Closed) => return,
+                    },
+                    _ = heartbeat.tick() => {
+                        let tips = graphs
+                            .read()
+                            .await
+                            .iter()
+                            .filter_map(|(id, g)| g.get_latest_block().map(|b| (id.clone(), b.height)))
+                            .collect();
+                        Ok(ReplicationEvent {
+                            event: Some(replication_event::Event::Heartbeat(ReplicationHeartbeat {
+                                tips,
+                                timestamp_ms: chrono::Utc::now().timestamp_millis(),
+                            })),
+                        })
+                    }
+                };
+
+                let lagged = message.is_err();
+                if tx.send(message).await.is_err() || lagged {
+                    tracing::info!("🔌 Follower stream closed");
+                    return;
+                }
+            }
+        });
+
+        Ok(Response::new(
+            Box::pin(ReceiverStream::new(rx)) as ReplicationStream
+        ))
+    }
+
+    /// Handles replication status: role, leader connection and lag per graph
+    pub async fn handle_get_replication_status(
+        &self,
+    ) -> Result<Response<GetReplicationStatusResponse>, Status> {
+        let graphs = self.graphs.read().await;
+        let connected_followers = self.events.receiver_count() as u32;
+
+        let leader_address = match self.replication.leader_address() {
+            Some(address) => address.to_string(),
+            None => {
+                return Ok(Response::new(GetReplicationStatusResponse {
+                    success: true,
+                    message: "Node is the leader".to_string(),
+                    role: "leader".to_string(),
+                    leader_address: String::new(),
+                    connected: true,
+                    last_event_at_ms: 0,
+                    total_lag_blocks: 0,
+                    graphs: vec![],
+                    connected_followers,
+                }));
+            }
+        };
+
+        let progress = self.replication.progress();
+        let mut ids: Vec<&String> = graphs.keys().chain(progress.leader_tips.keys()).collect();
+        ids.sort();
+        ids.dedup();
+
+        let lags: Vec<GraphReplicationLag> = ids
+            .into_iter()
+            .map(|id| {
+                let local = graphs
+                    .get(id)
+                    .and_then(|g| g.get_latest_block())
+                    .map(|b| b.height);
+                let leader_height = progress
+                    .leader_tips
+                    .get(id)
+                    .copied()
+                    .or(local)
+                    .unwrap_or_default();
+                let lag_blocks = match local {
+                    Some(height) => leader_height.saturating_sub(height),
+                    None => leader_height + 1,
+                };
+                GraphReplicationLag {
+                    graph_id: id.clone(),
+                    local_height: local.unwrap_or_default(),
+                    leader_height,
+                    lag_blocks,
+                }
+            })
+            .collect();
+        let total_lag_blocks = lags.iter().map(|l| l.lag_blocks).sum();
+
+        Ok(Response::new(GetReplicationStatusResponse {
+            success: true,
+            message: format!("Following {}", leader_address),
+            role: "follower".to_string(),
+            leader_address,
+            connected: progress.connected,
+            last_event_at_ms: progress.last_event_at_ms,
+            total_lag_blocks,
+            graphs: lags,
+            connected_followers,
+        }))
+    }
+
+    /// Next height this node needs for each graph it has
+    pub async fn replication_cursors(&self) -> Vec<GraphCursor> {
+        self.graphs
+            .read()
+            .await
+            .values()
+            .map(|g| GraphCursor {
+                graph_id: g.id.clone(),
+                next_height: g.get_chain_length(),
+            })
+            .collect()
+    }
+
+    /// Marks whether the follower stream to the leader is open
+    pub fn set_replication_connected(&self, connected: bool) {
+        self.replication.set_connected(connected);
+    }
+
+    /// Applies one event received from the leader. Blocks are verified with
+    /// the same rules as local commits before they are persisted.
+    pub async fn apply_replication_event(&self, event: ReplicationEvent) -> Result<(), String> {
+        self.replication.record_event();
+        match event.event {
+            Some(replication_event::Event::GraphCreated(graph)) => {
+                self.apply_replicated_graph(graph).await
+            }
+            Some(replication_event::Event::Block(block)) => {
+                self.apply_replicated_block(replication::block_from_proto(block))
+                    .await
+            }
+            Some(replication_event::Event::Reorg(reorg)) => {
+                // Applying the new branch in order makes it win the fork choice here too
+                for block in reorg.connected {
+                    self.apply_replicated_block(replication::block_from_proto(block))
+                        .await?;
+                }
+                Ok(())
+            }
+            Some(replication_event::Event::Heartbeat(heartbeat)) => {
+                self.replication.record_heartbeat(heartbeat.tips);
+                Ok(())
+            }
+            None => Ok(()),
+        }
+    }
+
+    async fn apply_replicated_graph(&self, replicated: ReplicatedGraph) -> Result<(), String> {
+        let genesis = replicated
+            .genesis
+            .map(replication::block_from_proto)
+            .ok_or_else(|| {
+                format!(
+                    "Graph '{}' arrived without a genesis block",
+                    replicated.graph_id
+                )
+            })?;
+
+        let graph = BlockchainGraph {
+            id: replicated.graph_id.clone(),
+            graph_type: GraphType::from_i32(replicated.graph_type),
+            description: replicated.description,
+            created_at: replicated.created_at,
+            difficulty: replicated.difficulty as usize,
+            consensus: replication::consensus_from_proto(replicated.consensus),
+            endorsement: replicated.endorsement.map(replication::policy_from_proto),
+            governance: replicated.governance.map(replication::policy_from_proto),
+            schema: replication::schema_from_proto(&replicated.schema),
+            chain: Vec::new(),
+            side_blocks: HashMap::new(),
+            state: Default::default(),
+        };
+        self.applier.apply_graph(graph, genesis).await?;
+
+        tracing::info!("🔁 Replicated graph '{}'", replicated.graph_id);
+        self.replication.observe_tip(&replicated.graph_id, 0);
+        Ok(())
+    }
+
+    async fn apply_replicated_block(&self, block: Block) -> Result<(), String> {
+        let (graph_id, height) = (block.graph_id.clone(), block.height);
+        self.applier.apply_block(block).await?;
+        self.replication.observe_tip(&graph_id, height);
+        Ok(())
+    }
+
+    /// Clustered AddBlock: the leader mines the block on the committed tip and
+    /// commits it through Raft; other members forward the request to the leader
+    async fn add_block_clustered(
+        &self,
+        cluster: &RaftNode,
+        request: AddBlockRequest,
+        forwarded: bool,
+        endorsements: Option<BlockEndorsements>,
+    ) -> AddBlockResponse {
+        let failure = |message: String| AddBlockResponse {
+            success: false,
+            message,
+            block: None,
+            proposal: None,
+            receipt: None,
+        };
+
+        if !cluster.is_leader() {
+            return match self.leader_for_forwarding(cluster, forwarded) {
+                Ok((leader, credentials)) => {
+                    let request = ForwardCommitRequest {
+                        block: Some(request),
+                        endorsements: endorsements
+                            .as_ref()
+                            .map(replication::endorsements_to_proto),
+                    };
+                    cluster_client::forward_commit(&leader, credentials, request)
+                        .await
+                        .unwrap_or_else(failure)
+                }
+                Err(message) => failure(message),
+            };
+        }
+
+        let _ordered = self.cluster_writes.lock().await;
+        let (mut block, difficulty, consensus) = {
+            let graphs = self.graphs.read().await;
+            let graph = match graphs.get(&request.graph_id) {
+                Some(graph) => graph,
+                None => return failure(format!("Graph '{}' not found", request.graph_id)),
+            };
+            let latest = match graph.get_latest_block() {
+                Some(latest) => latest,
+                None => return failure(format!("Graph '{}' has no blocks", request.graph_id)),
+            };
+            let mut block = Block::new(
+                latest.hash.clone(),
+                request.data,
+                request.graph_id.clone(),
+                latest.height + 1,
+                request.cross_references,
+            );
+            if let Some(endorsements) = endorsements {
+                block = block.with_endorsements(endorsements);
+            }
+            // Rejected payloads fail here; every member recomputes the state changes when applying
+            if let Err(e) = graph.check_block_data(&graph.chain, &block) {
+                return failure(e);
+            }
+            if let Err(e) = Self::check_block_references(&graphs, &block) {
+                return failure(e);
+            }
+            if let Err(e) = graph.next_state_changes(&block, self.contracts.as_ref()) {
+                return failure(e);
+            }
+            (block, graph.difficulty, graph.consensus.clone())
+        };
+
+        if let Err(e) = consensus.seal(&mut block, difficulty, self.authority_key.as_ref()) {
+            return failure(format!("Failed to add block: {}", e));
+        }
+
+        let command = match ClusterCommand::AddBlock(block.clone()).encode() {
+            Ok(command) => command,
+            Err(e) => return failure(e),
+        };
+        match cluster.propose(command).await {
+            Ok(()) => AddBlockResponse {
+                success: true,
+                message: "Block committed by the cluster".to_string(),
+                block: Some(self.block_to_proto(&block)),
+                proposal: None,
+                receipt: None,
+            },
+            Err(e) => failure(format!("Failed to add block: {}", e)),
+        }
+    }
+
+    /// Clustered CreateGraph, ordered through Raft like AddBlock
+    async fn create_graph_clustered(
+        &self,
+        cluster: &RaftNode,
+        request: CreateGraphRequest,
+        graph: BlockchainGraph,
+        forwarded: bool,
+    ) -> CreateGraphResponse {
+        let failure = |message: String| CreateGraphResponse {
+            success: false,
+            message,
+            graph_info: None,
+        };
+
+        if !cluster.is_leader() {
+            return match self.leader_for_forwarding(cluster, forwarded) {
+                Ok((leader, credentials)) => {
+                    cluster_client::forward_create_graph(&leader, credentials, request)
+                        .await
+                        .unwrap_or_else(failure)
+                }
+                Err(message) => failure(message),
+            };
+        }
+
+        let _ordered = self.cluster_writes.lock().await;
+        if self.graphs.read().await.contains_key(&request.graph_id) {
+            return failure(format!("Graph '{}' already exists", request.graph_id));
+        }
+
+        let graph_info = GraphInfo {
+            graph_id: graph.id.clone(),
+            graph_type: graph.graph_type.to_i32(),
+            total_blocks: graph.get_chain_length(),
+            description: graph.description.clone(),
+        };
+        let genesis = graph.chain[0].clone();
+
+        let command = match (ClusterCommand::CreateGraph {
+            graph: Box::new(graph),
+            genesis,
+        })
+        .encode()
+        {
+            Ok(command) => command,
+            Err(e) => return failure(e),
+        };
+        match cluster.propose(command).await {
+            Ok(()) => {
+                tracing::info!("✨ Graph '{}' committed by the cluster", request.graph_id);
+                CreateGraphResponse {
+                    success: true,
+                    message: "Graph created successfully".to_string(),
+                    graph_info: Some(graph_info),
+                }
+            }
+            Err(e) => failure(format!("Failed to create graph: {}", e)),
+        }
+    }
+
+    /// Cluster address of the leader to forward a write to, with the
+    /// credentials to present, unless the write was already forwarded once
+    fn leader_for_forwarding(
+        &self,
+        cluster: &RaftNode,
+        forwarded: bool,
+    ) -> Result<(String, &ClusterCredentials), String> {
+        if forwarded {
+            return Err("Forwarded write reached a node that is not the leader; retry".to_string());
+        }
+        let credentials = self
+            .cluster_credentials
+            .as_ref()
+            .ok_or_else(|| "Cluster credentials are not configured".to_string())?;
+        let leader = cluster
+            .leader_address()
+            .ok_or_else(|| "No cluster leader elected yet; retry".to_string())?;
+        Ok((leader, credentials))
+    }
+
+    /// Handles cluster status: Raft role, term, leader and log progress
+    pub async fn handle_get_cluster_status(
+        &self,
+    ) -> Result<Response<GetClusterStatusResponse>, Status> {
+        let cluster = match &self.cluster {
+            Some(cluster) => cluster,
+            None => {
+                return Ok(Response::new(GetClusterStatusResponse {
+                    success: false,
+                    message: "Clustered mode is not enabled".to_string(),
+                    ..Default::default()
+                }));
+            }
+        };
+
+        let status = cluster.status();
+        let mut peers: Vec<ClusterMember> = cluster
+            .peers()
+            .iter()
+            .map(|(node_id, address)| ClusterMember {
+                node_id: *node_id,
+                address: address.clone(),
+                match_index: status.match_index.get(node_id).copied().unwrap_or_default(),
+            })
+            .collect();
+        peers.sort_by_key(|p| p.node_id);
+
+        Ok(Response::new(GetClusterStatusResponse {
+            success: true,
+            message: format!("Node {} is {}", status.node_id, status.role.as_str()),
+            node_id: status.node_id,
+            role: status.role.as_str().to_string(),
+            term: status.term,
+            leader_id: status.leader_id.unwrap_or_default(),
+            leader_address: cluster.leader_address().unwrap_or_default(),
+            last_log_index: status.last_log_index,
+            commit_index: status.commit_index,
+            last_applied: status.last_applied,
+            peers,
+        }))
+    }
+
+    /// Tips of every local graph, sorted by graph id
+    pub async fn gossip_tips(&self) -> Vec<GraphTip> {
+        let graphs = self.graphs.read().await;
+        let mut tips: Vec<GraphTip> = graphs.values().filter_map(gossip::graph_tip).collect();
+        tips.sort_by(|a, b| a.graph_id.cmp(&b.graph_id));
+        tips
+    }
+
+    /// Latest local height of a graph, if it exists
+    pub async fn local_height(&self, graph_id: &str) -> Option<u64> {
+        self.graphs
+            .read()
+            .await
+            .get(graph_id)
+            .and_then(|g| g.get_latest_block())
+            .map(|b| b.height)
+    }
+
+    /// Announced tips that are ahead of this node (or of graphs it lacks)
+    pub async fn tips_ahead(&self, tips: Vec<GraphTip>) -> Vec<GraphTip> {
+        let graphs = self.graphs.read().await;
+        tips.into_iter()
+            .filter(
+                |tip| match graphs.get(&tip.graph_id).and_then(|g| g.get_latest_block()) {
+                    Some(local) => tip.height > local.height,
+                    None => true,
+                },
+            )
+            .collect()
+    }
+
+    /// Handles a peer announcing its tips: answers with ours and, when the
+    /// peer is ahead and told us where to reach it, schedules a pull
+    pub async fn handle_announce_tips(
+        &self,
+        request: AnnounceTipsRequest,
+    ) -> Result<Response<AnnounceTipsResponse>, Status> {
+        if !self.gossip.is_enabled() {
+            return Ok(Response::new(AnnounceTipsResponse {
+                success: false,
+                message: "Gossip is not enabled on this node".to_string(),
+                tips: vec![],
+            }));
+        }
+
+        let ahead = self.tips_ahead(request.tips).await;
+        let message = if ahead.is_empty() {
+            "Up to date".to_string()
+        } else if request.origin.is_empty() {
+            format!("Behind on {} graph(s)", ahead.len())
+        } else if !self.gossip.is_peer(&request.origin) {
+            tracing::warn!(
+                "Ignoring announcement from {}: not a configured gossip peer",
+                request.origin
+            );
+            format!(
+                "Behind on {} graph(s); {} is not a configured peer",
+                ahead.len(),
+                request.origin
+            )
+        } else {
+            tracing::info!(
+                "📣 {} is ahead on {} graph(s), pulling",
+                request.origin,
+                ahead.len()
+            );
+            self.gossip.request_sync(&request.origin);
+            format!("Pulling {} graph(s) from {}", ahead.len(), request.origin)
+        };
+
+        Ok(Response::new(AnnounceTipsResponse {
+            success: true,
+            message,
+            tips: self.gossip_tips().await,
+        }))
+    }
+
+    /// Handles gossip status: configured peers and sync progress
+    pub async fn handle_get_gossip_status(
+        &self,
+    ) -> Result<Response<GetGossipStatusResponse>, Status> {
+        if !self.gossip.is_enabled() {
+            return Ok(Response::new(GetGossipStatusResponse {
+                success: true,
+                message: "Gossip is not enabled on this node".to_string(),
+                enabled: false,
+                peers: vec![],
+            }));
+        }
+
+        let peers = self.gossip.peers();
+        let reachable = peers.iter().filter(|p| p.reachable).count();
+        Ok(Response::new(GetGossipStatusResponse {
+            success: true,
+            message: format!("{} of {} peer(s) reachable", reachable, peers.len()),
+            enabled: true,
+            peers,
+        }))
+    }
+
+    /// Handles reporting the outcome of the last integrity scrub
+    pub async fn handle_get_scrub_status(
+        &self,
+    ) -> Result<Response<GetScrubStatusResponse>, Status> {
+        let scrubber = match &self.scrubber {
+            Some(scrubber) => scrubber,
+            None => {
+                return Ok(Response::new(GetScrubStatusResponse {
+                    success: true,
+                    message: "Integrity scrubbing is not enabled on this node".to_string(),
+                    ..Default::default()
+                }));
+            }
+        };
+
+        let running = scrubber.is_running();
+        let report = match scrubber.last_report() {
+            Some(report) => report,
+            None => {
+                return Ok(Response::new(GetScrubStatusResponse {
+                    success: true,
+                    message: "No scrub has finished yet".to_string(),
+                    enabled: true,
+                    running,
+                    ..Default::default()
+                }));
+            }
+        };
+        Ok(Response::new(GetScrubStatusResponse {
+            success: true,
+            message: format!("Last scrub found {} issue(s)", report.issues.len()),
+            enabled: true,
+            running,
+            last_started_at_ms: report.started_at_ms,
+            last_finished_at_ms: report.finished_at_ms,
+            graphs_checked: report.graphs_checked,
+            blocks_checked: report.blocks_checked,
+            issues: report
+                .issues
+                .into_iter()
+                .map(|issue| ScrubIssue {
+                    graph_id: issue.graph_id,
+                    height: issue.height,
+                    message: issue.message,
+                })
+                .collect(),
+        }))
+    }
+
+    /// Handles replacing the authority set of a proof-of-authority graph.
+    /// The update is held as a proposal until the current authorities
+    /// approve it, then recorded in a block signed under the current set.
+    pub async fn handle_update_authorities(
+        &self,
+        request: UpdateAuthoritiesRequest,
+    ) -> Result<Response<AddBlockResponse>, Status> {
+        let failure = |message: String| {
+            Response::new(AddBlockResponse {
+                success: false,
+                message,
+                block: None,
+                proposal: None,
+                receipt: None,
+            })
+        };
+
+        let set = AuthoritySet {
+            authorities: request.authorities,
+            rotation: AuthorityRotation::from_i32(request.rotation),
+        };
+        if let Err(e) = set.validate() {
+            return Ok(failure(e));
+        }
+        match self.graphs.read().await.get(&request.graph_id) {
+            Some(graph) if graph.consensus.is_authority() => {}
+            Some(_) => {
+                return Ok(failure(format!(
+                    "Graph '{}' does not use proof of authority",
+                    request.graph_id
+                )))
+            }
+            None => return Ok(failure(format!("Graph '{}' not found", request.graph_id))),
         }
+
+        tracing::info!(
+            "🔑 Updating authorities of '{}' to {} key(s)",
+            request.graph_id,
+            set.authorities.len()
+        );
+        self.add_block(
+            AddBlockRequest {
+                graph_id: request.graph_id,
+                data: authority_update_data(&set),
+                cross_references: vec![],
+                include_receipt: false,
+            },
+            false,
+        )
+        .await
+    }
+
+    /// Handles binding a WebAssembly contract to a graph. The module is
+    /// recorded in a block and runs on every later block of the graph.
+    pub async fn handle_deploy_contract(
+        &self,
+        request: DeployContractRequest,
+    ) -> Result<Response<AddBlockResponse>, Status> {
+        let failure = |message: String| {
+            Response::new(AddBlockResponse {
+                success: false,
+                message,
+                block: None,
+                proposal: None,
+                receipt: None,
+            })
+        };
+
+        let fuel_limit = match request.fuel_limit {
+            0 => self.max_contract_fuel,
+            limit if limit > self.max_contract_fuel => {
+                return Ok(failure(format!(
+                    "Fuel limit {} exceeds the maximum of {}",
+                    limit, self.max_contract_fuel
+                )))
+            }
+            limit => limit,
+        };
+        let deployment = ContractDeployment::new(&request.code, fuel_limit);
+        if let Err(e) = self.contracts.validate(&deployment) {
+            return Ok(failure(e));
+        }
+        if !self.graphs.read().await.contains_key(&request.graph_id) {
+            return Ok(failure(format!("Graph '{}' not found", request.graph_id)));
+        }
+
+        tracing::info!(
+            "📜 Deploying contract {} to '{}' ({} bytes, fuel limit {})",
+            deployment.code_hash(),
+            request.graph_id,
+            request.code.len(),
+            fuel_limit
+        );
+        self.add_block(
+            AddBlockRequest {
+                graph_id: request.graph_id,
+                data: contract_deploy_data(&deployment),
+                cross_references: vec![],
+                include_receipt: false,
+            },
+            false,
+        )
+        .await
+    }
+
+    /// Handles replacing or removing the data schema of a graph. The change
+    /// is recorded on-chain and applies to blocks after it.
+    pub async fn handle_update_schema(
+        &self,
+        request: UpdateSchemaRequest,
+    ) -> Result<Response<AddBlockResponse>, Status> {
+        let failure = |message: String| {
+            Response::new(AddBlockResponse {
+                success: false,
+                message,
+                block: None,
+                proposal: None,
+                receipt: None,
+            })
+        };
+
+        let graph_type = match self.graphs.read().await.get(&request.graph_id) {
+            Some(graph) => graph.graph_type,
+            None => return Ok(failure(format!("Graph '{}' not found", request.graph_id))),
+        };
+        let schema =
+            match Self::requested_schema(graph_type, &request.schema, request.default_schema) {
+                Ok(schema) => schema,
+                Err(e) => return Ok(failure(e)),
+            };
+
+        tracing::info!(
+            "📐 {} schema of '{}'",
+            if schema.is_some() {
+                "Updating"
+            } else {
+                "Removing"
+            },
+            request.graph_id
+        );
+        self.add_block(
+            AddBlockRequest {
+                graph_id: request.graph_id,
+                data: schema_update_data(schema.as_ref()),
+                cross_references: vec![],
+                include_receipt: false,
+            },
+            false,
+        )
+        .await
+    }
+
+    /// Handles setting the asset policy of an asset graph with an on-chain
+    /// policy block
+    pub async fn handle_update_asset_policy(
+        &self,
+        request: UpdateAssetPolicyRequest,
+    ) -> Result<Response<AddBlockResponse>, Status> {
+        let graph_type = self
+            .graphs
+            .read()
+            .await
+            .get(&request.graph_id)
+            .map(|graph| graph.graph_type);
+        if graph_type != Some(GraphType::Asset) {
+            return Ok(Response::new(AddBlockResponse {
+                success: false,
+                message: format!("Graph '{}' is not an asset graph", request.graph_id),
+                block: None,
+                proposal: None,
+                receipt: None,
+            }));
+        }
+
+        let policy = AssetPolicy {
+            payment_graph: Some(request.payment_graph).filter(|graph| !graph.is_empty()),
+        };
+        tracing::info!("🏷️ Updating asset policy of '{}'", request.graph_id);
+        self.add_block(
+            AddBlockRequest {
+                graph_id: request.graph_id,
+                data: asset_policy_data(&policy),
+                cross_references: vec![],
+                include_receipt: false,
+            },
+            false,
+        )
+        .await
+    }
+
+    /// Graph asked for in CreateGraph: its consensus mode (proof of work
+    /// when unset), endorsement and governance policies and schema
+    fn requested_graph(&self, request: &CreateGraphRequest) -> Result<BlockchainGraph, String> {
+        let consensus = replication::consensus_from_proto(request.consensus.clone());
+        if let Consensus::ProofOfAuthority(set) = &consensus {
+            set.validate()?;
+        }
+        let graph_type = GraphType::from_i32(request.graph_type);
+        let mut graph = BlockchainGraph::new(
+            request.graph_id.clone(),
+            graph_type,
+            request.description.clone(),
+            self.default_difficulty,
+        )
+        .with_consensus(consensus);
+        if let Some(policy) = request.endorsement.clone() {
+            let policy = replication::policy_from_proto(policy);
+            policy.validate()?;
+            graph = graph.with_endorsement(policy);
+        }
+        if let Some(policy) = request.governance.clone() {
+            let policy = replication::policy_from_proto(policy);
+            policy.validate()?;
+            graph = graph.with_governance(policy);
+        }
+        if let Some(schema) =
+            Self::requested_schema(graph_type, &request.schema, request.default_schema)?
+        {
+            graph = graph.with_schema(schema);
+        }
+        Ok(graph)
+    }
+
+    /// Data schema asked for in CreateGraph or UpdateSchema
+    fn requested_schema(
+        graph_type: GraphType,
+        schema: &str,
+        default_schema: bool,
+    ) -> Result<Option<DataSchema>, String> {
+        if default_schema {
+            return DataSchema::default_for(graph_type)
+                .map(Some)
+                .ok_or_else(|| format!("Graph type {:?} has no built-in schema", graph_type));
+        }
+        if schema.trim().is_empty() {
+            return Ok(None);
+        }
+        DataSchema::parse(schema).map(Some)
+    }
+
+    /// Holds an AddBlock that needs endorsement as a pending proposal
+    async fn propose_block(
+        &self,
+        request: AddBlockRequest,
+        policy: &EndorsementPolicy,
+    ) -> AddBlockResponse {
+        let now_ms = chrono::Utc::now().timestamp_millis();
+        let proposal = Proposal::new(
+            request.graph_id,
+            request.data,
+            request.cross_references,
+            now_ms,
+            self.proposal_ttl.as_millis() as i64,
+        );
+        if let Err(message) = self.store_proposal(&proposal).await {
+            return AddBlockResponse {
+                success: false,
+                message,
+                block: None,
+                proposal: None,
+                receipt: None,
+            };
+        }
+
+        tracing::info!(
+            "📝 Proposal {} for '{}' awaits endorsement ({})",
+            proposal.id,
+            proposal.graph_id,
+            policy.describe()
+        );
+        AddBlockResponse {
+            success: true,
+            message: format!("Proposal pending endorsement ({})", policy.describe()),
+            block: None,
+            proposal: Some(Self::proposal_to_proto(&proposal, Some(policy))),
+            receipt: None,
+        }
+    }
+
+    /// Handles an endorsement of a pending proposal. The endorsement that
+    /// satisfies the policy appends the block.
+    pub async fn handle_endorse_proposal(
+        &self,
+        request: EndorseProposalRequest,
+    ) -> Result<Response<ProposalResponse>, Status> {
+        self.endorse_proposal(request, false).await
+    }
+
+    /// Handles an endorsement forwarded by another cluster member
+    pub async fn handle_forwarded_endorse_proposal(
+        &self,
+        request: EndorseProposalRequest,
+    ) -> Result<Response<ProposalResponse>, Status> {
+        self.endorse_proposal(request, true).await
+    }
+
+    /// Clustered, only the leader endorses: it holds the ordered copy of
+    /// every proposal and commits the block
+    async fn endorse_proposal(
+        &self,
+        request: EndorseProposalRequest,
+        forwarded: bool,
+    ) -> Result<Response<ProposalResponse>, Status> {
+        if let Some(message) = self.read_only_message() {
+            return Ok(Response::new(Self::proposal_failure(message)));
+        }
+        let _in_flight = match self.shutdown.try_enter() {
+            Some(guard) => guard,
+            None => {
+                return Ok(Response::new(Self::proposal_failure(
+                    "Service is shutting down".to_string(),
+                )))
+            }
+        };
+        if let Some(cluster) = self.cluster.as_ref().filter(|c| !c.is_leader()) {
+            let forwarded = match self.leader_for_forwarding(cluster, forwarded) {
+                Ok((leader, credentials)) => {
+                    cluster_client::forward_endorse_proposal(&leader, credentials, request)
+                        .await
+                        .unwrap_or_else(Self::proposal_failure)
+                }
+                Err(message) => Self::proposal_failure(message),
+            };
+            return Ok(Response::new(forwarded));
+        }
+
+        let _ordered = self.endorsements.lock().await;
+        let (mut proposal, policy) = match self
+            .load_proposal(&request.graph_id, &request.proposal_id)
+            .await
+        {
+            Ok(loaded) => loaded,
+            Err(message) => return Ok(Response::new(Self::proposal_failure(message))),
+        };
+        let policy = match policy {
+            Some(policy) => policy,
+            None => {
+                return Ok(Response::new(Self::proposal_failure(format!(
+                    "Proposal {} no longer needs endorsement; submit the block again",
+                    proposal.id
+                ))))
+            }
+        };
+        if proposal.nonce.is_empty() {
+            return Ok(Response::new(Self::proposal_failure(format!(
+                "Proposal {} was stored before blocks recorded endorsements; submit the block again",
+                proposal.id
+            ))));
+        }
+
+        let now_ms = chrono::Utc::now().timestamp_millis();
+        let endorsed = proposal.endorse(&policy, &request.org, &request.signature, now_ms);
+        if let Err(message) = endorsed {
+            // An expiry noticed here is recorded
+            let _ = self.store_expiry(&proposal).await;
+            return Ok(Response::new(ProposalResponse {
+                success: false,
+                message,
+                proposal: Some(Self::proposal_to_proto(&proposal, Some(&policy))),
+                block: None,
+            }));
+        }
+        tracing::info!(
+            "✍️  '{}' endorsed proposal {} ({}/{})",
+            request.org,
+            proposal.id,
+            proposal.endorsements.len(),
+            policy.required
+        );
+
+        let mut block = None;
+        let mut message = format!(
+            "Endorsement recorded ({} of {} required)",
+            proposal.endorsements.len(),
+            policy.required
+        );
+        if proposal.is_satisfied(&policy) {
+            let committed = self
+                .commit_block(
+                    AddBlockRequest {
+                        graph_id: proposal.graph_id.clone(),
+                        data: proposal.data.clone(),
+                        cross_references: proposal.cross_references.clone(),
+                        include_receipt: false,
+                    },
+                    false,
+                    Some(proposal.block_endorsements()),
+                )
+                .await;
+            match committed.block {
+                Some(committed_block) if committed.success => {
+                    proposal.status = ProposalStatus::Committed;
+                    proposal.block_hash = committed_block.hash.clone();
+                    message = "Proposal endorsed and block added".to_string();
+                    block = Some(committed_block);
+                }
+                _ => {
+                    proposal.status = ProposalStatus::Rejected;
+                    proposal.message = committed.message.clone();
+                    message = committed.message;
+                }
+            }
+        }
+
+        if let Err(message) = self.store_proposal(&proposal).await {
+            return Ok(Response::new(Self::proposal_failure(message)));
+        }
+        Ok(Response::new(ProposalResponse {
+            success: proposal.status != ProposalStatus::Rejected,
+            message,
+            proposal: Some(Self::proposal_to_proto(&proposal, Some(&policy))),
+            block,
+        }))
+    }
+
+    /// Handles looking up one proposal
+    pub async fn handle_get_proposal(
+        &self,
+        request: GetProposalRequest,
+    ) -> Result<Response<ProposalResponse>, Status> {
+        match self
+            .load_proposal(&request.graph_id, &request.proposal_id)
+            .await
+        {
+            Ok((proposal, policy)) => Ok(Response::new(ProposalResponse {
+                success: true,
+                message: format!("Proposal is {:?}", proposal.status),
+                proposal: Some(Self::proposal_to_proto(&proposal, policy.as_ref())),
+                block: None,
+            })),
+            Err(message) => Ok(Response::new(Self::proposal_failure(message))),
+        }
+    }
+
+    /// Handles listing the proposals of a graph
+    pub async fn handle_list_proposals(
+        &self,
+        request: ListProposalsRequest,
+    ) -> Result<Response<ListProposalsResponse>, Status> {
+        let failure = |message: String| {
+            Response::new(ListProposalsResponse {
+                success: false,
+                message,
+                proposals: vec![],
+            })
+        };
+
+        if !self.graphs.read().await.contains_key(&request.graph_id) {
+            return Ok(failure(format!("Graph '{}' not found", request.graph_id)));
+        }
+        let stored = match self.repository.list_proposals(&request.graph_id).await {
+            Ok(stored) => stored,
+            Err(e) => return Ok(failure(format!("Error: {}", e))),
+        };
+
+        let now_ms = chrono::Utc::now().timestamp_millis();
+        let mut proposals = Vec::new();
+        for mut proposal in stored {
+            if proposal.expire_if_due(now_ms) {
+                let _ = self.store_expiry(&proposal).await;
+            }
+            if !request.pending_only || proposal.status == ProposalStatus::Pending {
+                let policy = self.approval_policy(&proposal).await.ok().flatten();
+                proposals.push(Self::proposal_to_proto(&proposal, policy.as_ref()));
+            }
+        }
+
+        Ok(Response::new(ListProposalsResponse {
+            success: true,
+            message: format!("Found {} proposal(s)", proposals.len()),
+            proposals,
+        }))
+    }
+
+    /// Handles reading the current world-state value of a key
+    pub async fn handle_get_state(
+        &self,
+        request: GetStateRequest,
+    ) -> Result<Response<GetStateResponse>, Status> {
+        let failure = |message: String| {
+            Response::new(GetStateResponse {
+                success: false,
+                message,
+                entry: None,
+            })
+        };
+
+        let graphs = self.graphs.read().await;
+        let graph = match graphs.get(&request.graph_id) {
+            Some(graph) => graph,
+            None => return Ok(failure(format!("Graph '{}' not found", request.graph_id))),
+        };
+        match graph.state.get(&request.key) {
+            Some(entry) => Ok(Response::new(GetStateResponse {
+                success: true,
+                message: format!("Key '{}' at version {}", request.key, entry.version),
+                entry: Some(Self::state_entry_to_proto(&request.key, entry)),
+            })),
+            None => Ok(failure(format!("Key '{}' not found", request.key))),
+        }
+    }
+
+    /// Handles listing every version of a key, oldest first
+    pub async fn handle_get_state_history(
+        &self,
+        request: GetStateHistoryRequest,
+    ) -> Result<Response<StateEntriesResponse>, Status> {
+        if !self.graphs.read().await.contains_key(&request.graph_id) {
+            return Ok(Response::new(Self::state_entries_failure(format!(
+                "Graph '{}' not found",
+                request.graph_id
+            ))));
+        }
+        match self
+            .repository
+            .get_state_history(&request.graph_id, &request.key)
+            .await
+        {
+            Ok(history) => Ok(Response::new(StateEntriesResponse {
+                success: true,
+                message: format!("Found {} version(s)", history.len()),
+                entries: history
+                    .iter()
+                    .map(|entry| Self::state_entry_to_proto(&request.key, entry))
+                    .collect(),
+                next_key: String::new(),
+            })),
+            Err(e) => Ok(Response::new(Self::state_entries_failure(format!(
+                "Error: {}",
+                e
+            )))),
+        }
+    }
+
+    /// Handles listing the current values of a key range
+    pub async fn handle_range_state(
+        &self,
+        request: RangeStateRequest,
+    ) -> Result<Response<StateEntriesResponse>, Status> {
+        let limit = Self::page_size(request.limit);
+
+        let graphs = self.graphs.read().await;
+        let graph = match graphs.get(&request.graph_id) {
+            Some(graph) => graph,
+            None => {
+                return Ok(Response::new(Self::state_entries_failure(format!(
+                    "Graph '{}' not found",
+                    request.graph_id
+                ))))
+            }
+        };
+        let mut range = graph
+            .state
+            .range(request.start_key.clone()..)
+            .take_while(|(key, _)| request.end_key.is_empty() || **key < request.end_key);
+        let entries: Vec<proto::StateEntry> = range
+            .by_ref()
+            .take(limit)
+            .map(|(key, entry)| Self::state_entry_to_proto(key, entry))
+            .collect();
+        let next_key = range.next().map(|(key, _)| key.clone()).unwrap_or_default();
+
+        Ok(Response::new(StateEntriesResponse {
+            success: true,
+            message: format!("Found {} key(s)", entries.len()),
+            entries,
+            next_key,
+        }))
+    }
+
+    /// Handles defining a payload index; existing blocks are indexed before it returns
+    pub async fn handle_create_index(
+        &self,
+        request: CreateIndexRequest,
+    ) -> Result<Response<IndexResponse>, Status> {
+        let index = PayloadIndex::new(&request.name, &request.path);
+        if let Err(message) = index.validate() {
+            return Ok(Response::new(Self::index_response(false, message, 0)));
+        }
+
+        // Commits wait while the backfill runs so no block is missed
+        let graphs = self.graphs.write().await;
+        if !graphs.contains_key(&request.graph_id) {
+            return Ok(Response::new(Self::index_response(
+                false,
+                format!("Graph '{}' not found", request.graph_id),
+                0,
+            )));
+        }
+        match self
+            .repository
+            .create_index(&request.graph_id, &index)
+            .await
+        {
+            Ok(indexed) => {
+                tracing::info!(
+                    "🗂️ Index '{}' on {} of '{}' created ({} block(s))",
+                    index.name,
+                    index.path,
+                    request.graph_id,
+                    indexed
+                );
+                Ok(Response::new(Self::index_response(
+                    true,
+                    format!("Index '{}' created", index.name),
+                    indexed,
+                )))
+            }
+            Err(e) => Ok(Response::new(Self::index_response(
+                false,
+                format!("Error: {}", e),
+                0,
+            ))),
+        }
+    }
+
+    /// Handles removing a payload index
+    pub async fn handle_drop_index(
+        &self,
+        request: DropIndexRequest,
+    ) -> Result<Response<IndexResponse>, Status> {
+        match self
+            .repository
+            .drop_index(&request.graph_id, &request.name)
+            .await
+        {
+            Ok(true) => Ok(Response::new(Self::index_response(
+                true,
+                format!("Index '{}' dropped", request.name),
+                0,
+            ))),
+            Ok(false) => Ok(Response::new(Self::index_response(
+                false,
+                format!("Index '{}' not found", request.name),
+                0,
+            ))),
+            Err(e) => Ok(Response::new(Self::index_response(
+                false,
+                format!("Error: {}", e),
+                0,
+            ))),
+        }
+    }
+
+    /// Handles listing the payload indexes of a graph
+    pub async fn handle_list_indexes(
+        &self,
+        request: ListIndexesRequest,
+    ) -> Result<Response<ListIndexesResponse>, Status> {
+        match self.repository.list_indexes(&request.graph_id).await {
+            Ok(indexes) => Ok(Response::new(ListIndexesResponse {
+                success: true,
+                message: format!("Found {} index(es)", indexes.len()),
+                indexes: indexes
+                    .into_iter()
+                    .map(|index| IndexDefinition {
+                        name: index.name,
+                        path: index.path,
+                    })
+                    .collect(),
+            })),
+            Err(e) => Ok(Response::new(ListIndexesResponse {
+                success: false,
+                message: format!("Error: {}", e),
+                indexes: vec![],
+            })),
+        }
+    }
+
+    /// Handles looking up blocks through a payload index
+    pub async fn handle_query_blocks(
+        &self,
+        request: QueryBlocksRequest,
+    ) -> Result<Response<QueryBlocksResponse>, Status> {
+        let failure = |message: String| {
+            Response::new(QueryBlocksResponse {
+                success: false,
+                message,
+                blocks: vec![],
+                next_page_token: String::new(),
+            })
+        };
+
+        let from_height = match request.page_token.as_str() {
+            "" => 0,
+            token => match token.parse::<u64>() {
+                Ok(height) => height,
+                Err(_) => return Ok(failure(format!("Invalid page token '{}'", token))),
+            },
+        };
+        let page_size = Self::page_size(request.page_size);
+
+        // One extra block tells whether there is a next page
+        let mut blocks = match self
+            .repository
+            .query_index(
+                &request.graph_id,
+                &request.index,
+                &request.value,
+                from_height,
+                page_size + 1,
+            )
+            .await
+        {
+            Ok(blocks) => blocks,
+            Err(e) => return Ok(failure(format!("Error: {}", e))),
+        };
+        let next_page_token = if blocks.len() > page_size {
+            blocks
+                .pop()
+                .map(|b| b.height.to_string())
+                .unwrap_or_default()
+        } else {
+            String::new()
+        };
+
+        Ok(Response::new(QueryBlocksResponse {
+            success: true,
+            message: format!("Found {} block(s)", blocks.len()),
+            blocks: blocks.iter().map(|b| self.block_to_proto(b)).collect(),
+            next_page_token,
+        }))
+    }
+
+    /// Streams main-chain blocks with `from <= timestamp <= to` (Unix
+    /// seconds, `to` 0 meaning no upper bound), ordered by timestamp
+    pub async fn handle_get_blocks_by_time(
+        &self,
+        request: GetBlocksByTimeRequest,
+    ) -> Result<Response<BlockStream>, Status> {
+        if !self.graphs.read().await.contains_key(&request.graph_id) {
+            return Err(Status::not_found(format!(
+                "Graph '{}' not found",
+                request.graph_id
+            )));
+        }
+        let to = if request.to == 0 {
+            i64::MAX
+        } else {
+            request.to
+        };
+        if request.from > to {
+            return Err(Status::invalid_argument("'from' must not be after 'to'"));
+        }
+
+        let heights = self
+            .repository
+            .get_heights_by_time(&request.graph_id, request.from, to)
+            .await
+            .map_err(|e| Status::internal(format!("Error: {}", e)))?;
+        tracing::info!(
+            "🕒 Streaming {} block(s) of '{}' between {} and {}",
+            heights.len(),
+            request.graph_id,
+            request.from,
+            to
+        );
+
+        // Blocks are loaded as the client reads, so large ranges stay cheap
+        let (tx, rx) = mpsc::channel(BLOCK_STREAM_BUFFER);
+        let repository = self.repository.clone();
+        tokio::spawn(async move {
+            for height in heights {
+                let message = match repository
+                    .get_block_by_height(&request.graph_id, height)
+                    .await
+                {
+                    Ok(Some(block)) => Ok(replication::block_to_proto(&block)),
+                    Ok(None) => continue,
+                    Err(e) => Err(Status::internal(format!("Error: {}", e))),
+                };
+                let failed = message.is_err();
+                if tx.send(message).await.is_err() || failed {
+                    return;
+                }
+            }
+        });
+
+        Ok(Response::new(
+            Box::pin(ReceiverStream::new(rx)) as BlockStream
+        ))
+    }
+
+    /// Handles getting the last main-chain block at or before an instant
+    pub async fn handle_get_block_at_time(
+        &self,
+        request: GetBlockAtTimeRequest,
+    ) -> Result<Response<GetBlockResponse>, Status> {
+        let (success, message, block) = match self
+            .repository
+            .get_block_at_time(&request.graph_id, request.at)
+            .await
+        {
+            Ok(Some(block)) => (
+                true,
+                "Block found".to_string(),
+                Some(self.block_to_proto(&block)),
+            ),
+            Ok(None) => (false, format!("No block at or before {}", request.at), None),
+            Err(e) => (false, format!("Error: {}", e), None),
+        };
+        Ok(Response::new(GetBlockResponse {
+            success,
+            message,
+            block,
+        }))
+    }
+
+    /// Handles enabling or disabling the full-text index of a graph
+    pub async fn handle_configure_search(
+        &self,
+        request: ConfigureSearchRequest,
+    ) -> Result<Response<IndexResponse>, Status> {
+        if !request.enabled {
+            return match self.repository.disable_search(&request.graph_id).await {
+                Ok(true) => {
+                    tracing::info!("🔎 Search disabled for '{}'", request.graph_id);
+                    Ok(Response::new(Self::index_response(
+                        true,
+                        "Search disabled".to_string(),
+                        0,
+                    )))
+                }
+                Ok(false) => Ok(Response::new(Self::index_response(
+                    false,
+                    format!("Search is not enabled for graph '{}'", request.graph_id),
+                    0,
+                ))),
+                Err(e) => Ok(Response::new(Self::index_response(
+                    false,
+                    format!("Error: {}", e),
+                    0,
+                ))),
+            };
+        }
+
+        // Commits wait while the backfill runs so no block is missed
+        let graphs = self.graphs.write().await;
+        if !graphs.contains_key(&request.graph_id) {
+            return Ok(Response::new(Self::index_response(
+                false,
+                format!("Graph '{}' not found", request.graph_id),
+                0,
+            )));
+        }
+        match self.repository.enable_search(&request.graph_id).await {
+            Ok(indexed) => {
+                tracing::info!(
+                    "🔎 Search enabled for '{}' ({} block(s))",
+                    request.graph_id,
+                    indexed
+                );
+                Ok(Response::new(Self::index_response(
+                    true,
+                    "Search enabled".to_string(),
+                    indexed,
+                )))
+            }
+            Err(e) => Ok(Response::new(Self::index_response(
+                false,
+                format!("Error: {}", e),
+                0,
+            ))),
+        }
+    }
+
+    /// Handles rebuilding the full-text index of a graph from its chain
+    pub async fn handle_rebuild_search_index(
+        &self,
+        request: RebuildSearchIndexRequest,
+    ) -> Result<Response<IndexResponse>, Status> {
+        let graphs = self.graphs.write().await;
+        if !graphs.contains_key(&request.graph_id) {
+            return Ok(Response::new(Self::index_response(
+                false,
+                format!("Graph '{}' not found", request.graph_id),
+                0,
+            )));
+        }
+        match self
+            .repository
+            .rebuild_search_index(&request.graph_id)
+            .await
+        {
+            Ok(indexed) => {
+                tracing::info!(
+                    "🔎 Search index of '{}' rebuilt ({} block(s))",
+                    request.graph_id,
+                    indexed
+                );
+                Ok(Response::new(Self::index_response(
+                    true,
+                    "Search index rebuilt".to_string(),
+                    indexed,
+                )))
+            }
+            Err(e) => Ok(Response::new(Self::index_response(
+                false,
+                format!("Error: {}", e),
+                0,
+            ))),
+        }
+    }
+
+    /// Handles a full-text search over one or more graphs
+    pub async fn handle_search_blocks(
+        &self,
+        request: SearchBlocksRequest,
+    ) -> Result<Response<SearchBlocksResponse>, Status> {
+        let failure = |message: String| {
+            Response::new(SearchBlocksResponse {
+                success: false,
+                message,
+                hits: vec![],
+                next_page_token: String::new(),
+            })
+        };
+
+        let query = match SearchQuery::parse(&request.query) {
+            Ok(query) => query,
+            Err(message) => return Ok(failure(message)),
+        };
+        let graph_ids = if request.graph_ids.is_empty() {
+            let mut ids: Vec<String> = self.graphs.read().await.keys().cloned().collect();
+            ids.sort();
+            let mut searchable = Vec::new();
+            for id in ids {
+                match self.repository.search_enabled(&id).await {
+                    Ok(true) => searchable.push(id),
+                    Ok(false) => {}
+                    Err(e) => return Ok(failure(format!("Error: {}", e))),
+                }
+            }
+            searchable
+        } else {
+            request.graph_ids
+        };
+
+        // Tokens are `{position in graph_ids}:{height}` of the next hit
+        let (mut position, mut from_height) = match request.page_token.as_str() {
+            "" => (0, 0),
+            token => match token
+                .split_once(':')
+                .and_then(|(p, h)| Some((p.parse().ok()?, h.parse().ok()?)))
+            {
+                Some(start) => start,
+                None => return Ok(failure(format!("Invalid page token '{}'", token))),
+            },
+        };
+        let page_size = Self::page_size(request.page_size);
+
+        // One extra hit tells whether there is a next page
+        let mut hits = Vec::new();
+        let mut next = None;
+        while position < graph_ids.len() {
+            let graph_id = &graph_ids[position];
+            let wanted = page_size + 1 - hits.len();
+            let blocks = match self
+                .repository
+                .search_blocks(graph_id, &query, from_height, wanted)
+                .await
+            {
+                Ok(blocks) => blocks,
+                Err(e) => return Ok(failure(format!("Error: {}", e))),
+            };
+            for block in blocks {
+                if hits.len() == page_size {
+                    next = Some(format!("{}:{}", position, block.height));
+                    break;
+                }
+                hits.push(SearchHit {
+                    graph_id: graph_id.clone(),
+                    block: Some(self.block_to_proto(&block)),
+                });
+            }
+            if next.is_some() {
+                break;
+            }
+            position += 1;
+            from_height = 0;
+        }
+
+        Ok(Response::new(SearchBlocksResponse {
+            success: true,
+            message: format!("Found {} block(s)", hits.len()),
+            hits,
+            next_page_token: next.unwrap_or_default(),
+        }))
+    }
+
+    /// Handles reading the current record of an asset
+    pub async fn handle_get_asset(
+        &self,
+        request: GetAssetRequest,
+    ) -> Result<Response<AssetResponse>, Status> {
+        let failure = |message: String| {
+            Response::new(AssetResponse {
+                success: false,
+                message,
+                asset: None,
+            })
+        };
+
+        let graphs = self.graphs.read().await;
+        let graph = match Self::graph_of_type(&graphs, &request.graph_id, GraphType::Asset) {
+            Ok(graph) => graph,
+            Err(message) => return Ok(failure(message)),
+        };
+        match graph
+            .state
+            .get(&asset_key(&request.asset_id))
+            .and_then(Self::asset_to_proto)
+        {
+            Some(asset) => Ok(Response::new(AssetResponse {
+                success: true,
+                message: format!("Asset '{}' at version {}", request.asset_id, asset.version),
+                asset: Some(asset),
+            })),
+            None => Ok(failure(format!("Asset '{}' not found", request.asset_id))),
+        }
+    }
+
+    /// Handles listing every version of an asset, oldest first
+    pub async fn handle_get_asset_history(
+        &self,
+        request: GetAssetRequest,
+    ) -> Result<Response<AssetHistoryResponse>, Status> {
+        let failure = |message: String| {
+            Response::new(AssetHistoryResponse {
+                success: false,
+                message,
+                versions: vec![],
+            })
+        };
+
+        if let Err(message) = Self::graph_of_type(
+            &*self.graphs.read().await,
+            &request.graph_id,
+            GraphType::Asset,
+        ) {
+            return Ok(failure(message));
+        }
+        let history = match self
+            .repository
+            .get_state_history(&request.graph_id, &asset_key(&request.asset_id))
+            .await
+        {
+            Ok(history) => history,
+            Err(e) => return Ok(failure(format!("Error: {}", e))),
+        };
+        let versions: Vec<proto::Asset> = history.iter().filter_map(Self::asset_to_proto).collect();
+        if versions.is_empty() {
+            return Ok(failure(format!("Asset '{}' not found", request.asset_id)));
+        }
+        Ok(Response::new(AssetHistoryResponse {
+            success: true,
+            message: format!("Found {} version(s)", versions.len()),
+            versions,
+        }))
+    }
+
+    /// Handles listing the assets an owner holds, from the ownership index
+    pub async fn handle_list_assets_by_owner(
+        &self,
+        request: ListAssetsByOwnerRequest,
+    ) -> Result<Response<ListAssetsResponse>, Status> {
+        let failure = |message: String| {
+            Response::new(ListAssetsResponse {
+                success: false,
+                message,
+                assets: vec![],
+                next_page_token: String::new(),
+            })
+        };
+
+        if let Err(message) = Self::graph_of_type(
+            &*self.graphs.read().await,
+            &request.graph_id,
+            GraphType::Asset,
+        ) {
+            return Ok(failure(message));
+        }
+        let page_size = Self::page_size(request.page_size);
+
+        // Tokens are the id of the next asset; one extra id tells whether there is a next page
+        let mut asset_ids = match self
+            .repository
+            .list_assets_by_owner(
+                &request.graph_id,
+                &request.owner,
+                &request.page_token,
+                page_size + 1,
+            )
+            .await
+        {
+            Ok(asset_ids) => asset_ids,
+            Err(e) => return Ok(failure(format!("Error: {}", e))),
+        };
+        let next_page_token = if asset_ids.len() > page_size {
+            asset_ids.pop().unwrap_or_default()
+        } else {
+            String::new()
+        };
+
+        let graphs = self.graphs.read().await;
+        let assets: Vec<proto::Asset> = match graphs.get(&request.graph_id) {
+            Some(graph) => asset_ids
+                .iter()
+                .filter_map(|asset_id| graph.state.get(&asset_key(asset_id)))
+                .filter_map(Self::asset_to_proto)
+                .collect(),
+            None => vec![],
+        };
+        Ok(Response::new(ListAssetsResponse {
+            success: true,
+            message: format!("Found {} asset(s)", assets.len()),
+            assets,
+            next_page_token,
+        }))
+    }
+
+    /// Handles resolving the identity document of a subject at a height
+    pub async fn handle_resolve_identity(
+        &self,
+        request: ResolveIdentityRequest,
+    ) -> Result<Response<IdentityResponse>, Status> {
+        let failure = |message: String| {
+            Response::new(IdentityResponse {
+                success: false,
+                message,
+                document: None,
+            })
+        };
+
+        if let Err(message) = Self::graph_of_type(
+            &*self.graphs.read().await,
+            &request.graph_id,
+            GraphType::Identity,
+        ) {
+            return Ok(failure(message));
+        }
+        let history = match self
+            .repository
+            .get_state_history(&request.graph_id, &identity_key(&request.subject_id))
+            .await
+        {
+            Ok(history) => history,
+            Err(e) => return Ok(failure(format!("Error: {}", e))),
+        };
+
+        // The version in effect at a height is the last one written at or below it
+        let height = match request.height {
+            0 => u64::MAX,
+            height => height,
+        };
+        let entry = history.iter().rev().find(|entry| entry.version <= height);
+        match entry.and_then(Self::identity_to_proto) {
+            Some(document) => Ok(Response::new(IdentityResponse {
+                success: true,
+                message: format!(
+                    "Identity '{}' at version {}",
+                    request.subject_id, document.version
+                ),
+                document: Some(document),
+            })),
+            None => Ok(failure(format!(
+                "Identity '{}' is not registered at height {}",
+                request.subject_id, request.height
+            ))),
+        }
+    }
+
+    fn graph_of_type<'a>(
+        graphs: &'a HashMap<String, BlockchainGraph>,
+        graph_id: &str,
+        graph_type: GraphType,
+    ) -> Result<&'a BlockchainGraph, String> {
+        match graphs.get(graph_id) {
+            Some(graph) if graph.graph_type == graph_type => Ok(graph),
+            Some(_) => {
+                let type_name = format!("{:?}", graph_type).to_lowercase();
+                Err(format!(
+                    "Graph '{}' is not an {} graph",
+                    graph_id, type_name
+                ))
+            }
+            None => Err(format!("Graph '{}' not found", graph_id)),
+        }
+    }
+
+    fn identity_to_proto(entry: &StateEntry) -> Option<IdentityDocument> {
+        let record = IdentityRecord::from_bytes(&entry.value)?;
+        let status = match record.status {
+            crate::domain::IdentityStatus::Active => proto::IdentityStatus::IdentityActive,
+            crate::domain::IdentityStatus::Revoked => proto::IdentityStatus::IdentityRevoked,
+        };
+        Some(IdentityDocument {
+            subject_id: record.subject_id,
+            public_keys: record.public_keys,
+            attributes_hash: record.attributes_hash,
+            status: status as i32,
+            operation: record.operation,
+            version: entry.version,
+            block_hash: entry.block_hash.clone(),
+        })
+    }
+
+    fn asset_to_proto(entry: &StateEntry) -> Option<proto::Asset> {
+        let record = AssetRecord::from_bytes(&entry.value)?;
+        Some(proto::Asset {
+            asset_id: record.asset_id,
+            owner: record.owner,
+            metadata: serde_json::Value::Object(record.metadata).to_string(),
+            burned: record.burned,
+            operation: record.operation,
+            version: entry.version,
+            block_hash: entry.block_hash.clone(),
+        })
+    }
+
+    fn index_response(success: bool, message: String, blocks_indexed: u64) -> IndexResponse {
+        IndexResponse {
+            success,
+            message,
+            blocks_indexed,
+        }
+    }
+
+    fn page_size(requested: u32) -> usize {
+        match requested {
+            0 => DEFAULT_PAGE_SIZE,
+            size => (size as usize).min(MAX_PAGE_SIZE),
+        }
+    }
+
+    fn state_entry_to_proto(key: &str, entry: &StateEntry) -> proto::StateEntry {
+        proto::StateEntry {
+            key: key.to_string(),
+            value: entry.value.clone(),
+            version: entry.version,
+            block_hash: entry.block_hash.clone(),
+            deleted: entry.deleted,
+        }
+    }
+
+    fn state_entries_failure(message: String) -> StateEntriesResponse {
+        StateEntriesResponse {
+            success: false,
+            message,
+            entries: vec![],
+            next_key: String::new(),
+        }
+    }
+
+    /// Policy that must approve `proposal` at the graph's current tip, None
+    /// once its block no longer needs endorsement
+    async fn approval_policy(
+        &self,
+        proposal: &Proposal,
+    ) -> Result<Option<EndorsementPolicy>, String> {
+        match self.graphs.read().await.get(&proposal.graph_id) {
+            Some(graph) => {
+                graph.approval_policy(graph.current_authorities().as_ref(), &proposal.data)
+            }
+            None => Err(format!("Graph '{}' not found", proposal.graph_id)),
+        }
+    }
+
+    /// Loads a proposal with the policy that must approve it, recording an
+    /// expiry that is due
+    async fn load_proposal(
+        &self,
+        graph_id: &str,
+        proposal_id: &str,
+    ) -> Result<(Proposal, Option<EndorsementPolicy>), String> {
+        if !self.graphs.read().await.contains_key(graph_id) {
+            return Err(format!("Graph '{}' not found", graph_id));
+        }
+        let mut proposal = self
+            .repository
+            .get_proposal(graph_id, proposal_id)
+            .await
+            .map_err(|e| format!("Error: {}", e))?
+            .ok_or_else(|| format!("Proposal '{}' not found", proposal_id))?;
+        if proposal.expire_if_due(chrono::Utc::now().timestamp_millis()) {
+            tracing::info!("⌛ Proposal {} for '{}' expired", proposal.id, graph_id);
+            self.store_expiry(&proposal).await?;
+        }
+        let policy = self.approval_policy(&proposal).await?;
+        Ok((proposal, policy))
+    }
+
+    /// Stores a new or updated proposal. Clustered, the leader orders it
+    /// through Raft so every member keeps the same copy.
+    async fn store_proposal(&self, proposal: &Proposal) -> Result<(), String> {
+        match &self.cluster {
+            Some(cluster) => {
+                let command = ClusterCommand::SaveProposal(Box::new(proposal.clone())).encode()?;
+                cluster
+                    .propose(command)
+                    .await
+                    .map_err(|e| format!("Failed to store proposal: {}", e))
+            }
+            None => self
+                .repository
+                .save_proposal(proposal)
+                .await
+                .map_err(|e| format!("Failed to store proposal: {}", e)),
+        }
+    }
+
+    /// Records an expiry noticed on read. Other members only report it;
+    /// expiry follows from the deadline, and the leader stores it.
+    async fn store_expiry(&self, proposal: &Proposal) -> Result<(), String> {
+        if self.cluster.as_ref().is_some_and(|c| !c.is_leader()) {
+            return Ok(());
+        }
+        self.store_proposal(proposal).await
+    }
+
+    fn proposal_failure(message: String) -> ProposalResponse {
+        ProposalResponse {
+            success: false,
+            message,
+            proposal: None,
+            block: None,
+        }
+    }
+
+    fn proposal_to_proto(
+        proposal: &Proposal,
+        policy: Option<&EndorsementPolicy>,
+    ) -> proto::Proposal {
+        proto::Proposal {
+            proposal_id: proposal.id.clone(),
+            graph_id: proposal.graph_id.clone(),
+            data: proposal.data.clone(),
+            cross_references: proposal.cross_references.clone(),
+            created_at_ms: proposal.created_at_ms,
+            expires_at_ms: proposal.expires_at_ms,
+            endorsed_by: proposal.endorsements.keys().cloned().collect(),
+            required: policy.map_or(0, |policy| policy.required as u32),
+            status: proposal.status.to_i32(),
+            block_hash: proposal.block_hash.clone(),
+            message: proposal.message.clone(),
+            nonce: proposal.nonce.clone(),
+        }
+    }
+
+    fn receipt_to_proto(receipt: Receipt) -> proto::Receipt {
+        proto::Receipt {
+            graph_id: receipt.graph_id,
+            height: receipt.height,
+            block_hash: receipt.block_hash,
+            previous_hash: receipt.previous_hash,
+            timestamp: receipt.timestamp,
+            anchor: receipt.anchor.map(|anchor| proto::ReceiptAnchor {
+                graph_id: anchor.graph_id,
+                height: anchor.height,
+                hash: anchor.hash,
+                anchored_height: anchor.anchored_height,
+                anchored_hash: anchor.anchored_hash,
+            }),
+            signer: receipt.signer,
+            signature: receipt.signature,
+        }
+    }
+
+    /// Error message for writes sent to a follower
+    fn read_only_message(&self) -> Option<String> {
+        self.replication.leader_address().map(|leader| {
+            format!(
+                "Node is a read-only follower; send writes to the leader at {}",
+                leader
+            )
+        })
+    }
+
+    /// Converts domain Block to proto Block
+    fn block_to_proto(&self, block: &Block) -> crate::infrastructure::grpc::blockchain::Block {
+        replication::block_to_proto(block)
     }
 }
@@ -0,0 +1,252 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+use tokio::sync::{broadcast, RwLock};
+
+use super::replication::ChainEvent;
+use crate::domain::traits::{BlockchainRepository, ContractRuntime};
+use crate::domain::world_state::{commit_changes, state_diff};
+use crate::domain::{Block, BlockOutcome, BlockchainGraph, Proposal, StateSwitch};
+
+/// Why a graph or block was not applied
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ApplyError {
+    /// Invalid graph or block: every node rejects it the same way
+    Rejected(String),
+    /// Storage failed on this node; applying it again may succeed
+    Storage(String),
+}
+
+impl fmt::Display for ApplyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ApplyError::Rejected(message) | ApplyError::Storage(message) => f.write_str(message),
+        }
+    }
+}
+
+impl From<ApplyError> for String {
+    fn from(error: ApplyError) -> Self {
+        error.to_string()
+    }
+}
+
+/// Applies graphs and blocks that were ordered elsewhere (a replication
+/// leader or the Raft log) to the in-memory cache and to storage.
+/// Blocks are verified with `BlockchainGraph::accept_block`, their state
+/// changes are recomputed (read/write sets and the graph's contract) and
+/// applying the same change twice is a no-op.
+#[derive(Clone)]
+pub struct ChainApplier {
+    repository: Arc<dyn BlockchainRepository>,
+    graphs: Arc<RwLock<HashMap<String, BlockchainGraph>>>,
+    events: broadcast::Sender<ChainEvent>,
+    contracts: Arc<dyn ContractRuntime>,
+}
+
+impl ChainApplier {
+    pub fn new(
+        repository: Arc<dyn BlockchainRepository>,
+        graphs: Arc<RwLock<HashMap<String, BlockchainGraph>>>,
+        events: broadcast::Sender<ChainEvent>,
+        contracts: Arc<dyn ContractRuntime>,
+    ) -> Self {
+        Self {
+            repository,
+            graphs,
+            events,
+            contracts,
+        }
+    }
+
+    /// Creates a graph from its metadata and genesis block
+    pub async fn apply_graph(
+        &self,
+        mut graph: BlockchainGraph,
+        genesis: Block,
+    ) -> Result<(), ApplyError> {
+        let mut graphs = self.graphs.write().await;
+        if let Some(existing) = graphs.get(&graph.id) {
+            return match existing.chain.first() {
+                Some(local) if local.hash == genesis.hash => Ok(()),
+                _ => Err(ApplyError::Rejected(format!(
+                    "Graph '{}' already exists with a different genesis block",
+                    graph.id
+                ))),
+            };
+        }
+
+        graph.load_blocks(Vec::new());
+        graph
+            .append_block(genesis.clone())
+            .map_err(ApplyError::Rejected)?;
+
+        self.repository
+            .save_block(&graph.id, &genesis)
+            .await
+            .map_err(|e| ApplyError::Storage(format!("Failed to persist genesis block: {}", e)))?;
+        self.repository
+            .save_graph(&graph)
+            .await
+            .map_err(|e| ApplyError::Storage(format!("Failed to save graph: {}", e)))?;
+
+        let _ = self.events.send(ChainEvent::GraphCreated(graph.clone()));
+        graphs.insert(graph.id.clone(), graph);
+        Ok(())
+    }
+
+    /// Stores a new or updated proposal of an endorsed graph
+    pub async fn apply_proposal(&self, proposal: &Proposal) -> Result<(), ApplyError> {
+        self.repository
+            .save_proposal(proposal)
+            .await
+            .map_err(|e| ApplyError::Storage(format!("Failed to store proposal: {}", e)))
+    }
+
+    /// Applies a mined block to its graph: it extends the main chain or a
+    /// side branch, and a branch with more cumulative work replaces the main
+    /// chain (the switch is persisted atomically and published as a reorg)
+    pub async fn apply_block(&self, block: Block) -> Result<(), ApplyError> {
+        let mut graphs = self.graphs.write().await;
+        let (graph_id, height) = (block.graph_id.clone(), block.height);
+        let graph = graphs
+            .get(&graph_id)
+            .ok_or_else(|| ApplyError::Rejected(format!("Graph '{}' not found", graph_id)))?;
+
+        // Payment and signer references, checked against the chain the block builds on
+        if !graph.knows_block(&block.hash) {
+            let parent = graph
+                .chain
+                .iter()
+                .position(|b| b.hash == block.previous_hash);
+            let chain = parent.map_or(&graph.chain[..], |parent| &graph.chain[..=parent]);
+            graph
+                .check_references(chain, &block, &graphs)
+                .map_err(|e| {
+                    ApplyError::Rejected(format!(
+                        "Rejected block #{} of graph '{}': {}",
+                        height, graph_id, e
+                    ))
+                })?;
+        }
+        let graph = graphs
+            .get_mut(&graph_id)
+            .expect("looked up above under the same lock");
+
+        let outcome = graph.accept_block(block.clone()).map_err(|e| {
+            ApplyError::Rejected(format!(
+                "Rejected block #{} of graph '{}': {}",
+                height, graph_id, e
+            ))
+        })?;
+
+        match outcome {
+            BlockOutcome::Duplicate => return Ok(()),
+            BlockOutcome::Extended => {
+                let parent = graph.chain.len() - 1;
+                let changes = match graph.block_state_changes(
+                    &graph.chain[..parent],
+                    graph.state.clone(),
+                    &block,
+                    self.contracts.as_ref(),
+                ) {
+                    Ok(changes) => changes,
+                    Err(e) => {
+                        graph.chain.pop();
+                        return Err(ApplyError::Rejected(format!(
+                            "Rejected block #{} of graph '{}': {}",
+                            height, graph_id, e
+                        )));
+                    }
+                };
+                let writes = commit_changes(&changes, &block);
+                if let Err(e) = self
+                    .repository
+                    .save_block_with_state(&graph_id, &block, &writes)
+                    .await
+                {
+                    graph.chain.pop();
+                    return Err(ApplyError::Storage(format!(
+                        "Failed to persist block: {}",
+                        e
+                    )));
+                }
+                graph.apply_state_writes(&writes);
+                let _ = self.events.send(ChainEvent::BlockCommitted(block));
+            }
+            BlockOutcome::SideBranch => {
+                if let Err(e) = self.repository.save_side_block(&graph_id, &block).await {
+                    graph.side_blocks.remove(&block.hash);
+                    return Err(ApplyError::Storage(format!(
+                        "Failed to persist side block: {}",
+                        e
+                    )));
+                }
+                tracing::info!(
+                    "🌿 Block #{} of graph '{}' stored on a side branch",
+                    height,
+                    graph_id
+                );
+            }
+            BlockOutcome::Reorganized(reorg) => {
+                // The world state of the new branch is rebuilt by replaying it
+                let (state, mut writes) = match graph.replay_state(self.contracts.as_ref()) {
+                    Ok(replayed) => replayed,
+                    Err(e) => {
+                        graph.revert_reorg(&reorg);
+                        graph.side_blocks.remove(&block.hash);
+                        return Err(ApplyError::Rejected(format!(
+                            "Rejected branch of graph '{}': {}",
+                            graph_id, e
+                        )));
+                    }
+                };
+                let switch = StateSwitch {
+                    diff: state_diff(&graph.state, &state),
+                    connected: writes.split_off(reorg.fork_height as usize),
+                };
+                if let Err(e) = self.repository.reorganize(&graph_id, &reorg, &switch).await {
+                    graph.revert_reorg(&reorg);
+                    graph.side_blocks.remove(&block.hash);
+                    return Err(ApplyError::Storage(format!(
+                        "Failed to persist reorganization: {}",
+                        e
+                    )));
+                }
+                graph.state = Arc::new(state);
+                tracing::warn!(
+                    "🔀 Graph '{}' reorganized at height {}: {} block(s) replaced by {}",
+                    graph_id,
+                    reorg.fork_height,
+                    reorg.disconnected.len(),
+                    reorg.connected.len()
+                );
+                let _ = self.events.send(ChainEvent::Reorganized {
+                    graph_id: graph_id.clone(),
+                    reorg,
+                });
+            }
+        }
+
+        let pruned = graph.prune_side_blocks();
+        if !pruned.is_empty() {
+            if let Err(e) = self.repository.delete_side_blocks(&graph_id, &pruned).await {
+                tracing::warn!(
+                    "⚠️  Failed to delete pruned side blocks of '{}': {}",
+                    graph_id,
+                    e
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns true if the block is on the main chain or a side branch of its graph
+    pub async fn knows_block(&self, graph_id: &str, hash: &str) -> bool {
+        self.graphs
+            .read()
+            .await
+            .get(graph_id)
+            .is_some_and(|g| g.knows_block(hash))
+    }
+}
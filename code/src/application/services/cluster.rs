@@ -0,0 +1,74 @@
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+use super::chain_applier::{ApplyError, ChainApplier};
+use super::raft::RaftStateMachine;
+use crate::domain::{Block, BlockchainGraph, Proposal};
+
+/// A write ordered through the Raft log. Blocks are mined by the leader
+/// before proposing, so every member applies identical blocks; proposals
+/// of endorsed graphs are stored on every member the same way.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum ClusterCommand {
+    CreateGraph {
+        graph: Box<BlockchainGraph>,
+        genesis: Block,
+    },
+    AddBlock(Block),
+    SaveProposal(Box<Proposal>),
+}
+
+impl ClusterCommand {
+    pub fn encode(&self) -> Result<Vec<u8>, String> {
+        bincode::serialize(self).map_err(|e| format!("Failed to encode command: {}", e))
+    }
+
+    pub fn decode(bytes: &[u8]) -> Result<Self, String> {
+        bincode::deserialize(bytes).map_err(|e| format!("Failed to decode command: {}", e))
+    }
+}
+
+#[async_trait]
+impl RaftStateMachine for ChainApplier {
+    async fn apply(&self, command: &[u8]) -> Result<(), ApplyError> {
+        match ClusterCommand::decode(command).map_err(ApplyError::Rejected)? {
+            ClusterCommand::CreateGraph { graph, genesis } => {
+                self.apply_graph(*graph, genesis).await
+            }
+            ClusterCommand::AddBlock(block) => self.apply_block(block).await,
+            ClusterCommand::SaveProposal(proposal) => self.apply_proposal(&proposal).await,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::GraphType;
+
+    #[test]
+    fn test_command_round_trip_keeps_genesis() {
+        let graph = BlockchainGraph::new(
+            "payments".to_string(),
+            GraphType::Asset,
+            "Test".to_string(),
+            1,
+        );
+        let genesis = graph.chain[0].clone();
+        let command = ClusterCommand::CreateGraph {
+            graph: Box::new(graph),
+            genesis: genesis.clone(),
+        };
+
+        match ClusterCommand::decode(&command.encode().unwrap()).unwrap() {
+            ClusterCommand::CreateGraph {
+                graph,
+                genesis: decoded,
+            } => {
+                assert_eq!(graph.graph_type, GraphType::Asset);
+                assert_eq!(decoded, genesis);
+            }
+            other => panic!("unexpected command {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,228 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::sync::Mutex;
+
+use tokio::sync::Notify;
+
+use super::replication::{
+    consensus_from_proto, consensus_to_proto, policy_from_proto, policy_to_proto,
+    schema_from_proto, schema_to_proto,
+};
+use crate::config::settings::{MAX_DIFFICULTY, MIN_DIFFICULTY};
+use crate::domain::{BlockchainGraph, GraphType};
+use crate::infrastructure::grpc::blockchain::{GossipPeer, GraphTip};
+
+/// What this node knows about one gossip peer
+#[derive(Debug, Clone, Default)]
+pub struct PeerProgress {
+    pub reachable: bool,
+    /// Unix timestamp in milliseconds of the last successful exchange (0 = never)
+    pub last_seen_ms: i64,
+    pub blocks_received: u64,
+    pub last_error: String,
+}
+
+#[derive(Debug, Default)]
+struct GossipInner {
+    peers: BTreeMap<String, PeerProgress>,
+    /// Peers that announced tips ahead of ours and should be pulled from
+    pending: BTreeSet<String>,
+}
+
+/// Peer-to-peer gossip state: progress per static peer and pull requests
+/// raised by incoming announcements
+#[derive(Debug, Default)]
+pub struct GossipState {
+    enabled: bool,
+    inner: Mutex<GossipInner>,
+    wake: Notify,
+}
+
+impl GossipState {
+    pub fn disabled() -> Self {
+        Self::default()
+    }
+
+    pub fn new(peers: &[String]) -> Self {
+        let peers = peers
+            .iter()
+            .map(|p| (p.clone(), PeerProgress::default()))
+            .collect();
+        Self {
+            enabled: true,
+            inner: Mutex::new(GossipInner {
+                peers,
+                pending: BTreeSet::new(),
+            }),
+            wake: Notify::new(),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Whether `address` is one of the configured peers
+    pub fn is_peer(&self, address: &str) -> bool {
+        self.enabled && self.lock().peers.contains_key(address)
+    }
+
+    /// Asks the gossip task to pull from `peer` as soon as possible
+    pub fn request_sync(&self, peer: &str) {
+        self.lock().pending.insert(peer.to_string());
+        self.wake.notify_one();
+    }
+
+    /// Waits until at least one pull has been requested
+    pub async fn sync_requested(&self) {
+        self.wake.notified().await;
+    }
+
+    /// Returns and clears the peers waiting to be pulled from
+    pub fn take_sync_requests(&self) -> Vec<String> {
+        std::mem::take(&mut self.lock().pending)
+            .into_iter()
+            .collect()
+    }
+
+    pub fn record_success(&self, peer: &str, blocks_received: u64) {
+        let mut inner = self.lock();
+        let progress = inner.peers.entry(peer.to_string()).or_default();
+        progress.reachable = true;
+        progress.last_seen_ms = chrono::Utc::now().timestamp_millis();
+        progress.blocks_received += blocks_received;
+        progress.last_error.clear();
+    }
+
+    pub fn record_failure(&self, peer: &str, error: String) {
+        let mut inner = self.lock();
+        let progress = inner.peers.entry(peer.to_string()).or_default();
+        progress.reachable = false;
+        progress.last_error = error;
+    }
+
+    pub fn peers(&self) -> Vec<GossipPeer> {
+        self.lock()
+            .peers
+            .iter()
+            .map(|(address, p)| GossipPeer {
+                address: address.clone(),
+                reachable: p.reachable,
+                last_seen_ms: p.last_seen_ms,
+                blocks_received: p.blocks_received,
+                last_error: p.last_error.clone(),
+            })
+            .collect()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, GossipInner> {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Tip of a local graph as announced to peers
+pub fn graph_tip(graph: &BlockchainGraph) -> Option<GraphTip> {
+    graph.get_latest_block().map(|block| GraphTip {
+        graph_id: graph.id.clone(),
+        graph_type: graph.graph_type.to_i32(),
+        description: graph.description.clone(),
+        created_at: graph.created_at,
+        difficulty: graph.difficulty as u64,
+        height: block.height,
+        hash: block.hash.clone(),
+        consensus: Some(consensus_to_proto(&graph.consensus, None)),
+        endorsement: graph.endorsement.as_ref().map(policy_to_proto),
+        schema: schema_to_proto(graph.schema.as_ref()),
+        governance: graph.governance.as_ref().map(policy_to_proto),
+    })
+}
+
+/// Graph metadata from a peer's tip; its chain is filled from genesis.
+/// Fails when the peer's difficulty is outside the range this node accepts.
+pub fn graph_from_tip(tip: &GraphTip) -> Result<BlockchainGraph, String> {
+    let difficulty = tip.difficulty as usize;
+    if !(MIN_DIFFICULTY..=MAX_DIFFICULTY).contains(&difficulty) {
+        return Err(format!(
+            "Peer announced graph '{}' with difficulty {}, expected {} to {}",
+            tip.graph_id, tip.difficulty, MIN_DIFFICULTY, MAX_DIFFICULTY
+        ));
+    }
+
+    Ok(BlockchainGraph {
+        id: tip.graph_id.clone(),
+        graph_type: GraphType::from_i32(tip.graph_type),
+        description: tip.description.clone(),
+        created_at: tip.created_at,
+        difficulty,
+        consensus: consensus_from_proto(tip.consensus.clone()),
+        endorsement: tip.endorsement.clone().map(policy_from_proto),
+        governance: tip.governance.clone().map(policy_from_proto),
+        schema: schema_from_proto(&tip.schema),
+        chain: Vec::new(),
+        side_blocks: HashMap::new(),
+        state: Default::default(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sync_requests_are_deduplicated() {
+        let state = GossipState::new(&["127.0.0.1:50052".to_string()]);
+        state.request_sync("127.0.0.1:50053");
+        state.request_sync("127.0.0.1:50053");
+        assert_eq!(
+            state.take_sync_requests(),
+            vec!["127.0.0.1:50053".to_string()]
+        );
+        assert!(state.take_sync_requests().is_empty());
+
+        state.record_failure("127.0.0.1:50052", "connection refused".to_string());
+        state.record_success("127.0.0.1:50053", 4);
+        let peers = state.peers();
+        assert_eq!(peers.len(), 2);
+        assert!(!peers[0].reachable);
+        assert_eq!(peers[1].blocks_received, 4);
+    }
+
+    #[test]
+    fn test_only_configured_addresses_are_peers() {
+        let state = GossipState::new(&["127.0.0.1:50052".to_string()]);
+        assert!(state.is_peer("127.0.0.1:50052"));
+        assert!(!state.is_peer("10.0.0.9:50052"));
+        assert!(!GossipState::disabled().is_peer("127.0.0.1:50052"));
+    }
+
+    #[test]
+    fn test_graph_round_trips_through_tip() {
+        let graph = BlockchainGraph::new(
+            "people".to_string(),
+            GraphType::Identity,
+            "Test".to_string(),
+            3,
+        );
+        let tip = graph_tip(&graph).unwrap();
+        assert_eq!(tip.height, 0);
+
+        let rebuilt = graph_from_tip(&tip).unwrap();
+        assert_eq!(rebuilt.graph_type, GraphType::Identity);
+        assert_eq!(rebuilt.difficulty, 3);
+        assert_eq!(rebuilt.created_at, graph.created_at);
+    }
+
+    #[test]
+    fn test_tip_with_out_of_range_difficulty_is_refused() {
+        let graph = BlockchainGraph::new(
+            "people".to_string(),
+            GraphType::Identity,
+            "Test".to_string(),
+            3,
+        );
+        let mut tip = graph_tip(&graph).unwrap();
+        tip.difficulty = 0;
+        assert!(graph_from_tip(&tip).is_err());
+        tip.difficulty = (MAX_DIFFICULTY + 1) as u64;
+        assert!(graph_from_tip(&tip).is_err());
+    }
+}
@@ -1,5 +1,21 @@
+pub mod audit;
 pub mod blockchain_service;
+pub mod chain_applier;
+pub mod cluster;
+pub mod gossip;
+pub mod raft;
+pub mod replication;
+pub mod scrubber;
+pub mod shutdown_coordinator;
 pub mod validation_service;
 
+pub use audit::{AuditEvent, AuditLog, AuditOutcome};
 pub use blockchain_service::BlockchainServiceImpl;
+pub use chain_applier::{ApplyError, ChainApplier};
+pub use cluster::ClusterCommand;
+pub use gossip::GossipState;
+pub use raft::{RaftConfig, RaftNode};
+pub use replication::{ChainEvent, ReplicationState};
+pub use scrubber::{IntegrityScrubber, ScrubReport};
+pub use shutdown_coordinator::ShutdownCoordinator;
 pub use validation_service::ValidationService;
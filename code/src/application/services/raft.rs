@@ -0,0 +1,1075 @@
+use async_trait::async_trait;
+use rand::Rng;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+use tokio::sync::{oneshot, Notify};
+use tokio::task::JoinHandle;
+
+use super::chain_applier::ApplyError;
+use crate::infrastructure::grpc::blockchain::{
+    RaftAppendRequest, RaftAppendResponse, RaftEntry, RaftVoteRequest, RaftVoteResponse,
+};
+use crate::infrastructure::persistence::raft_store::{
+    RaftCompaction, RaftHardState, RaftLogEntry, RaftStore,
+};
+
+/// How often timers are checked
+const TICK: Duration = Duration::from_millis(10);
+/// Most entries sent in one AppendEntries call
+const MAX_ENTRIES_PER_APPEND: usize = 64;
+/// How long a proposal waits to be committed and applied
+const PROPOSAL_TIMEOUT: Duration = Duration::from_secs(10);
+/// Pause before applying an entry again after a storage error
+const APPLY_RETRY_DELAY: Duration = Duration::from_secs(1);
+
+/// Static Raft membership and timing for one node
+#[derive(Debug, Clone)]
+pub struct RaftConfig {
+    pub node_id: u64,
+    /// Other members of the cluster: node id -> cluster listener address
+    pub peers: HashMap<u64, String>,
+    pub election_timeout_min: Duration,
+    pub election_timeout_max: Duration,
+    pub heartbeat_interval: Duration,
+    /// Applied entries kept in the log for peers that fall behind; older
+    /// ones are dropped since the chains already hold their effect
+    pub retained_log_entries: u64,
+}
+
+/// Sends Raft RPCs to other members
+#[async_trait]
+pub trait RaftTransport: Send + Sync {
+    async fn request_vote(
+        &self,
+        peer: u64,
+        request: RaftVoteRequest,
+    ) -> Result<RaftVoteResponse, String>;
+    async fn append_entries(
+        &self,
+        peer: u64,
+        request: RaftAppendRequest,
+    ) -> Result<RaftAppendResponse, String>;
+}
+
+/// Applies committed commands, in log order, on every member.
+/// Must be deterministic and idempotent (entries can be re-applied after a crash).
+/// A `Rejected` command is skipped on every member alike; a `Storage` error
+/// is retried until the entry applies, so no member skips a committed entry.
+#[async_trait]
+pub trait RaftStateMachine: Send + Sync {
+    async fn apply(&self, command: &[u8]) -> Result<(), ApplyError>;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RaftRole {
+    Follower,
+    Candidate,
+    Leader,
+}
+
+impl RaftRole {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RaftRole::Follower => "follower",
+            RaftRole::Candidate => "candidate",
+            RaftRole::Leader => "leader",
+        }
+    }
+}
+
+/// Why a proposal was not committed
+#[derive(Debug, Clone, PartialEq)]
+pub enum ProposeError {
+    /// This node is not the leader; carries the leader id if known
+    NotLeader(Option<u64>),
+    Rejected(String),
+}
+
+impl fmt::Display for ProposeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProposeError::NotLeader(Some(id)) => {
+                write!(f, "Not the leader (leader is node {})", id)
+            }
+            ProposeError::NotLeader(None) => write!(f, "No leader elected yet"),
+            ProposeError::Rejected(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+/// Point-in-time view of a node's Raft state
+#[derive(Debug, Clone)]
+pub struct RaftStatus {
+    pub node_id: u64,
+    pub role: RaftRole,
+    pub term: u64,
+    pub leader_id: Option<u64>,
+    pub last_log_index: u64,
+    pub commit_index: u64,
+    pub last_applied: u64,
+    /// Replication progress per peer (leader only)
+    pub match_index: HashMap<u64, u64>,
+}
+
+type Waiter = (u64, oneshot::Sender<Result<(), String>>);
+
+struct RaftState {
+    hard: RaftHardState,
+    /// Last entry dropped from the front of the log
+    compacted: RaftCompaction,
+    /// Entry `i` has Raft index `compacted.index + i + 1`
+    log: Vec<RaftLogEntry>,
+    role: RaftRole,
+    leader_id: Option<u64>,
+    commit_index: u64,
+    last_applied: u64,
+    next_index: HashMap<u64, u64>,
+    match_index: HashMap<u64, u64>,
+    votes: HashSet<u64>,
+    election_deadline: Instant,
+    heartbeat_due: Instant,
+    /// Index of the current leader's no-op; writes wait until it is applied
+    ready_index: u64,
+    /// Proposals waiting for their index to be applied, with the term they were proposed in
+    waiters: HashMap<u64, Waiter>,
+    /// Peers with an AppendEntries call in progress
+    in_flight: HashSet<u64>,
+    /// Peers that need entries already compacted away (leader only)
+    behind_compaction: HashSet<u64>,
+}
+
+impl RaftState {
+    fn last_index(&self) -> u64 {
+        self.compacted.index + self.log.len() as u64
+    }
+
+    /// Term of the entry at `index`; 0 when unknown, including entries
+    /// compacted before the last one dropped
+    fn term_at(&self, index: u64) -> u64 {
+        if index == self.compacted.index {
+            return self.compacted.term;
+        }
+        self.entry(index).map(|e| e.term).unwrap_or(0)
+    }
+
+    fn entry(&self, index: u64) -> Option<&RaftLogEntry> {
+        let offset = index.checked_sub(self.compacted.index + 1)?;
+        self.log.get(offset as usize)
+    }
+
+    /// Position in `log` of the entry at `index`, which must not be compacted
+    fn offset(&self, index: u64) -> usize {
+        (index - self.compacted.index - 1) as usize
+    }
+}
+
+/// A Raft consensus node: leader election, log replication and ordered
+/// application of committed commands to a state machine
+pub struct RaftNode {
+    config: RaftConfig,
+    store: RaftStore,
+    transport: Arc<dyn RaftTransport>,
+    machine: Arc<dyn RaftStateMachine>,
+    state: Mutex<RaftState>,
+    commit_notify: Notify,
+    replicate_notify: Notify,
+    stopped: AtomicBool,
+}
+
+impl RaftNode {
+    /// Restores the persisted term, vote and the log kept after compaction
+    pub fn new(
+        config: RaftConfig,
+        store: RaftStore,
+        transport: Arc<dyn RaftTransport>,
+        machine: Arc<dyn RaftStateMachine>,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let hard = store.load_hard_state()?;
+        let compacted = store.load_compaction()?;
+        let log = store.load_log(&compacted)?;
+        let applied = store
+            .load_applied()?
+            .clamp(compacted.index, compacted.index + log.len() as u64);
+        let now = Instant::now();
+
+        let node = Self {
+            config,
+            store,
+            transport,
+            machine,
+            state: Mutex::new(RaftState {
+                hard,
+                compacted,
+                log,
+                role: RaftRole::Follower,
+                leader_id: None,
+                commit_index: applied,
+                last_applied: applied,
+                next_index: HashMap::new(),
+                match_index: HashMap::new(),
+                votes: HashSet::new(),
+                election_deadline: now,
+                heartbeat_due: now,
+                ready_index: 0,
+                waiters: HashMap::new(),
+                in_flight: HashSet::new(),
+                behind_compaction: HashSet::new(),
+            }),
+            commit_notify: Notify::new(),
+            replicate_notify: Notify::new(),
+            stopped: AtomicBool::new(false),
+        };
+        let deadline = node.random_deadline();
+        node.lock().election_deadline = deadline;
+
+        Ok(node)
+    }
+
+    /// Starts the timer and apply loops
+    pub fn start(self: &Arc<Self>) -> JoinHandle<()> {
+        tracing::info!(
+            "🗳️  Raft node {} starting with {} peer(s)",
+            self.config.node_id,
+            self.config.peers.len()
+        );
+        let ticker = self.clone().run_ticker();
+        let applier = self.clone().run_applier();
+        tokio::spawn(async move {
+            tokio::join!(ticker, applier);
+        })
+    }
+
+    /// Stops timers, replication and applying; the node steps down
+    pub fn stop(&self) {
+        self.stopped.store(true, Ordering::SeqCst);
+        let mut st = self.lock();
+        st.role = RaftRole::Follower;
+        st.in_flight.clear();
+        drop(st);
+        self.commit_notify.notify_one();
+        self.replicate_notify.notify_one();
+    }
+
+    fn is_stopped(&self) -> bool {
+        self.stopped.load(Ordering::SeqCst)
+    }
+
+    pub fn node_id(&self) -> u64 {
+        self.config.node_id
+    }
+
+    pub fn is_leader(&self) -> bool {
+        self.lock().role == RaftRole::Leader
+    }
+
+    /// gRPC address of the current leader, if known and not this node
+    pub fn leader_address(&self) -> Option<String> {
+        let leader_id = self.lock().leader_id?;
+        self.config.peers.get(&leader_id).cloned()
+    }
+
+    /// Other members of the cluster: node id -> cluster listener address
+    pub fn peers(&self) -> &HashMap<u64, String> {
+        &self.config.peers
+    }
+
+    pub fn status(&self) -> RaftStatus {
+        let st = self.lock();
+        RaftStatus {
+            node_id: self.config.node_id,
+            role: st.role,
+            term: st.hard.term,
+            leader_id: st.leader_id,
+            last_log_index: st.last_index(),
+            commit_index: st.commit_index,
+            last_applied: st.last_applied,
+            match_index: st.match_index.clone(),
+        }
+    }
+
+    /// Appends a command on the leader and waits until it is committed by a
+    /// majority and applied locally
+    pub async fn propose(&self, command: Vec<u8>) -> Result<(), ProposeError> {
+        let receiver = {
+            let mut st = self.lock();
+            if st.role != RaftRole::Leader {
+                return Err(ProposeError::NotLeader(st.leader_id));
+            }
+            if st.last_applied < st.ready_index {
+                return Err(ProposeError::Rejected(
+                    "Leader is still applying earlier entries; retry".to_string(),
+                ));
+            }
+
+            let term = st.hard.term;
+            self.append_local(&mut st, vec![RaftLogEntry { term, command }])
+                .map_err(ProposeError::Rejected)?;
+            let index = st.last_index();
+            let (sender, receiver) = oneshot::channel();
+            st.waiters.insert(index, (term, sender));
+            self.advance_commit(&mut st);
+            receiver
+        };
+        self.replicate_notify.notify_one();
+
+        match tokio::time::timeout(PROPOSAL_TIMEOUT, receiver).await {
+            Ok(Ok(result)) => result.map_err(ProposeError::Rejected),
+            Ok(Err(_)) => Err(ProposeError::Rejected("Proposal was dropped".to_string())),
+            Err(_) => Err(ProposeError::Rejected(
+                "Timed out waiting for the cluster to commit".to_string(),
+            )),
+        }
+    }
+
+    /// Handles a RequestVote RPC from a candidate; candidates that are not
+    /// configured members never get a vote
+    pub fn handle_request_vote(&self, request: RaftVoteRequest) -> RaftVoteResponse {
+        let mut st = self.lock();
+        if !self.config.peers.contains_key(&request.candidate_id) {
+            tracing::warn!(
+                "⚠️  Refusing vote to node {}, which is not a cluster member",
+                request.candidate_id
+            );
+            return RaftVoteResponse {
+                term: st.hard.term,
+                vote_granted: false,
+            };
+        }
+        if request.term > st.hard.term {
+            self.step_down(&mut st, request.term);
+        }
+
+        let up_to_date = request.last_log_term > st.term_at(st.last_index())
+            || (request.last_log_term == st.term_at(st.last_index())
+                && request.last_log_index >= st.last_index());
+        let can_vote =
+            st.hard.voted_for.is_none() || st.hard.voted_for == Some(request.candidate_id);
+        let mut vote_granted = request.term == st.hard.term && can_vote && up_to_date;
+
+        if vote_granted {
+            st.hard.voted_for = Some(request.candidate_id);
+            if let Err(e) = self.store.save_hard_state(&st.hard) {
+                tracing::error!("❌ Failed to persist Raft vote: {}", e);
+                st.hard.voted_for = None;
+                vote_granted = false;
+            } else {
+                st.election_deadline = self.random_deadline();
+            }
+        }
+
+        RaftVoteResponse {
+            term: st.hard.term,
+            vote_granted,
+        }
+    }
+
+    /// Handles an AppendEntries RPC (replication or heartbeat) from the leader;
+    /// requests naming a leader that is not a configured member are rejected
+    pub fn handle_append_entries(&self, request: RaftAppendRequest) -> RaftAppendResponse {
+        let mut st = self.lock();
+        let reject = |st: &RaftState, conflict_index: u64| RaftAppendResponse {
+            term: st.hard.term,
+            success: false,
+            match_index: 0,
+            conflict_index,
+        };
+
+        if !self.config.peers.contains_key(&request.leader_id) {
+            tracing::warn!(
+                "⚠️  Rejecting entries from node {}, which is not a cluster member",
+                request.leader_id
+            );
+            return reject(&st, 0);
+        }
+        if request.term < st.hard.term {
+            return reject(&st, 0);
+        }
+        if request.term > st.hard.term || st.role != RaftRole::Follower {
+            self.step_down(&mut st, request.term);
+        }
+        st.leader_id = Some(request.leader_id);
+        st.election_deadline = self.random_deadline();
+
+        // Entries up to the compacted index were committed and applied here;
+        // committed entries never conflict, so they are skipped
+        let mut request = request;
+        if request.prev_log_index < st.compacted.index {
+            let skip = (st.compacted.index - request.prev_log_index) as usize;
+            request.entries.drain(..skip.min(request.entries.len()));
+            request.prev_log_index = st.compacted.index;
+            request.prev_log_term = st.compacted.term;
+        }
+
+        // Our log must contain the entry preceding the new ones
+        if request.prev_log_index > st.last_index() {
+            let conflict_index = st.last_index() + 1;
+            return reject(&st, conflict_index);
+        }
+        if st.term_at(request.prev_log_index) != request.prev_log_term {
+            let conflict_index = request.prev_log_index.max(1);
+            return reject(&st, conflict_index);
+        }
+
+        // Skip entries we already have; truncate from the first conflict
+        let mut first_new = None;
+        for (offset, entry) in request.entries.iter().enumerate() {
+            let index = request.prev_log_index + 1 + offset as u64;
+            if index > st.last_index() || st.term_at(index) != entry.term {
+                first_new = Some(offset);
+                break;
+            }
+        }
+
+        if let Some(offset) = first_new {
+            let first_index = request.prev_log_index + 1 + offset as u64;
+            let entries: Vec<RaftLogEntry> = request.entries[offset..]
+                .iter()
+                .map(|e| RaftLogEntry {
+                    term: e.term,
+                    command: e.command.clone(),
+                })
+                .collect();
+            let old_last = st.last_index();
+            if let Err(e) = self.store.replace_log_from(first_index, &entries, old_last) {
+                tracing::error!("❌ Failed to persist Raft entries: {}", e);
+                return reject(&st, first_index);
+            }
+            let offset = st.offset(first_index);
+            st.log.truncate(offset);
+            st.log.extend(entries);
+        }
+
+        let match_index = request.prev_log_index + request.entries.len() as u64;
+        // The commit index never moves back, even when this request's entries end below it
+        let commit_index = request.leader_commit.min(match_index);
+        if commit_index > st.commit_index {
+            st.commit_index = commit_index;
+            self.commit_notify.notify_one();
+        }
+
+        RaftAppendResponse {
+            term: st.hard.term,
+            success: true,
+            match_index,
+            conflict_index: 0,
+        }
+    }
+
+    async fn run_ticker(self: Arc<Self>) {
+        loop {
+            tokio::select! {
+                _ = tokio::time::sleep(TICK) => {}
+                _ = self.replicate_notify.notified() => {}
+            }
+            if self.is_stopped() {
+                return;
+            }
+
+            let now = Instant::now();
+            let (role, election_due) = {
+                let st = self.lock();
+                (st.role, now >= st.election_deadline)
+            };
+
+            match role {
+                RaftRole::Leader => self.broadcast_append_entries(),
+                RaftRole::Follower | RaftRole::Candidate if election_due => self.start_election(),
+                _ => {}
+            }
+        }
+    }
+
+    async fn run_applier(self: Arc<Self>) {
+        while !self.is_stopped() {
+            let next = {
+                let st = self.lock();
+                if st.last_applied < st.commit_index {
+                    let index = st.last_applied + 1;
+                    st.entry(index).cloned().map(|entry| (index, entry))
+                } else {
+                    None
+                }
+            };
+
+            let (index, entry) = match next {
+                Some(next) => next,
+                None => {
+                    self.commit_notify.notified().await;
+                    continue;
+                }
+            };
+
+            let result = if entry.command.is_empty() {
+                Ok(())
+            } else {
+                self.machine.apply(&entry.command).await
+            };
+            let result = match result {
+                Err(ApplyError::Storage(e)) => {
+                    // The entry stays unapplied; later entries wait for it
+                    tracing::error!(
+                        "❌ Raft entry {} could not be applied, retrying: {}",
+                        index,
+                        e
+                    );
+                    tokio::time::sleep(APPLY_RETRY_DELAY).await;
+                    continue;
+                }
+                Err(ApplyError::Rejected(e)) => {
+                    tracing::warn!("⚠️  Raft entry {} was rejected: {}", index, e);
+                    Err(e)
+                }
+                Ok(()) => Ok(()),
+            };
+
+            let mut st = self.lock();
+            st.last_applied = index;
+            if let Err(e) = self.store.save_applied(index) {
+                tracing::error!("❌ Failed to persist Raft applied index: {}", e);
+            }
+            if let Some((term, sender)) = st.waiters.remove(&index) {
+                let outcome = if term == entry.term {
+                    result
+                } else {
+                    Err("Entry was replaced after a leader change".to_string())
+                };
+                let _ = sender.send(outcome);
+            }
+            self.compact_if_due(&mut st);
+        }
+    }
+
+    /// Drops applied entries from the front of the log once it holds twice
+    /// `retained_log_entries` of them, keeping the newest ones for peers
+    /// that fall behind
+    fn compact_if_due(&self, st: &mut RaftState) {
+        let retained = self.config.retained_log_entries;
+        if st.last_applied <= st.compacted.index + retained.saturating_mul(2) {
+            return;
+        }
+        let index = st.last_applied - retained;
+        let compaction = RaftCompaction {
+            index,
+            term: st.term_at(index),
+        };
+        if let Err(e) = self.store.compact_log(&compaction, st.compacted.index) {
+            tracing::error!("❌ Failed to compact the Raft log: {}", e);
+            return;
+        }
+        let dropped = st.offset(index) + 1;
+        st.log.drain(..dropped);
+        st.compacted = compaction;
+        tracing::debug!("🗜️  Compacted the Raft log up to index {}", index);
+    }
+
+    fn start_election(self: &Arc<Self>) {
+        let (term, request) = {
+            let mut st = self.lock();
+            st.hard.term += 1;
+            st.hard.voted_for = Some(self.config.node_id);
+            st.election_deadline = self.random_deadline();
+            if let Err(e) = self.store.save_hard_state(&st.hard) {
+                tracing::error!("❌ Failed to persist Raft term: {}", e);
+                return;
+            }
+
+            st.role = RaftRole::Candidate;
+            st.leader_id = None;
+            st.votes = HashSet::from([self.config.node_id]);
+            tracing::info!(
+                "🗳️  Node {} starting election for term {}",
+                self.config.node_id,
+                st.hard.term
+            );
+
+            if st.votes.len() >= self.quorum() {
+                self.become_leader(&mut st);
+                return;
+            }
+
+            let request = RaftVoteRequest {
+                term: st.hard.term,
+                candidate_id: self.config.node_id,
+                last_log_index: st.last_index(),
+                last_log_term: st.term_at(st.last_index()),
+            };
+            (st.hard.term, request)
+        };
+
+        for &peer in self.config.peers.keys() {
+            let node = self.clone();
+            let request = request.clone();
+            tokio::spawn(async move {
+                if let Ok(response) = node.transport.request_vote(peer, request).await {
+                    node.on_vote_response(peer, term, response);
+                }
+            });
+        }
+    }
+
+    fn on_vote_response(&self, peer: u64, term: u64, response: RaftVoteResponse) {
+        let mut st = self.lock();
+        if response.term > st.hard.term {
+            self.step_down(&mut st, response.term);
+            return;
+        }
+        if st.role == RaftRole::Candidate && st.hard.term == term && response.vote_granted {
+            st.votes.insert(peer);
+            if st.votes.len() >= self.quorum() {
+                self.become_leader(&mut st);
+            }
+        }
+    }
+
+    fn become_leader(&self, st: &mut RaftState) {
+        st.role = RaftRole::Leader;
+        st.leader_id = Some(self.config.node_id);
+        let next = st.last_index() + 1;
+        for &peer in self.config.peers.keys() {
+            st.next_index.insert(peer, next);
+            st.match_index.insert(peer, 0);
+        }
+
+        // A no-op from the new term lets earlier entries be committed
+        let term = st.hard.term;
+        if let Err(e) = self.append_local(
+            st,
+            vec![RaftLogEntry {
+                term,
+                command: Vec::new(),
+            }],
+        ) {
+            tracing::error!("❌ Failed to append leader no-op: {}", e);
+        }
+        st.ready_index = st.last_index();
+        st.heartbeat_due = Instant::now();
+        self.advance_commit(st);
+        self.replicate_notify.notify_one();
+
+        tracing::info!(
+            "👑 Node {} is the leader for term {}",
+            self.config.node_id,
+            term
+        );
+    }
+
+    fn step_down(&self, st: &mut RaftState, term: u64) {
+        if term > st.hard.term {
+            st.hard.term = term;
+            st.hard.voted_for = None;
+            if let Err(e) = self.store.save_hard_state(&st.hard) {
+                tracing::error!("❌ Failed to persist Raft term: {}", e);
+            }
+        }
+        if st.role == RaftRole::Leader {
+            tracing::info!(
+                "🔻 Node {} stepped down in term {}",
+                self.config.node_id,
+                st.hard.term
+            );
+        }
+        st.role = RaftRole::Follower;
+        st.in_flight.clear();
+    }
+
+    /// Sends AppendEntries to every peer that is due a heartbeat or is
+    /// missing entries and has no call in flight
+    fn broadcast_append_entries(self: &Arc<Self>) {
+        let peers: Vec<u64> = {
+            let mut st = self.lock();
+            let now = Instant::now();
+            let heartbeat = now >= st.heartbeat_due;
+            if heartbeat {
+                st.heartbeat_due = now + self.config.heartbeat_interval;
+            }
+
+            let last_index = st.last_index();
+            let peers: Vec<u64> = self
+                .config
+                .peers
+                .keys()
+                .copied()
+                .filter(|peer| !st.in_flight.contains(peer))
+                .filter(|peer| {
+                    heartbeat
+                        || (st.next_index.get(peer).copied().unwrap_or(1) <= last_index
+                            && !st.behind_compaction.contains(peer))
+                })
+                .collect();
+            st.in_flight.extend(peers.iter().copied());
+            peers
+        };
+
+        for peer in peers {
+            tokio::spawn(self.clone().replicate_to(peer));
+        }
+    }
+
+    async fn replicate_to(self: Arc<Self>, peer: u64) {
+        loop {
+            let request = {
+                let mut st = self.lock();
+                if st.role != RaftRole::Leader || self.is_stopped() {
+                    st.in_flight.remove(&peer);
+                    return;
+                }
+                let next = st.next_index.get(&peer).copied().unwrap_or(1).max(1);
+                // A peer that needs compacted entries only gets heartbeats, so
+                // it does not start elections; it has to be restored from a
+                // backup of another member
+                let stalled = next <= st.compacted.index;
+                if !stalled {
+                    st.behind_compaction.remove(&peer);
+                } else if st.behind_compaction.insert(peer) {
+                    tracing::warn!(
+                        "⚠️  Node {} needs Raft entries from index {}, compacted up to {}; restore it from a backup",
+                        peer,
+                        next,
+                        st.compacted.index
+                    );
+                }
+                let prev_log_index = (next - 1).max(st.compacted.index);
+                let entries = st
+                    .log
+                    .iter()
+                    .skip((prev_log_index - st.compacted.index) as usize)
+                    .take(if stalled { 0 } else { MAX_ENTRIES_PER_APPEND })
+                    .map(|e| RaftEntry {
+                        term: e.term,
+                        command: e.command.clone(),
+                    })
+                    .collect();
+                RaftAppendRequest {
+                    term: st.hard.term,
+                    leader_id: self.config.node_id,
+                    prev_log_index,
+                    prev_log_term: st.term_at(prev_log_index),
+                    entries,
+                    leader_commit: st.commit_index,
+                }
+            };
+
+            let response = self.transport.append_entries(peer, request.clone()).await;
+
+            let mut st = self.lock();
+            let response = match response {
+                Ok(response) => response,
+                Err(_) => {
+                    st.in_flight.remove(&peer);
+                    return;
+                }
+            };
+
+            if response.term > st.hard.term {
+                self.step_down(&mut st, response.term);
+                return;
+            }
+            if st.role != RaftRole::Leader || st.hard.term != request.term {
+                st.in_flight.remove(&peer);
+                return;
+            }
+
+            let more = if response.success {
+                let matched = response
+                    .match_index
+                    .max(st.match_index.get(&peer).copied().unwrap_or(0));
+                st.match_index.insert(peer, matched);
+                st.next_index.insert(peer, matched + 1);
+                self.advance_commit(&mut st);
+                matched < st.last_index()
+            } else {
+                let next = response
+                    .conflict_index
+                    .clamp(1, request.prev_log_index.max(1));
+                st.next_index.insert(peer, next);
+                next > st.compacted.index
+            };
+
+            if !more {
+                st.in_flight.remove(&peer);
+                return;
+            }
+        }
+    }
+
+    /// Commits the highest index stored on a majority, if it is from this term
+    fn advance_commit(&self, st: &mut RaftState) {
+        let mut matched: Vec<u64> = self
+            .config
+            .peers
+            .keys()
+            .map(|peer| st.match_index.get(peer).copied().unwrap_or(0))
+            .collect();
+        matched.push(st.last_index());
+        matched.sort_unstable_by(|a, b| b.cmp(a));
+
+        let candidate = matched[self.quorum() - 1];
+        if candidate > st.commit_index && st.term_at(candidate) == st.hard.term {
+            st.commit_index = candidate;
+            self.commit_notify.notify_one();
+        }
+    }
+
+    fn append_local(&self, st: &mut RaftState, entries: Vec<RaftLogEntry>) -> Result<(), String> {
+        let first_index = st.last_index() + 1;
+        self.store
+            .replace_log_from(first_index, &entries, first_index - 1)
+            .map_err(|e| format!("Failed to persist Raft entry: {}", e))?;
+        st.log.extend(entries);
+        Ok(())
+    }
+
+    fn quorum(&self) -> usize {
+        let members = self.config.peers.len() + 1;
+        members / 2 + 1
+    }
+
+    fn random_deadline(&self) -> Instant {
+        let min = self.config.election_timeout_min.as_millis() as u64;
+        let max = (self.config.election_timeout_max.as_millis() as u64).max(min);
+        Instant::now() + Duration::from_millis(rand::thread_rng().gen_range(min..=max))
+    }
+
+    fn lock(&self) -> MutexGuard<'_, RaftState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infrastructure::persistence::RocksDbAdapter;
+    use tempfile::tempdir;
+
+    struct NoPeers;
+
+    #[async_trait]
+    impl RaftTransport for NoPeers {
+        async fn request_vote(
+            &self,
+            _: u64,
+            _: RaftVoteRequest,
+        ) -> Result<RaftVoteResponse, String> {
+            Err("no peers".to_string())
+        }
+        async fn append_entries(
+            &self,
+            _: u64,
+            _: RaftAppendRequest,
+        ) -> Result<RaftAppendResponse, String> {
+            Err("no peers".to_string())
+        }
+    }
+
+    #[derive(Default)]
+    struct Recorder(Mutex<Vec<Vec<u8>>>);
+
+    #[async_trait]
+    impl RaftStateMachine for Recorder {
+        async fn apply(&self, command: &[u8]) -> Result<(), ApplyError> {
+            self.0.lock().unwrap().push(command.to_vec());
+            Ok(())
+        }
+    }
+
+    fn config(node_id: u64, peers: HashMap<u64, String>) -> RaftConfig {
+        RaftConfig {
+            node_id,
+            peers,
+            election_timeout_min: Duration::from_millis(20),
+            election_timeout_max: Duration::from_millis(40),
+            heartbeat_interval: Duration::from_millis(10),
+            retained_log_entries: 1000,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_single_node_commits_and_restores_log() {
+        let dir = tempdir().unwrap();
+        let db = Arc::new(RocksDbAdapter::new(dir.path()).unwrap());
+        let machine = Arc::new(Recorder::default());
+        let node = Arc::new(
+            RaftNode::new(
+                config(1, HashMap::new()),
+                RaftStore::new(db.clone()),
+                Arc::new(NoPeers),
+                machine.clone(),
+            )
+            .unwrap(),
+        );
+        let task = node.start();
+
+        let mut proposed = Err(ProposeError::NotLeader(None));
+        for _ in 0..100 {
+            proposed = node.propose(b"first".to_vec()).await;
+            if proposed.is_ok() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        proposed.unwrap();
+        assert_eq!(*machine.0.lock().unwrap(), vec![b"first".to_vec()]);
+        node.stop();
+        task.await.unwrap();
+        assert!(!node.is_leader());
+
+        let restored = RaftNode::new(
+            config(1, HashMap::new()),
+            RaftStore::new(db),
+            Arc::new(NoPeers),
+            machine,
+        )
+        .unwrap();
+        let status = restored.status();
+        assert_eq!(status.role, RaftRole::Follower);
+        assert_eq!(status.last_log_index, 2); // no-op + command
+        assert_eq!(status.last_applied, 2);
+        assert!(status.term >= 1);
+    }
+
+    #[tokio::test]
+    async fn test_applied_entries_are_compacted() {
+        let dir = tempdir().unwrap();
+        let db = Arc::new(RocksDbAdapter::new(dir.path()).unwrap());
+        let machine = Arc::new(Recorder::default());
+        let mut retaining_one = config(1, HashMap::new());
+        retaining_one.retained_log_entries = 1;
+        let node = Arc::new(
+            RaftNode::new(
+                retaining_one.clone(),
+                RaftStore::new(db.clone()),
+                Arc::new(NoPeers),
+                machine.clone(),
+            )
+            .unwrap(),
+        );
+        let task = node.start();
+        for command in ["a", "b", "c", "d", "e"] {
+            let mut proposed = Err(ProposeError::NotLeader(None));
+            for _ in 0..100 {
+                proposed = node.propose(command.as_bytes().to_vec()).await;
+                if proposed.is_ok() {
+                    break;
+                }
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+            proposed.unwrap();
+        }
+        node.stop();
+        task.await.unwrap();
+        // no-op + 5 commands: compacted up to 2, then up to 4, keeping one applied entry
+        assert_eq!(node.lock().compacted.index, 4);
+        assert_eq!(node.lock().log.len(), 2);
+        assert_eq!(machine.0.lock().unwrap().len(), 5);
+
+        retaining_one.peers = HashMap::from([(2, "127.0.0.1:1".to_string())]);
+        let restored = RaftNode::new(
+            retaining_one,
+            RaftStore::new(db),
+            Arc::new(NoPeers),
+            machine,
+        )
+        .unwrap();
+        let status = restored.status();
+        assert_eq!(status.last_log_index, 6);
+        assert_eq!(status.last_applied, 6);
+
+        // A leader resending entries from before the compaction is not refused
+        let response = restored.handle_append_entries(RaftAppendRequest {
+            term: status.term,
+            leader_id: 2,
+            prev_log_index: 1,
+            prev_log_term: 1,
+            entries: vec![],
+            leader_commit: 6,
+        });
+        assert!(response.success);
+        assert_eq!(response.match_index, 4);
+    }
+
+    #[test]
+    fn test_follower_rejects_stale_term_and_truncates_conflicts() {
+        let dir = tempdir().unwrap();
+        let db = Arc::new(RocksDbAdapter::new(dir.path()).unwrap());
+        let peers = HashMap::from([
+            (2, "127.0.0.1:1".to_string()),
+            (3, "127.0.0.1:2".to_string()),
+        ]);
+        let node = RaftNode::new(
+            config(1, peers),
+            RaftStore::new(db),
+            Arc::new(NoPeers),
+            Arc::new(Recorder::default()),
+        )
+        .unwrap();
+        let entry = |term: u64, command: &str| RaftEntry {
+            term,
+            command: command.as_bytes().to_vec(),
+        };
+
+        let response = node.handle_append_entries(RaftAppendRequest {
+            term: 1,
+            leader_id: 2,
+            prev_log_index: 0,
+            prev_log_term: 0,
+            entries: vec![entry(1, "a"), entry(1, "b")],
+            leader_commit: 1,
+        });
+        assert!(response.success);
+        assert_eq!(node.status().commit_index, 1);
+
+        // A new leader replaces the uncommitted second entry
+        let response = node.handle_append_entries(RaftAppendRequest {
+            term: 2,
+            leader_id: 2,
+            prev_log_index: 1,
+            prev_log_term: 1,
+            entries: vec![entry(2, "c")],
+            leader_commit: 1,
+        });
+        assert!(response.success);
+        assert_eq!(node.lock().log[1].command, b"c".to_vec());
+
+        let stale = node.handle_append_entries(RaftAppendRequest {
+            term: 1,
+            leader_id: 3,
+            prev_log_index: 0,
+            prev_log_term: 0,
+            entries: vec![],
+            leader_commit: 0,
+        });
+        assert!(!stale.success);
+        assert_eq!(stale.term, 2);
+
+        let vote = node.handle_request_vote(RaftVoteRequest {
+            term: 3,
+            candidate_id: 3,
+            last_log_index: 1,
+            last_log_term: 1,
+        });
+        assert!(!vote.vote_granted, "candidate log is behind");
+
+        // Nodes outside the membership can neither lead nor collect votes
+        let outsider = node.handle_append_entries(RaftAppendRequest {
+            term: 9,
+            leader_id: 9,
+            prev_log_index: 2,
+            prev_log_term: 2,
+            entries: vec![entry(9, "x")],
+            leader_commit: 3,
+        });
+        assert!(!outsider.success);
+        let vote = node.handle_request_vote(RaftVoteRequest {
+            term: 9,
+            candidate_id: 9,
+            last_log_index: 10,
+            last_log_term: 9,
+        });
+        assert!(!vote.vote_granted);
+        assert_eq!(node.status().term, 3);
+        assert_eq!(node.status().last_log_index, 2);
+    }
+}
@@ -0,0 +1,326 @@
+use std::collections::{HashMap, VecDeque};
+use std::pin::Pin;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use tokio_stream::Stream;
+use tonic::Status;
+
+use crate::domain::{
+    AuthorityRotation, AuthoritySet, Block, BlockEndorsements, BlockchainGraph, Consensus,
+    DataSchema, EndorsementPolicy, Endorser, Reorg,
+};
+use crate::infrastructure::grpc::blockchain::{self as proto, replication_event, ReplicationEvent};
+
+/// How often a leader sends its tips to followers
+pub const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Events buffered per follower before it must resubscribe
+pub const EVENT_BUFFER: usize = 1024;
+
+/// Stream returned by the SubscribeReplication RPC
+pub type ReplicationStream = Pin<Box<dyn Stream<Item = Result<ReplicationEvent, Status>> + Send>>;
+
+/// A committed change published to followers (Observer Pattern)
+#[derive(Debug, Clone)]
+pub enum ChainEvent {
+    /// A new graph; its chain holds only the genesis block
+    GraphCreated(BlockchainGraph),
+    BlockCommitted(Block),
+    /// The main chain of a graph switched to a heavier branch
+    Reorganized {
+        graph_id: String,
+        reorg: Reorg,
+    },
+}
+
+impl ChainEvent {
+    /// Converts the event to its wire representation
+    pub fn to_proto(&self) -> ReplicationEvent {
+        let event = match self {
+            ChainEvent::GraphCreated(graph) => {
+                replication_event::Event::GraphCreated(graph_to_proto(graph))
+            }
+            ChainEvent::BlockCommitted(block) => {
+                replication_event::Event::Block(block_to_proto(block))
+            }
+            ChainEvent::Reorganized { graph_id, reorg } => {
+                replication_event::Event::Reorg(proto::ChainReorg {
+                    graph_id: graph_id.clone(),
+                    fork_height: reorg.fork_height,
+                    disconnected_hashes: reorg
+                        .disconnected
+                        .iter()
+                        .map(|b| b.hash.clone())
+                        .collect(),
+                    connected: reorg.connected.iter().map(block_to_proto).collect(),
+                })
+            }
+        };
+        ReplicationEvent { event: Some(event) }
+    }
+}
+
+/// What a follower knows about its leader
+#[derive(Debug, Clone, Default)]
+pub struct FollowerProgress {
+    pub connected: bool,
+    /// Unix timestamp in milliseconds of the last event received (0 = never)
+    pub last_event_at_ms: i64,
+    /// Latest height per graph announced by the leader
+    pub leader_tips: HashMap<String, u64>,
+}
+
+/// Replication role of a node and, for followers, its progress
+#[derive(Debug, Default)]
+pub struct ReplicationState {
+    leader_address: Option<String>,
+    progress: Mutex<FollowerProgress>,
+}
+
+impl ReplicationState {
+    pub fn leader() -> Self {
+        Self::default()
+    }
+
+    pub fn follower(leader_address: String) -> Self {
+        Self {
+            leader_address: Some(leader_address),
+            progress: Mutex::new(FollowerProgress::default()),
+        }
+    }
+
+    /// Followers are read-only; writes go to the leader
+    pub fn is_follower(&self) -> bool {
+        self.leader_address.is_some()
+    }
+
+    pub fn leader_address(&self) -> Option<&str> {
+        self.leader_address.as_deref()
+    }
+
+    pub fn set_connected(&self, connected: bool) {
+        self.lock().connected = connected;
+    }
+
+    /// Records that an event arrived from the leader
+    pub fn record_event(&self) {
+        self.lock().last_event_at_ms = chrono::Utc::now().timestamp_millis();
+    }
+
+    /// Raises the known leader tip of a graph
+    pub fn observe_tip(&self, graph_id: &str, height: u64) {
+        let mut progress = self.lock();
+        let tip = progress
+            .leader_tips
+            .entry(graph_id.to_string())
+            .or_insert(height);
+        *tip = (*tip).max(height);
+    }
+
+    /// Replaces the known leader tips with a heartbeat
+    pub fn record_heartbeat(&self, tips: HashMap<String, u64>) {
+        self.lock().leader_tips = tips;
+    }
+
+    pub fn progress(&self) -> FollowerProgress {
+        self.lock().clone()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, FollowerProgress> {
+        self.progress.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Converts domain Block to proto Block
+pub fn block_to_proto(block: &Block) -> proto::Block {
+    proto::Block {
+        hash: block.hash.clone(),
+        previous_hash: block.previous_hash.clone(),
+        timestamp: block.timestamp,
+        nonce: block.nonce,
+        data: block.data.clone(),
+        height: block.height,
+        graph_id: block.graph_id.clone(),
+        cross_references: block.cross_references.clone(),
+        signer: block.signer.clone(),
+        signature: block.signature.clone(),
+        endorsements: block.endorsements.as_ref().map(endorsements_to_proto),
+    }
+}
+
+/// Converts proto Block to domain Block (the hash is kept, not recomputed)
+pub fn block_from_proto(block: proto::Block) -> Block {
+    Block {
+        hash: block.hash,
+        previous_hash: block.previous_hash,
+        timestamp: block.timestamp,
+        data: block.data,
+        nonce: block.nonce,
+        height: block.height,
+        graph_id: block.graph_id,
+        cross_references: block.cross_references,
+        signer: block.signer,
+        signature: block.signature,
+        endorsements: block.endorsements.map(endorsements_from_proto),
+    }
+}
+
+/// Converts the endorsements of a block to their wire form
+pub fn endorsements_to_proto(endorsements: &BlockEndorsements) -> proto::BlockEndorsements {
+    proto::BlockEndorsements {
+        nonce: endorsements.nonce.clone(),
+        signatures: endorsements.signatures.clone().into_iter().collect(),
+    }
+}
+
+/// Converts wire endorsements (verified when the block is appended)
+pub fn endorsements_from_proto(endorsements: proto::BlockEndorsements) -> BlockEndorsements {
+    BlockEndorsements {
+        nonce: endorsements.nonce,
+        signatures: endorsements.signatures.into_iter().collect(),
+    }
+}
+
+/// Converts a consensus mode to its wire form; for proof of authority,
+/// `authorities` is the set to advertise (initial or currently in effect)
+pub fn consensus_to_proto(
+    consensus: &Consensus,
+    authorities: Option<&AuthoritySet>,
+) -> proto::ConsensusConfig {
+    match (consensus, authorities) {
+        (Consensus::ProofOfWork, _) => proto::ConsensusConfig::default(),
+        (Consensus::ProofOfAuthority(initial), current) => {
+            let set = current.unwrap_or(initial);
+            proto::ConsensusConfig {
+                mode: proto::ConsensusMode::ProofOfAuthority as i32,
+                authorities: set.authorities.clone(),
+                rotation: set.rotation.to_i32(),
+            }
+        }
+    }
+}
+
+/// Converts a wire consensus mode; a missing config means proof of work
+pub fn consensus_from_proto(config: Option<proto::ConsensusConfig>) -> Consensus {
+    match config {
+        Some(config) if config.mode == proto::ConsensusMode::ProofOfAuthority as i32 => {
+            Consensus::ProofOfAuthority(AuthoritySet {
+                authorities: config.authorities,
+                rotation: AuthorityRotation::from_i32(config.rotation),
+            })
+        }
+        _ => Consensus::ProofOfWork,
+    }
+}
+
+/// Converts an endorsement policy to its wire form
+pub fn policy_to_proto(policy: &EndorsementPolicy) -> proto::EndorsementPolicy {
+    proto::EndorsementPolicy {
+        required: policy.required as u32,
+        endorsers: policy
+            .endorsers
+            .iter()
+            .map(|e| proto::Endorser {
+                org: e.org.clone(),
+                public_key: e.public_key.clone(),
+            })
+            .collect(),
+    }
+}
+
+/// Converts a wire endorsement policy (not validated)
+pub fn policy_from_proto(policy: proto::EndorsementPolicy) -> EndorsementPolicy {
+    EndorsementPolicy {
+        required: policy.required as usize,
+        endorsers: policy
+            .endorsers
+            .into_iter()
+            .map(|e| Endorser {
+                org: e.org,
+                public_key: e.public_key,
+            })
+            .collect(),
+    }
+}
+
+/// Converts a data schema to its wire form (empty without one)
+pub fn schema_to_proto(schema: Option<&DataSchema>) -> String {
+    schema.map(|s| s.as_str().to_string()).unwrap_or_default()
+}
+
+/// Converts a wire data schema; empty or unparsable text means none
+pub fn schema_from_proto(schema: &str) -> Option<DataSchema> {
+    match schema {
+        "" => None,
+        text => DataSchema::parse(text).ok(),
+    }
+}
+
+/// Merges per-graph queues of timestamped items into one, earliest first,
+/// keeping the order within each queue; ties go to the earlier queue
+pub fn interleave_by_time<T>(mut queues: Vec<VecDeque<(i64, T)>>) -> Vec<T> {
+    let mut merged = Vec::with_capacity(queues.iter().map(VecDeque::len).sum());
+    while let Some(queue) = queues
+        .iter_mut()
+        .filter(|queue| !queue.is_empty())
+        .min_by_key(|queue| queue[0].0)
+    {
+        merged.extend(queue.pop_front().map(|(_, item)| item));
+    }
+    merged
+}
+
+fn graph_to_proto(graph: &BlockchainGraph) -> proto::ReplicatedGraph {
+    proto::ReplicatedGraph {
+        graph_id: graph.id.clone(),
+        graph_type: graph.graph_type.to_i32(),
+        description: graph.description.clone(),
+        created_at: graph.created_at,
+        difficulty: graph.difficulty as u64,
+        genesis: graph.chain.first().map(block_to_proto),
+        consensus: Some(consensus_to_proto(&graph.consensus, None)),
+        endorsement: graph.endorsement.as_ref().map(policy_to_proto),
+        schema: schema_to_proto(graph.schema.as_ref()),
+        governance: graph.governance.as_ref().map(policy_to_proto),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_follower_progress_tracks_tips() {
+        let state = ReplicationState::follower("127.0.0.1:50051".to_string());
+        assert!(state.is_follower());
+        assert!(!ReplicationState::leader().is_follower());
+
+        state.observe_tip("payments", 3);
+        state.observe_tip("payments", 1);
+        assert_eq!(state.progress().leader_tips.get("payments"), Some(&3));
+
+        state.record_heartbeat(HashMap::from([("people".to_string(), 5)]));
+        let progress = state.progress();
+        assert_eq!(progress.leader_tips.get("payments"), None);
+        assert_eq!(progress.leader_tips.get("people"), Some(&5));
+    }
+
+    #[test]
+    fn test_interleave_by_time_keeps_each_queue_in_order() {
+        let queues = vec![
+            VecDeque::from([(10, "a1"), (30, "a2"), (20, "a3")]),
+            VecDeque::from([(10, "b1"), (25, "b2")]),
+        ];
+        assert_eq!(
+            interleave_by_time(queues),
+            vec!["a1", "b1", "b2", "a2", "a3"]
+        );
+    }
+
+    #[test]
+    fn test_block_proto_round_trip() {
+        let block = Block::genesis("payments".to_string());
+        assert_eq!(block_from_proto(block_to_proto(&block)), block);
+    }
+}
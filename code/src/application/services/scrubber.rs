@@ -0,0 +1,369 @@
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::domain::consensus::parse_authority_update;
+use crate::domain::{AuthoritySet, Block, BlockchainGraph};
+use crate::infrastructure::persistence::BlockchainRepositoryImpl;
+
+/// Blocks read from storage per batch; the scrubber sleeps between batches
+/// to stay under its blocks-per-second budget
+const SCRUB_BATCH: usize = 100;
+
+/// One inconsistency found in storage
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ScrubIssue {
+    pub graph_id: String,
+    /// Height of the block concerned (the tip height for a bad `latest:` pointer)
+    pub height: u64,
+    pub message: String,
+}
+
+/// Outcome of one pass over every graph in storage
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ScrubReport {
+    pub started_at_ms: i64,
+    pub finished_at_ms: i64,
+    pub graphs_checked: u64,
+    pub blocks_checked: u64,
+    pub issues: Vec<ScrubIssue>,
+}
+
+impl ScrubReport {
+    pub fn is_clean(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    fn issue(&mut self, graph_id: &str, height: u64, message: impl Into<String>) {
+        self.issues.push(ScrubIssue {
+            graph_id: graph_id.to_string(),
+            height,
+            message: message.into(),
+        });
+    }
+}
+
+#[derive(Debug, Default)]
+struct ScrubProgress {
+    running: bool,
+    scrubs_completed: u64,
+    last: Option<ScrubReport>,
+}
+
+/// Background integrity scrubber: re-reads every graph from storage (not the
+/// in-memory chains) with RocksDB checksums verified and checks block hashes,
+/// links, seals, the hash index and the `latest:` pointer, throttled to
+/// `max_blocks_per_sec`
+pub struct IntegrityScrubber {
+    repository: Arc<BlockchainRepositoryImpl>,
+    max_blocks_per_sec: u64,
+    progress: Mutex<ScrubProgress>,
+}
+
+impl IntegrityScrubber {
+    /// Creates a scrubber, picking up the report of the last scrub from storage
+    pub fn new(repository: Arc<BlockchainRepositoryImpl>, max_blocks_per_sec: u64) -> Self {
+        let last = match repository.load_scrub_report() {
+            Ok(Some(bytes)) => serde_json::from_slice(&bytes).ok(),
+            _ => None,
+        };
+        Self {
+            repository,
+            max_blocks_per_sec: max_blocks_per_sec.max(1),
+            progress: Mutex::new(ScrubProgress {
+                last,
+                ..Default::default()
+            }),
+        }
+    }
+
+    /// Whether a scrub is in progress
+    pub fn is_running(&self) -> bool {
+        self.lock().running
+    }
+
+    /// Scrubs finished since the node started
+    pub fn scrubs_completed(&self) -> u64 {
+        self.lock().scrubs_completed
+    }
+
+    /// Report of the last finished scrub (possibly from a previous run)
+    pub fn last_report(&self) -> Option<ScrubReport> {
+        self.lock().last.clone()
+    }
+
+    /// Scrubs every graph once, records the report and returns it
+    pub async fn scrub(&self) -> ScrubReport {
+        self.lock().running = true;
+        let started = Instant::now();
+        let mut report = ScrubReport {
+            started_at_ms: chrono::Utc::now().timestamp_millis(),
+            ..Default::default()
+        };
+
+        // Errors are not `Send`, so they are turned into strings before awaiting
+        let graphs = self
+            .repository
+            .read_graphs_verified()
+            .map_err(|e| e.to_string());
+        match graphs {
+            Ok(graphs) => {
+                for (graph_id, graph) in graphs {
+                    report.graphs_checked += 1;
+                    match graph {
+                        Ok(graph) => self.scrub_graph(&graph, &mut report, started).await,
+                        Err(e) => {
+                            report.issue(&graph_id, 0, format!("Unreadable graph metadata: {}", e))
+                        }
+                    }
+                }
+            }
+            Err(e) => report.issue("", 0, format!("Failed to read graph metadata: {}", e)),
+        }
+
+        report.finished_at_ms = chrono::Utc::now().timestamp_millis();
+        if report.is_clean() {
+            tracing::info!(
+                "🩺 Scrubbed {} graph(s), {} block(s): no issues",
+                report.graphs_checked,
+                report.blocks_checked
+            );
+        } else {
+            for issue in &report.issues {
+                tracing::error!(
+                    "❌ Scrub: graph '{}' #{}: {}",
+                    issue.graph_id,
+                    issue.height,
+                    issue.message
+                );
+            }
+        }
+        match serde_json::to_vec(&report) {
+            Ok(bytes) => {
+                if let Err(e) = self.repository.save_scrub_report(&bytes) {
+                    tracing::warn!("⚠️  Failed to store scrub report: {}", e);
+                }
+            }
+            Err(e) => tracing::warn!("⚠️  Failed to encode scrub report: {}", e),
+        }
+
+        let mut progress = self.lock();
+        progress.running = false;
+        progress.scrubs_completed += 1;
+        progress.last = Some(report.clone());
+        report
+    }
+
+    /// Scrubs every `interval` (after the previous scrub ends) until the task is aborted
+    pub async fn run(self: Arc<Self>, interval: Duration) {
+        loop {
+            tokio::time::sleep(interval).await;
+            self.scrub().await;
+        }
+    }
+
+    /// Walks the stored main chain of `graph` in batches, keeping only the
+    /// previous block and the authority set in effect in memory
+    async fn scrub_graph(
+        &self,
+        graph: &BlockchainGraph,
+        report: &mut ScrubReport,
+        started: Instant,
+    ) {
+        let mut authorities = graph.authorities_at(&[]);
+        let mut previous: Option<Block> = None;
+        let mut next_height = 0;
+
+        loop {
+            let batch =
+                match self
+                    .repository
+                    .read_blocks_verified(&graph.id, next_height, SCRUB_BATCH)
+                {
+                    Ok(batch) => batch,
+                    Err(e) => {
+                        // A checksum mismatch surfaces here as a RocksDB corruption error
+                        report.issue(
+                            &graph.id,
+                            next_height,
+                            format!("Failed to read blocks: {}", e),
+                        );
+                        return;
+                    }
+                };
+            let batch_len = batch.len();
+
+            for stored in batch {
+                report.blocks_checked += 1;
+                if stored.key_height != next_height {
+                    report.issue(&graph.id, next_height, "Block is missing");
+                }
+                next_height = stored.key_height + 1;
+
+                let block = match stored.block {
+                    Ok(block) => block,
+                    Err(e) => {
+                        report.issue(
+                            &graph.id,
+                            stored.key_height,
+                            format!("Undecodable block: {}", e),
+                        );
+                        previous = None;
+                        continue;
+                    }
+                };
+                let checked = Self::check_block(
+                    graph,
+                    &block,
+                    stored.key_height,
+                    previous.as_ref(),
+                    authorities.as_ref(),
+                );
+                if let Err(message) = checked {
+                    report.issue(&graph.id, stored.key_height, message);
+                }
+                if stored.indexed_height != Some(block.height) {
+                    report.issue(
+                        &graph.id,
+                        stored.key_height,
+                        "Hash index does not point to the block",
+                    );
+                }
+                if graph.consensus.is_authority() && block.height > 0 {
+                    if let Some(update) = parse_authority_update(&block.data) {
+                        authorities = Some(update);
+                    }
+                }
+                previous = Some(block);
+            }
+
+            self.throttle(report.blocks_checked, started).await;
+            if batch_len < SCRUB_BATCH {
+                break;
+            }
+        }
+
+        let tip = next_height.checked_sub(1);
+        match self.repository.stored_tip_height(&graph.id) {
+            Ok(latest) if latest == tip => {}
+            Ok(latest) => report.issue(
+                &graph.id,
+                tip.unwrap_or_default(),
+                format!(
+                    "Latest pointer names height {:?}, stored tip is {:?}",
+                    latest, tip
+                ),
+            ),
+            Err(e) => report.issue(
+                &graph.id,
+                tip.unwrap_or_default(),
+                format!("Unreadable latest pointer: {}", e),
+            ),
+        }
+    }
+
+    /// Checks one block against its key, its predecessor and the graph's seal rules
+    fn check_block(
+        graph: &BlockchainGraph,
+        block: &Block,
+        key_height: u64,
+        previous: Option<&Block>,
+        authorities: Option<&AuthoritySet>,
+    ) -> Result<(), String> {
+        if block.height != key_height || block.graph_id != graph.id {
+            return Err(format!(
+                "Block is stored as #{} of '{}'",
+                key_height, graph.id
+            ));
+        }
+        if !block.is_valid() {
+            return Err("Invalid block hash".to_string());
+        }
+        if let Some(previous) = previous.filter(|p| p.height + 1 == block.height) {
+            if block.previous_hash != previous.hash {
+                return Err("Previous hash does not match the block below".to_string());
+            }
+        }
+        if block.height > 0 {
+            graph
+                .consensus
+                .check_seal(block, graph.difficulty, authorities)?;
+        }
+        Ok(())
+    }
+
+    /// Sleeps until `blocks_checked` fits the blocks-per-second budget
+    async fn throttle(&self, blocks_checked: u64, started: Instant) {
+        let due = Duration::from_secs_f64(blocks_checked as f64 / self.max_blocks_per_sec as f64);
+        if let Some(wait) = due.checked_sub(started.elapsed()) {
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, ScrubProgress> {
+        self.progress.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::traits::BlockchainRepository;
+    use crate::domain::GraphType;
+    use crate::infrastructure::persistence::RocksDbAdapter;
+    use tempfile::tempdir;
+
+    #[tokio::test]
+    async fn test_scrub_finds_a_rewritten_block() {
+        let dir = tempdir().unwrap();
+        let repository = Arc::new(BlockchainRepositoryImpl::new(Arc::new(
+            RocksDbAdapter::new(dir.path()).unwrap(),
+        )));
+        let mut graph = BlockchainGraph::new(
+            "ledger".to_string(),
+            GraphType::Transaction,
+            String::new(),
+            1,
+        );
+        for i in 0..3 {
+            let tip = graph.get_latest_block().unwrap().clone();
+            let block = Block::new(
+                tip.hash,
+                format!("tx {}", i),
+                "ledger".to_string(),
+                tip.height + 1,
+                vec![],
+            );
+            graph.add_block(block).unwrap();
+        }
+        for block in &graph.chain {
+            repository.save_block("ledger", block).await.unwrap();
+        }
+        repository.save_graph(&graph).await.unwrap();
+
+        let scrubber = IntegrityScrubber::new(repository.clone(), 1000);
+        let report = scrubber.scrub().await;
+        assert!(report.is_clean(), "{:?}", report.issues);
+        assert_eq!((report.graphs_checked, report.blocks_checked), (1, 4));
+
+        // Same height, data changed without re-mining
+        let mut tampered = graph.chain[2].clone();
+        tampered.data = "tx 99".to_string();
+        repository.save_block("ledger", &tampered).await.unwrap();
+        repository
+            .save_block("ledger", &graph.chain[3])
+            .await
+            .unwrap();
+
+        let report = scrubber.scrub().await;
+        assert_eq!(report.issues.len(), 1, "{:?}", report.issues);
+        assert_eq!(
+            (report.issues[0].height, report.issues[0].message.as_str()),
+            (2, "Invalid block hash")
+        );
+        assert_eq!(
+            IntegrityScrubber::new(repository, 1000).last_report(),
+            Some(report)
+        );
+    }
+}
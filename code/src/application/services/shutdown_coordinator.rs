@@ -0,0 +1,129 @@
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::time::Duration;
+use tokio::sync::Notify;
+
+/// Tracks in-flight write operations so shutdown can drain them
+/// before storage is flushed and closed
+pub struct ShutdownCoordinator {
+    accepting: AtomicBool,
+    in_flight: AtomicUsize,
+    idle: Notify,
+}
+
+/// Marks one in-flight operation; released on drop
+pub struct InFlightGuard<'a> {
+    coordinator: &'a ShutdownCoordinator,
+}
+
+impl ShutdownCoordinator {
+    pub fn new() -> Self {
+        Self {
+            accepting: AtomicBool::new(true),
+            in_flight: AtomicUsize::new(0),
+            idle: Notify::new(),
+        }
+    }
+
+    /// Registers a new operation, or returns None once shutdown has begun
+    pub fn try_enter(&self) -> Option<InFlightGuard<'_>> {
+        self.in_flight.fetch_add(1, Ordering::SeqCst);
+
+        if !self.accepting.load(Ordering::SeqCst) {
+            self.leave();
+            return None;
+        }
+
+        Some(InFlightGuard { coordinator: self })
+    }
+
+    /// Stops accepting new operations
+    pub fn begin_shutdown(&self) {
+        self.accepting.store(false, Ordering::SeqCst);
+    }
+
+    /// Returns true while new operations are accepted
+    pub fn is_accepting(&self) -> bool {
+        self.accepting.load(Ordering::SeqCst)
+    }
+
+    /// Number of operations currently in flight
+    pub fn in_flight(&self) -> usize {
+        self.in_flight.load(Ordering::SeqCst)
+    }
+
+    /// Waits until every in-flight operation has finished.
+    /// Returns false if the timeout elapsed first.
+    pub async fn wait_idle(&self, timeout: Duration) -> bool {
+        let drained = async {
+            loop {
+                let notified = self.idle.notified();
+                if self.in_flight() == 0 {
+                    return;
+                }
+                notified.await;
+            }
+        };
+
+        tokio::time::timeout(timeout, drained).await.is_ok()
+    }
+
+    fn leave(&self) {
+        if self.in_flight.fetch_sub(1, Ordering::SeqCst) == 1 {
+            self.idle.notify_waiters();
+        }
+    }
+}
+
+impl Default for ShutdownCoordinator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for InFlightGuard<'_> {
+    fn drop(&mut self) {
+        self.coordinator.leave();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_rejects_after_shutdown() {
+        let coordinator = ShutdownCoordinator::new();
+
+        let guard = coordinator.try_enter();
+        assert!(guard.is_some());
+        assert_eq!(coordinator.in_flight(), 1);
+
+        coordinator.begin_shutdown();
+        assert!(coordinator.try_enter().is_none());
+        assert_eq!(coordinator.in_flight(), 1);
+
+        drop(guard);
+        assert_eq!(coordinator.in_flight(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_wait_idle_drains_and_times_out() {
+        let coordinator = Arc::new(ShutdownCoordinator::new());
+        assert!(coordinator.wait_idle(Duration::from_millis(10)).await);
+
+        let worker = coordinator.clone();
+        let (started_tx, started_rx) = tokio::sync::oneshot::channel();
+        let handle = tokio::spawn(async move {
+            let _guard = worker.try_enter().unwrap();
+            started_tx.send(()).unwrap();
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        });
+        started_rx.await.unwrap();
+
+        coordinator.begin_shutdown();
+        assert!(!coordinator.wait_idle(Duration::from_millis(1)).await);
+        assert!(coordinator.wait_idle(Duration::from_secs(5)).await);
+        handle.await.unwrap();
+    }
+}
@@ -2,7 +2,7 @@ use async_trait::async_trait;
 use std::error::Error;
 
 use crate::domain::traits::ValidationStrategy;
-use crate::domain::BlockchainGraph;
+use crate::domain::{BlockchainGraph, VerifiedCheckpoint};
 
 /// Service for validating blockchain graphs (Strategy Pattern)
 pub struct ValidationService {
@@ -15,15 +15,40 @@ impl ValidationService {
             strategies: vec![
                 Box::new(ChainIntegrityValidator),
                 Box::new(BlockHashValidator),
-                Box::new(DifficultyValidator),
+                Box::new(SealValidator),
             ],
         }
     }
 
     /// Validates a graph using all registered strategies
     pub async fn validate_graph(&self, graph: &BlockchainGraph) -> Result<bool, Box<dyn Error>> {
+        self.validate_above(graph, 0).await
+    }
+
+    /// Validates only the blocks above `checkpoint` (the whole graph without
+    /// one, or when its block is no longer on the chain) and returns the tip
+    /// as the new checkpoint, or None if the graph is invalid
+    pub async fn validate_since(
+        &self,
+        graph: &BlockchainGraph,
+        checkpoint: Option<&VerifiedCheckpoint>,
+    ) -> Result<Option<VerifiedCheckpoint>, Box<dyn Error>> {
+        let from = checkpoint
+            .and_then(|c| c.resume_height(&graph.chain))
+            .unwrap_or(0);
+        if !self.validate_above(graph, from).await? {
+            return Ok(None);
+        }
+        Ok(graph.get_latest_block().map(VerifiedCheckpoint::of))
+    }
+
+    async fn validate_above(
+        &self,
+        graph: &BlockchainGraph,
+        from: u64,
+    ) -> Result<bool, Box<dyn Error>> {
         for strategy in &self.strategies {
-            if !strategy.validate(graph).await? {
+            if !strategy.validate(graph, from).await? {
                 return Ok(false);
             }
         }
@@ -32,13 +57,19 @@ impl ValidationService {
     }
 }
 
+impl Default for ValidationService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 /// Validates chain integrity (previous hash linking)
 struct ChainIntegrityValidator;
 
 #[async_trait]
 impl ValidationStrategy for ChainIntegrityValidator {
-    async fn validate(&self, graph: &BlockchainGraph) -> Result<bool, Box<dyn Error>> {
-        for i in 1..graph.chain.len() {
+    async fn validate(&self, graph: &BlockchainGraph, from: u64) -> Result<bool, Box<dyn Error>> {
+        for i in from as usize + 1..graph.chain.len() {
             let current = &graph.chain[i];
             let previous = &graph.chain[i - 1];
 
@@ -60,8 +91,8 @@ struct BlockHashValidator;
 
 #[async_trait]
 impl ValidationStrategy for BlockHashValidator {
-    async fn validate(&self, graph: &BlockchainGraph) -> Result<bool, Box<dyn Error>> {
-        for block in &graph.chain {
+    async fn validate(&self, graph: &BlockchainGraph, from: u64) -> Result<bool, Box<dyn Error>> {
+        for block in graph.chain.iter().skip(from as usize) {
             if !block.is_valid() {
                 return Ok(false);
             }
@@ -71,19 +102,15 @@ impl ValidationStrategy for BlockHashValidator {
     }
 }
 
-/// Validates proof of work difficulty
-struct DifficultyValidator;
+/// Validates block seals: proof-of-work difficulty, or authority
+/// signatures following on-chain authority updates
+struct SealValidator;
 
 #[async_trait]
-impl ValidationStrategy for DifficultyValidator {
-    async fn validate(&self, graph: &BlockchainGraph) -> Result<bool, Box<dyn Error>> {
-        for block in &graph.chain {
-            if !block.has_valid_difficulty(graph.difficulty) {
-                return Ok(false);
-            }
-        }
-
-        Ok(true)
+impl ValidationStrategy for SealValidator {
+    async fn validate(&self, graph: &BlockchainGraph, from: u64) -> Result<bool, Box<dyn Error>> {
+        // The genesis block is never sealed, so it is exempt from the check
+        Ok(graph.verify_seals_above(from).is_ok())
     }
 }
 
@@ -94,7 +121,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_validation_service() {
-        let mut graph = BlockchainGraph::new(
+        let graph = BlockchainGraph::new(
             "test".to_string(),
             GraphType::Transaction,
             "Test".to_string(),
@@ -107,4 +134,67 @@ mod tests {
         assert!(result.is_ok());
         assert!(result.unwrap());
     }
+
+    #[tokio::test]
+    async fn test_validation_detects_tampering() {
+        let mut graph = BlockchainGraph::new(
+            "test".to_string(),
+            GraphType::Transaction,
+            "Test".to_string(),
+            2,
+        );
+
+        let latest = graph.get_latest_block().unwrap().clone();
+        let block = Block::new(
+            latest.hash,
+            "data".to_string(),
+            "test".to_string(),
+            1,
+            vec![],
+        );
+        graph.add_block(block).unwrap();
+
+        let service = ValidationService::new();
+        assert!(service.validate_graph(&graph).await.unwrap());
+
+        graph.chain[1].data = "tampered".to_string();
+        assert!(!service.validate_graph(&graph).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_validate_since_checkpoint() {
+        let mut graph = BlockchainGraph::new(
+            "test".to_string(),
+            GraphType::Transaction,
+            "Test".to_string(),
+            1,
+        );
+        let service = ValidationService::new();
+        for data in ["a", "b"] {
+            let latest = graph.get_latest_block().unwrap().clone();
+            let height = latest.height + 1;
+            graph
+                .add_block(Block::new(
+                    latest.hash,
+                    data.to_string(),
+                    "test".to_string(),
+                    height,
+                    vec![],
+                ))
+                .unwrap();
+        }
+        let checkpoint = service.validate_since(&graph, None).await.unwrap().unwrap();
+        assert_eq!(checkpoint.height, 2);
+
+        // Only a full validation looks below the checkpoint again
+        graph.chain[1].data = "tampered".to_string();
+        assert_eq!(
+            service
+                .validate_since(&graph, Some(&checkpoint))
+                .await
+                .unwrap(),
+            Some(checkpoint)
+        );
+        assert_eq!(service.validate_since(&graph, None).await.unwrap(), None);
+    }
 }
@@ -30,7 +30,13 @@ impl AddBlockUseCase {
         };
 
         // Create new block
-        let block = Block::new(previous_hash, data, graph_id.clone(), height, cross_references);
+        let block = Block::new(
+            previous_hash,
+            data,
+            graph_id.clone(),
+            height,
+            cross_references,
+        );
 
         // Save block
         self.repository.save_block(&graph_id, &block).await?;
@@ -0,0 +1,422 @@
+use chrono::Utc;
+use flate2::read::MultiGzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::domain::traits::BlockchainRepository;
+use crate::domain::{Block, BlockchainGraph};
+use crate::infrastructure::contracts::wasm_runtime::DEFAULT_MAX_FUEL;
+use crate::infrastructure::contracts::WasmContractRuntime;
+
+/// Format identifier written in every archive manifest
+pub const ARCHIVE_FORMAT: &str = "blockchain-grpc-archive";
+/// Current archive format version
+pub const ARCHIVE_VERSION: u32 = 1;
+
+/// Summary of one graph in the archive manifest
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ArchivedGraphSummary {
+    pub graph_id: String,
+    pub block_count: u64,
+    pub tip_height: u64,
+    pub tip_hash: String,
+}
+
+/// First record of every archive; lists the tip of each exported graph
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ArchiveManifest {
+    pub format: String,
+    pub version: u32,
+    pub created_at: i64,
+    pub graphs: Vec<ArchivedGraphSummary>,
+}
+
+/// One line of a JSON Lines archive (see `docs/ARCHIVE_FORMAT.md`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "record", rename_all = "snake_case")]
+pub enum ArchiveRecord {
+    Manifest(ArchiveManifest),
+    Graph(BlockchainGraph),
+    Block(Block),
+}
+
+/// Opens an archive file for writing, gzip-compressed if requested
+pub fn create_archive_file<P: AsRef<Path>>(
+    path: P,
+    compress: bool,
+) -> Result<Box<dyn Write>, Box<dyn std::error::Error>> {
+    let file = BufWriter::new(File::create(path)?);
+    if compress {
+        Ok(Box::new(GzEncoder::new(file, Compression::default())))
+    } else {
+        Ok(Box::new(file))
+    }
+}
+
+/// Opens an archive file for reading, detecting gzip compression
+pub fn open_archive_file<P: AsRef<Path>>(
+    path: P,
+) -> Result<Box<dyn BufRead>, Box<dyn std::error::Error>> {
+    let mut file = BufReader::new(File::open(path)?);
+    let is_gzip = file.fill_buf()?.starts_with(&[0x1f, 0x8b]);
+    if is_gzip {
+        Ok(Box::new(BufReader::new(MultiGzDecoder::new(file))))
+    } else {
+        Ok(Box::new(file))
+    }
+}
+
+/// Use case for exporting graphs into a portable archive
+pub struct ExportGraphsUseCase {
+    repository: Arc<dyn BlockchainRepository>,
+}
+
+impl ExportGraphsUseCase {
+    pub fn new(repository: Arc<dyn BlockchainRepository>) -> Self {
+        Self { repository }
+    }
+
+    /// Exports the given graphs (all graphs if empty) and returns the manifest
+    pub async fn execute<W: Write>(
+        &self,
+        graph_ids: &[String],
+        mut writer: W,
+    ) -> Result<ArchiveManifest, Box<dyn std::error::Error>> {
+        let graphs = if graph_ids.is_empty() {
+            self.repository.list_graphs().await?
+        } else {
+            let mut graphs = Vec::new();
+            for graph_id in graph_ids {
+                match self.repository.get_graph(graph_id).await? {
+                    Some(graph) => graphs.push(graph),
+                    None => return Err(format!("Graph '{}' not found", graph_id).into()),
+                }
+            }
+            graphs
+        };
+
+        let mut summaries = Vec::new();
+        for graph in &graphs {
+            let tip = graph
+                .get_latest_block()
+                .ok_or_else(|| format!("Graph '{}' has no blocks", graph.id))?;
+            summaries.push(ArchivedGraphSummary {
+                graph_id: graph.id.clone(),
+                block_count: graph.get_chain_length(),
+                tip_height: tip.height,
+                tip_hash: tip.hash.clone(),
+            });
+        }
+
+        let manifest = ArchiveManifest {
+            format: ARCHIVE_FORMAT.to_string(),
+            version: ARCHIVE_VERSION,
+            created_at: Utc::now().timestamp(),
+            graphs: summaries,
+        };
+
+        write_record(&mut writer, &ArchiveRecord::Manifest(manifest.clone()))?;
+        for graph in graphs {
+            let blocks = graph.chain.clone();
+            write_record(&mut writer, &ArchiveRecord::Graph(graph))?;
+            for block in blocks {
+                write_record(&mut writer, &ArchiveRecord::Block(block))?;
+            }
+        }
+        writer.flush()?;
+
+        Ok(manifest)
+    }
+}
+
+/// Use case for importing graphs from an archive.
+/// Every block is re-verified (hash, link, height, difficulty), the world
+/// state is rebuilt by replaying read/write sets and contracts, and each
+/// graph must match its manifest entry before anything is written.
+pub struct ImportGraphsUseCase {
+    repository: Arc<dyn BlockchainRepository>,
+    max_contract_fuel: u64,
+}
+
+impl ImportGraphsUseCase {
+    pub fn new(repository: Arc<dyn BlockchainRepository>) -> Self {
+        Self {
+            repository,
+            max_contract_fuel: DEFAULT_MAX_FUEL,
+        }
+    }
+
+    /// Caps the fuel limit of the contracts deployed in imported graphs
+    pub fn with_max_contract_fuel(mut self, fuel: u64) -> Self {
+        self.max_contract_fuel = fuel;
+        self
+    }
+
+    /// Reads and verifies an archive without writing anything
+    pub fn read_archive<R: Read>(
+        reader: R,
+    ) -> Result<(ArchiveManifest, Vec<BlockchainGraph>), Box<dyn std::error::Error>> {
+        let reader = BufReader::new(reader);
+        let mut manifest: Option<ArchiveManifest> = None;
+        let mut graphs: Vec<BlockchainGraph> = Vec::new();
+
+        for (index, line) in reader.lines().enumerate() {
+            let line_no = index + 1;
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let record: ArchiveRecord = serde_json::from_str(&line)
+                .map_err(|e| format!("line {}: invalid record: {}", line_no, e))?;
+
+            match record {
+                ArchiveRecord::Manifest(m) => {
+                    if manifest.is_some() {
+                        return Err(format!("line {}: duplicate manifest", line_no).into());
+                    }
+                    if m.format != ARCHIVE_FORMAT || m.version != ARCHIVE_VERSION {
+                        return Err(format!(
+                            "Unsupported archive format {} v{}",
+                            m.format, m.version
+                        )
+                        .into());
+                    }
+                    manifest = Some(m);
+                }
+                _ if manifest.is_none() => {
+                    return Err(
+                        format!("line {}: manifest must be the first record", line_no).into(),
+                    );
+                }
+                ArchiveRecord::Graph(mut graph) => {
+                    if graphs.iter().any(|g| g.id == graph.id) {
+                        return Err(
+                            format!("line {}: duplicate graph '{}'", line_no, graph.id).into()
+                        );
+                    }
+                    graph.load_blocks(Vec::new());
+                    graphs.push(graph);
+                }
+                ArchiveRecord::Block(block) => {
+                    let graph = graphs.last_mut().ok_or_else(|| {
+                        format!("line {}: block before any graph record", line_no)
+                    })?;
+                    let height = block.height;
+                    graph.append_block(block).map_err(|e| {
+                        format!(
+                            "line {}: block {} of graph '{}': {}",
+                            line_no, height, graph.id, e
+                        )
+                    })?;
+                }
+            }
+        }
+
+        let manifest = manifest.ok_or("Archive is empty")?;
+        Self::check_manifest(&manifest, &graphs)?;
+
+        Ok((manifest, graphs))
+    }
+
+    /// Verifies and imports an archive. Fails without writing if any graph
+    /// is invalid, already exists or names payments or signers that are
+    /// neither in the archive nor stored.
+    pub async fn execute<R: Read>(
+        &self,
+        reader: R,
+    ) -> Result<ArchiveManifest, Box<dyn std::error::Error>> {
+        let (manifest, graphs) = Self::read_archive(reader)?;
+
+        for graph in &graphs {
+            if self.repository.graph_exists(&graph.id).await? {
+                return Err(format!("Graph '{}' already exists", graph.id).into());
+            }
+        }
+
+        // Payments and signers named by a block resolve against the archive
+        // and the graphs already stored
+        let order: Vec<String> = graphs.iter().map(|g| g.id.clone()).collect();
+        let mut directory: HashMap<String, BlockchainGraph> = self
+            .repository
+            .list_graphs()
+            .await?
+            .into_iter()
+            .map(|g| (g.id.clone(), g))
+            .collect();
+        directory.extend(graphs.into_iter().map(|g| (g.id.clone(), g)));
+        let graphs: Vec<&BlockchainGraph> = order.iter().map(|id| &directory[id]).collect();
+        for graph in &graphs {
+            for (index, block) in graph.chain.iter().enumerate().skip(1) {
+                graph
+                    .check_references(&graph.chain[..index], block, &directory)
+                    .map_err(|e| format!("Graph '{}', block #{}: {}", graph.id, block.height, e))?;
+            }
+        }
+
+        let runtime = WasmContractRuntime::with_max_fuel(self.max_contract_fuel);
+        let mut replayed = Vec::new();
+        for graph in &graphs {
+            let (_, writes) = graph
+                .replay_state(&runtime)
+                .map_err(|e| format!("Graph '{}': {}", graph.id, e))?;
+            replayed.push(writes);
+        }
+
+        for (graph, writes) in graphs.iter().zip(replayed) {
+            // Every block after genesis is written with its write set
+            if let Some(genesis) = graph.chain.first() {
+                self.repository.save_block(&graph.id, genesis).await?;
+            }
+            for (block, block_writes) in graph.chain.iter().skip(1).zip(&writes) {
+                self.repository
+                    .save_block_with_state(&graph.id, block, block_writes)
+                    .await?;
+            }
+            self.repository.save_graph(graph).await?;
+            tracing::info!(
+                "📥 Imported graph '{}' ({} blocks)",
+                graph.id,
+                graph.get_chain_length()
+            );
+        }
+
+        Ok(manifest)
+    }
+
+    /// Checks that the imported graphs match the manifest tips exactly
+    fn check_manifest(
+        manifest: &ArchiveManifest,
+        graphs: &[BlockchainGraph],
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let by_id: HashMap<&str, &BlockchainGraph> =
+            graphs.iter().map(|g| (g.id.as_str(), g)).collect();
+
+        if manifest.graphs.len() != graphs.len() {
+            return Err(format!(
+                "Manifest lists {} graph(s) but archive contains {}",
+                manifest.graphs.len(),
+                graphs.len()
+            )
+            .into());
+        }
+
+        for summary in &manifest.graphs {
+            let graph = by_id
+                .get(summary.graph_id.as_str())
+                .ok_or_else(|| format!("Graph '{}' missing from archive", summary.graph_id))?;
+            let tip = graph
+                .get_latest_block()
+                .ok_or_else(|| format!("Graph '{}' has no blocks", summary.graph_id))?;
+
+            if graph.get_chain_length() != summary.block_count
+                || tip.height != summary.tip_height
+                || tip.hash != summary.tip_hash
+            {
+                return Err(format!(
+                    "Graph '{}' does not match manifest tip #{} {}",
+                    summary.graph_id, summary.tip_height, summary.tip_hash
+                )
+                .into());
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn write_record<W: Write>(
+    writer: &mut W,
+    record: &ArchiveRecord,
+) -> Result<(), Box<dyn std::error::Error>> {
+    serde_json::to_writer(&mut *writer, record)?;
+    writer.write_all(b"\n")?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::GraphType;
+
+    fn sample_graph() -> BlockchainGraph {
+        let mut graph = BlockchainGraph::new(
+            "payments".to_string(),
+            GraphType::Transaction,
+            "Payments".to_string(),
+            1,
+        );
+        for i in 0..2 {
+            let latest = graph.get_latest_block().unwrap().clone();
+            let block = Block::new(
+                latest.hash,
+                format!("tx {}", i),
+                "payments".to_string(),
+                latest.height + 1,
+                vec![],
+            );
+            graph.add_block(block).unwrap();
+        }
+        graph
+    }
+
+    fn archive_lines(graph: &BlockchainGraph) -> Vec<String> {
+        let tip = graph.get_latest_block().unwrap();
+        let manifest = ArchiveManifest {
+            format: ARCHIVE_FORMAT.to_string(),
+            version: ARCHIVE_VERSION,
+            created_at: 0,
+            graphs: vec![ArchivedGraphSummary {
+                graph_id: graph.id.clone(),
+                block_count: graph.get_chain_length(),
+                tip_height: tip.height,
+                tip_hash: tip.hash.clone(),
+            }],
+        };
+
+        let mut lines = vec![
+            serde_json::to_string(&ArchiveRecord::Manifest(manifest)).unwrap(),
+            serde_json::to_string(&ArchiveRecord::Graph(graph.clone())).unwrap(),
+        ];
+        for block in &graph.chain {
+            lines.push(serde_json::to_string(&ArchiveRecord::Block(block.clone())).unwrap());
+        }
+        lines
+    }
+
+    #[test]
+    fn test_read_valid_archive() {
+        let graph = sample_graph();
+        let archive = archive_lines(&graph).join("\n");
+
+        let (manifest, graphs) = ImportGraphsUseCase::read_archive(archive.as_bytes()).unwrap();
+        assert_eq!(manifest.graphs.len(), 1);
+        assert_eq!(graphs[0].chain, graph.chain);
+    }
+
+    #[test]
+    fn test_read_rejects_tampered_block() {
+        let graph = sample_graph();
+        let mut lines = archive_lines(&graph);
+        lines[3] = lines[3].replace("tx 0", "tx 9");
+
+        let err = ImportGraphsUseCase::read_archive(lines.join("\n").as_bytes()).unwrap_err();
+        assert!(err.to_string().contains("Invalid block hash"));
+    }
+
+    #[test]
+    fn test_read_rejects_truncated_archive() {
+        let graph = sample_graph();
+        let mut lines = archive_lines(&graph);
+        lines.pop();
+
+        let err = ImportGraphsUseCase::read_archive(lines.join("\n").as_bytes()).unwrap_err();
+        assert!(err.to_string().contains("does not match manifest"));
+    }
+}
@@ -1,2 +1,3 @@
 pub mod add_block;
+pub mod graph_archive;
 pub mod verify_graphs;
@@ -9,6 +9,8 @@ use crate::domain::BlockchainGraph;
 pub struct VerifyGraphsUseCase {
     repository: Arc<dyn BlockchainRepository>,
     validation_service: ValidationService,
+    /// Ignore stored checkpoints and verify every block
+    full: bool,
 }
 
 impl VerifyGraphsUseCase {
@@ -16,9 +18,17 @@ impl VerifyGraphsUseCase {
         Self {
             repository,
             validation_service: ValidationService::new(),
+            full: false,
         }
     }
 
+    /// Verifies every block from genesis instead of resuming from the
+    /// stored checkpoints (deep audit)
+    pub fn with_full_verification(mut self, full: bool) -> Self {
+        self.full = full;
+        self
+    }
+
     /// Executes cross-validation of all graphs
     pub async fn execute(&self) -> Result<HashMap<String, bool>, Box<dyn std::error::Error>> {
         let mut results = HashMap::new();
@@ -28,7 +38,7 @@ impl VerifyGraphsUseCase {
 
         // Validate each graph internally
         for graph in &graphs {
-            let is_valid = self.validation_service.validate_graph(graph).await?;
+            let is_valid = self.verify_graph(graph).await?;
             results.insert(graph.id.clone(), is_valid);
         }
 
@@ -44,4 +54,36 @@ impl VerifyGraphsUseCase {
 
         Ok(results)
     }
+
+    /// Validates one graph above its stored checkpoint and moves the
+    /// checkpoint to the tip; an invalid graph loses its checkpoint
+    pub async fn verify_graph(
+        &self,
+        graph: &BlockchainGraph,
+    ) -> Result<bool, Box<dyn std::error::Error>> {
+        let checkpoint = match self.full {
+            true => None,
+            false => self.repository.get_verified_checkpoint(&graph.id).await?,
+        };
+        match self
+            .validation_service
+            .validate_since(graph, checkpoint.as_ref())
+            .await?
+        {
+            Some(verified) => {
+                if checkpoint.as_ref() != Some(&verified) {
+                    self.repository
+                        .save_verified_checkpoint(&graph.id, &verified)
+                        .await?;
+                }
+                Ok(true)
+            }
+            None => {
+                self.repository
+                    .delete_verified_checkpoint(&graph.id)
+                    .await?;
+                Ok(false)
+            }
+        }
+    }
 }
@@ -0,0 +1,756 @@
+//! Offline administration tool for the blockchain RocksDB store.
+//! Opens the database directly, so the gRPC server must be stopped first.
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use clap::{Parser, Subcommand};
+
+use blockchain_grpc::application::use_cases::graph_archive::{
+    create_archive_file, open_archive_file, ExportGraphsUseCase, ImportGraphsUseCase,
+};
+use blockchain_grpc::application::use_cases::verify_graphs::VerifyGraphsUseCase;
+use blockchain_grpc::config::SettingsOverrides;
+use blockchain_grpc::domain::asset::parse_asset_operation;
+use blockchain_grpc::domain::consensus::{
+    generate_signing_key, public_key_hex, sign_message, signing_key_from_hex,
+};
+use blockchain_grpc::domain::identity::{parse_identity_operation, sign_as_identity};
+use blockchain_grpc::domain::receipt::{verify_receipt, Receipt};
+use blockchain_grpc::domain::traits::BlockchainRepository;
+use blockchain_grpc::infrastructure::persistence::backup::{list_backups_in, restore_backup};
+use blockchain_grpc::infrastructure::persistence::{
+    BackupManager, BlockchainRepositoryImpl, RocksDbAdapter,
+};
+use blockchain_grpc::Settings;
+
+#[derive(Parser)]
+#[command(
+    name = "blockchain-admin",
+    about = "Offline operations on the blockchain database (stop the server first)"
+)]
+struct Cli {
+    /// JSON configuration file (defaults to ./config.json if present)
+    #[arg(long)]
+    config: Option<String>,
+
+    /// Overrides `storage.data_dir` from the configuration
+    #[arg(long)]
+    data_dir: Option<String>,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Lists every graph with its type, difficulty and tip
+    ListGraphs,
+
+    /// Prints a block as JSON, selected by height or hash
+    DumpBlock {
+        graph_id: String,
+        #[arg(long, conflicts_with = "hash", required_unless_present = "hash")]
+        height: Option<u64>,
+        #[arg(long)]
+        hash: Option<String>,
+    },
+
+    /// Verifies one graph, or all graphs plus cross-references, from the
+    /// stored verified-up-to checkpoints
+    Verify {
+        graph_id: Option<String>,
+        /// Verifies every block from genesis, ignoring checkpoints
+        #[arg(long)]
+        full: bool,
+    },
+
+    /// Prints key, byte and block counts for the database
+    Stats,
+
+    /// Rebuilds `graph_list` and `latest:` keys from stored block data
+    Repair {
+        /// Reports what would change without writing
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Builds the full-text search index of graphs (all with search enabled
+    /// if none given), enabling search on the ones named without it
+    RebuildSearch { graph_ids: Vec<String> },
+
+    /// Exports graphs (all if none given) to a JSON Lines archive
+    Export {
+        /// Archive file to write
+        #[arg(long, short)]
+        output: PathBuf,
+        /// Gzip-compresses the archive
+        #[arg(long)]
+        gzip: bool,
+        graph_ids: Vec<String>,
+    },
+
+    /// Verifies an archive and imports its graphs (creates the database if needed)
+    Import {
+        /// Archive file to read (plain or gzip)
+        input: PathBuf,
+        /// Only verifies the archive, writes nothing
+        #[arg(long)]
+        verify_only: bool,
+    },
+
+    /// Creates a checkpoint backup of a stopped database
+    /// (use the CreateBackup RPC while the server is running)
+    Backup,
+
+    /// Lists backups in `storage.backup_dir`
+    ListBackups,
+
+    /// Restores a backup into the data directory and validates every chain
+    Restore {
+        /// Backup id, backup directory path, or `latest`
+        backup: String,
+        /// Moves an existing data directory aside instead of failing
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Creates a proof-of-authority key file and prints its public key
+    GenerateKey {
+        /// Key file to write (must not exist)
+        #[arg(long, short)]
+        out: PathBuf,
+    },
+
+    /// Prints the endorsement signature of a proposal id (for EndorseProposal)
+    SignProposal {
+        /// Key file of the endorsing organization
+        #[arg(long)]
+        key: PathBuf,
+        proposal_id: String,
+    },
+
+    /// Signs an asset operation as the asset's owner and prints the block
+    /// data to send with AddBlock
+    SignAsset {
+        /// Key file of the current owner
+        #[arg(long)]
+        key: PathBuf,
+        /// Asset graph the operation is for
+        #[arg(long)]
+        graph_id: String,
+        /// Current version of the asset (GetAsset)
+        #[arg(long)]
+        version: u64,
+        /// Operation JSON, e.g. {"op":"transfer","asset_id":"car-7","to":"<public key>"}
+        operation: String,
+    },
+
+    /// Signs an identity operation and prints the block data to send with
+    /// AddBlock (register with one of the new keys, rotate or revoke with a
+    /// current one)
+    SignIdentity {
+        /// Key file of the identity
+        #[arg(long)]
+        key: PathBuf,
+        /// Identity graph the operation is for
+        #[arg(long)]
+        graph_id: String,
+        /// Current version of the identity (ResolveIdentity); 0 to register
+        #[arg(long, default_value_t = 0)]
+        version: u64,
+        /// Operation JSON, e.g. {"op":"rotate","id":"did:example:alice","public_keys":["<public key>"]}
+        operation: String,
+    },
+
+    /// Adds a `signer` to JSON block data so it is signed by an identity
+    SignData {
+        /// Key file of the identity
+        #[arg(long)]
+        key: PathBuf,
+        /// Graph the data will be added to
+        #[arg(long)]
+        graph_id: String,
+        /// Identity graph the identity is registered in
+        #[arg(long)]
+        identity_graph: String,
+        /// DID of the identity
+        #[arg(long)]
+        id: String,
+        data: String,
+    },
+
+    /// Verifies a notarization receipt from AddBlock offline, against an
+    /// exported archive or, for the block itself, a trusted tip hash
+    VerifyReceipt {
+        /// Receipt JSON file
+        receipt: PathBuf,
+        /// Archive holding the receipt's graph (and anchor graph, if anchored)
+        #[arg(long, required_unless_present = "tip")]
+        archive: Option<PathBuf>,
+        /// Hash of a trusted tip the block must lead to
+        #[arg(long)]
+        tip: Option<String>,
+        /// Public key the node must have signed with
+        #[arg(long)]
+        public_key: Option<String>,
+    },
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    tracing_subscriber::fmt()
+        .with_env_filter(
+            tracing_subscriber::EnvFilter::try_from_default_env()
+                .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new("warn")),
+        )
+        .init();
+
+    let cli = Cli::parse();
+
+    let overrides = SettingsOverrides {
+        data_dir: cli.data_dir,
+        ..Default::default()
+    };
+    let settings = Settings::load_layered(cli.config.as_deref(), &overrides)?;
+    let data_dir = settings.storage.data_dir;
+    let backup_dir = settings.storage.backup_dir;
+
+    // These commands must not open (or create) the live database
+    match &cli.command {
+        Command::ListBackups => return list_backups(&backup_dir),
+        Command::GenerateKey { out } => return generate_key(out),
+        Command::SignProposal { key, proposal_id } => return sign_proposal(key, proposal_id),
+        Command::SignAsset {
+            key,
+            graph_id,
+            version,
+            operation,
+        } => return sign_asset(key, graph_id, *version, operation),
+        Command::SignIdentity {
+            key,
+            graph_id,
+            version,
+            operation,
+        } => return sign_identity(key, graph_id, *version, operation),
+        Command::SignData {
+            key,
+            graph_id,
+            identity_graph,
+            id,
+            data,
+        } => return sign_data(key, graph_id, identity_graph, id, data),
+        Command::VerifyReceipt {
+            receipt,
+            archive,
+            tip,
+            public_key,
+        } => {
+            return verify_receipt_file(
+                receipt,
+                archive.as_deref(),
+                tip.as_deref(),
+                public_key.as_deref(),
+            )
+        }
+        Command::Restore { backup, force } => {
+            return restore(&backup_dir, backup, &data_dir, *force).await
+        }
+        _ => {}
+    }
+
+    let db = match cli.command {
+        Command::Import { .. } => RocksDbAdapter::new(&data_dir),
+        _ => RocksDbAdapter::open_existing(&data_dir),
+    }
+    .map_err(|e| format!("Failed to open database at {}: {}", data_dir, e))?;
+    let db = Arc::new(db);
+    let repository = Arc::new(BlockchainRepositoryImpl::new(db.clone()));
+
+    match cli.command {
+        Command::ListGraphs => list_graphs(&repository).await,
+        Command::DumpBlock {
+            graph_id,
+            height,
+            hash,
+        } => dump_block(&repository, &graph_id, height, hash).await,
+        Command::Verify { graph_id, full } => verify(repository, graph_id, full).await,
+        Command::Stats => stats(&repository),
+        Command::Repair { dry_run } => repair(&repository, dry_run).await,
+        Command::RebuildSearch { graph_ids } => rebuild_search(&repository, graph_ids).await,
+        Command::Export {
+            output,
+            gzip,
+            graph_ids,
+        } => export(repository, &graph_ids, output, gzip).await,
+        Command::Import { input, verify_only } => {
+            import(repository, input, verify_only, settings.contracts.max_fuel).await
+        }
+        Command::Backup => {
+            let backup = BackupManager::new(db, &backup_dir, settings.storage.max_backups)
+                .create_backup()?;
+            println!(
+                "Backup {} created at {}",
+                backup.backup_id,
+                backup.path.display()
+            );
+            Ok(())
+        }
+        Command::ListBackups
+        | Command::Restore { .. }
+        | Command::GenerateKey { .. }
+        | Command::SignProposal { .. }
+        | Command::SignAsset { .. }
+        | Command::SignIdentity { .. }
+        | Command::SignData { .. }
+        | Command::VerifyReceipt { .. } => {
+            unreachable!("handled above")
+        }
+    }
+}
+
+async fn list_graphs(
+    repository: &BlockchainRepositoryImpl,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let graphs = repository.list_graphs().await?;
+
+    println!("{} graph(s)", graphs.len());
+    for graph in graphs {
+        let tip = graph
+            .get_latest_block()
+            .map(|b| format!("#{} {}", b.height, b.hash))
+            .unwrap_or_else(|| "<empty>".to_string());
+
+        println!(
+            "  {} ({:?}) difficulty={} blocks={} tip={}",
+            graph.id,
+            graph.graph_type,
+            graph.difficulty,
+            graph.get_chain_length(),
+            tip
+        );
+    }
+
+    Ok(())
+}
+
+async fn dump_block(
+    repository: &BlockchainRepositoryImpl,
+    graph_id: &str,
+    height: Option<u64>,
+    hash: Option<String>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let block = match (height, hash) {
+        (Some(height), _) => repository.get_block_by_height(graph_id, height).await?,
+        (None, Some(hash)) => repository.get_block(graph_id, &hash).await?,
+        (None, None) => return Err("Either --height or --hash is required".into()),
+    };
+
+    match block {
+        Some(block) => {
+            println!("{}", serde_json::to_string_pretty(&block)?);
+            Ok(())
+        }
+        None => Err(format!("Block not found in graph '{}'", graph_id).into()),
+    }
+}
+
+async fn verify(
+    repository: Arc<BlockchainRepositoryImpl>,
+    graph_id: Option<String>,
+    full: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let use_case = VerifyGraphsUseCase::new(repository.clone()).with_full_verification(full);
+    let results = match graph_id {
+        Some(graph_id) => {
+            let graph = repository
+                .get_graph(&graph_id)
+                .await?
+                .ok_or_else(|| format!("Graph '{}' not found", graph_id))?;
+            let is_valid = use_case.verify_graph(&graph).await?;
+            vec![(graph_id, is_valid)]
+        }
+        None => {
+            let mut results: Vec<_> = use_case.execute().await?.into_iter().collect();
+            results.sort();
+            results
+        }
+    };
+
+    let mut all_valid = true;
+    for (graph_id, is_valid) in &results {
+        println!("  {} {}", if *is_valid { "✓" } else { "✗" }, graph_id);
+        all_valid &= is_valid;
+    }
+
+    if all_valid {
+        println!("All {} graph(s) valid", results.len());
+        Ok(())
+    } else {
+        Err("Verification failed".into())
+    }
+}
+
+fn stats(repository: &BlockchainRepositoryImpl) -> Result<(), Box<dyn std::error::Error>> {
+    let stats = repository.storage_stats()?;
+
+    println!("Keys:  {}", stats.total_keys);
+    println!("Bytes: {}", stats.total_bytes);
+    if let Some(size) = stats.estimated_live_data_size {
+        println!("Estimated live data size: {}", size);
+    }
+    if let Some(size) = stats.total_sst_files_size {
+        println!("Total SST files size: {}", size);
+    }
+
+    println!("By prefix:");
+    for (prefix, prefix_stats) in &stats.prefixes {
+        println!(
+            "  {:<12} keys={} bytes={}",
+            prefix, prefix_stats.keys, prefix_stats.bytes
+        );
+    }
+
+    println!("Blocks per graph:");
+    for (graph_id, blocks) in &stats.blocks_per_graph {
+        println!("  {:<20} {}", graph_id, blocks);
+    }
+
+    Ok(())
+}
+
+async fn repair(
+    repository: &BlockchainRepositoryImpl,
+    dry_run: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let report = repository.repair_indexes(dry_run).await?;
+
+    if report.previous_graph_list != report.graph_list {
+        println!(
+            "graph_list: {:?} -> {:?}",
+            report.previous_graph_list, report.graph_list
+        );
+    }
+    for (graph_id, old, new) in &report.latest_fixed {
+        println!("latest:{}: {:?} -> {}", graph_id, old, new);
+    }
+    for (graph_id, missing) in &report.gaps {
+        println!(
+            "⚠️  {}: block at height {} is missing, tip truncated",
+            graph_id, missing
+        );
+    }
+    for graph_id in &report.orphan_block_graphs {
+        println!("⚠️  {}: blocks found without graph metadata", graph_id);
+    }
+
+    match (report.has_changes(), dry_run) {
+        (false, _) => println!("Nothing to repair"),
+        (true, true) => println!("Dry run: no changes written"),
+        (true, false) => println!("Repair complete"),
+    }
+
+    Ok(())
+}
+
+async fn rebuild_search(
+    repository: &BlockchainRepositoryImpl,
+    graph_ids: Vec<String>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let graph_ids = if graph_ids.is_empty() {
+        let mut enabled = Vec::new();
+        for graph in repository.list_graphs().await? {
+            if repository.search_enabled(&graph.id).await? {
+                enabled.push(graph.id);
+            }
+        }
+        enabled
+    } else {
+        graph_ids
+    };
+    if graph_ids.is_empty() {
+        println!("No graph has search enabled");
+        return Ok(());
+    }
+
+    for graph_id in &graph_ids {
+        if !repository.graph_exists(graph_id).await? {
+            return Err(format!("Graph '{}' not found", graph_id).into());
+        }
+        let indexed = if repository.search_enabled(graph_id).await? {
+            repository.rebuild_search_index(graph_id).await?
+        } else {
+            repository.enable_search(graph_id).await?
+        };
+        println!("  {} {} block(s) indexed", graph_id, indexed);
+    }
+
+    Ok(())
+}
+
+async fn export(
+    repository: Arc<BlockchainRepositoryImpl>,
+    graph_ids: &[String],
+    output: PathBuf,
+    gzip: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let writer = create_archive_file(&output, gzip)?;
+    let manifest = ExportGraphsUseCase::new(repository)
+        .execute(graph_ids, writer)
+        .await?;
+
+    for graph in &manifest.graphs {
+        println!(
+            "  {} blocks={} tip=#{} {}",
+            graph.graph_id, graph.block_count, graph.tip_height, graph.tip_hash
+        );
+    }
+    println!(
+        "Exported {} graph(s) to {}",
+        manifest.graphs.len(),
+        output.display()
+    );
+
+    Ok(())
+}
+
+async fn import(
+    repository: Arc<BlockchainRepositoryImpl>,
+    input: PathBuf,
+    verify_only: bool,
+    max_contract_fuel: u64,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let reader = open_archive_file(&input)?;
+
+    let manifest = if verify_only {
+        ImportGraphsUseCase::read_archive(reader)?.0
+    } else {
+        ImportGraphsUseCase::new(repository)
+            .with_max_contract_fuel(max_contract_fuel)
+            .execute(reader)
+            .await?
+    };
+
+    for graph in &manifest.graphs {
+        println!(
+            "  ✓ {} blocks={} tip=#{}",
+            graph.graph_id, graph.block_count, graph.tip_height
+        );
+    }
+    if verify_only {
+        println!(
+            "Archive verified, {} graph(s), nothing written",
+            manifest.graphs.len()
+        );
+    } else {
+        println!("Imported {} graph(s)", manifest.graphs.len());
+    }
+
+    Ok(())
+}
+
+/// Writes a new authority key (hex seed) readable only by the owner
+fn generate_key(out: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    if out.exists() {
+        return Err(format!("{} already exists", out.display()).into());
+    }
+    let key = generate_signing_key();
+    std::fs::write(out, hex::encode(key.to_bytes()))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(out, std::fs::Permissions::from_mode(0o600))?;
+    }
+
+    println!("Key written to {}", out.display());
+    println!("Public key: {}", public_key_hex(&key));
+    Ok(())
+}
+
+fn sign_proposal(key: &Path, proposal_id: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let key = signing_key_from_hex(&std::fs::read_to_string(key)?)?;
+    println!("{}", sign_message(&key, proposal_id.as_bytes()));
+    Ok(())
+}
+
+fn sign_asset(
+    key: &Path,
+    graph_id: &str,
+    version: u64,
+    operation: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let key = signing_key_from_hex(&std::fs::read_to_string(key)?)?;
+    let operation = parse_asset_operation(operation)?
+        .ok_or("Operation must be a JSON object with an \"op\" field")?;
+    println!("{}", operation.signed(&key, graph_id, version).to_data());
+    Ok(())
+}
+
+fn sign_identity(
+    key: &Path,
+    graph_id: &str,
+    version: u64,
+    operation: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let key = signing_key_from_hex(&std::fs::read_to_string(key)?)?;
+    let operation = parse_identity_operation(operation)?
+        .ok_or("Operation must be a JSON object with an \"op\" field")?;
+    println!("{}", operation.signed(&key, graph_id, version).to_data());
+    Ok(())
+}
+
+fn sign_data(
+    key: &Path,
+    graph_id: &str,
+    identity_graph: &str,
+    id: &str,
+    data: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let key = signing_key_from_hex(&std::fs::read_to_string(key)?)?;
+    println!(
+        "{}",
+        sign_as_identity(data, graph_id, identity_graph, id, &key)?
+    );
+    Ok(())
+}
+
+/// Without an archive the trusted tip must be the receipted block itself
+fn verify_receipt_file(
+    receipt: &Path,
+    archive: Option<&Path>,
+    tip: Option<&str>,
+    public_key: Option<&str>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let receipt: Receipt = serde_json::from_str(&std::fs::read_to_string(receipt)?)?;
+    let anchor_checked = match archive {
+        Some(archive) => {
+            let (_, graphs) = ImportGraphsUseCase::read_archive(open_archive_file(archive)?)?;
+            verify_receipt(&receipt, &graphs, public_key, tip)?;
+            receipt
+                .anchor
+                .as_ref()
+                .is_some_and(|anchor| graphs.iter().any(|graph| graph.id == anchor.graph_id))
+        }
+        None => {
+            receipt.verify_signature(public_key)?;
+            if tip != Some(receipt.block_hash.as_str()) {
+                return Err(
+                    "The block is not the trusted tip; pass --archive to verify the path to it"
+                        .into(),
+                );
+            }
+            false
+        }
+    };
+
+    println!(
+        "✓ Block #{} {} of '{}' notarized by {}",
+        receipt.height, receipt.block_hash, receipt.graph_id, receipt.signer
+    );
+    if let Some(anchor) = &receipt.anchor {
+        let status = if anchor_checked {
+            "✓"
+        } else {
+            "not checked:"
+        };
+        println!(
+            "  {} anchored by #{} {} of '{}'",
+            status, anchor.height, anchor.hash, anchor.graph_id
+        );
+    }
+    Ok(())
+}
+
+fn list_backups(backup_dir: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let backups = list_backups_in(backup_dir)?;
+
+    println!("{} backup(s) in {}", backups.len(), backup_dir);
+    for backup in backups {
+        let created_at = chrono::DateTime::from_timestamp_millis(backup.created_at_ms)
+            .map(|t| t.to_rfc3339())
+            .unwrap_or_default();
+        println!("  {} {}", backup.backup_id, created_at);
+    }
+
+    Ok(())
+}
+
+/// Restores into a staging directory, validates every chain (hashes, links,
+/// difficulty and cross-references) and only then moves it into place
+async fn restore(
+    backup_dir: &str,
+    backup: &str,
+    data_dir: &str,
+    force: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let backup_path = if backup == "latest" {
+        list_backups_in(backup_dir)?
+            .pop()
+            .ok_or_else(|| format!("No backups found in {}", backup_dir))?
+            .path
+    } else if Path::new(backup).is_dir() {
+        PathBuf::from(backup)
+    } else {
+        Path::new(backup_dir).join(backup)
+    };
+    if !backup_path.is_dir() {
+        return Err(format!("Backup {} not found", backup_path.display()).into());
+    }
+
+    let data_path = Path::new(data_dir);
+    if data_path.exists() && !force {
+        return Err(format!(
+            "{} already exists; stop the server and pass --force to replace it",
+            data_dir
+        )
+        .into());
+    }
+
+    let staging = PathBuf::from(format!("{}.restoring", data_dir));
+    if staging.exists() {
+        std::fs::remove_dir_all(&staging)?;
+    }
+    restore_backup(&backup_path, &staging)?;
+
+    let validation = {
+        let db = Arc::new(RocksDbAdapter::open_existing(&staging)?);
+        let repository = Arc::new(BlockchainRepositoryImpl::new(db.clone()));
+        let results = VerifyGraphsUseCase::new(repository)
+            .with_full_verification(true)
+            .execute()
+            .await?;
+        if results.values().all(|valid| *valid) {
+            db.mark_clean_shutdown()?;
+        }
+        results
+    };
+
+    let mut invalid: Vec<_> = validation
+        .iter()
+        .filter(|(_, valid)| !**valid)
+        .map(|(id, _)| id.clone())
+        .collect();
+    if !invalid.is_empty() {
+        invalid.sort();
+        std::fs::remove_dir_all(&staging)?;
+        return Err(format!("Restored backup has invalid graphs: {}", invalid.join(", ")).into());
+    }
+
+    if data_path.exists() {
+        let aside = format!(
+            "{}.pre-restore-{}",
+            data_dir,
+            chrono::Utc::now().timestamp()
+        );
+        std::fs::rename(data_path, &aside)?;
+        println!("Previous data directory moved to {}", aside);
+    }
+    std::fs::rename(&staging, data_path)?;
+
+    println!(
+        "Restored {} into {} ({} graph(s) validated)",
+        backup_path.display(),
+        data_dir,
+        validation.len()
+    );
+    Ok(())
+}
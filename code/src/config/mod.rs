@@ -1,3 +1,6 @@
 pub mod settings;
 
-pub use settings::Settings;
+pub use settings::{
+    AuthoritySettings, ClusterSettings, EndorsementSettings, GossipSettings, NodeRole, Settings,
+    SettingsOverrides,
+};
@@ -1,5 +1,10 @@
+use config::{Config, Environment, File, FileFormat};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fs;
+use std::net::SocketAddr;
+use std::path::Path;
+use std::time::Duration;
 
 /// Application settings (Configuration Pattern)
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -7,23 +12,443 @@ pub struct Settings {
     pub server: ServerSettings,
     pub blockchain: BlockchainSettings,
     pub storage: StorageSettings,
+    #[serde(default)]
+    pub replication: ReplicationSettings,
+    #[serde(default)]
+    pub cluster: ClusterSettings,
+    #[serde(default)]
+    pub gossip: GossipSettings,
+    #[serde(default)]
+    pub authority: AuthoritySettings,
+    #[serde(default)]
+    pub endorsement: EndorsementSettings,
+    #[serde(default)]
+    pub contracts: ContractSettings,
+    #[serde(default)]
+    pub http: HttpSettings,
+    #[serde(default)]
+    pub audit: AuditSettings,
+    #[serde(default)]
+    pub anchoring: AnchorSettings,
+    #[serde(default)]
+    pub receipts: ReceiptSettings,
+    #[serde(default)]
+    pub scrub: ScrubSettings,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ServerSettings {
     pub host: String,
     pub port: u16,
+    /// Seconds to wait for in-flight requests on shutdown
+    #[serde(default = "default_shutdown_timeout_secs")]
+    pub shutdown_timeout_secs: u64,
+}
+
+fn default_shutdown_timeout_secs() -> u64 {
+    30
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BlockchainSettings {
     pub default_difficulty: usize,
     pub max_block_size: usize,
+    /// Seconds between moves of the verified checkpoints to the graph tips
+    #[serde(default = "default_checkpoint_interval_secs")]
+    pub checkpoint_interval_secs: u64,
+}
+
+fn default_checkpoint_interval_secs() -> u64 {
+    60
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StorageSettings {
     pub data_dir: String,
+    /// Directory where online backups (checkpoints) are written
+    #[serde(default = "default_backup_dir")]
+    pub backup_dir: String,
+    /// Number of backups to keep; older ones are deleted (0 keeps all)
+    #[serde(default = "default_max_backups")]
+    pub max_backups: usize,
+}
+
+/// Replication role of a node
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum NodeRole {
+    /// Accepts writes and streams them to followers
+    #[default]
+    Leader,
+    /// Read-only replica of `leader_address`
+    Follower,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplicationSettings {
+    #[serde(default)]
+    pub role: NodeRole,
+    /// gRPC address of the leader (required for followers)
+    #[serde(default)]
+    pub leader_address: String,
+    /// Milliseconds to wait before reconnecting to the leader
+    #[serde(default = "default_retry_interval_ms")]
+    pub retry_interval_ms: u64,
+}
+
+impl Default for ReplicationSettings {
+    fn default() -> Self {
+        Self {
+            role: NodeRole::Leader,
+            leader_address: String::new(),
+            retry_interval_ms: default_retry_interval_ms(),
+        }
+    }
+}
+
+fn default_retry_interval_ms() -> u64 {
+    2000
+}
+
+/// One member of a Raft cluster
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ClusterMemberSettings {
+    pub id: u64,
+    /// Address of this member's cluster listener (Raft and forwarded writes);
+    /// a node binds `server.host` on this address's port
+    pub address: String,
+}
+
+/// Clustered mode: AddBlock/CreateGraph are ordered through a Raft log
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClusterSettings {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Id of this node; must appear in `members`
+    #[serde(default)]
+    pub node_id: u64,
+    /// Every member of the cluster, including this node
+    #[serde(default)]
+    pub members: Vec<ClusterMemberSettings>,
+    /// Shared secret every member presents on the cluster listener;
+    /// best set through `BLOCKCHAIN_CLUSTER__SECRET`
+    #[serde(default)]
+    pub secret: String,
+    #[serde(default = "default_election_timeout_min_ms")]
+    pub election_timeout_min_ms: u64,
+    #[serde(default = "default_election_timeout_max_ms")]
+    pub election_timeout_max_ms: u64,
+    #[serde(default = "default_heartbeat_interval_ms")]
+    pub heartbeat_interval_ms: u64,
+    /// Applied Raft entries kept for members that fall behind; a member
+    /// further behind has to be restored from a backup
+    #[serde(default = "default_retained_log_entries")]
+    pub retained_log_entries: u64,
+}
+
+impl Default for ClusterSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            node_id: 0,
+            members: Vec::new(),
+            secret: String::new(),
+            election_timeout_min_ms: default_election_timeout_min_ms(),
+            election_timeout_max_ms: default_election_timeout_max_ms(),
+            heartbeat_interval_ms: default_heartbeat_interval_ms(),
+            retained_log_entries: default_retained_log_entries(),
+        }
+    }
+}
+
+fn default_election_timeout_min_ms() -> u64 {
+    300
+}
+
+fn default_election_timeout_max_ms() -> u64 {
+    600
+}
+
+fn default_heartbeat_interval_ms() -> u64 {
+    100
+}
+
+fn default_retained_log_entries() -> u64 {
+    10_000
+}
+
+/// Peer-to-peer gossip: tips are exchanged with static peers and missing
+/// blocks are pulled from them
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GossipSettings {
+    #[serde(default)]
+    pub enabled: bool,
+    /// gRPC addresses of the peers to gossip with
+    #[serde(default)]
+    pub peers: Vec<String>,
+    /// Address peers use to pull from this node after an announcement
+    /// (optional; without it peers catch up on their next round)
+    #[serde(default)]
+    pub advertise_address: String,
+    /// Milliseconds between tip exchanges with every peer
+    #[serde(default = "default_gossip_interval_ms")]
+    pub interval_ms: u64,
+    /// Blocks requested per GetBlockRange call while catching up
+    #[serde(default = "default_gossip_batch_size")]
+    pub batch_size: u64,
+}
+
+impl Default for GossipSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            peers: Vec::new(),
+            advertise_address: String::new(),
+            interval_ms: default_gossip_interval_ms(),
+            batch_size: default_gossip_batch_size(),
+        }
+    }
+}
+
+fn default_gossip_interval_ms() -> u64 {
+    1000
+}
+
+fn default_gossip_batch_size() -> u64 {
+    100
+}
+
+/// Signing key for proof-of-authority graphs
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AuthoritySettings {
+    /// File holding the hex-encoded Ed25519 seed (empty = this node does
+    /// not sign; created with `blockchain_admin generate-key`)
+    #[serde(default)]
+    pub key_file: String,
+}
+
+/// Node key for notarization receipts, kept apart from the authority key
+/// so a node can issue receipts without sealing proof-of-authority blocks
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ReceiptSettings {
+    /// File holding the hex-encoded Ed25519 seed (empty = this node does
+    /// not issue receipts; created with `blockchain_admin generate-key`)
+    #[serde(default)]
+    pub key_file: String,
+}
+
+/// Proposals on graphs with an endorsement policy
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EndorsementSettings {
+    /// Seconds a proposal waits for endorsements before it expires
+    #[serde(default = "default_proposal_ttl_secs")]
+    pub proposal_ttl_secs: u64,
+}
+
+impl Default for EndorsementSettings {
+    fn default() -> Self {
+        Self {
+            proposal_ttl_secs: default_proposal_ttl_secs(),
+        }
+    }
+}
+
+fn default_proposal_ttl_secs() -> u64 {
+    3600
+}
+
+/// WebAssembly contracts bound to graphs
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContractSettings {
+    /// Most fuel (roughly, executed instructions) a contract may be
+    /// deployed with; also the limit when DeployContract asks for 0
+    #[serde(default = "default_max_contract_fuel")]
+    pub max_fuel: u64,
+}
+
+impl Default for ContractSettings {
+    fn default() -> Self {
+        Self {
+            max_fuel: default_max_contract_fuel(),
+        }
+    }
+}
+
+fn default_max_contract_fuel() -> u64 {
+    10_000_000
+}
+
+/// HTTP endpoint serving GraphQL on `server.host`, next to gRPC
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HttpSettings {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_http_port")]
+    pub port: u16,
+    /// Deepest selection nesting a GraphQL query may have
+    #[serde(default = "default_graphql_max_depth")]
+    pub graphql_max_depth: usize,
+    /// Most fields a GraphQL query may select, aliases included
+    #[serde(default = "default_graphql_max_fields")]
+    pub graphql_max_fields: usize,
+    /// Largest `first` accepted by paginated GraphQL fields
+    #[serde(default = "default_graphql_max_page_size")]
+    pub graphql_max_page_size: u64,
+    /// Largest request body accepted by `POST /graphql`
+    #[serde(default = "default_http_max_body_bytes")]
+    pub max_body_bytes: usize,
+}
+
+impl Default for HttpSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port: default_http_port(),
+            graphql_max_depth: default_graphql_max_depth(),
+            graphql_max_fields: default_graphql_max_fields(),
+            graphql_max_page_size: default_graphql_max_page_size(),
+            max_body_bytes: default_http_max_body_bytes(),
+        }
+    }
+}
+
+fn default_http_port() -> u16 {
+    8080
+}
+
+fn default_graphql_max_depth() -> usize {
+    8
+}
+
+fn default_graphql_max_fields() -> usize {
+    200
+}
+
+fn default_graphql_max_page_size() -> u64 {
+    100
+}
+
+fn default_http_max_body_bytes() -> usize {
+    64 * 1024
+}
+
+/// Audit trail of node operations, appended to an audit graph in batches
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditSettings {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Audit graph the events are appended to (created if missing)
+    #[serde(default = "default_audit_graph_id")]
+    pub graph_id: String,
+    /// Milliseconds between flushes of pending events
+    #[serde(default = "default_audit_flush_interval_ms")]
+    pub flush_interval_ms: u64,
+    /// Most events per audit block; a full batch is flushed right away
+    #[serde(default = "default_audit_max_batch")]
+    pub max_batch: usize,
+}
+
+impl Default for AuditSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            graph_id: default_audit_graph_id(),
+            flush_interval_ms: default_audit_flush_interval_ms(),
+            max_batch: default_audit_max_batch(),
+        }
+    }
+}
+
+fn default_audit_graph_id() -> String {
+    "audit".to_string()
+}
+
+fn default_audit_flush_interval_ms() -> u64 {
+    5000
+}
+
+fn default_audit_max_batch() -> usize {
+    100
+}
+
+/// Periodic anchoring of every graph's tip into an audit graph
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnchorSettings {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Audit graph the anchors are written to (created if missing)
+    #[serde(default = "default_anchor_graph_id")]
+    pub graph_id: String,
+    /// Milliseconds between anchors; none is written while no tip moves
+    #[serde(default = "default_anchor_interval_ms")]
+    pub interval_ms: u64,
+}
+
+impl Default for AnchorSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            graph_id: default_anchor_graph_id(),
+            interval_ms: default_anchor_interval_ms(),
+        }
+    }
+}
+
+fn default_anchor_graph_id() -> String {
+    "anchors".to_string()
+}
+
+fn default_anchor_interval_ms() -> u64 {
+    60_000
+}
+
+/// Background integrity scrub of every graph in storage
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScrubSettings {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Seconds between the end of one scrub and the start of the next
+    #[serde(default = "default_scrub_interval_secs")]
+    pub interval_secs: u64,
+    /// Most blocks read per second, to limit the I/O a scrub adds
+    #[serde(default = "default_scrub_max_blocks_per_sec")]
+    pub max_blocks_per_sec: u64,
+}
+
+impl Default for ScrubSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval_secs: default_scrub_interval_secs(),
+            max_blocks_per_sec: default_scrub_max_blocks_per_sec(),
+        }
+    }
+}
+
+fn default_scrub_interval_secs() -> u64 {
+    3600
+}
+
+fn default_scrub_max_blocks_per_sec() -> u64 {
+    1000
+}
+
+/// Largest GetBlockRange batch requested from a peer
+pub const MAX_GOSSIP_BATCH_SIZE: u64 = 1000;
+
+/// Smallest and largest supported Raft cluster
+pub const MIN_CLUSTER_MEMBERS: usize = 3;
+pub const MAX_CLUSTER_MEMBERS: usize = 5;
+
+/// Shortest accepted cluster secret
+pub const MIN_CLUSTER_SECRET_LEN: usize = 16;
+
+fn default_backup_dir() -> String {
+    "./data/backups".to_string()
+}
+
+fn default_max_backups() -> usize {
+    7
 }
 
 impl Default for Settings {
@@ -32,31 +457,377 @@ impl Default for Settings {
             server: ServerSettings {
                 host: "0.0.0.0".to_string(),
                 port: 50051,
+                shutdown_timeout_secs: default_shutdown_timeout_secs(),
             },
             blockchain: BlockchainSettings {
                 default_difficulty: 2,
                 max_block_size: 1024 * 1024, // 1MB
+                checkpoint_interval_secs: default_checkpoint_interval_secs(),
             },
             storage: StorageSettings {
                 data_dir: "./data/blockchain".to_string(),
+                backup_dir: default_backup_dir(),
+                max_backups: default_max_backups(),
             },
+            replication: ReplicationSettings::default(),
+            cluster: ClusterSettings::default(),
+            gossip: GossipSettings::default(),
+            authority: AuthoritySettings::default(),
+            endorsement: EndorsementSettings::default(),
+            contracts: ContractSettings::default(),
+            http: HttpSettings::default(),
+            audit: AuditSettings::default(),
+            anchoring: AnchorSettings::default(),
+            receipts: ReceiptSettings::default(),
+            scrub: ScrubSettings::default(),
         }
     }
 }
 
+/// Prefix of environment variables that override settings,
+/// e.g. `BLOCKCHAIN_SERVER__PORT=6000` sets `server.port`
+pub const ENV_PREFIX: &str = "BLOCKCHAIN";
+
+/// Config file used when no `--config` is given (optional)
+pub const DEFAULT_CONFIG_PATH: &str = "config.json";
+
+/// Lowest accepted proof-of-work difficulty
+pub const MIN_DIFFICULTY: usize = 1;
+/// Highest accepted proof-of-work difficulty (leading hex zeros)
+pub const MAX_DIFFICULTY: usize = 8;
+
+/// Values given on the command line; they take precedence over every other layer
+#[derive(Debug, Clone, Default)]
+pub struct SettingsOverrides {
+    pub host: Option<String>,
+    pub port: Option<u16>,
+    pub data_dir: Option<String>,
+    pub default_difficulty: Option<usize>,
+    /// Runs the node as a follower of this leader address
+    pub leader_address: Option<String>,
+    /// Raft node id of this member (clustered mode)
+    pub node_id: Option<u64>,
+    /// Enables gossip with these peers
+    pub gossip_peers: Option<Vec<String>>,
+    /// Signs proof-of-authority blocks with the key in this file
+    pub authority_key_file: Option<String>,
+    /// Signs notarization receipts with the key in this file
+    pub receipt_key_file: Option<String>,
+}
+
 impl Settings {
-    /// Loads settings from a file or creates default
+    /// Loads settings from `config_path` (which must exist) and environment variables
     pub fn load(config_path: &str) -> Result<Self, Box<dyn std::error::Error>> {
-        if let Ok(content) = fs::read_to_string(config_path) {
-            let settings: Settings = serde_json::from_str(&content)?;
-            Ok(settings)
+        Self::load_layered(Some(config_path), &SettingsOverrides::default())
+    }
+
+    /// Loads settings in layers: defaults, then the JSON config file, then
+    /// `BLOCKCHAIN_*` environment variables, then command-line overrides.
+    /// An explicit `config_path` must exist; otherwise `config.json` is used
+    /// if present. Nothing is ever written to disk.
+    pub fn load_layered(
+        config_path: Option<&str>,
+        overrides: &SettingsOverrides,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        Self::load_from_sources(config_path, Self::environment(), overrides)
+    }
+
+    /// Environment source mapping `BLOCKCHAIN_SECTION__KEY` to `section.key`
+    fn environment() -> Environment {
+        Environment::with_prefix(ENV_PREFIX)
+            .prefix_separator("_")
+            .separator("__")
+            .try_parsing(true)
+    }
+
+    fn load_from_sources(
+        config_path: Option<&str>,
+        environment: Environment,
+        overrides: &SettingsOverrides,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let (path, required) = match config_path {
+            Some(path) => (path, true),
+            None => (DEFAULT_CONFIG_PATH, false),
+        };
+
+        if required && !Path::new(path).is_file() {
+            return Err(format!("Configuration file '{}' not found", path).into());
+        }
+
+        let config = Config::builder()
+            .add_source(Config::try_from(&Settings::default())?)
+            .add_source(File::new(path, FileFormat::Json).required(false))
+            .add_source(environment)
+            .build()
+            .map_err(|e| format!("Failed to load configuration: {}", e))?;
+
+        let mut settings: Settings = config
+            .try_deserialize()
+            .map_err(|e| format!("Invalid configuration: {}", e))?;
+        settings.apply_overrides(overrides);
+        settings.validate()?;
+
+        Ok(settings)
+    }
+
+    /// Applies command-line overrides
+    pub fn apply_overrides(&mut self, overrides: &SettingsOverrides) {
+        if let Some(host) = &overrides.host {
+            self.server.host = host.clone();
+        }
+        if let Some(port) = overrides.port {
+            self.server.port = port;
+        }
+        if let Some(data_dir) = &overrides.data_dir {
+            self.storage.data_dir = data_dir.clone();
+        }
+        if let Some(difficulty) = overrides.default_difficulty {
+            self.blockchain.default_difficulty = difficulty;
+        }
+        if let Some(leader_address) = &overrides.leader_address {
+            self.replication.role = NodeRole::Follower;
+            self.replication.leader_address = leader_address.clone();
+        }
+        if let Some(node_id) = overrides.node_id {
+            self.cluster.node_id = node_id;
+        }
+        if let Some(peers) = &overrides.gossip_peers {
+            self.gossip.enabled = true;
+            self.gossip.peers = peers.clone();
+        }
+        if let Some(key_file) = &overrides.authority_key_file {
+            self.authority.key_file = key_file.clone();
+        }
+        if let Some(key_file) = &overrides.receipt_key_file {
+            self.receipts.key_file = key_file.clone();
+        }
+    }
+
+    /// Checks every value and reports all problems at once
+    pub fn validate(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let mut errors = Vec::new();
+
+        if self.server.port == 0 {
+            errors.push("server.port must be between 1 and 65535".to_string());
+        }
+        if self.server_address().parse::<SocketAddr>().is_err() {
+            errors.push(format!(
+                "server.host '{}' is not a valid IP address",
+                self.server.host
+            ));
+        }
+
+        let difficulty = self.blockchain.default_difficulty;
+        if !(MIN_DIFFICULTY..=MAX_DIFFICULTY).contains(&difficulty) {
+            errors.push(format!(
+                "blockchain.default_difficulty must be between {} and {} (got {})",
+                MIN_DIFFICULTY, MAX_DIFFICULTY, difficulty
+            ));
+        }
+        if self.blockchain.max_block_size == 0 {
+            errors.push("blockchain.max_block_size must be greater than 0".to_string());
+        }
+        if self.blockchain.checkpoint_interval_secs == 0 {
+            errors.push("blockchain.checkpoint_interval_secs must be greater than 0".to_string());
+        }
+
+        if self.storage.data_dir.trim().is_empty() {
+            errors.push("storage.data_dir must not be empty".to_string());
+        } else if Path::new(&self.storage.data_dir).is_file() {
+            errors.push(format!(
+                "storage.data_dir '{}' is a file, expected a directory",
+                self.storage.data_dir
+            ));
+        }
+        if self.storage.backup_dir.trim().is_empty() {
+            errors.push("storage.backup_dir must not be empty".to_string());
+        } else if self.storage.backup_dir == self.storage.data_dir {
+            errors.push("storage.backup_dir must differ from storage.data_dir".to_string());
+        }
+
+        if self.replication.role == NodeRole::Follower
+            && self.replication.leader_address.trim().is_empty()
+        {
+            errors.push("replication.leader_address is required for followers".to_string());
+        }
+
+        if self.endorsement.proposal_ttl_secs == 0 {
+            errors.push("endorsement.proposal_ttl_secs must be greater than 0".to_string());
+        }
+
+        if self.contracts.max_fuel == 0 {
+            errors.push("contracts.max_fuel must be greater than 0".to_string());
+        }
+
+        if self.http.enabled {
+            if self.http.port == 0 || self.http.port == self.server.port {
+                errors.push(
+                    "http.port must be between 1 and 65535 and differ from server.port".to_string(),
+                );
+            }
+            if self.http.graphql_max_depth == 0
+                || self.http.graphql_max_fields == 0
+                || self.http.graphql_max_page_size == 0
+                || self.http.max_body_bytes == 0
+            {
+                errors.push(
+                    "http.graphql_max_depth, graphql_max_fields, graphql_max_page_size and \
+                     max_body_bytes must be greater than 0"
+                        .to_string(),
+                );
+            }
+        }
+
+        if self.audit.enabled {
+            if self.replication.role == NodeRole::Follower {
+                errors.push(
+                    "audit.enabled cannot be combined with replication.role = follower".to_string(),
+                );
+            }
+            if self.audit.graph_id.trim().is_empty() {
+                errors.push("audit.graph_id must not be empty".to_string());
+            }
+            if self.audit.flush_interval_ms == 0 || self.audit.max_batch == 0 {
+                errors.push(
+                    "audit.flush_interval_ms and max_batch must be greater than 0".to_string(),
+                );
+            }
+        }
+
+        if self.anchoring.enabled {
+            if self.replication.role == NodeRole::Follower {
+                errors.push(
+                    "anchoring.enabled cannot be combined with replication.role = follower"
+                        .to_string(),
+                );
+            }
+            if self.anchoring.graph_id.trim().is_empty() {
+                errors.push("anchoring.graph_id must not be empty".to_string());
+            }
+            if self.anchoring.interval_ms == 0 {
+                errors.push("anchoring.interval_ms must be greater than 0".to_string());
+            }
+        }
+
+        if self.scrub.enabled
+            && (self.scrub.interval_secs == 0 || self.scrub.max_blocks_per_sec == 0)
+        {
+            errors.push(
+                "scrub.interval_secs and scrub.max_blocks_per_sec must be greater than 0"
+                    .to_string(),
+            );
+        }
+
+        if self.cluster.enabled {
+            errors.extend(self.cluster_errors());
+        }
+        if self.gossip.enabled {
+            errors.extend(self.gossip_errors());
+        }
+
+        if errors.is_empty() {
+            Ok(())
         } else {
-            let settings = Settings::default();
-            settings.save(config_path)?;
-            Ok(settings)
+            Err(format!("Invalid configuration: {}", errors.join("; ")).into())
         }
     }
 
+    fn cluster_errors(&self) -> Vec<String> {
+        let cluster = &self.cluster;
+        let mut errors = Vec::new();
+
+        if self.replication.role == NodeRole::Follower {
+            errors.push(
+                "cluster.enabled cannot be combined with replication.role = follower".to_string(),
+            );
+        }
+        if !(MIN_CLUSTER_MEMBERS..=MAX_CLUSTER_MEMBERS).contains(&cluster.members.len()) {
+            errors.push(format!(
+                "cluster.members must list between {} and {} nodes (got {})",
+                MIN_CLUSTER_MEMBERS,
+                MAX_CLUSTER_MEMBERS,
+                cluster.members.len()
+            ));
+        }
+        if !cluster.members.iter().any(|m| m.id == cluster.node_id) {
+            errors.push(format!(
+                "cluster.node_id {} is not in cluster.members",
+                cluster.node_id
+            ));
+        }
+
+        let mut ids: Vec<u64> = cluster.members.iter().map(|m| m.id).collect();
+        ids.sort_unstable();
+        ids.dedup();
+        if ids.len() != cluster.members.len() || ids.contains(&0) {
+            errors.push("cluster.members ids must be unique and greater than 0".to_string());
+        }
+        if cluster.members.iter().any(|m| m.address.trim().is_empty()) {
+            errors.push("cluster.members addresses must not be empty".to_string());
+        }
+        match self.cluster_address() {
+            Some(address) if address.ends_with(&format!(":{}", self.server.port)) => errors
+                .push("cluster listener must not share the client port (server.port)".to_string()),
+            Some(_) => {}
+            None => errors.push(format!(
+                "cluster.members address of node {} must be host:port",
+                cluster.node_id
+            )),
+        }
+        if cluster.secret.len() < MIN_CLUSTER_SECRET_LEN {
+            errors.push(format!(
+                "cluster.secret must be at least {} characters",
+                MIN_CLUSTER_SECRET_LEN
+            ));
+        }
+
+        if cluster.election_timeout_min_ms > cluster.election_timeout_max_ms {
+            errors.push(
+                "cluster.election_timeout_min_ms must not exceed election_timeout_max_ms"
+                    .to_string(),
+            );
+        }
+        if cluster.heartbeat_interval_ms == 0
+            || cluster.heartbeat_interval_ms >= cluster.election_timeout_min_ms
+        {
+            errors.push(
+                "cluster.heartbeat_interval_ms must be between 1 and election_timeout_min_ms"
+                    .to_string(),
+            );
+        }
+
+        errors
+    }
+
+    fn gossip_errors(&self) -> Vec<String> {
+        let gossip = &self.gossip;
+        let mut errors = Vec::new();
+
+        if self.replication.role == NodeRole::Follower || self.cluster.enabled {
+            errors.push(
+                "gossip.enabled cannot be combined with a follower role or clustered mode"
+                    .to_string(),
+            );
+        }
+        if gossip.peers.is_empty() {
+            errors.push("gossip.peers must list at least one peer".to_string());
+        }
+        if gossip.peers.iter().any(|p| p.trim().is_empty()) {
+            errors.push("gossip.peers addresses must not be empty".to_string());
+        }
+        if gossip.interval_ms == 0 {
+            errors.push("gossip.interval_ms must be greater than 0".to_string());
+        }
+        if !(1..=MAX_GOSSIP_BATCH_SIZE).contains(&gossip.batch_size) {
+            errors.push(format!(
+                "gossip.batch_size must be between 1 and {} (got {})",
+                MAX_GOSSIP_BATCH_SIZE, gossip.batch_size
+            ));
+        }
+
+        errors
+    }
+
     /// Saves settings to a file
     pub fn save(&self, config_path: &str) -> Result<(), Box<dyn std::error::Error>> {
         let content = serde_json::to_string_pretty(self)?;
@@ -68,4 +839,237 @@ impl Settings {
     pub fn server_address(&self) -> String {
         format!("{}:{}", self.server.host, self.server.port)
     }
+
+    /// Gets the HTTP (GraphQL) listen address
+    pub fn http_address(&self) -> String {
+        format!("{}:{}", self.server.host, self.http.port)
+    }
+
+    /// Leader address when this node is a follower
+    pub fn leader_address(&self) -> Option<&str> {
+        match self.replication.role {
+            NodeRole::Follower => Some(self.replication.leader_address.as_str()),
+            NodeRole::Leader => None,
+        }
+    }
+
+    /// Gets the delay between reconnection attempts to the leader
+    pub fn replication_retry_interval(&self) -> Duration {
+        Duration::from_millis(self.replication.retry_interval_ms)
+    }
+
+    /// Listen address of this node's cluster listener
+    pub fn cluster_address(&self) -> Option<String> {
+        let member = self
+            .cluster
+            .members
+            .iter()
+            .find(|m| m.id == self.cluster.node_id)?;
+        let port = member.address.rsplit_once(':')?.1.parse::<u16>().ok()?;
+        Some(format!("{}:{}", self.server.host, port))
+    }
+
+    /// Other cluster members (id -> address), excluding this node
+    pub fn cluster_peers(&self) -> HashMap<u64, String> {
+        self.cluster
+            .members
+            .iter()
+            .filter(|m| m.id != self.cluster.node_id)
+            .map(|m| (m.id, m.address.clone()))
+            .collect()
+    }
+
+    /// Path of the authority key file, if configured
+    pub fn authority_key_file(&self) -> Option<&str> {
+        let path = self.authority.key_file.trim();
+        (!path.is_empty()).then_some(path)
+    }
+
+    /// Path of the receipt key file, if configured
+    pub fn receipt_key_file(&self) -> Option<&str> {
+        let path = self.receipts.key_file.trim();
+        (!path.is_empty()).then_some(path)
+    }
+
+    /// Gets the delay between gossip rounds
+    pub fn gossip_interval(&self) -> Duration {
+        Duration::from_millis(self.gossip.interval_ms)
+    }
+
+    /// Gets the delay between audit trail flushes
+    pub fn audit_flush_interval(&self) -> Duration {
+        Duration::from_millis(self.audit.flush_interval_ms)
+    }
+
+    /// Gets the delay between anchors
+    pub fn anchor_interval(&self) -> Duration {
+        Duration::from_millis(self.anchoring.interval_ms)
+    }
+
+    /// Gets the delay between moves of the verified checkpoints
+    pub fn checkpoint_interval(&self) -> Duration {
+        Duration::from_secs(self.blockchain.checkpoint_interval_secs)
+    }
+
+    /// Gets the delay between integrity scrubs
+    pub fn scrub_interval(&self) -> Duration {
+        Duration::from_secs(self.scrub.interval_secs)
+    }
+
+    /// Gets the graceful shutdown timeout
+    pub fn shutdown_timeout(&self) -> Duration {
+        Duration::from_secs(self.server.shutdown_timeout_secs)
+    }
+
+    /// Gets how long proposals wait for endorsements
+    pub fn proposal_ttl(&self) -> Duration {
+        Duration::from_secs(self.endorsement.proposal_ttl_secs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn env(vars: &[(&str, &str)]) -> Environment {
+        let map = vars
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        Settings::environment().source(Some(map))
+    }
+
+    #[test]
+    fn test_layers_override_in_order() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("config.json");
+        fs::write(
+            &path,
+            r#"{"server": {"port": 7000}, "storage": {"data_dir": "/srv/chain"}}"#,
+        )
+        .unwrap();
+
+        let overrides = SettingsOverrides {
+            data_dir: Some("/tmp/cli".to_string()),
+            ..Default::default()
+        };
+        let settings = Settings::load_from_sources(
+            Some(path.to_str().unwrap()),
+            env(&[
+                ("BLOCKCHAIN_SERVER__PORT", "7100"),
+                ("BLOCKCHAIN_STORAGE__DATA_DIR", "/srv/env"),
+            ]),
+            &overrides,
+        )
+        .unwrap();
+
+        assert_eq!(settings.server.port, 7100);
+        assert_eq!(settings.server.host, "0.0.0.0");
+        assert_eq!(settings.storage.data_dir, "/tmp/cli");
+        assert_eq!(settings.blockchain.default_difficulty, 2);
+    }
+
+    #[test]
+    fn test_missing_explicit_config_is_an_error() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("missing.json");
+
+        let result = Settings::load_from_sources(
+            Some(path.to_str().unwrap()),
+            env(&[]),
+            &SettingsOverrides::default(),
+        );
+
+        assert!(result.is_err());
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_validation_reports_bad_values() {
+        let mut settings = Settings::default();
+        settings.server.port = 0;
+        settings.blockchain.default_difficulty = MAX_DIFFICULTY + 1;
+
+        let message = settings.validate().unwrap_err().to_string();
+        assert!(message.contains("server.port"));
+        assert!(message.contains("default_difficulty"));
+
+        let result = Settings::load_from_sources(
+            None,
+            env(&[("BLOCKCHAIN_SERVER__HOST", "not an ip")]),
+            &SettingsOverrides::default(),
+        );
+        assert!(result.unwrap_err().to_string().contains("server.host"));
+    }
+
+    #[test]
+    fn test_follower_role_from_env_and_cli() {
+        let result = Settings::load_from_sources(
+            None,
+            env(&[("BLOCKCHAIN_REPLICATION__ROLE", "follower")]),
+            &SettingsOverrides::default(),
+        );
+        assert!(result.unwrap_err().to_string().contains("leader_address"));
+
+        let overrides = SettingsOverrides {
+            leader_address: Some("127.0.0.1:50051".to_string()),
+            ..Default::default()
+        };
+        let settings = Settings::load_from_sources(None, env(&[]), &overrides).unwrap();
+        assert_eq!(settings.replication.role, NodeRole::Follower);
+        assert_eq!(settings.leader_address(), Some("127.0.0.1:50051"));
+    }
+
+    #[test]
+    fn test_cluster_membership_validation() {
+        let mut settings = Settings::default();
+        settings.cluster.enabled = true;
+        settings.cluster.node_id = 2;
+        settings.cluster.members = (1..=3)
+            .map(|id| ClusterMemberSettings {
+                id,
+                address: format!("10.0.0.{}:50061", id),
+            })
+            .collect();
+        assert!(settings
+            .validate()
+            .unwrap_err()
+            .to_string()
+            .contains("cluster.secret"));
+        settings.cluster.secret = "0123456789abcdef".to_string();
+        settings.validate().unwrap();
+        assert_eq!(settings.cluster_peers().len(), 2);
+        assert!(!settings.cluster_peers().contains_key(&2));
+        assert_eq!(settings.cluster_address().as_deref(), Some("0.0.0.0:50061"));
+
+        settings.cluster.node_id = 9;
+        settings.cluster.members.truncate(2);
+        let message = settings.validate().unwrap_err().to_string();
+        assert!(message.contains("between 3 and 5"));
+        assert!(message.contains("node_id 9"));
+    }
+
+    #[test]
+    fn test_gossip_peers_from_cli() {
+        let overrides = SettingsOverrides {
+            gossip_peers: Some(vec!["127.0.0.1:50052".to_string()]),
+            ..Default::default()
+        };
+        let settings = Settings::load_from_sources(None, env(&[]), &overrides).unwrap();
+        assert!(settings.gossip.enabled);
+        assert_eq!(settings.gossip.peers, vec!["127.0.0.1:50052".to_string()]);
+
+        let result = Settings::load_from_sources(
+            None,
+            env(&[
+                ("BLOCKCHAIN_GOSSIP__ENABLED", "true"),
+                ("BLOCKCHAIN_GOSSIP__BATCH_SIZE", "0"),
+            ]),
+            &SettingsOverrides::default(),
+        );
+        let message = result.unwrap_err().to_string();
+        assert!(message.contains("gossip.peers"));
+        assert!(message.contains("gossip.batch_size"));
+    }
 }
@@ -0,0 +1,109 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+
+use super::BlockchainGraph;
+
+/// Key of the tip list in the data of an anchor block,
+/// e.g. `{"graph_anchors": [{"graph_id": "people", "height": 12, "hash": "00ab..."}]}`
+pub const ANCHOR_KEY: &str = "graph_anchors";
+
+/// Tip of one graph as recorded by an anchor block
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct GraphAnchor {
+    pub graph_id: String,
+    pub height: u64,
+    pub hash: String,
+}
+
+impl GraphAnchor {
+    /// Whether the graph still has this block at this height
+    pub fn matches(&self, graphs: &HashMap<String, BlockchainGraph>) -> bool {
+        graphs
+            .get(&self.graph_id)
+            .and_then(|graph| graph.chain.get(self.height as usize))
+            .is_some_and(|block| block.hash == self.hash)
+    }
+}
+
+/// Tips of every graph but `anchor_graph`, sorted by graph id
+pub fn graph_tips(
+    graphs: &HashMap<String, BlockchainGraph>,
+    anchor_graph: &str,
+) -> Vec<GraphAnchor> {
+    let mut tips: Vec<GraphAnchor> = graphs
+        .values()
+        .filter(|graph| graph.id != anchor_graph)
+        .filter_map(|graph| {
+            graph.get_latest_block().map(|tip| GraphAnchor {
+                graph_id: graph.id.clone(),
+                height: tip.height,
+                hash: tip.hash.clone(),
+            })
+        })
+        .collect();
+    tips.sort_by(|a, b| a.graph_id.cmp(&b.graph_id));
+    tips
+}
+
+/// Block data carrying the tips
+pub fn anchor_data(anchors: &[GraphAnchor]) -> String {
+    serde_json::json!({ ANCHOR_KEY: anchors }).to_string()
+}
+
+/// Reads the tips recorded by an anchor block
+pub fn parse_anchors(data: &str) -> Option<Vec<GraphAnchor>> {
+    if !data.contains(ANCHOR_KEY) {
+        return None;
+    }
+    let value: Value = serde_json::from_str(data).ok()?;
+    serde_json::from_value(value.get(ANCHOR_KEY)?.clone()).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::{Block, GraphType};
+
+    #[test]
+    fn test_anchors_break_when_a_tip_is_rewritten() {
+        let mut people =
+            BlockchainGraph::new("people".to_string(), GraphType::Identity, String::new(), 1);
+        let genesis = people.get_latest_block().unwrap().hash.clone();
+        let block = Block::new(
+            genesis.clone(),
+            "alice".to_string(),
+            "people".to_string(),
+            1,
+            vec![],
+        );
+        people.add_block(block).unwrap();
+        let anchors =
+            BlockchainGraph::new("anchors".to_string(), GraphType::Audit, String::new(), 1);
+        let mut graphs = HashMap::from([
+            ("people".to_string(), people),
+            ("anchors".to_string(), anchors),
+        ]);
+
+        let tips = graph_tips(&graphs, "anchors");
+        assert_eq!(tips.len(), 1);
+        assert_eq!(tips[0].height, 1);
+        assert_eq!(parse_anchors(&anchor_data(&tips)), Some(tips.clone()));
+        assert!(tips[0].matches(&graphs));
+
+        // Same height, different history
+        let people = graphs.get_mut("people").unwrap();
+        people.chain.pop();
+        let forged = Block::new(
+            genesis,
+            "mallory".to_string(),
+            "people".to_string(),
+            1,
+            vec![],
+        );
+        people.add_block(forged).unwrap();
+        assert!(people.is_valid());
+        assert!(!tips[0].matches(&graphs));
+        assert_eq!(parse_anchors(r#"{"name": "alice"}"#), None);
+    }
+}
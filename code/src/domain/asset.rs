@@ -0,0 +1,343 @@
+use ed25519_dalek::SigningKey;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use super::consensus::{parse_public_key, sign_message, verify_message};
+use super::world_state::{StateChanges, WorldState};
+use super::Block;
+
+/// Prefix of the world-state keys holding asset records; on asset graphs
+/// only asset operations may write them
+pub const ASSET_KEY_PREFIX: &str = "asset/";
+
+/// Key of the JSON object that sets the asset policy of a graph,
+/// e.g. `{"asset_policy": {"payment_graph": "payments"}}`
+pub const ASSET_POLICY_KEY: &str = "asset_policy";
+
+/// Rules an asset graph applies to its operations
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct AssetPolicy {
+    /// Transaction graph whose payment block a transfer must cross-reference
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub payment_graph: Option<String>,
+}
+
+/// An operation carried in the data of an asset graph block, e.g.
+/// `{"op": "transfer", "asset_id": "car-7", "to": "<public key>", "signature": "<hex>"}`.
+/// Owners are hex-encoded Ed25519 public keys; every operation but mint is
+/// signed by the current owner (see [`AssetOperation::signing_message`]).
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "op", rename_all = "lowercase")]
+pub enum AssetOperation {
+    Mint {
+        asset_id: String,
+        owner: String,
+        #[serde(default)]
+        metadata: Map<String, Value>,
+    },
+    Transfer {
+        asset_id: String,
+        to: String,
+        #[serde(default)]
+        signature: String,
+    },
+    Burn {
+        asset_id: String,
+        #[serde(default)]
+        signature: String,
+    },
+    /// Merges `metadata` into the asset's; null values remove a field
+    Metadata {
+        asset_id: String,
+        metadata: Map<String, Value>,
+        #[serde(default)]
+        signature: String,
+    },
+}
+
+impl AssetOperation {
+    pub fn asset_id(&self) -> &str {
+        match self {
+            Self::Mint { asset_id, .. }
+            | Self::Transfer { asset_id, .. }
+            | Self::Burn { asset_id, .. }
+            | Self::Metadata { asset_id, .. } => asset_id,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Mint { .. } => "mint",
+            Self::Transfer { .. } => "transfer",
+            Self::Burn { .. } => "burn",
+            Self::Metadata { .. } => "metadata",
+        }
+    }
+
+    fn signature(&self) -> &str {
+        match self {
+            Self::Mint { .. } => "",
+            Self::Transfer { signature, .. }
+            | Self::Burn { signature, .. }
+            | Self::Metadata { signature, .. } => signature,
+        }
+    }
+
+    /// Bytes the owner signs: the graph, the asset version the operation
+    /// applies to (so it cannot be replayed later) and the unsigned operation
+    pub fn signing_message(&self, graph_id: &str, version: u64) -> Vec<u8> {
+        let mut unsigned = self.clone();
+        if let Self::Transfer { signature, .. }
+        | Self::Burn { signature, .. }
+        | Self::Metadata { signature, .. } = &mut unsigned
+        {
+            signature.clear();
+        }
+        let operation = serde_json::to_string(&unsigned).unwrap_or_default();
+        format!("{}:{}:{}", graph_id, version, operation).into_bytes()
+    }
+
+    /// Signs the operation as the owner of the asset at `version`
+    pub fn signed(mut self, key: &SigningKey, graph_id: &str, version: u64) -> Self {
+        let message = self.signing_message(graph_id, version);
+        if let Self::Transfer { signature, .. }
+        | Self::Burn { signature, .. }
+        | Self::Metadata { signature, .. } = &mut self
+        {
+            *signature = sign_message(key, &message);
+        }
+        self
+    }
+
+    /// Block data carrying the operation
+    pub fn to_data(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+}
+
+/// Current state of an asset, stored as JSON under [`asset_key`]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct AssetRecord {
+    pub asset_id: String,
+    pub owner: String,
+    #[serde(default)]
+    pub metadata: Map<String, Value>,
+    #[serde(default)]
+    pub burned: bool,
+    /// Operation that produced this version
+    pub operation: String,
+}
+
+impl AssetRecord {
+    pub fn from_bytes(value: &[u8]) -> Option<Self> {
+        serde_json::from_slice(value).ok()
+    }
+
+    /// Owner listed in the ownership index; burned assets have none
+    pub fn holder(&self) -> Option<&str> {
+        (!self.burned).then_some(self.owner.as_str())
+    }
+}
+
+/// World-state key of an asset
+pub fn asset_key(asset_id: &str) -> String {
+    format!("{}{}", ASSET_KEY_PREFIX, asset_id)
+}
+
+/// Reads the asset operation of block data. Data that is not a JSON
+/// object with an `op` field carries none; an unknown or malformed
+/// operation is an error.
+pub fn parse_asset_operation(data: &str) -> Result<Option<AssetOperation>, String> {
+    let value = match serde_json::from_str::<Value>(data) {
+        Ok(value) if value.get("op").is_some() => value,
+        _ => return Ok(None),
+    };
+    serde_json::from_value(value)
+        .map(Some)
+        .map_err(|e| format!("Invalid asset operation: {}", e))
+}
+
+/// Block data that sets the asset policy
+pub fn asset_policy_data(policy: &AssetPolicy) -> String {
+    serde_json::json!({ ASSET_POLICY_KEY: policy }).to_string()
+}
+
+/// Reads an asset policy update from block data
+pub fn parse_asset_policy(data: &str) -> Option<AssetPolicy> {
+    if !data.contains(ASSET_POLICY_KEY) {
+        return None;
+    }
+    let value: Value = serde_json::from_str(data).ok()?;
+    serde_json::from_value(value.get(ASSET_POLICY_KEY)?.clone()).ok()
+}
+
+/// Checks `operation`, carried by `block`, against the asset records in
+/// `state` and returns the new record. Cross-references are only checked
+/// for presence here; that they point at a payment block is checked
+/// against the other graphs when the block is added.
+pub fn apply_asset_operation(
+    operation: &AssetOperation,
+    state: &WorldState,
+    block: &Block,
+    policy: &AssetPolicy,
+) -> Result<StateChanges, String> {
+    let asset_id = operation.asset_id();
+    if asset_id.is_empty() {
+        return Err("Asset ids must not be empty".to_string());
+    }
+    let key = asset_key(asset_id);
+    let current = state.get(&key);
+
+    let record = match (operation, current) {
+        (
+            AssetOperation::Mint {
+                owner, metadata, ..
+            },
+            None,
+        ) => {
+            check_owner_key(owner)?;
+            AssetRecord {
+                asset_id: asset_id.to_string(),
+                owner: owner.clone(),
+                metadata: metadata.clone(),
+                burned: false,
+                operation: operation.name().to_string(),
+            }
+        }
+        (AssetOperation::Mint { .. }, Some(_)) => {
+            return Err(format!("Asset '{}' already exists", asset_id))
+        }
+        (_, None) => return Err(format!("Asset '{}' does not exist", asset_id)),
+        (_, Some(entry)) => {
+            let mut record = AssetRecord::from_bytes(&entry.value)
+                .ok_or_else(|| format!("Asset '{}' has an unreadable record", asset_id))?;
+            if record.burned {
+                return Err(format!("Asset '{}' was burned", asset_id));
+            }
+            let message = operation.signing_message(&block.graph_id, entry.version);
+            if !verify_message(&record.owner, &message, operation.signature()) {
+                return Err(format!(
+                    "Only the owner of asset '{}' may {} it",
+                    asset_id,
+                    operation.name()
+                ));
+            }
+            match operation {
+                AssetOperation::Transfer { to, .. } => {
+                    check_owner_key(to)?;
+                    if let Some(payment_graph) = &policy.payment_graph {
+                        if block.cross_references.is_empty() {
+                            return Err(format!(
+                                "Transfer of asset '{}' must cross-reference a payment block in graph '{}'",
+                                asset_id, payment_graph
+                            ));
+                        }
+                    }
+                    record.owner = to.clone();
+                }
+                AssetOperation::Burn { .. } => record.burned = true,
+                AssetOperation::Metadata { metadata, .. } => {
+                    for (field, value) in metadata {
+                        match value {
+                            Value::Null => record.metadata.remove(field),
+                            value => record.metadata.insert(field.clone(), value.clone()),
+                        };
+                    }
+                }
+                AssetOperation::Mint { .. } => {
+                    unreachable!("mints of existing assets are rejected above")
+                }
+            }
+            record.operation = operation.name().to_string();
+            record
+        }
+    };
+
+    let value = serde_json::to_vec(&record).map_err(|e| e.to_string())?;
+    Ok(StateChanges::from([(key, Some(value))]))
+}
+
+fn check_owner_key(owner: &str) -> Result<(), String> {
+    parse_public_key(owner).map(|_| ()).map_err(|_| {
+        format!(
+            "Asset owner '{}' is not a hex-encoded Ed25519 public key",
+            owner
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::consensus::{generate_signing_key, public_key_hex};
+    use crate::domain::world_state::{apply_writes, commit_changes};
+
+    #[test]
+    fn test_only_the_owner_transfers_and_signatures_do_not_replay() {
+        let alice = generate_signing_key();
+        let bob = generate_signing_key();
+        let policy = AssetPolicy::default();
+        let mut state = WorldState::new();
+        let mut height = 0;
+        let mut apply = |state: &mut WorldState, operation: &AssetOperation| {
+            height += 1;
+            let block = Block::new(
+                String::new(),
+                operation.to_data(),
+                "titles".to_string(),
+                height,
+                vec![],
+            );
+            let changes = apply_asset_operation(operation, state, &block, &policy)?;
+            apply_writes(state, &commit_changes(&changes, &block));
+            Ok::<_, String>(())
+        };
+        let record =
+            |state: &WorldState| AssetRecord::from_bytes(&state[&asset_key("car")].value).unwrap();
+
+        let mint = AssetOperation::Mint {
+            asset_id: "car".to_string(),
+            owner: public_key_hex(&alice),
+            metadata: Map::new(),
+        };
+        assert_eq!(
+            parse_asset_operation(&mint.to_data()),
+            Ok(Some(mint.clone()))
+        );
+        apply(&mut state, &mint).unwrap();
+        assert!(apply(&mut state, &mint)
+            .unwrap_err()
+            .contains("already exists"));
+
+        let transfer = AssetOperation::Transfer {
+            asset_id: "car".to_string(),
+            to: public_key_hex(&bob),
+            signature: String::new(),
+        };
+        let forged = transfer.clone().signed(&bob, "titles", 1);
+        assert!(apply(&mut state, &forged)
+            .unwrap_err()
+            .contains("Only the owner"));
+        let signed = transfer.signed(&alice, "titles", 1);
+        apply(&mut state, &signed).unwrap();
+        assert_eq!(record(&state).owner, public_key_hex(&bob));
+        assert_eq!(record(&state).operation, "transfer");
+
+        // The same signature is bound to version 1 and the previous owner
+        assert!(apply(&mut state, &signed).is_err());
+
+        let burn = AssetOperation::Burn {
+            asset_id: "car".to_string(),
+            signature: String::new(),
+        };
+        let version = state[&asset_key("car")].version;
+        apply(&mut state, &burn.clone().signed(&bob, "titles", version)).unwrap();
+        assert_eq!(record(&state).holder(), None);
+        assert!(apply(&mut state, &burn.signed(&bob, "titles", version + 1))
+            .unwrap_err()
+            .contains("burned"));
+
+        assert!(parse_asset_operation(r#"{"op": "melt", "asset_id": "car"}"#).is_err());
+        assert_eq!(parse_asset_operation(r#"{"asset_id": "car"}"#), Ok(None));
+    }
+}
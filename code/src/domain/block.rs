@@ -1,7 +1,11 @@
 use chrono::Utc;
+use ed25519_dalek::SigningKey;
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 
+use super::consensus::{public_key_hex, sign_message, verify_message};
+use super::endorsement::BlockEndorsements;
+
 /// Represents a single block in the blockchain
 /// Immutable by design (following functional programming principles)
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -14,6 +18,15 @@ pub struct Block {
     pub height: u64,
     pub graph_id: String,
     pub cross_references: Vec<String>, // References to blocks in other graphs
+    /// Hex public key of the authority that signed the block (proof of authority)
+    #[serde(default)]
+    pub signer: String,
+    /// Hex Ed25519 signature of `hash` by `signer`
+    #[serde(default)]
+    pub signature: String,
+    /// Endorsements the block was committed with (endorsed graphs)
+    #[serde(default)]
+    pub endorsements: Option<BlockEndorsements>,
 }
 
 impl Block {
@@ -37,12 +50,23 @@ impl Block {
             height,
             graph_id: graph_id.clone(),
             cross_references,
+            signer: String::new(),
+            signature: String::new(),
+            endorsements: None,
         };
 
         block.hash = block.calculate_hash();
         block
     }
 
+    /// Records the endorsements the block is committed with; they are
+    /// covered by the hash
+    pub fn with_endorsements(mut self, endorsements: BlockEndorsements) -> Self {
+        self.endorsements = Some(endorsements);
+        self.hash = self.calculate_hash();
+        self
+    }
+
     /// Creates the genesis block (Factory Method Pattern)
     pub fn genesis(graph_id: String) -> Self {
         Self::new(
@@ -55,8 +79,9 @@ impl Block {
     }
 
     /// Calculates the hash of the block
+    /// (the signer and endorsements are covered once set; the signature is not)
     pub fn calculate_hash(&self) -> String {
-        let content = format!(
+        let mut content = format!(
             "{}{}{}{}{}{}{}",
             self.previous_hash,
             self.timestamp,
@@ -66,6 +91,13 @@ impl Block {
             self.graph_id,
             self.cross_references.join(",")
         );
+        if !self.signer.is_empty() {
+            content.push_str(&self.signer);
+        }
+        if let Some(endorsements) = &self.endorsements {
+            content
+                .push_str(&serde_json::to_string(endorsements).expect("strings always serialize"));
+        }
 
         let mut hasher = Sha256::new();
         hasher.update(content.as_bytes());
@@ -88,6 +120,19 @@ impl Block {
         }
     }
 
+    /// Signs the block with an authority key (Proof of Authority)
+    pub fn sign(&mut self, key: &SigningKey) {
+        self.signer = public_key_hex(key);
+        self.hash = self.calculate_hash();
+        self.signature = sign_message(key, self.hash.as_bytes());
+    }
+
+    /// Checks that `signature` is a valid signature of the hash by `signer`
+    pub fn has_valid_signature(&self) -> bool {
+        !self.signer.is_empty()
+            && verify_message(&self.signer, self.hash.as_bytes(), &self.signature)
+    }
+
     /// Checks if block has required difficulty
     pub fn has_valid_difficulty(&self, difficulty: usize) -> bool {
         let target = "0".repeat(difficulty);
@@ -137,4 +182,28 @@ mod tests {
         assert!(block.hash.starts_with("00"));
         assert!(block.is_valid());
     }
+
+    #[test]
+    fn test_signed_block_binds_signer_and_hash() {
+        let key = crate::domain::consensus::generate_signing_key();
+        let mut block = Block::new(
+            "prev".to_string(),
+            "data".to_string(),
+            "graph".to_string(),
+            1,
+            vec![],
+        );
+        block.sign(&key);
+        assert!(block.is_valid());
+        assert!(block.has_valid_signature());
+
+        let mut forged = block.clone();
+        forged.signer = public_key_hex(&crate::domain::consensus::generate_signing_key());
+        assert!(!forged.is_valid());
+
+        forged = block.clone();
+        forged.data = "tampered".to_string();
+        forged.hash = forged.calculate_hash();
+        assert!(!forged.has_valid_signature());
+    }
 }
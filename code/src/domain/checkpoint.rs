@@ -0,0 +1,31 @@
+use serde::{Deserialize, Serialize};
+
+use super::Block;
+
+/// Highest block of a graph whose chain up to it passed verification.
+/// Routine checks trust everything at or below it and only verify the
+/// blocks added since.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct VerifiedCheckpoint {
+    pub height: u64,
+    pub hash: String,
+}
+
+impl VerifiedCheckpoint {
+    pub fn of(block: &Block) -> Self {
+        Self {
+            height: block.height,
+            hash: block.hash.clone(),
+        }
+    }
+
+    /// Height verification can resume from, or None when `chain` no longer
+    /// holds the checkpointed block (e.g. after a reorganization or a
+    /// rewrite in storage) and must be verified from genesis
+    pub fn resume_height(&self, chain: &[Block]) -> Option<u64> {
+        chain
+            .get(self.height as usize)
+            .filter(|block| block.hash == self.hash)
+            .map(|block| block.height)
+    }
+}
@@ -0,0 +1,295 @@
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+
+use super::endorsement::{EndorsementPolicy, Endorser};
+use super::Block;
+
+/// Key of the JSON object that announces a new authority set in block data,
+/// e.g. `{"authority_update": {"authorities": ["<hex key>"], "rotation": "RoundRobin"}}`
+pub const AUTHORITY_UPDATE_KEY: &str = "authority_update";
+
+/// Which authority may sign a given block
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub enum AuthorityRotation {
+    /// Any authority may sign any block
+    #[default]
+    AnyOf,
+    /// Block `h` must be signed by `authorities[h % authorities.len()]`
+    RoundRobin,
+}
+
+impl AuthorityRotation {
+    pub fn from_i32(value: i32) -> Self {
+        match value {
+            1 => AuthorityRotation::RoundRobin,
+            _ => AuthorityRotation::AnyOf,
+        }
+    }
+
+    pub fn to_i32(&self) -> i32 {
+        match self {
+            AuthorityRotation::AnyOf => 0,
+            AuthorityRotation::RoundRobin => 1,
+        }
+    }
+}
+
+/// Ed25519 public keys (hex) allowed to sign blocks of a graph
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct AuthoritySet {
+    pub authorities: Vec<String>,
+    pub rotation: AuthorityRotation,
+}
+
+impl AuthoritySet {
+    /// Requires at least one key, every key valid and no duplicates
+    pub fn validate(&self) -> Result<(), String> {
+        if self.authorities.is_empty() {
+            return Err("At least one authority key is required".to_string());
+        }
+        for (i, key) in self.authorities.iter().enumerate() {
+            parse_public_key(key)?;
+            if self.authorities[..i].contains(key) {
+                return Err(format!("Authority key {} is listed twice", key));
+            }
+        }
+        Ok(())
+    }
+
+    /// Key that must sign block `height`, if the rotation fixes one
+    pub fn expected_signer(&self, height: u64) -> Option<&str> {
+        match self.rotation {
+            AuthorityRotation::AnyOf => None,
+            AuthorityRotation::RoundRobin => {
+                let index = (height % self.authorities.len().max(1) as u64) as usize;
+                self.authorities.get(index).map(String::as_str)
+            }
+        }
+    }
+
+    pub fn allows(&self, signer: &str, height: u64) -> bool {
+        match self.expected_signer(height) {
+            Some(expected) => expected == signer,
+            None => self.authorities.iter().any(|a| a == signer),
+        }
+    }
+
+    /// Signatures an authority update needs from this set: one authority
+    /// under `AnyOf`, a majority otherwise. Each authority endorses under
+    /// its own key (hex) as org.
+    pub fn approval_policy(&self) -> EndorsementPolicy {
+        let required = match self.rotation {
+            AuthorityRotation::AnyOf => 1,
+            AuthorityRotation::RoundRobin => self.authorities.len() / 2 + 1,
+        };
+        EndorsementPolicy {
+            required,
+            endorsers: self
+                .authorities
+                .iter()
+                .map(|key| Endorser {
+                    org: key.clone(),
+                    public_key: key.clone(),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// How blocks of a graph are sealed
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub enum Consensus {
+    /// Blocks are mined to the graph difficulty
+    #[default]
+    ProofOfWork,
+    /// Blocks are signed by one of the authorities; the initial set can be
+    /// replaced on-chain with an authority update block
+    ProofOfAuthority(AuthoritySet),
+}
+
+impl Consensus {
+    pub fn is_authority(&self) -> bool {
+        matches!(self, Consensus::ProofOfAuthority(_))
+    }
+
+    /// Seals a new block: mines it, or signs it with `key`
+    pub fn seal(
+        &self,
+        block: &mut Block,
+        difficulty: usize,
+        key: Option<&SigningKey>,
+    ) -> Result<(), String> {
+        match self {
+            Consensus::ProofOfWork => {
+                block.mine_block(difficulty);
+                Ok(())
+            }
+            Consensus::ProofOfAuthority(_) => {
+                let key =
+                    key.ok_or("Graph uses proof of authority but this node has no authority key")?;
+                block.sign(key);
+                Ok(())
+            }
+        }
+    }
+
+    /// Checks the seal of a block after genesis. `authorities` is the set in
+    /// effect at the block's height (ignored for proof of work).
+    pub fn check_seal(
+        &self,
+        block: &Block,
+        difficulty: usize,
+        authorities: Option<&AuthoritySet>,
+    ) -> Result<(), String> {
+        match self {
+            Consensus::ProofOfWork => {
+                if block.has_valid_difficulty(difficulty) {
+                    Ok(())
+                } else {
+                    Err("Block does not meet difficulty requirement".to_string())
+                }
+            }
+            Consensus::ProofOfAuthority(_) => {
+                if !block.has_valid_signature() {
+                    return Err("Missing or invalid authority signature".to_string());
+                }
+                let authorities = authorities.ok_or("No authority set in effect")?;
+                if !authorities.allows(&block.signer, block.height) {
+                    return Err(match authorities.expected_signer(block.height) {
+                        Some(expected) => format!(
+                            "Block #{} must be signed by authority {}",
+                            block.height, expected
+                        ),
+                        None => format!("Signer {} is not an authority", block.signer),
+                    });
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Block data announcing a new authority set
+pub fn authority_update_data(set: &AuthoritySet) -> String {
+    serde_json::json!({ AUTHORITY_UPDATE_KEY: set }).to_string()
+}
+
+/// Reads a valid authority update from block data
+pub fn parse_authority_update(data: &str) -> Option<AuthoritySet> {
+    if !data.contains(AUTHORITY_UPDATE_KEY) {
+        return None;
+    }
+    let value: serde_json::Value = serde_json::from_str(data).ok()?;
+    let set: AuthoritySet =
+        serde_json::from_value(value.get(AUTHORITY_UPDATE_KEY)?.clone()).ok()?;
+    set.validate().ok()?;
+    Some(set)
+}
+
+/// Creates a new random authority key
+pub fn generate_signing_key() -> SigningKey {
+    let mut seed = [0u8; 32];
+    rand::rngs::OsRng.fill_bytes(&mut seed);
+    SigningKey::from_bytes(&seed)
+}
+
+/// Reads an authority key from its hex-encoded 32-byte seed
+pub fn signing_key_from_hex(seed: &str) -> Result<SigningKey, String> {
+    let bytes = hex::decode(seed.trim()).map_err(|e| format!("Invalid authority key: {}", e))?;
+    let seed: [u8; 32] = bytes
+        .try_into()
+        .map_err(|_| "Authority key must be 32 bytes".to_string())?;
+    Ok(SigningKey::from_bytes(&seed))
+}
+
+/// Hex-encoded public key of an authority key
+pub fn public_key_hex(key: &SigningKey) -> String {
+    hex::encode(key.verifying_key().as_bytes())
+}
+
+pub(crate) fn parse_public_key(key: &str) -> Result<VerifyingKey, String> {
+    let bytes = hex::decode(key).map_err(|_| format!("Authority key {} is not hex", key))?;
+    let bytes: [u8; 32] = bytes
+        .try_into()
+        .map_err(|_| format!("Authority key {} must be 32 bytes", key))?;
+    VerifyingKey::from_bytes(&bytes)
+        .map_err(|_| format!("Authority key {} is not a valid Ed25519 key", key))
+}
+
+/// Signs `message` and returns the hex-encoded signature
+pub fn sign_message(key: &SigningKey, message: &[u8]) -> String {
+    hex::encode(key.sign(message).to_bytes())
+}
+
+/// Verifies a hex-encoded signature made by the hex-encoded public key
+pub fn verify_message(public_key: &str, message: &[u8], signature: &str) -> bool {
+    let key = match parse_public_key(public_key) {
+        Ok(key) => key,
+        Err(_) => return false,
+    };
+    let signature = match hex::decode(signature)
+        .ok()
+        .and_then(|b| <[u8; 64]>::try_from(b).ok())
+    {
+        Some(bytes) => Signature::from_bytes(&bytes),
+        None => return false,
+    };
+    key.verify(message, &signature).is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_robin_fixes_the_signer() {
+        let keys: Vec<String> = (0..3)
+            .map(|_| public_key_hex(&generate_signing_key()))
+            .collect();
+        let set = AuthoritySet {
+            authorities: keys.clone(),
+            rotation: AuthorityRotation::RoundRobin,
+        };
+        set.validate().unwrap();
+        assert!(set.allows(&keys[1], 4));
+        assert!(!set.allows(&keys[0], 4));
+
+        let any_of = AuthoritySet {
+            rotation: AuthorityRotation::AnyOf,
+            ..set.clone()
+        };
+        assert!(any_of.allows(&keys[0], 4));
+        assert!(!any_of.allows("ab", 4));
+    }
+
+    #[test]
+    fn test_authority_update_round_trip() {
+        let set = AuthoritySet {
+            authorities: vec![public_key_hex(&generate_signing_key())],
+            rotation: AuthorityRotation::AnyOf,
+        };
+        assert_eq!(
+            parse_authority_update(&authority_update_data(&set)),
+            Some(set)
+        );
+        assert_eq!(
+            parse_authority_update(
+                r#"{"authority_update": {"authorities": [], "rotation": "AnyOf"}}"#
+            ),
+            None
+        );
+        assert_eq!(parse_authority_update("plain data"), None);
+    }
+
+    #[test]
+    fn test_key_hex_round_trip_and_signatures() {
+        let key = generate_signing_key();
+        let restored = signing_key_from_hex(&hex::encode(key.to_bytes())).unwrap();
+        assert_eq!(public_key_hex(&key), public_key_hex(&restored));
+
+        let signature = sign_message(&key, b"hello");
+        assert!(verify_message(&public_key_hex(&key), b"hello", &signature));
+        assert!(!verify_message(&public_key_hex(&key), b"hullo", &signature));
+    }
+}
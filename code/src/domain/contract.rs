@@ -0,0 +1,85 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use super::world_state::StateChanges;
+
+/// Key of the JSON object that binds a contract to a graph in block data,
+/// e.g. `{"contract_deploy": {"code": "<hex wasm>", "fuel_limit": 1000000}}`
+pub const CONTRACT_DEPLOY_KEY: &str = "contract_deploy";
+
+/// A WebAssembly module deployed on a graph. It runs on every later block
+/// until another deployment replaces it.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ContractDeployment {
+    /// Module bytes, hex encoded
+    pub code: String,
+    /// Fuel available to each execution
+    pub fuel_limit: u64,
+}
+
+impl ContractDeployment {
+    pub fn new(code: &[u8], fuel_limit: u64) -> Self {
+        Self {
+            code: hex::encode(code),
+            fuel_limit,
+        }
+    }
+
+    pub fn code_bytes(&self) -> Result<Vec<u8>, String> {
+        hex::decode(&self.code).map_err(|e| format!("Invalid contract code: {}", e))
+    }
+
+    /// SHA-256 of the module bytes (hex)
+    pub fn code_hash(&self) -> String {
+        let code = self.code_bytes().unwrap_or_default();
+        hex::encode(Sha256::digest(code))
+    }
+}
+
+/// What a contract decided about a block
+#[derive(Debug, Clone, PartialEq)]
+pub enum ContractOutcome {
+    /// The block may be appended together with these state writes
+    Accepted {
+        changes: StateChanges,
+        fuel_used: u64,
+    },
+    /// The block is refused (explicitly, by a trap or by running out of fuel)
+    Rejected(String),
+}
+
+/// Block data deploying a contract
+pub fn contract_deploy_data(deployment: &ContractDeployment) -> String {
+    serde_json::json!({ CONTRACT_DEPLOY_KEY: deployment }).to_string()
+}
+
+/// Reads a contract deployment from block data
+pub fn parse_contract_deploy(data: &str) -> Option<ContractDeployment> {
+    if !data.contains(CONTRACT_DEPLOY_KEY) {
+        return None;
+    }
+    let value: serde_json::Value = serde_json::from_str(data).ok()?;
+    let deployment: ContractDeployment =
+        serde_json::from_value(value.get(CONTRACT_DEPLOY_KEY)?.clone()).ok()?;
+    deployment.code_bytes().ok()?;
+    Some(deployment)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_deploy_data_round_trip() {
+        let deployment = ContractDeployment::new(b"\0asm\x01\0\0\0", 5_000);
+        assert_eq!(
+            parse_contract_deploy(&contract_deploy_data(&deployment)),
+            Some(deployment)
+        );
+        assert_eq!(
+            parse_contract_deploy(r#"{"contract_deploy": {"code": "zz", "fuel_limit": 1}}"#),
+            None
+        );
+        assert_eq!(parse_contract_deploy("plain data"), None);
+    }
+}
@@ -0,0 +1,309 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+
+use super::consensus::{parse_public_key, verify_message};
+
+/// An organization allowed to endorse proposals, with its Ed25519 key (hex)
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Endorser {
+    pub org: String,
+    pub public_key: String,
+}
+
+/// "`required` of {endorsers}": blocks of the graph are held as proposals
+/// until that many distinct organizations have signed them
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct EndorsementPolicy {
+    pub required: usize,
+    pub endorsers: Vec<Endorser>,
+}
+
+impl EndorsementPolicy {
+    /// Requires 1 <= required <= endorsers, unique orgs and valid keys
+    pub fn validate(&self) -> Result<(), String> {
+        if self.endorsers.is_empty() {
+            return Err("Endorsement policy needs at least one endorser".to_string());
+        }
+        if self.required == 0 || self.required > self.endorsers.len() {
+            return Err(format!(
+                "Endorsement policy requires {} of {} endorsers",
+                self.required,
+                self.endorsers.len()
+            ));
+        }
+        for (i, endorser) in self.endorsers.iter().enumerate() {
+            if endorser.org.trim().is_empty() {
+                return Err("Endorser org must not be empty".to_string());
+            }
+            if self.endorsers[..i].iter().any(|e| e.org == endorser.org) {
+                return Err(format!("Endorser org '{}' is listed twice", endorser.org));
+            }
+            parse_public_key(&endorser.public_key)?;
+        }
+        Ok(())
+    }
+
+    pub fn endorser(&self, org: &str) -> Option<&Endorser> {
+        self.endorsers.iter().find(|e| e.org == org)
+    }
+
+    /// Human-readable form, e.g. "2 of {orgA, orgB, orgC}"
+    pub fn describe(&self) -> String {
+        let orgs: Vec<&str> = self.endorsers.iter().map(|e| e.org.as_str()).collect();
+        format!("{} of {{{}}}", self.required, orgs.join(", "))
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum ProposalStatus {
+    /// Waiting for endorsements
+    Pending,
+    /// Endorsed and appended to the graph
+    Committed,
+    /// Not endorsed before `expires_at_ms`
+    Expired,
+    /// Endorsed, but the block could not be sealed or appended
+    Rejected,
+}
+
+impl ProposalStatus {
+    pub fn to_i32(&self) -> i32 {
+        match self {
+            ProposalStatus::Pending => 0,
+            ProposalStatus::Committed => 1,
+            ProposalStatus::Expired => 2,
+            ProposalStatus::Rejected => 3,
+        }
+    }
+}
+
+/// Id of a proposal: the hash endorsers sign. It covers the block content
+/// and a nonce, so it can be recomputed from a block carrying both.
+pub fn proposal_id(graph_id: &str, data: &str, cross_references: &[String], nonce: &str) -> String {
+    let content = serde_json::to_vec(&(graph_id, data, cross_references, nonce))
+        .expect("strings always serialize");
+    hex::encode(Sha256::digest(&content))
+}
+
+/// Endorsements recorded in a block: the nonce of its proposal and each
+/// org's signature of the proposal id
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct BlockEndorsements {
+    pub nonce: String,
+    pub signatures: BTreeMap<String, String>,
+}
+
+impl BlockEndorsements {
+    /// Checks that at least `policy.required` endorsers of the policy signed
+    /// the proposal id recomputed from `graph_id`, `data` and
+    /// `cross_references`
+    pub fn verify(
+        &self,
+        policy: &EndorsementPolicy,
+        graph_id: &str,
+        data: &str,
+        cross_references: &[String],
+    ) -> Result<(), String> {
+        if self.nonce.is_empty() {
+            return Err("Endorsements carry no proposal nonce".to_string());
+        }
+        let id = proposal_id(graph_id, data, cross_references, &self.nonce);
+        for (org, signature) in &self.signatures {
+            let endorser = policy
+                .endorser(org)
+                .ok_or_else(|| format!("'{}' is not an endorser", org))?;
+            if !verify_message(&endorser.public_key, id.as_bytes(), signature) {
+                return Err(format!("Invalid endorsement signature from '{}'", org));
+            }
+        }
+        if self.signatures.len() < policy.required {
+            return Err(format!(
+                "Block has {} endorsement(s); {} required",
+                self.signatures.len(),
+                policy.describe()
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Block content held until the graph's endorsement policy is satisfied.
+/// Endorsers sign the proposal id (see [`proposal_id`]).
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Proposal {
+    pub id: String,
+    pub graph_id: String,
+    pub data: String,
+    pub cross_references: Vec<String>,
+    pub created_at_ms: i64,
+    pub expires_at_ms: i64,
+    /// Signature (hex) per endorsing org
+    pub endorsements: BTreeMap<String, String>,
+    pub status: ProposalStatus,
+    /// Hash of the appended block once committed
+    pub block_hash: String,
+    /// Why the proposal was rejected
+    pub message: String,
+    /// Random value making the id unique; recorded with the block so the
+    /// id can be recomputed there. Empty for proposals stored before it.
+    pub nonce: String,
+}
+
+impl Proposal {
+    pub fn new(
+        graph_id: String,
+        data: String,
+        cross_references: Vec<String>,
+        now_ms: i64,
+        ttl_ms: i64,
+    ) -> Self {
+        let nonce = hex::encode(rand::random::<[u8; 16]>());
+        Self {
+            id: proposal_id(&graph_id, &data, &cross_references, &nonce),
+            graph_id,
+            data,
+            cross_references,
+            created_at_ms: now_ms,
+            expires_at_ms: now_ms.saturating_add(ttl_ms),
+            endorsements: BTreeMap::new(),
+            status: ProposalStatus::Pending,
+            block_hash: String::new(),
+            message: String::new(),
+            nonce,
+        }
+    }
+
+    /// Marks a pending proposal as expired once its deadline has passed.
+    /// Returns true if the status changed.
+    pub fn expire_if_due(&mut self, now_ms: i64) -> bool {
+        if self.status == ProposalStatus::Pending && now_ms >= self.expires_at_ms {
+            self.status = ProposalStatus::Expired;
+            return true;
+        }
+        false
+    }
+
+    /// Records `org`'s signature of the proposal id
+    pub fn endorse(
+        &mut self,
+        policy: &EndorsementPolicy,
+        org: &str,
+        signature: &str,
+        now_ms: i64,
+    ) -> Result<(), String> {
+        self.expire_if_due(now_ms);
+        if self.status != ProposalStatus::Pending {
+            return Err(format!("Proposal is {:?}", self.status));
+        }
+        let endorser = policy
+            .endorser(org)
+            .ok_or_else(|| format!("'{}' is not an endorser of this graph", org))?;
+        if self.endorsements.contains_key(org) {
+            return Err(format!("'{}' already endorsed this proposal", org));
+        }
+        if !verify_message(&endorser.public_key, self.id.as_bytes(), signature) {
+            return Err(format!("Invalid endorsement signature from '{}'", org));
+        }
+        self.endorsements
+            .insert(org.to_string(), signature.to_string());
+        Ok(())
+    }
+
+    pub fn is_satisfied(&self, policy: &EndorsementPolicy) -> bool {
+        self.endorsements.len() >= policy.required
+    }
+
+    /// Endorsements to record in the block this proposal becomes
+    pub fn block_endorsements(&self) -> BlockEndorsements {
+        BlockEndorsements {
+            nonce: self.nonce.clone(),
+            signatures: self.endorsements.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::consensus::{generate_signing_key, public_key_hex, sign_message};
+
+    #[test]
+    fn test_proposal_collects_endorsements_until_expiry() {
+        let keys: Vec<_> = (0..3).map(|_| generate_signing_key()).collect();
+        let policy = EndorsementPolicy {
+            required: 2,
+            endorsers: ["orgA", "orgB", "orgC"]
+                .iter()
+                .zip(&keys)
+                .map(|(org, key)| Endorser {
+                    org: org.to_string(),
+                    public_key: public_key_hex(key),
+                })
+                .collect(),
+        };
+        policy.validate().unwrap();
+        assert_eq!(policy.describe(), "2 of {orgA, orgB, orgC}");
+
+        let mut proposal =
+            Proposal::new("payments".to_string(), "tx".to_string(), vec![], 1_000, 500);
+        let signature_a = sign_message(&keys[0], proposal.id.as_bytes());
+
+        // Signatures must come from the org's own key and count once
+        assert!(proposal
+            .endorse(&policy, "orgB", &signature_a, 1_100)
+            .is_err());
+        assert!(proposal
+            .endorse(&policy, "orgD", &signature_a, 1_100)
+            .is_err());
+        proposal
+            .endorse(&policy, "orgA", &signature_a, 1_100)
+            .unwrap();
+        assert!(proposal
+            .endorse(&policy, "orgA", &signature_a, 1_100)
+            .is_err());
+        assert!(!proposal.is_satisfied(&policy));
+
+        // Too late: the proposal expires instead
+        let signature_c = sign_message(&keys[2], proposal.id.as_bytes());
+        let mut late = proposal.clone();
+        assert!(late.endorse(&policy, "orgC", &signature_c, 1_500).is_err());
+        assert_eq!(late.status, ProposalStatus::Expired);
+
+        proposal
+            .endorse(&policy, "orgC", &signature_c, 1_200)
+            .unwrap();
+        assert!(proposal.is_satisfied(&policy));
+
+        // The block carries what is needed to check the endorsements again
+        let endorsements = proposal.block_endorsements();
+        endorsements.verify(&policy, "payments", "tx", &[]).unwrap();
+        assert!(endorsements
+            .verify(&policy, "payments", "tx changed", &[])
+            .is_err());
+        let mut one = endorsements.clone();
+        one.signatures.remove("orgA");
+        assert!(one.verify(&policy, "payments", "tx", &[]).is_err());
+    }
+
+    #[test]
+    fn test_policy_validation() {
+        let key = public_key_hex(&generate_signing_key());
+        let endorser = |org: &str| Endorser {
+            org: org.to_string(),
+            public_key: key.clone(),
+        };
+        let policy = |required, endorsers| EndorsementPolicy {
+            required,
+            endorsers,
+        };
+
+        assert!(policy(1, vec![endorser("orgA")]).validate().is_ok());
+        assert!(policy(0, vec![endorser("orgA")]).validate().is_err());
+        assert!(policy(2, vec![endorser("orgA")]).validate().is_err());
+        assert!(policy(1, vec![endorser("orgA"), endorser("orgA")])
+            .validate()
+            .is_err());
+        assert!(policy(1, vec![]).validate().is_err());
+    }
+}
@@ -0,0 +1,47 @@
+use super::Block;
+
+/// Side branches forking more than this many blocks below the tip are
+/// rejected, and side blocks that fall this far behind are pruned
+pub const MAX_REORG_DEPTH: u64 = 100;
+
+/// Expected number of hashes needed to meet `difficulty` leading hex zeros.
+/// Difficulty is set per graph, so every block of a graph carries the same work.
+pub fn block_work(difficulty: usize) -> u128 {
+    16u128.saturating_pow(difficulty as u32)
+}
+
+/// A switch of the main chain to a branch with more cumulative work
+#[derive(Debug, Clone, PartialEq)]
+pub struct Reorg {
+    /// Height of the last block shared by the old and the new chain
+    pub fork_height: u64,
+    /// Blocks removed from the main chain (kept as a side branch), in height order
+    pub disconnected: Vec<Block>,
+    /// Blocks that became part of the main chain, in height order
+    pub connected: Vec<Block>,
+}
+
+/// What `BlockchainGraph::accept_block` did with a valid block
+#[derive(Debug, Clone, PartialEq)]
+pub enum BlockOutcome {
+    /// The block extended the main chain
+    Extended,
+    /// The block is known already (main chain or side branch)
+    Duplicate,
+    /// The block was stored on a side branch with less or equal work
+    SideBranch,
+    /// The block's branch overtook the main chain
+    Reorganized(Reorg),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_block_work_grows_with_difficulty() {
+        assert_eq!(block_work(0), 1);
+        assert_eq!(block_work(2), 256);
+        assert!(block_work(64) > block_work(8));
+    }
+}
@@ -1,7 +1,29 @@
 use chrono::Utc;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::sync::Arc;
 
+use ed25519_dalek::SigningKey;
+
+use super::anchor::parse_anchors;
+use super::asset::{
+    apply_asset_operation, parse_asset_operation, parse_asset_policy, AssetOperation, AssetPolicy,
+    ASSET_KEY_PREFIX,
+};
+use super::checkpoint::VerifiedCheckpoint;
+use super::consensus::{parse_authority_update, AuthoritySet, Consensus};
+use super::contract::{parse_contract_deploy, ContractDeployment, ContractOutcome};
+use super::endorsement::EndorsementPolicy;
+use super::fork::{block_work, BlockOutcome, Reorg, MAX_REORG_DEPTH};
+use super::identity::{
+    apply_identity_operation, parse_identity_operation, parse_signer, signed_by_identity_at,
+    IDENTITY_KEY_PREFIX,
+};
+use super::schema::{parse_schema_update, DataSchema};
+use super::traits::ContractRuntime;
+use super::world_state::{
+    apply_writes, commit_changes, parse_rwset, StateChanges, StateWrites, WorldState,
+};
 use super::Block;
 
 /// Types of blockchain graphs for different data responsibilities
@@ -45,8 +67,27 @@ pub struct BlockchainGraph {
     pub description: String,
     pub created_at: i64,
     pub difficulty: usize,
+    #[serde(default)]
+    pub consensus: Consensus,
+    /// When set, blocks are held as proposals until endorsed
+    #[serde(default)]
+    pub endorsement: Option<EndorsementPolicy>,
+    /// When set, approves the blocks that change the graph's settings
+    /// (contract deployments, schema updates and asset policies)
+    #[serde(default)]
+    pub governance: Option<EndorsementPolicy>,
+    /// Schema block data must match when the graph was created; on-chain
+    /// updates replace it from their height on
+    #[serde(default)]
+    pub schema: Option<DataSchema>,
     #[serde(skip)]
     pub chain: Vec<Block>, // In-memory cache, not persisted
+    /// Valid blocks that are not on the main chain, keyed by hash
+    #[serde(skip)]
+    pub side_blocks: HashMap<String, Block>,
+    /// World state written by block payloads and the graph's contract, as of the tip
+    #[serde(skip)]
+    pub state: Arc<WorldState>,
 }
 
 impl BlockchainGraph {
@@ -60,12 +101,61 @@ impl BlockchainGraph {
             description,
             created_at: Utc::now().timestamp(),
             difficulty,
+            consensus: Consensus::ProofOfWork,
+            endorsement: None,
+            governance: None,
+            schema: None,
             chain: vec![genesis],
+            side_blocks: HashMap::new(),
+            state: Arc::default(),
         }
     }
 
+    /// Sets how blocks are sealed (proof of work by default)
+    pub fn with_consensus(mut self, consensus: Consensus) -> Self {
+        self.consensus = consensus;
+        self
+    }
+
+    /// Requires endorsements before blocks are appended
+    pub fn with_endorsement(mut self, policy: EndorsementPolicy) -> Self {
+        self.endorsement = Some(policy);
+        self
+    }
+
+    /// Requires endorsements from `policy` for blocks that change settings
+    pub fn with_governance(mut self, policy: EndorsementPolicy) -> Self {
+        self.governance = Some(policy);
+        self
+    }
+
+    /// Requires block data to match a JSON Schema
+    pub fn with_schema(mut self, schema: DataSchema) -> Self {
+        self.schema = Some(schema);
+        self
+    }
+
+    /// Seals a new block on the tip (mining it, or signing it with `key`
+    /// under proof of authority) and adds it to the graph
+    pub fn seal_block(
+        &mut self,
+        mut block: Block,
+        key: Option<&SigningKey>,
+    ) -> Result<Block, String> {
+        if !self.consensus.is_authority() {
+            return self.add_block(block);
+        }
+        self.consensus.seal(&mut block, self.difficulty, key)?;
+        self.append_block(block.clone())?;
+        Ok(block)
+    }
+
     /// Adds a new block to the graph
     pub fn add_block(&mut self, mut block: Block) -> Result<Block, String> {
+        if self.consensus.is_authority() {
+            return Err("Graph uses proof of authority; blocks must be signed".to_string());
+        }
+
         // Validate previous hash
         if let Some(last_block) = self.chain.last() {
             if block.previous_hash != last_block.hash {
@@ -75,6 +165,7 @@ impl BlockchainGraph {
                 return Err("Invalid block height".to_string());
             }
         }
+        self.check_block_data(&self.chain, &block)?;
 
         // Mine the block
         block.mine_block(self.difficulty);
@@ -92,6 +183,591 @@ impl BlockchainGraph {
         Ok(block)
     }
 
+    /// Appends an already-mined block (e.g. from an import or a peer),
+    /// applying the same checks as `add_block` without mining
+    pub fn append_block(&mut self, block: Block) -> Result<(), String> {
+        if block.graph_id != self.id {
+            return Err(format!(
+                "Block belongs to graph '{}', expected '{}'",
+                block.graph_id, self.id
+            ));
+        }
+
+        if !block.is_valid() {
+            return Err("Invalid block hash".to_string());
+        }
+
+        match self.chain.last() {
+            Some(last_block) => {
+                if block.previous_hash != last_block.hash {
+                    return Err("Invalid previous hash".to_string());
+                }
+                if block.height != last_block.height + 1 {
+                    return Err("Invalid block height".to_string());
+                }
+                let authorities = self.authorities_at(&self.chain);
+                self.consensus
+                    .check_seal(&block, self.difficulty, authorities.as_ref())?;
+                self.check_block_data(&self.chain, &block)?;
+            }
+            None => {
+                if block.height != 0 || block.previous_hash != "0" {
+                    return Err("First block must be a genesis block".to_string());
+                }
+            }
+        }
+
+        self.chain.push(block);
+        Ok(())
+    }
+
+    /// Accepts an already-mined block that extends either the main chain or
+    /// a side branch. When a side branch gains more cumulative work than the
+    /// main chain it becomes the main chain (fork choice); on equal work the
+    /// chain seen first is kept.
+    pub fn accept_block(&mut self, block: Block) -> Result<BlockOutcome, String> {
+        if self.knows_block(&block.hash) {
+            return Ok(BlockOutcome::Duplicate);
+        }
+
+        let extends_tip = match self.chain.last() {
+            Some(tip) => block.previous_hash == tip.hash,
+            None => true,
+        };
+        if extends_tip {
+            self.append_block(block)?;
+            return Ok(BlockOutcome::Extended);
+        }
+
+        if block.graph_id != self.id {
+            return Err(format!(
+                "Block belongs to graph '{}', expected '{}'",
+                block.graph_id, self.id
+            ));
+        }
+        if !block.is_valid() {
+            return Err("Invalid block hash".to_string());
+        }
+
+        let parent_height = match self.parent_height(&block.previous_hash) {
+            Some(height) => height,
+            None => return Err(format!("Unknown parent block {}", block.previous_hash)),
+        };
+        if block.height != parent_height + 1 {
+            return Err("Invalid block height".to_string());
+        }
+
+        let (fork_height, branch) = self.branch_ending_with(block)?;
+        let tip_height = self.tip_height();
+        if tip_height - fork_height > MAX_REORG_DEPTH {
+            return Err(format!(
+                "Fork at height {} is more than {} blocks below the tip",
+                fork_height, MAX_REORG_DEPTH
+            ));
+        }
+
+        // The seal is checked against the authorities in effect on this branch
+        let mut ancestors = self.chain[..=fork_height as usize].to_vec();
+        ancestors.extend(branch[..branch.len() - 1].iter().cloned());
+        let authorities = self.authorities_at(&ancestors);
+        self.consensus.check_seal(
+            &branch[branch.len() - 1],
+            self.difficulty,
+            authorities.as_ref(),
+        )?;
+        self.check_block_data(&ancestors, &branch[branch.len() - 1])?;
+
+        let new_block = branch.last().cloned().ok_or("Empty branch")?;
+        self.side_blocks.insert(new_block.hash.clone(), new_block);
+
+        let work = self.work_per_block();
+        let branch_work = work * branch.len() as u128;
+        let main_work = work * (tip_height - fork_height) as u128;
+        if branch_work <= main_work {
+            return Ok(BlockOutcome::SideBranch);
+        }
+
+        for block in &branch {
+            self.side_blocks.remove(&block.hash);
+        }
+        let disconnected = self.chain.split_off(fork_height as usize + 1);
+        for block in &disconnected {
+            self.side_blocks.insert(block.hash.clone(), block.clone());
+        }
+        self.chain.extend(branch.iter().cloned());
+
+        Ok(BlockOutcome::Reorganized(Reorg {
+            fork_height,
+            disconnected,
+            connected: branch,
+        }))
+    }
+
+    /// Undoes a reorganization returned by `accept_block` (e.g. when it
+    /// could not be persisted); the connected blocks go back to a side branch
+    pub fn revert_reorg(&mut self, reorg: &Reorg) {
+        self.chain.truncate(reorg.fork_height as usize + 1);
+        for block in &reorg.connected {
+            self.side_blocks.insert(block.hash.clone(), block.clone());
+        }
+        for block in &reorg.disconnected {
+            self.side_blocks.remove(&block.hash);
+            self.chain.push(block.clone());
+        }
+    }
+
+    /// Drops side blocks more than `MAX_REORG_DEPTH` below the tip and
+    /// returns their hashes
+    pub fn prune_side_blocks(&mut self) -> Vec<String> {
+        let min_height = self.tip_height().saturating_sub(MAX_REORG_DEPTH);
+        let stale: Vec<String> = self
+            .side_blocks
+            .values()
+            .filter(|b| b.height < min_height)
+            .map(|b| b.hash.clone())
+            .collect();
+        for hash in &stale {
+            self.side_blocks.remove(hash);
+        }
+        stale
+    }
+
+    /// Returns true if the block is on the main chain or a side branch
+    pub fn knows_block(&self, hash: &str) -> bool {
+        self.side_blocks.contains_key(hash) || self.chain.iter().rev().any(|b| b.hash == hash)
+    }
+
+    /// Total work of the main chain (the genesis block is not sealed)
+    pub fn cumulative_work(&self) -> u128 {
+        self.work_per_block() * self.tip_height() as u128
+    }
+
+    /// Work of one block: its expected mining effort, or 1 per signed block
+    fn work_per_block(&self) -> u128 {
+        match self.consensus {
+            Consensus::ProofOfWork => block_work(self.difficulty),
+            Consensus::ProofOfAuthority(_) => 1,
+        }
+    }
+
+    /// Authority set in effect after `chain`: the latest on-chain update,
+    /// or the set the graph was created with
+    pub fn authorities_at(&self, chain: &[Block]) -> Option<AuthoritySet> {
+        match &self.consensus {
+            Consensus::ProofOfWork => None,
+            Consensus::ProofOfAuthority(initial) => Some(
+                chain
+                    .iter()
+                    .rev()
+                    .filter(|b| b.height > 0)
+                    .find_map(|b| parse_authority_update(&b.data))
+                    .unwrap_or_else(|| initial.clone()),
+            ),
+        }
+    }
+
+    /// Authority set that signs the next block
+    pub fn current_authorities(&self) -> Option<AuthoritySet> {
+        self.authorities_at(&self.chain)
+    }
+
+    /// Checks the seal of every block after genesis, following on-chain
+    /// authority updates
+    pub fn verify_seals(&self) -> Result<(), String> {
+        self.verify_seals_above(0)
+    }
+
+    /// Checks the seal of every block above height `from`, starting from the
+    /// authority set in effect at `from`
+    pub fn verify_seals_above(&self, from: u64) -> Result<(), String> {
+        let start = (from as usize + 1).min(self.chain.len());
+        let mut authorities = self.authorities_at(&self.chain[..start]);
+        for block in &self.chain[start..] {
+            self.consensus
+                .check_seal(block, self.difficulty, authorities.as_ref())
+                .map_err(|e| format!("Block #{}: {}", block.height, e))?;
+            if self.consensus.is_authority() {
+                if let Some(update) = parse_authority_update(&block.data) {
+                    authorities = Some(update);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Data schema in effect after `chain`: the latest on-chain update, or
+    /// the schema the graph was created with
+    pub fn schema_at(&self, chain: &[Block]) -> Option<DataSchema> {
+        chain
+            .iter()
+            .rev()
+            .filter(|b| b.height > 0)
+            .find_map(|b| parse_schema_update(&b.data))
+            .unwrap_or_else(|| self.schema.clone())
+    }
+
+    /// Data schema the next block must match
+    pub fn current_schema(&self) -> Option<DataSchema> {
+        self.schema_at(&self.chain)
+    }
+
+    /// Checks the data of `block`, which extends `chain`, against the schema
+    /// in effect. Blocks that change graph settings are not data and skip it.
+    pub fn check_block_schema(&self, chain: &[Block], block: &Block) -> Result<(), String> {
+        match self.schema_at(chain) {
+            Some(schema) if !self.is_control_data(&block.data) => schema.validate_data(&block.data),
+            _ => Ok(()),
+        }
+    }
+
+    /// Checks `block`, which extends `chain`: its data against the schema in
+    /// effect and its endorsements against the policy that must approve it
+    pub fn check_block_data(&self, chain: &[Block], block: &Block) -> Result<(), String> {
+        self.check_block_schema(chain, block)?;
+        let authorities = self.authorities_at(chain);
+        if let Some(policy) = self.approval_policy(authorities.as_ref(), &block.data)? {
+            let nonce = check_endorsements(&policy, block)?;
+            if chain
+                .iter()
+                .filter_map(|b| b.endorsements.as_ref())
+                .any(|e| e.nonce == nonce)
+            {
+                return Err("Block reuses the endorsements of an earlier block".to_string());
+            }
+        }
+        Ok(())
+    }
+
+    /// Policy whose endorsements a block with `data` must carry, given the
+    /// authority set in effect: authority updates are approved by the
+    /// current authorities, governed settings by the graph's governors and
+    /// other blocks by its endorsement policy. None when the block is
+    /// committed directly; an error when nobody may approve it.
+    pub fn approval_policy(
+        &self,
+        authorities: Option<&AuthoritySet>,
+        data: &str,
+    ) -> Result<Option<EndorsementPolicy>, String> {
+        match authorities {
+            Some(authorities) if parse_authority_update(data).is_some() => {
+                Ok(Some(authorities.approval_policy()))
+            }
+            _ if self.is_governed_data(data) => {
+                self.governors(authorities).map(Some).ok_or_else(|| {
+                    format!(
+                        "Graph '{}' has no governance; its settings are fixed at creation",
+                        self.id
+                    )
+                })
+            }
+            _ => Ok(self.endorsement.clone()),
+        }
+    }
+
+    /// Who approves changes to governed settings: the governance policy,
+    /// else the authorities in effect, else the endorsers
+    fn governors(&self, authorities: Option<&AuthoritySet>) -> Option<EndorsementPolicy> {
+        self.governance
+            .clone()
+            .or_else(|| authorities.map(AuthoritySet::approval_policy))
+            .or_else(|| self.endorsement.clone())
+    }
+
+    /// Whether `data` changes a setting approved by the governors: contract
+    /// deployments, schema updates and asset policies on asset graphs
+    fn is_governed_data(&self, data: &str) -> bool {
+        parse_contract_deploy(data).is_some()
+            || parse_schema_update(data).is_some()
+            || (self.graph_type == GraphType::Asset && parse_asset_policy(data).is_some())
+    }
+
+    /// Checks every block after genesis against the schema in effect at its height
+    pub fn verify_data(&self) -> Result<(), String> {
+        self.verify_data_above(0)
+    }
+
+    /// Checks every block above height `from` against the schema in effect at
+    /// its height, and its endorsements against the policy that had to approve it
+    pub fn verify_data_above(&self, from: u64) -> Result<(), String> {
+        let start = (from as usize + 1).min(self.chain.len());
+        let mut schema = self.schema_at(&self.chain[..start]);
+        let mut authorities = self.authorities_at(&self.chain[..start]);
+        let mut nonces: HashSet<&str> = self.chain[..start]
+            .iter()
+            .filter_map(|b| b.endorsements.as_ref())
+            .map(|e| e.nonce.as_str())
+            .collect();
+        for block in &self.chain[start..] {
+            let policy = self
+                .approval_policy(authorities.as_ref(), &block.data)
+                .map_err(|e| format!("Block #{}: {}", block.height, e))?;
+            if let Some(policy) = policy {
+                let nonce = check_endorsements(&policy, block)
+                    .map_err(|e| format!("Block #{}: {}", block.height, e))?;
+                if !nonces.insert(nonce) {
+                    return Err(format!(
+                        "Block #{}: Block reuses the endorsements of an earlier block",
+                        block.height
+                    ));
+                }
+            }
+            if self.consensus.is_authority() {
+                if let Some(update) = parse_authority_update(&block.data) {
+                    authorities = Some(update);
+                }
+            }
+            if let Some(update) = parse_schema_update(&block.data) {
+                schema = update;
+            } else if let Some(schema) = schema
+                .as_ref()
+                .filter(|_| !self.is_control_data(&block.data))
+            {
+                schema
+                    .validate_data(&block.data)
+                    .map_err(|e| format!("Block #{}: {}", block.height, e))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether `data` changes the settings of this graph: contract
+    /// deployments and schema updates on any graph, authority updates on
+    /// proof-of-authority graphs and asset policies on asset graphs
+    pub fn is_control_data(&self, data: &str) -> bool {
+        parse_contract_deploy(data).is_some()
+            || parse_schema_update(data).is_some()
+            || (self.consensus.is_authority() && parse_authority_update(data).is_some())
+            || (self.graph_type == GraphType::Asset && parse_asset_policy(data).is_some())
+    }
+
+    /// Contract in effect after `chain`: the latest deployment on it
+    pub fn contract_at(&self, chain: &[Block]) -> Option<ContractDeployment> {
+        chain
+            .iter()
+            .rev()
+            .filter(|b| b.height > 0)
+            .find_map(|b| parse_contract_deploy(&b.data))
+    }
+
+    /// Contract that runs on the next block
+    pub fn current_contract(&self) -> Option<ContractDeployment> {
+        self.contract_at(&self.chain)
+    }
+
+    /// Asset policy in effect after `chain`: the latest on-chain update, or
+    /// the default. Only asset graphs have one.
+    pub fn asset_policy_at(&self, chain: &[Block]) -> Option<AssetPolicy> {
+        if self.graph_type != GraphType::Asset {
+            return None;
+        }
+        Some(
+            chain
+                .iter()
+                .rev()
+                .filter(|b| b.height > 0)
+                .find_map(|b| parse_asset_policy(&b.data))
+                .unwrap_or_default(),
+        )
+    }
+
+    /// Payment graph a block extending `chain` must cross-reference, if its
+    /// data is an asset transfer and the asset policy asks for payments
+    pub fn required_payment_graph(&self, chain: &[Block], data: &str) -> Option<String> {
+        match parse_asset_operation(data) {
+            Ok(Some(AssetOperation::Transfer { .. })) => self.asset_policy_at(chain)?.payment_graph,
+            _ => None,
+        }
+    }
+
+    /// Checks what `block`, which extends `chain`, says about other graphs:
+    /// an asset policy must name a transaction graph and, while one is
+    /// named, transfers must cross-reference one of its blocks; a `signer`
+    /// must be an identity of an identity graph holding the signing key
+    /// when the block was created. Every path that admits a block runs it.
+    pub fn check_references(
+        &self,
+        chain: &[Block],
+        block: &Block,
+        graphs: &HashMap<String, BlockchainGraph>,
+    ) -> Result<(), String> {
+        if self.graph_type == GraphType::Asset {
+            if let Some(payment_graph) =
+                parse_asset_policy(&block.data).and_then(|policy| policy.payment_graph)
+            {
+                match graphs.get(&payment_graph) {
+                    Some(payments) if payments.graph_type == GraphType::Transaction => {}
+                    _ => {
+                        return Err(format!(
+                            "Payment graph '{}' is not a transaction graph",
+                            payment_graph
+                        ))
+                    }
+                }
+            }
+            if let Some(payment_graph) = self.required_payment_graph(chain, &block.data) {
+                let paid = graphs.get(&payment_graph).is_some_and(|payments| {
+                    block
+                        .cross_references
+                        .iter()
+                        .any(|hash| payments.chain.iter().skip(1).any(|b| &b.hash == hash))
+                });
+                if !paid {
+                    return Err(format!(
+                        "Transfer must cross-reference a payment block in graph '{}'",
+                        payment_graph
+                    ));
+                }
+            }
+        }
+
+        let (signer, message) = match parse_signer(&block.graph_id, &block.data)? {
+            Some(signed) => signed,
+            None => return Ok(()),
+        };
+        let identities = match graphs.get(&signer.graph_id) {
+            Some(graph) if graph.graph_type == GraphType::Identity => graph,
+            Some(_) => {
+                return Err(format!(
+                    "Graph '{}' is not an identity graph",
+                    signer.graph_id
+                ))
+            }
+            None => return Err(format!("Graph '{}' not found", signer.graph_id)),
+        };
+        if !signed_by_identity_at(
+            &identities.chain,
+            &signer.subject_id,
+            &message,
+            &signer.signature,
+            block.timestamp,
+        ) {
+            return Err(format!(
+                "Signature of '{}' does not match a key of the identity in '{}' at the block's time",
+                signer.subject_id, signer.graph_id
+            ));
+        }
+        Ok(())
+    }
+
+    /// Built-in operations of the graph type, with the settings in effect
+    /// after `chain`
+    fn registry_at(&self, chain: &[Block]) -> Option<Registry> {
+        match self.graph_type {
+            GraphType::Asset => self.asset_policy_at(chain).map(Registry::Assets),
+            GraphType::Identity => Some(Registry::Identities),
+            _ => None,
+        }
+    }
+
+    /// State changes of `block`, which extends `chain`: the payload's
+    /// read/write set, the writes of the contract in effect, then the
+    /// block's asset or identity operation
+    pub fn block_state_changes(
+        &self,
+        chain: &[Block],
+        state: Arc<WorldState>,
+        block: &Block,
+        runtime: &dyn ContractRuntime,
+    ) -> Result<StateChanges, String> {
+        run_block(
+            self.contract_at(chain).as_ref(),
+            self.registry_at(chain).as_ref(),
+            state,
+            block,
+            runtime,
+        )
+    }
+
+    /// State changes of the next block, against the current state
+    pub fn next_state_changes(
+        &self,
+        block: &Block,
+        runtime: &dyn ContractRuntime,
+    ) -> Result<StateChanges, String> {
+        self.block_state_changes(&self.chain, self.state.clone(), block, runtime)
+    }
+
+    /// Rebuilds the world state by replaying the main chain. Also returns
+    /// the write set of every block after genesis, in height order.
+    pub fn replay_state(
+        &self,
+        runtime: &dyn ContractRuntime,
+    ) -> Result<(WorldState, Vec<StateWrites>), String> {
+        let mut state = Arc::new(WorldState::new());
+        let mut writes = Vec::with_capacity(self.chain.len().saturating_sub(1));
+        let mut deployment = None;
+        let mut registry = self.registry_at(&[]);
+        for block in self.chain.iter().skip(1) {
+            let changes = run_block(
+                deployment.as_ref(),
+                registry.as_ref(),
+                state.clone(),
+                block,
+                runtime,
+            )?;
+            let block_writes = commit_changes(&changes, block);
+            apply_writes(Arc::make_mut(&mut state), &block_writes);
+            writes.push(block_writes);
+            if let Some(deployed) = parse_contract_deploy(&block.data) {
+                deployment = Some(deployed);
+            }
+            if let (Some(Registry::Assets(policy)), Some(update)) =
+                (registry.as_mut(), parse_asset_policy(&block.data))
+            {
+                *policy = update;
+            }
+        }
+        let state = Arc::try_unwrap(state).unwrap_or_else(|shared| (*shared).clone());
+        Ok((state, writes))
+    }
+
+    /// Applies a committed block's write set to the in-memory state
+    pub fn apply_state_writes(&mut self, writes: &StateWrites) {
+        if !writes.is_empty() {
+            apply_writes(Arc::make_mut(&mut self.state), writes);
+        }
+    }
+
+    fn tip_height(&self) -> u64 {
+        self.chain.last().map(|b| b.height).unwrap_or_default()
+    }
+
+    /// Height of a known block that a new block builds on
+    fn parent_height(&self, hash: &str) -> Option<u64> {
+        if let Some(block) = self.side_blocks.get(hash) {
+            return Some(block.height);
+        }
+        self.chain
+            .iter()
+            .rev()
+            .find(|b| b.hash == hash)
+            .map(|b| b.height)
+    }
+
+    /// Walks back from `block` through side blocks to the main chain.
+    /// Returns the fork height and the branch in height order.
+    fn branch_ending_with(&self, block: Block) -> Result<(u64, Vec<Block>), String> {
+        let mut branch = vec![block];
+        loop {
+            let first = &branch[branch.len() - 1];
+            let parent_height = first.height - 1;
+            if self.chain.get(parent_height as usize).map(|b| &b.hash) == Some(&first.previous_hash)
+            {
+                branch.reverse();
+                return Ok((parent_height, branch));
+            }
+            match self.side_blocks.get(&first.previous_hash) {
+                Some(parent) => branch.push(parent.clone()),
+                None => {
+                    return Err(format!(
+                        "Side branch is missing block {}",
+                        first.previous_hash
+                    ))
+                }
+            }
+        }
+    }
+
     /// Gets the latest block in the graph
     pub fn get_latest_block(&self) -> Option<&Block> {
         self.chain.last()
@@ -99,32 +775,50 @@ impl BlockchainGraph {
 
     /// Validates the entire chain
     pub fn is_valid(&self) -> bool {
-        for i in 1..self.chain.len() {
+        self.verify_since(None).is_ok()
+    }
+
+    /// Validates the blocks above `checkpoint`, trusting the chain up to it,
+    /// or the entire chain without one or when the checkpointed block is no
+    /// longer on it. Returns the tip as the new checkpoint.
+    pub fn verify_since(
+        &self,
+        checkpoint: Option<&VerifiedCheckpoint>,
+    ) -> Result<VerifiedCheckpoint, String> {
+        let from = checkpoint
+            .and_then(|c| c.resume_height(&self.chain))
+            .unwrap_or(0);
+        for i in from as usize + 1..self.chain.len() {
             let current = &self.chain[i];
             let previous = &self.chain[i - 1];
 
             // Check if block is valid
             if !current.is_valid() {
-                return false;
+                return Err(format!("Block #{}: Invalid block hash", current.height));
             }
 
             // Check if previous hash matches
             if current.previous_hash != previous.hash {
-                return false;
-            }
-
-            // Check difficulty
-            if !current.has_valid_difficulty(self.difficulty) {
-                return false;
+                return Err(format!(
+                    "Block #{}: Previous hash does not match",
+                    current.height
+                ));
             }
 
             // Check height
             if current.height != previous.height + 1 {
-                return false;
+                return Err(format!(
+                    "Block #{}: Height does not follow the previous block",
+                    current.height
+                ));
             }
         }
 
-        true
+        // Check difficulty or authority signatures, then block data
+        self.verify_seals_above(from)?;
+        self.verify_data_above(from)?;
+        let tip = self.get_latest_block().ok_or("Graph has no blocks")?;
+        Ok(VerifiedCheckpoint::of(tip))
     }
 
     /// Loads blocks into the in-memory cache
@@ -168,11 +862,130 @@ impl BlockchainGraph {
 
         Ok(())
     }
+
+    /// Graphs whose history no longer matches a tip anchored in this graph
+    /// (anchors are only read from audit graphs)
+    pub fn broken_anchors(
+        &self,
+        other_graphs: &HashMap<String, BlockchainGraph>,
+    ) -> BTreeSet<String> {
+        if self.graph_type != GraphType::Audit {
+            return BTreeSet::new();
+        }
+        self.chain
+            .iter()
+            .filter_map(|block| parse_anchors(&block.data))
+            .flatten()
+            .filter(|anchor| !anchor.matches(other_graphs))
+            .map(|anchor| anchor.graph_id)
+            .collect()
+    }
+}
+
+/// Built-in operations that asset and identity graphs give block data
+enum Registry {
+    Assets(AssetPolicy),
+    Identities,
+}
+
+impl Registry {
+    /// Prefix of the state keys only the registry's operations may write
+    fn key_prefix(&self) -> &'static str {
+        match self {
+            Registry::Assets(_) => ASSET_KEY_PREFIX,
+            Registry::Identities => IDENTITY_KEY_PREFIX,
+        }
+    }
+
+    /// Record changes of the operation in the block's data, if any
+    fn apply(&self, state: &WorldState, block: &Block) -> Result<StateChanges, String> {
+        match self {
+            Registry::Assets(policy) => match parse_asset_operation(&block.data)? {
+                Some(operation) => apply_asset_operation(&operation, state, block, policy),
+                None => Ok(StateChanges::new()),
+            },
+            Registry::Identities => match parse_identity_operation(&block.data)? {
+                Some(operation) => apply_identity_operation(&operation, state, block),
+                None => Ok(StateChanges::new()),
+            },
+        }
+    }
+}
+
+/// Verifies the endorsements recorded in `block` against `policy` and
+/// returns their nonce
+fn check_endorsements<'a>(policy: &EndorsementPolicy, block: &'a Block) -> Result<&'a str, String> {
+    let endorsements = block
+        .endorsements
+        .as_ref()
+        .ok_or_else(|| format!("Block must be endorsed by {}", policy.describe()))?;
+    endorsements.verify(
+        policy,
+        &block.graph_id,
+        &block.data,
+        &block.cross_references,
+    )?;
+    Ok(&endorsements.nonce)
+}
+
+/// State changes of a block: its read/write set is checked against
+/// `state`, then `deployment` runs on it and its writes take precedence.
+/// Deployment blocks are checked by the runtime instead of being executed.
+/// On asset and identity graphs (`registry` set) the block's operation is
+/// applied last, and only it may write the registry's records.
+fn run_block(
+    deployment: Option<&ContractDeployment>,
+    registry: Option<&Registry>,
+    state: Arc<WorldState>,
+    block: &Block,
+    runtime: &dyn ContractRuntime,
+) -> Result<StateChanges, String> {
+    let mut changes = match parse_rwset(&block.data) {
+        Ok(Some(rwset)) => rwset
+            .check(&state)
+            .map_err(|e| format!("Block #{}: {}", block.height, e))?,
+        Ok(None) => StateChanges::new(),
+        Err(e) => return Err(format!("Block #{}: {}", block.height, e)),
+    };
+    if let Some(deployed) = parse_contract_deploy(&block.data) {
+        runtime.validate(&deployed)?;
+    } else if let Some(deployment) = deployment {
+        match runtime.execute(deployment, state.clone(), block) {
+            ContractOutcome::Accepted {
+                changes: writes, ..
+            } => changes.extend(writes),
+            ContractOutcome::Rejected(reason) => {
+                return Err(format!(
+                    "Contract rejected block #{}: {}",
+                    block.height, reason
+                ))
+            }
+        }
+    }
+    let registry = match registry {
+        Some(registry) => registry,
+        None => return Ok(changes),
+    };
+    if let Some(key) = changes
+        .keys()
+        .find(|key| key.starts_with(registry.key_prefix()))
+    {
+        return Err(format!(
+            "Block #{}: state key '{}' is reserved for registry operations",
+            block.height, key
+        ));
+    }
+    let records = registry
+        .apply(&state, block)
+        .map_err(|e| format!("Block #{}: {}", block.height, e))?;
+    changes.extend(records);
+    Ok(changes)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::domain::BlockEndorsements;
 
     #[test]
     fn test_graph_creation() {
@@ -231,4 +1044,571 @@ mod tests {
         let result = graph.add_block(block);
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_append_block_rejects_unmined_and_tampered() {
+        let mut source = BlockchainGraph::new(
+            "test_graph".to_string(),
+            GraphType::Transaction,
+            "Test".to_string(),
+            2,
+        );
+        let latest = source.get_latest_block().unwrap().clone();
+        let block = Block::new(
+            latest.hash,
+            "data".to_string(),
+            "test_graph".to_string(),
+            1,
+            vec![],
+        );
+        let mined = source.add_block(block.clone()).unwrap();
+
+        let mut replica = source.clone();
+        replica.load_blocks(vec![]);
+        replica.append_block(source.chain[0].clone()).unwrap();
+
+        let mut tampered = mined.clone();
+        tampered.data = "tampered".to_string();
+        assert!(replica.append_block(tampered).is_err());
+
+        // Unmined blocks hash correctly but miss the difficulty target
+        if !block.has_valid_difficulty(2) {
+            assert!(replica.append_block(block).is_err());
+        }
+
+        replica.append_block(mined).unwrap();
+        assert_eq!(replica.chain.len(), 2);
+        assert!(replica.is_valid());
+    }
+
+    /// Governance policy with a single org, and its key
+    fn governor() -> (SigningKey, EndorsementPolicy) {
+        use crate::domain::consensus::{generate_signing_key, public_key_hex};
+        use crate::domain::Endorser;
+
+        let key = generate_signing_key();
+        let policy = EndorsementPolicy {
+            required: 1,
+            endorsers: vec![Endorser {
+                org: "governor".to_string(),
+                public_key: public_key_hex(&key),
+            }],
+        };
+        (key, policy)
+    }
+
+    /// Endorsements of the governor from [`governor`] for `data`
+    fn approved(key: &SigningKey, graph_id: &str, data: &str) -> BlockEndorsements {
+        use crate::domain::consensus::sign_message;
+        use crate::domain::Proposal;
+
+        let mut proposal = Proposal::new(graph_id.to_string(), data.to_string(), vec![], 0, 1_000);
+        let signature = sign_message(key, proposal.id.as_bytes());
+        proposal
+            .endorsements
+            .insert("governor".to_string(), signature);
+        proposal.block_endorsements()
+    }
+
+    fn mined_child(parent: &Block, data: &str) -> Block {
+        let mut block = Block::new(
+            parent.hash.clone(),
+            data.to_string(),
+            parent.graph_id.clone(),
+            parent.height + 1,
+            vec![],
+        );
+        block.mine_block(1);
+        block
+    }
+
+    /// Mined child of `parent` carrying the governor's approval of `data`
+    fn approved_child(parent: &Block, data: &str, key: &SigningKey) -> Block {
+        let mut block =
+            mined_child(parent, data).with_endorsements(approved(key, &parent.graph_id, data));
+        block.mine_block(1);
+        block
+    }
+
+    #[test]
+    fn test_heavier_branch_reorganizes_main_chain() {
+        let mut graph = BlockchainGraph::new(
+            "forks".to_string(),
+            GraphType::Transaction,
+            "Test".to_string(),
+            1,
+        );
+        let genesis = graph.chain[0].clone();
+        let a1 = mined_child(&genesis, "a1");
+        let b1 = mined_child(&genesis, "b1");
+        let b2 = mined_child(&b1, "b2");
+
+        assert_eq!(
+            graph.accept_block(a1.clone()).unwrap(),
+            BlockOutcome::Extended
+        );
+        assert_eq!(
+            graph.accept_block(a1.clone()).unwrap(),
+            BlockOutcome::Duplicate
+        );
+
+        // Equal work: the chain seen first stays
+        assert_eq!(
+            graph.accept_block(b1.clone()).unwrap(),
+            BlockOutcome::SideBranch
+        );
+        assert_eq!(graph.get_latest_block().unwrap().hash, a1.hash);
+        let work_before = graph.cumulative_work();
+
+        match graph.accept_block(b2.clone()).unwrap() {
+            BlockOutcome::Reorganized(reorg) => {
+                assert_eq!(reorg.fork_height, 0);
+                assert_eq!(reorg.disconnected, vec![a1.clone()]);
+                assert_eq!(reorg.connected, vec![b1.clone(), b2.clone()]);
+
+                graph.revert_reorg(&reorg);
+                assert_eq!(graph.get_latest_block().unwrap().hash, a1.hash);
+                assert!(graph.side_blocks.contains_key(&b2.hash));
+            }
+            other => panic!("expected a reorg, got {:?}", other),
+        }
+
+        // Re-delivering the tip of the side branch is a duplicate; a child switches again
+        assert_eq!(
+            graph.accept_block(b2.clone()).unwrap(),
+            BlockOutcome::Duplicate
+        );
+        let b3 = mined_child(&b2, "b3");
+        assert!(matches!(
+            graph.accept_block(b3.clone()).unwrap(),
+            BlockOutcome::Reorganized(_)
+        ));
+        assert_eq!(graph.get_latest_block().unwrap().hash, b3.hash);
+        assert!(graph.cumulative_work() > work_before);
+        assert!(graph.side_blocks.contains_key(&a1.hash));
+        assert!(graph.is_valid());
+    }
+
+    #[test]
+    fn test_accept_block_rejects_orphans_and_invalid_side_blocks() {
+        let mut graph = BlockchainGraph::new(
+            "forks".to_string(),
+            GraphType::Transaction,
+            "Test".to_string(),
+            1,
+        );
+        let genesis = graph.chain[0].clone();
+        let a1 = mined_child(&genesis, "a1");
+        graph.accept_block(a1).unwrap();
+
+        let unknown = mined_child(&mined_child(&genesis, "x1"), "x2");
+        assert!(graph
+            .accept_block(unknown)
+            .unwrap_err()
+            .contains("Unknown parent"));
+
+        let mut forged = mined_child(&genesis, "b1");
+        forged.data = "forged".to_string();
+        assert!(graph.accept_block(forged).is_err());
+        assert!(graph.side_blocks.is_empty());
+    }
+
+    #[test]
+    fn test_blocks_are_checked_against_the_schema_at_their_height() {
+        use crate::domain::schema::schema_update_data;
+
+        let strict = DataSchema::parse(r#"{"type": "object", "required": ["amount"]}"#).unwrap();
+        let (key, governance) = governor();
+        let mut graph = BlockchainGraph::new(
+            "payments".to_string(),
+            GraphType::Transaction,
+            "Test".to_string(),
+            1,
+        )
+        .with_schema(DataSchema::parse(r#"{"type": "object"}"#).unwrap())
+        .with_governance(governance);
+
+        let err = graph
+            .add_block(mined_child(&graph.chain[0], "plain text"))
+            .unwrap_err();
+        assert!(err.contains("not valid JSON"), "{}", err);
+        let b1 = mined_child(&graph.chain[0], r#"{"memo": "no amount"}"#);
+        graph.accept_block(b1).unwrap();
+
+        // Schema updates need the governors' approval
+        let update = schema_update_data(Some(&strict));
+        let err = graph
+            .accept_block(mined_child(&graph.chain[1], &update))
+            .unwrap_err();
+        assert!(err.contains("must be endorsed by"), "{}", err);
+
+        // From height 2 on, blocks need an amount; block 1 stays valid
+        let b2 = approved_child(&graph.chain[1], &update, &key);
+        graph.accept_block(b2).unwrap();
+        let err = graph
+            .accept_block(mined_child(&graph.chain[2], r#"{"memo": "x"}"#))
+            .unwrap_err();
+        assert!(
+            err.contains("missing required property 'amount'"),
+            "{}",
+            err
+        );
+        // Only settings of its own type let a block skip the schema
+        let policy = mined_child(
+            &graph.chain[2],
+            r#"{"asset_policy": {"payment_graph": null}}"#,
+        );
+        let err = graph.accept_block(policy).unwrap_err();
+        assert!(
+            err.contains("missing required property 'amount'"),
+            "{}",
+            err
+        );
+        graph
+            .accept_block(mined_child(&graph.chain[2], r#"{"amount": 5}"#))
+            .unwrap();
+        assert_eq!(graph.current_schema(), Some(strict));
+        assert!(graph.verify_data().is_ok());
+
+        // A replica whose chain was not checked on the way in is flagged
+        let mut replica = graph.clone();
+        replica
+            .chain
+            .push(mined_child(&graph.chain[3], r#"{"memo": "y"}"#));
+        assert!(replica.verify_data().unwrap_err().starts_with("Block #4"));
+        assert!(!replica.is_valid());
+
+        // Removing the schema accepts anything again
+        graph
+            .accept_block(approved_child(
+                &graph.chain[3],
+                &schema_update_data(None),
+                &key,
+            ))
+            .unwrap();
+        graph
+            .accept_block(mined_child(&graph.chain[4], "plain text"))
+            .unwrap();
+        assert!(graph.is_valid());
+    }
+
+    #[test]
+    fn test_blocks_of_endorsed_graphs_carry_valid_endorsements() {
+        use crate::domain::consensus::{generate_signing_key, public_key_hex, sign_message};
+        use crate::domain::{Endorser, Proposal};
+
+        let keys: Vec<_> = (0..2).map(|_| generate_signing_key()).collect();
+        let policy = EndorsementPolicy {
+            required: 2,
+            endorsers: ["orgA", "orgB"]
+                .iter()
+                .zip(&keys)
+                .map(|(org, key)| Endorser {
+                    org: org.to_string(),
+                    public_key: public_key_hex(key),
+                })
+                .collect(),
+        };
+        let mut graph = BlockchainGraph::new(
+            "payments".to_string(),
+            GraphType::Transaction,
+            "Test".to_string(),
+            1,
+        )
+        .with_endorsement(policy.clone());
+        let endorsed = |data: &str, signers: usize| {
+            let mut proposal =
+                Proposal::new("payments".to_string(), data.to_string(), vec![], 0, 1_000);
+            for (org, key) in ["orgA", "orgB"].iter().zip(&keys).take(signers) {
+                let signature = sign_message(key, proposal.id.as_bytes());
+                proposal.endorse(&policy, org, &signature, 0).unwrap();
+            }
+            proposal.block_endorsements()
+        };
+        let endorsed_child = |parent: &Block, data: &str, signers: usize| {
+            let mut block = mined_child(parent, data).with_endorsements(endorsed(data, signers));
+            block.mine_block(1);
+            block
+        };
+
+        // A block written around the proposal flow is refused
+        let err = graph
+            .accept_block(mined_child(&graph.chain[0], "tx"))
+            .unwrap_err();
+        assert!(err.contains("must be endorsed by 2 of"), "{}", err);
+        let err = graph
+            .accept_block(endorsed_child(&graph.chain[0], "tx", 1))
+            .unwrap_err();
+        assert!(err.contains("1 endorsement(s)"), "{}", err);
+
+        // Signatures are bound to the content they were given for
+        let mut swapped =
+            mined_child(&graph.chain[0], "other tx").with_endorsements(endorsed("tx", 2));
+        swapped.mine_block(1);
+        assert!(graph.accept_block(swapped).is_err());
+
+        let b1 = endorsed_child(&graph.chain[0], "tx", 2);
+        graph.accept_block(b1.clone()).unwrap();
+        assert!(graph.verify_data().is_ok());
+
+        // The same endorsements cannot commit the content a second time
+        let mut replay =
+            mined_child(&graph.chain[1], "tx").with_endorsements(b1.endorsements.clone().unwrap());
+        replay.mine_block(1);
+        let err = graph.accept_block(replay.clone()).unwrap_err();
+        assert!(err.contains("reuses"), "{}", err);
+
+        // A replica whose chain was not checked on the way in is flagged
+        let mut replica = graph.clone();
+        replica.chain.push(replay);
+        assert!(replica.verify_data().unwrap_err().starts_with("Block #2"));
+        let mut replica = graph.clone();
+        replica.chain.push(mined_child(&graph.chain[1], "tx2"));
+        assert!(!replica.is_valid());
+    }
+
+    #[test]
+    fn test_verify_since_checks_only_blocks_above_the_checkpoint() {
+        use crate::domain::schema::schema_update_data;
+
+        let strict = DataSchema::parse(r#"{"type": "object", "required": ["amount"]}"#).unwrap();
+        let (key, governance) = governor();
+        let mut graph = BlockchainGraph::new(
+            "payments".to_string(),
+            GraphType::Transaction,
+            "Test".to_string(),
+            1,
+        )
+        .with_governance(governance);
+        graph
+            .accept_block(approved_child(
+                &graph.chain[0],
+                &schema_update_data(Some(&strict)),
+                &key,
+            ))
+            .unwrap();
+        graph
+            .accept_block(mined_child(&graph.chain[1], r#"{"amount": 1}"#))
+            .unwrap();
+        let checkpoint = graph.verify_since(None).unwrap();
+        assert_eq!(checkpoint, VerifiedCheckpoint::of(&graph.chain[2]));
+
+        // Tampering at or below the checkpoint is left to a full check
+        graph.chain[1].data = "tampered".to_string();
+        assert_eq!(
+            graph.verify_since(Some(&checkpoint)),
+            Ok(checkpoint.clone())
+        );
+        assert!(graph
+            .verify_since(None)
+            .unwrap_err()
+            .starts_with("Block #1"));
+        graph.chain[1].data = schema_update_data(Some(&strict));
+
+        // Blocks above it are checked against the schema in effect at the checkpoint
+        let mut replica = graph.clone();
+        replica
+            .chain
+            .push(mined_child(&graph.chain[2], r#"{"memo": "x"}"#));
+        assert!(replica
+            .verify_since(Some(&checkpoint))
+            .unwrap_err()
+            .starts_with("Block #3"));
+        graph
+            .accept_block(mined_child(&graph.chain[2], r#"{"amount": 2}"#))
+            .unwrap();
+        assert_eq!(graph.verify_since(Some(&checkpoint)).unwrap().height, 3);
+
+        // A checkpoint whose block left the chain means verifying from genesis
+        let stale = VerifiedCheckpoint {
+            height: 2,
+            hash: "ff".to_string(),
+        };
+        assert_eq!(stale.resume_height(&graph.chain), None);
+        graph.chain[1].data = "tampered".to_string();
+        assert!(graph.verify_since(Some(&stale)).is_err());
+    }
+
+    #[test]
+    fn test_proof_of_authority_follows_rotation_and_updates() {
+        use crate::domain::consensus::{
+            authority_update_data, generate_signing_key, public_key_hex, sign_message,
+        };
+        use crate::domain::{proposal_id, AuthorityRotation, AuthoritySet};
+
+        let keys: Vec<SigningKey> = (0..3).map(|_| generate_signing_key()).collect();
+        let set = AuthoritySet {
+            authorities: keys[..2].iter().map(public_key_hex).collect(),
+            rotation: AuthorityRotation::RoundRobin,
+        };
+        let mut graph = BlockchainGraph::new(
+            "poa".to_string(),
+            GraphType::Identity,
+            "Test".to_string(),
+            4,
+        )
+        .with_consensus(Consensus::ProofOfAuthority(set));
+        let next = |graph: &BlockchainGraph, data: &str| {
+            let tip = graph.get_latest_block().unwrap();
+            Block::new(
+                tip.hash.clone(),
+                data.to_string(),
+                "poa".to_string(),
+                tip.height + 1,
+                vec![],
+            )
+        };
+
+        // Mining is not accepted and block 1 belongs to the second authority
+        assert!(graph.add_block(next(&graph, "mined")).is_err());
+        let err = graph
+            .seal_block(next(&graph, "b1"), Some(&keys[0]))
+            .unwrap_err();
+        assert!(err.contains("must be signed by"), "{}", err);
+        graph
+            .seal_block(next(&graph, "b1"), Some(&keys[1]))
+            .unwrap();
+
+        // Block 2 replaces the set with the third key, which signs from then
+        // on; under round robin both current authorities must approve it
+        let update = AuthoritySet {
+            authorities: vec![public_key_hex(&keys[2])],
+            rotation: AuthorityRotation::AnyOf,
+        };
+        let data = authority_update_data(&update);
+        let approved_by = |signers: &[SigningKey]| BlockEndorsements {
+            nonce: "n1".to_string(),
+            signatures: signers
+                .iter()
+                .map(|key| {
+                    let id = proposal_id("poa", &data, &[], "n1");
+                    (public_key_hex(key), sign_message(key, id.as_bytes()))
+                })
+                .collect(),
+        };
+        let err = graph
+            .seal_block(next(&graph, &data), Some(&keys[0]))
+            .unwrap_err();
+        assert!(err.contains("must be endorsed by 2 of"), "{}", err);
+        let err = graph
+            .seal_block(
+                next(&graph, &data).with_endorsements(approved_by(&keys[..1])),
+                Some(&keys[0]),
+            )
+            .unwrap_err();
+        assert!(err.contains("1 endorsement(s)"), "{}", err);
+        let err = graph
+            .seal_block(
+                next(&graph, &data).with_endorsements(approved_by(&keys[1..])),
+                Some(&keys[0]),
+            )
+            .unwrap_err();
+        assert!(err.contains("not an endorser"), "{}", err);
+        graph
+            .seal_block(
+                next(&graph, &data).with_endorsements(approved_by(&keys[..2])),
+                Some(&keys[0]),
+            )
+            .unwrap();
+        assert_eq!(graph.current_authorities(), Some(update));
+        assert!(graph
+            .seal_block(next(&graph, "b3"), Some(&keys[0]))
+            .is_err());
+        graph
+            .seal_block(next(&graph, "b3"), Some(&keys[2]))
+            .unwrap();
+        assert!(graph.is_valid());
+
+        // A forged signature is detected on verification
+        let mut forged = graph.clone();
+        forged.chain[3].signer = public_key_hex(&keys[0]);
+        forged.chain[3].hash = forged.chain[3].calculate_hash();
+        assert!(!forged.is_valid());
+    }
+
+    /// Records each payload under `last` and refuses "bad"
+    struct RecordingRuntime;
+
+    impl ContractRuntime for RecordingRuntime {
+        fn validate(&self, _deployment: &ContractDeployment) -> Result<(), String> {
+            Ok(())
+        }
+
+        fn execute(
+            &self,
+            _deployment: &ContractDeployment,
+            _state: Arc<WorldState>,
+            block: &Block,
+        ) -> ContractOutcome {
+            if block.data == "bad" {
+                return ContractOutcome::Rejected("bad payload".to_string());
+            }
+            let changes = [("last".to_string(), Some(block.data.as_bytes().to_vec()))]
+                .into_iter()
+                .collect();
+            ContractOutcome::Accepted {
+                changes,
+                fuel_used: 1,
+            }
+        }
+    }
+
+    #[test]
+    fn test_contract_state_follows_deployment_and_replays() {
+        use crate::domain::contract::contract_deploy_data;
+
+        let (key, policy) = governor();
+        let mut graph =
+            BlockchainGraph::new("c".to_string(), GraphType::Custom, "Test".to_string(), 1);
+        let runtime = RecordingRuntime;
+        let child = |graph: &BlockchainGraph, data: &str| {
+            let tip = graph.get_latest_block().unwrap();
+            Block::new(
+                tip.hash.clone(),
+                data.to_string(),
+                "c".to_string(),
+                tip.height + 1,
+                vec![],
+            )
+        };
+        let commit = |graph: &mut BlockchainGraph, data: &str| -> Result<(), String> {
+            let mut block = child(graph, data);
+            if graph.is_control_data(data) {
+                block = block.with_endorsements(approved(&key, "c", data));
+            }
+            let changes = graph.next_state_changes(&block, &runtime)?;
+            let block = graph.add_block(block)?;
+            graph.apply_state_writes(&commit_changes(&changes, &block));
+            Ok(())
+        };
+
+        // Nothing runs before a contract is deployed
+        commit(&mut graph, "bad").unwrap();
+        assert!(graph.state.is_empty());
+
+        // Deployments need the approval of the graph's governors
+        let deploy = contract_deploy_data(&ContractDeployment::new(b"wasm", 10));
+        let err = commit(&mut graph, &deploy).unwrap_err();
+        assert!(err.contains("no governance"), "{}", err);
+        graph.governance = Some(policy);
+        let err = graph.add_block(child(&graph, &deploy)).unwrap_err();
+        assert!(
+            err.contains("must be endorsed by 1 of {governor}"),
+            "{}",
+            err
+        );
+
+        commit(&mut graph, &deploy).unwrap();
+        assert!(graph.current_contract().is_some());
+        commit(&mut graph, "first").unwrap();
+        commit(&mut graph, "second").unwrap();
+        let err = commit(&mut graph, "bad").unwrap_err();
+        assert!(err.contains("bad payload"), "{}", err);
+
+        assert_eq!(graph.state["last"].value, b"second");
+        assert_eq!(graph.state["last"].version, 4);
+        let (state, writes) = graph.replay_state(&runtime).unwrap();
+        assert_eq!(state, *graph.state);
+        assert_eq!(writes.len(), 4);
+    }
 }
@@ -0,0 +1,527 @@
+use ed25519_dalek::SigningKey;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::consensus::{parse_public_key, sign_message, verify_message};
+use super::world_state::{StateChanges, WorldState};
+use super::Block;
+
+/// Prefix of the world-state keys holding identity records; on identity
+/// graphs only identity operations may write them
+pub const IDENTITY_KEY_PREFIX: &str = "identity/";
+
+/// Key of the object naming the identity that signed block data of any
+/// graph, e.g. `{"amount": 5, "signer": {"graph_id": "ids", "id": "did:example:alice", "signature": "<hex>"}}`
+pub const SIGNER_KEY: &str = "signer";
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum IdentityStatus {
+    Active,
+    Revoked,
+}
+
+/// An operation carried in the data of an identity graph block, e.g.
+/// `{"op": "rotate", "id": "did:example:alice", "public_keys": ["<hex>"], "signature": "<hex>"}`.
+/// Registration is signed by one of the new keys (proof of possession);
+/// rotation and revocation by one of the current keys.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "op", rename_all = "lowercase")]
+pub enum IdentityOperation {
+    Register {
+        #[serde(rename = "id")]
+        subject_id: String,
+        public_keys: Vec<String>,
+        #[serde(default)]
+        attributes_hash: String,
+        #[serde(default)]
+        signature: String,
+    },
+    /// Replaces the keys and, when given, the attributes hash
+    Rotate {
+        #[serde(rename = "id")]
+        subject_id: String,
+        public_keys: Vec<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        attributes_hash: Option<String>,
+        #[serde(default)]
+        signature: String,
+    },
+    Revoke {
+        #[serde(rename = "id")]
+        subject_id: String,
+        #[serde(default)]
+        signature: String,
+    },
+}
+
+impl IdentityOperation {
+    pub fn subject_id(&self) -> &str {
+        match self {
+            Self::Register { subject_id, .. }
+            | Self::Rotate { subject_id, .. }
+            | Self::Revoke { subject_id, .. } => subject_id,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Register { .. } => "register",
+            Self::Rotate { .. } => "rotate",
+            Self::Revoke { .. } => "revoke",
+        }
+    }
+
+    fn signature_mut(&mut self) -> &mut String {
+        match self {
+            Self::Register { signature, .. }
+            | Self::Rotate { signature, .. }
+            | Self::Revoke { signature, .. } => signature,
+        }
+    }
+
+    /// Bytes the signing key signs: the graph, the identity version the
+    /// operation applies to (0 to register) and the unsigned operation
+    pub fn signing_message(&self, graph_id: &str, version: u64) -> Vec<u8> {
+        let mut unsigned = self.clone();
+        unsigned.signature_mut().clear();
+        let operation = serde_json::to_string(&unsigned).unwrap_or_default();
+        format!("{}:{}:{}", graph_id, version, operation).into_bytes()
+    }
+
+    /// Signs the operation for the identity at `version`
+    pub fn signed(mut self, key: &SigningKey, graph_id: &str, version: u64) -> Self {
+        let signature = sign_message(key, &self.signing_message(graph_id, version));
+        *self.signature_mut() = signature;
+        self
+    }
+
+    /// Block data carrying the operation
+    pub fn to_data(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+}
+
+/// Current state of an identity (its DID document), stored as JSON under
+/// [`identity_key`]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct IdentityRecord {
+    #[serde(rename = "id")]
+    pub subject_id: String,
+    pub public_keys: Vec<String>,
+    #[serde(default)]
+    pub attributes_hash: String,
+    pub status: IdentityStatus,
+    /// Operation that produced this version
+    pub operation: String,
+}
+
+impl IdentityRecord {
+    pub fn from_bytes(value: &[u8]) -> Option<Self> {
+        serde_json::from_slice(value).ok()
+    }
+
+    /// Whether one of the identity's keys made `signature`; revoked
+    /// identities sign nothing
+    pub fn verify(&self, message: &[u8], signature: &str) -> bool {
+        self.status == IdentityStatus::Active
+            && signed_by_any(&self.public_keys, message, signature)
+    }
+}
+
+/// Identity that signed block data, with the signature over [`signer_message`]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Signer {
+    /// Identity graph the identity is registered in
+    pub graph_id: String,
+    #[serde(rename = "id")]
+    pub subject_id: String,
+    pub signature: String,
+}
+
+/// World-state key of an identity
+pub fn identity_key(subject_id: &str) -> String {
+    format!("{}{}", IDENTITY_KEY_PREFIX, subject_id)
+}
+
+/// Reads the identity operation of block data. Data that is not a JSON
+/// object with an `op` field carries none; an unknown or malformed
+/// operation is an error.
+pub fn parse_identity_operation(data: &str) -> Result<Option<IdentityOperation>, String> {
+    let value = match serde_json::from_str::<Value>(data) {
+        Ok(value) if value.get("op").is_some() => value,
+        _ => return Ok(None),
+    };
+    serde_json::from_value(value)
+        .map(Some)
+        .map_err(|e| format!("Invalid identity operation: {}", e))
+}
+
+/// Checks `operation`, carried by `block`, against the identity records in
+/// `state` and returns the new record
+pub fn apply_identity_operation(
+    operation: &IdentityOperation,
+    state: &WorldState,
+    block: &Block,
+) -> Result<StateChanges, String> {
+    let subject_id = operation.subject_id();
+    check_did(subject_id)?;
+    let key = identity_key(subject_id);
+    let current = state.get(&key);
+    let signature = match operation {
+        IdentityOperation::Register { signature, .. }
+        | IdentityOperation::Rotate { signature, .. }
+        | IdentityOperation::Revoke { signature, .. } => signature,
+    };
+
+    let record = match (operation, current) {
+        (
+            IdentityOperation::Register {
+                public_keys,
+                attributes_hash,
+                ..
+            },
+            None,
+        ) => {
+            check_keys(public_keys)?;
+            if !signed_by_any(
+                public_keys,
+                &operation.signing_message(&block.graph_id, 0),
+                signature,
+            ) {
+                return Err(format!(
+                    "Registration of '{}' must be signed by one of its keys",
+                    subject_id
+                ));
+            }
+            IdentityRecord {
+                subject_id: subject_id.to_string(),
+                public_keys: public_keys.clone(),
+                attributes_hash: attributes_hash.clone(),
+                status: IdentityStatus::Active,
+                operation: operation.name().to_string(),
+            }
+        }
+        (IdentityOperation::Register { .. }, Some(_)) => {
+            return Err(format!("Identity '{}' is already registered", subject_id))
+        }
+        (_, None) => return Err(format!("Identity '{}' is not registered", subject_id)),
+        (_, Some(entry)) => {
+            let mut record = IdentityRecord::from_bytes(&entry.value)
+                .ok_or_else(|| format!("Identity '{}' has an unreadable record", subject_id))?;
+            if record.status == IdentityStatus::Revoked {
+                return Err(format!("Identity '{}' was revoked", subject_id));
+            }
+            if !record.verify(
+                &operation.signing_message(&block.graph_id, entry.version),
+                signature,
+            ) {
+                return Err(format!(
+                    "Only a current key of '{}' may {} it",
+                    subject_id,
+                    operation.name()
+                ));
+            }
+            match operation {
+                IdentityOperation::Rotate {
+                    public_keys,
+                    attributes_hash,
+                    ..
+                } => {
+                    check_keys(public_keys)?;
+                    record.public_keys = public_keys.clone();
+                    if let Some(attributes_hash) = attributes_hash {
+                        record.attributes_hash = attributes_hash.clone();
+                    }
+                }
+                IdentityOperation::Revoke { .. } => record.status = IdentityStatus::Revoked,
+                IdentityOperation::Register { .. } => {
+                    unreachable!("registered identities are rejected above")
+                }
+            }
+            record.operation = operation.name().to_string();
+            record
+        }
+    };
+
+    let value = serde_json::to_vec(&record).map_err(|e| e.to_string())?;
+    Ok(StateChanges::from([(key, Some(value))]))
+}
+
+/// Bytes an identity signs to put its name on block data of `graph_id`:
+/// the graph and the data without its `signer`, as compact JSON with
+/// sorted keys
+pub fn signer_message(graph_id: &str, unsigned: &Value) -> Vec<u8> {
+    format!("{}:{}", graph_id, unsigned).into_bytes()
+}
+
+/// Reads the signer of block data for `graph_id`, with the bytes it must
+/// have signed. Data without a `signer` object has none.
+pub fn parse_signer(graph_id: &str, data: &str) -> Result<Option<(Signer, Vec<u8>)>, String> {
+    if !data.contains(SIGNER_KEY) {
+        return Ok(None);
+    }
+    let mut value = match serde_json::from_str::<Value>(data) {
+        Ok(Value::Object(object)) => object,
+        _ => return Ok(None),
+    };
+    let signer = match value.remove(SIGNER_KEY) {
+        Some(signer) => serde_json::from_value::<Signer>(signer)
+            .map_err(|e| format!("Invalid {}: {}", SIGNER_KEY, e))?,
+        None => return Ok(None),
+    };
+    Ok(Some((
+        signer,
+        signer_message(graph_id, &Value::Object(value)),
+    )))
+}
+
+/// Whether identity `subject_id` of the identity graph `chain` could have
+/// signed `message` at `timestamp`: registered by then, not revoked before
+/// then, and `signature` made by one of the keys it held up to then. A
+/// later rotation or revocation does not undo earlier signatures, so the
+/// answer depends on the chain alone and not on when it is asked.
+pub fn signed_by_identity_at(
+    chain: &[Block],
+    subject_id: &str,
+    message: &[u8],
+    signature: &str,
+    timestamp: i64,
+) -> bool {
+    let mut keys: Vec<String> = Vec::new();
+    let mut registered = false;
+    for block in chain.iter().skip(1).filter(|b| b.timestamp <= timestamp) {
+        let operation = match parse_identity_operation(&block.data) {
+            Ok(Some(operation)) if operation.subject_id() == subject_id => operation,
+            _ => continue,
+        };
+        match operation {
+            IdentityOperation::Register { public_keys, .. } => {
+                registered = true;
+                keys.extend(public_keys);
+            }
+            IdentityOperation::Rotate { public_keys, .. } => keys.extend(public_keys),
+            IdentityOperation::Revoke { .. } if block.timestamp < timestamp => return false,
+            IdentityOperation::Revoke { .. } => {}
+        }
+    }
+    registered && signed_by_any(&keys, message, signature)
+}
+
+/// Adds a `signer` to JSON object `data` for `graph_id`, signed with `key`
+/// as identity `subject_id` of `identity_graph`
+pub fn sign_as_identity(
+    data: &str,
+    graph_id: &str,
+    identity_graph: &str,
+    subject_id: &str,
+    key: &SigningKey,
+) -> Result<String, String> {
+    let mut value = match serde_json::from_str::<Value>(data) {
+        Ok(Value::Object(object)) => object,
+        _ => return Err("Signed data must be a JSON object".to_string()),
+    };
+    value.remove(SIGNER_KEY);
+    let signature = sign_message(
+        key,
+        &signer_message(graph_id, &Value::Object(value.clone())),
+    );
+    let signer = Signer {
+        graph_id: identity_graph.to_string(),
+        subject_id: subject_id.to_string(),
+        signature,
+    };
+    value.insert(
+        SIGNER_KEY.to_string(),
+        serde_json::to_value(signer).map_err(|e| e.to_string())?,
+    );
+    Ok(Value::Object(value).to_string())
+}
+
+fn signed_by_any(public_keys: &[String], message: &[u8], signature: &str) -> bool {
+    public_keys
+        .iter()
+        .any(|key| verify_message(key, message, signature))
+}
+
+/// Subject ids are DIDs: `did:{method}:{method-specific id}`
+fn check_did(subject_id: &str) -> Result<(), String> {
+    let mut parts = subject_id.splitn(3, ':');
+    let method =
+        |method: &str| !method.is_empty() && method.bytes().all(|b| b.is_ascii_alphanumeric());
+    let valid = parts.next() == Some("did")
+        && parts.next().is_some_and(method)
+        && parts.next().is_some_and(|id| !id.is_empty());
+    if valid {
+        Ok(())
+    } else {
+        Err(format!(
+            "Identity id '{}' is not a DID (did:method:id)",
+            subject_id
+        ))
+    }
+}
+
+fn check_keys(public_keys: &[String]) -> Result<(), String> {
+    if public_keys.is_empty() {
+        return Err("An identity needs at least one public key".to_string());
+    }
+    for key in public_keys {
+        parse_public_key(key).map_err(|_| {
+            format!(
+                "Identity key '{}' is not a hex-encoded Ed25519 public key",
+                key
+            )
+        })?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::consensus::{generate_signing_key, public_key_hex};
+    use crate::domain::world_state::{apply_writes, commit_changes};
+
+    #[test]
+    fn test_register_rotate_revoke_and_sign_as_identity() {
+        let first = generate_signing_key();
+        let second = generate_signing_key();
+        let alice = "did:example:alice".to_string();
+        let mut state = WorldState::new();
+        let mut height = 0;
+        let mut apply = |state: &mut WorldState, operation: &IdentityOperation| {
+            height += 1;
+            let block = Block::new(
+                String::new(),
+                operation.to_data(),
+                "ids".to_string(),
+                height,
+                vec![],
+            );
+            let changes = apply_identity_operation(operation, state, &block)?;
+            apply_writes(state, &commit_changes(&changes, &block));
+            Ok::<_, String>(())
+        };
+        let record = |state: &WorldState| {
+            IdentityRecord::from_bytes(&state[&identity_key(&alice)].value).unwrap()
+        };
+
+        let register = IdentityOperation::Register {
+            subject_id: alice.clone(),
+            public_keys: vec![public_key_hex(&first)],
+            attributes_hash: "ab12".to_string(),
+            signature: String::new(),
+        };
+        assert!(apply(&mut state, &register.clone().signed(&second, "ids", 0)).is_err());
+        apply(&mut state, &register.clone().signed(&first, "ids", 0)).unwrap();
+        assert_eq!(
+            parse_identity_operation(&register.to_data()),
+            Ok(Some(register))
+        );
+
+        // Only a current key rotates; the old key stops signing afterwards
+        let rotate = IdentityOperation::Rotate {
+            subject_id: alice.clone(),
+            public_keys: vec![public_key_hex(&second)],
+            attributes_hash: None,
+            signature: String::new(),
+        };
+        let version = state[&identity_key(&alice)].version;
+        assert!(apply(&mut state, &rotate.clone().signed(&second, "ids", version)).is_err());
+        apply(&mut state, &rotate.signed(&first, "ids", version)).unwrap();
+        assert_eq!(record(&state).attributes_hash, "ab12");
+
+        let data =
+            sign_as_identity(r#"{"amount": 5}"#, "payments", "ids", &alice, &second).unwrap();
+        let (signer, message) = parse_signer("payments", &data).unwrap().unwrap();
+        assert_eq!(signer.subject_id, alice);
+        assert!(record(&state).verify(&message, &signer.signature));
+        let (_, elsewhere) = parse_signer("other", &data).unwrap().unwrap();
+        assert!(!record(&state).verify(&elsewhere, &signer.signature));
+
+        let version = state[&identity_key(&alice)].version;
+        let revoke = IdentityOperation::Revoke {
+            subject_id: alice.clone(),
+            signature: String::new(),
+        };
+        apply(&mut state, &revoke.signed(&second, "ids", version)).unwrap();
+        assert!(!record(&state).verify(&message, &signer.signature));
+
+        let invalid = IdentityOperation::Revoke {
+            subject_id: "alice".to_string(),
+            signature: String::new(),
+        };
+        assert!(apply(&mut state, &invalid)
+            .unwrap_err()
+            .contains("not a DID"));
+    }
+
+    #[test]
+    fn test_signatures_are_checked_against_the_keys_held_at_the_time() {
+        let first = generate_signing_key();
+        let second = generate_signing_key();
+        let alice = "did:example:alice";
+        let mut chain = vec![Block::new(
+            "0".to_string(),
+            String::new(),
+            "ids".to_string(),
+            0,
+            vec![],
+        )];
+        let mut push = |operation: IdentityOperation, timestamp: i64| {
+            let height = chain.len() as u64;
+            let mut block = Block::new(
+                String::new(),
+                operation.to_data(),
+                "ids".to_string(),
+                height,
+                vec![],
+            );
+            block.timestamp = timestamp;
+            chain.push(block);
+        };
+        push(
+            IdentityOperation::Register {
+                subject_id: alice.to_string(),
+                public_keys: vec![public_key_hex(&first)],
+                attributes_hash: String::new(),
+                signature: String::new(),
+            },
+            100,
+        );
+        push(
+            IdentityOperation::Rotate {
+                subject_id: alice.to_string(),
+                public_keys: vec![public_key_hex(&second)],
+                attributes_hash: None,
+                signature: String::new(),
+            },
+            200,
+        );
+        push(
+            IdentityOperation::Revoke {
+                subject_id: alice.to_string(),
+                signature: String::new(),
+            },
+            300,
+        );
+
+        let signed = |key| {
+            let data = sign_as_identity(r#"{"amount": 5}"#, "payments", "ids", alice, key).unwrap();
+            parse_signer("payments", &data).unwrap().unwrap()
+        };
+        let ((with_first, message), (with_second, _)) = (signed(&first), signed(&second));
+        let at = |signer: &Signer, timestamp| {
+            signed_by_identity_at(&chain, alice, &message, &signer.signature, timestamp)
+        };
+
+        // The old key stays valid for blocks from before the rotation
+        assert!(!at(&with_first, 50));
+        assert!(at(&with_first, 150));
+        assert!(!at(&with_second, 150));
+        assert!(at(&with_second, 250));
+        assert!(at(&with_second, 300));
+        assert!(!at(&with_second, 301));
+    }
+}
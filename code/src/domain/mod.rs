@@ -1,8 +1,38 @@
+pub mod anchor;
+pub mod asset;
 pub mod block;
+pub mod checkpoint;
+pub mod consensus;
+pub mod contract;
+pub mod endorsement;
+pub mod fork;
 pub mod graph;
+pub mod identity;
+pub mod payload_index;
+pub mod receipt;
+pub mod schema;
+pub mod search;
 pub mod traits;
 pub mod transaction;
+pub mod world_state;
 
+pub use anchor::GraphAnchor;
+pub use asset::{AssetOperation, AssetPolicy, AssetRecord};
 pub use block::Block;
+pub use checkpoint::VerifiedCheckpoint;
+pub use consensus::{AuthorityRotation, AuthoritySet, Consensus};
+pub use contract::{ContractDeployment, ContractOutcome};
+pub use endorsement::{
+    proposal_id, BlockEndorsements, EndorsementPolicy, Endorser, Proposal, ProposalStatus,
+};
+pub use fork::{BlockOutcome, Reorg};
 pub use graph::{BlockchainGraph, GraphType};
+pub use identity::{IdentityOperation, IdentityRecord, IdentityStatus};
+pub use payload_index::PayloadIndex;
+pub use receipt::{Receipt, ReceiptAnchor};
+pub use schema::DataSchema;
+pub use search::SearchQuery;
 pub use transaction::Transaction;
+pub use world_state::{
+    ReadWriteSet, StateChanges, StateEntry, StateSwitch, StateWrites, WorldState,
+};
@@ -0,0 +1,105 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Longest index name accepted
+const MAX_INDEX_NAME: usize = 64;
+
+/// Secondary index over a field of JSON block data, e.g. `/from` or
+/// `/asset/id`. Indexes are local to a node: each node decides what it
+/// indexes and builds the entries from its own copy of the chain.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct PayloadIndex {
+    pub name: String,
+    /// JSON pointer (RFC 6901) into the block data
+    pub path: String,
+}
+
+impl PayloadIndex {
+    pub fn new(name: &str, path: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            path: path.to_string(),
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.name.is_empty() || self.name.len() > MAX_INDEX_NAME {
+            return Err(format!(
+                "Index names must have 1 to {} characters",
+                MAX_INDEX_NAME
+            ));
+        }
+        if !self
+            .name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+        {
+            return Err(format!(
+                "Invalid index name '{}': use letters, digits, '_' and '-'",
+                self.name
+            ));
+        }
+        if !self.path.starts_with('/') {
+            return Err(format!(
+                "Index path '{}' must be a JSON pointer such as /from",
+                self.path
+            ));
+        }
+        Ok(())
+    }
+
+    /// Values of block data at the index path. Strings are taken as is,
+    /// numbers and booleans as their JSON text, and arrays contribute each
+    /// of their scalar items. Non-JSON data has no values.
+    pub fn values(&self, data: &str) -> Vec<String> {
+        let parsed: Value = match serde_json::from_str(data) {
+            Ok(parsed) => parsed,
+            Err(_) => return Vec::new(),
+        };
+        let mut values = Vec::new();
+        match parsed.pointer(&self.path) {
+            Some(Value::Array(items)) => values.extend(items.iter().filter_map(index_key)),
+            Some(value) => values.extend(index_key(value)),
+            None => {}
+        }
+        values.sort();
+        values.dedup();
+        values
+    }
+}
+
+/// Indexed form of a scalar value
+fn index_key(value: &Value) -> Option<String> {
+    match value {
+        Value::String(text) => Some(text.clone()),
+        Value::Number(_) | Value::Bool(_) => Some(value.to_string()),
+        Value::Null | Value::Array(_) | Value::Object(_) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_values_at_path() {
+        let index = PayloadIndex::new("customer", "/customer/id");
+        assert_eq!(
+            index.values(r#"{"customer": {"id": "c-42"}}"#),
+            vec!["c-42"]
+        );
+        assert_eq!(index.values(r#"{"customer": {"id": 42}}"#), vec!["42"]);
+        assert!(index.values(r#"{"customer": {"id": null}}"#).is_empty());
+        assert!(index.values("customer c-42").is_empty());
+
+        let tags = PayloadIndex::new("tags", "/tags");
+        assert_eq!(
+            tags.values(r#"{"tags": ["b", "a", "b", {"x": 1}]}"#),
+            vec!["a", "b"]
+        );
+
+        assert!(PayloadIndex::new("by:from", "/from").validate().is_err());
+        assert!(PayloadIndex::new("from", "from").validate().is_err());
+        assert!(PayloadIndex::new("from", "/from").validate().is_ok());
+    }
+}
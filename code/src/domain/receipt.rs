@@ -0,0 +1,287 @@
+use ed25519_dalek::SigningKey;
+use serde::{Deserialize, Serialize};
+
+use super::anchor::{parse_anchors, GraphAnchor};
+use super::consensus::{public_key_hex, sign_message, verify_message};
+use super::{Block, BlockchainGraph};
+
+/// Anchor block that recorded an earlier tip of the receipt's graph, tying
+/// the receipted block to history already anchored in another chain
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ReceiptAnchor {
+    /// Anchor graph
+    pub graph_id: String,
+    pub height: u64,
+    pub hash: String,
+    /// Tip of the receipt's graph recorded by the anchor block
+    pub anchored_height: u64,
+    pub anchored_hash: String,
+}
+
+/// Notarization receipt: the node's signature over a committed block, which
+/// a third party can check offline against an exported chain or a trusted tip
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Receipt {
+    pub graph_id: String,
+    pub height: u64,
+    pub block_hash: String,
+    pub previous_hash: String,
+    pub timestamp: i64,
+    #[serde(default)]
+    pub anchor: Option<ReceiptAnchor>,
+    /// Hex public key of the node that issued the receipt
+    pub signer: String,
+    /// Hex Ed25519 signature of [`Receipt::message`] by `signer`
+    pub signature: String,
+}
+
+impl Receipt {
+    /// Signs a receipt for `block` with the node key
+    pub fn issue(key: &SigningKey, block: &Block, anchor: Option<ReceiptAnchor>) -> Self {
+        let mut receipt = Self {
+            graph_id: block.graph_id.clone(),
+            height: block.height,
+            block_hash: block.hash.clone(),
+            previous_hash: block.previous_hash.clone(),
+            timestamp: block.timestamp,
+            anchor,
+            signer: public_key_hex(key),
+            signature: String::new(),
+        };
+        receipt.signature = sign_message(key, receipt.message().as_bytes());
+        receipt
+    }
+
+    /// Message the signature covers: every field but the signature
+    pub fn message(&self) -> String {
+        let anchor = self
+            .anchor
+            .as_ref()
+            .map(|a| {
+                format!(
+                    "{}:{}:{}:{}:{}",
+                    a.graph_id, a.height, a.hash, a.anchored_height, a.anchored_hash
+                )
+            })
+            .unwrap_or_default();
+        format!(
+            "receipt:{}:{}:{}:{}:{}:{}:{}",
+            self.graph_id,
+            self.height,
+            self.block_hash,
+            self.previous_hash,
+            self.timestamp,
+            anchor,
+            self.signer
+        )
+    }
+
+    /// Checks the signature, and that it was made by `trusted_signer` when given
+    pub fn verify_signature(&self, trusted_signer: Option<&str>) -> Result<(), String> {
+        if trusted_signer.is_some_and(|key| key != self.signer) {
+            return Err(format!(
+                "Receipt is signed by {}, not by the trusted key",
+                self.signer
+            ));
+        }
+        if !verify_message(&self.signer, self.message().as_bytes(), &self.signature) {
+            return Err("Invalid receipt signature".to_string());
+        }
+        Ok(())
+    }
+
+    /// Checks that `path` starts with the receipted block and links it by
+    /// hash to its last block, which must be `trusted_tip` when given.
+    /// Only the hash chain is checked, not proof-of-work difficulty or
+    /// authority seals: the path is only as trustworthy as `trusted_tip`, or
+    /// as the archive it came from (`blockchain-admin verify-receipt` checks
+    /// the archive as on import)
+    pub fn verify_path(&self, path: &[Block], trusted_tip: Option<&str>) -> Result<(), String> {
+        let block = path.first().ok_or("Empty block path")?;
+        if block.graph_id != self.graph_id
+            || block.height != self.height
+            || block.hash != self.block_hash
+            || block.previous_hash != self.previous_hash
+            || block.timestamp != self.timestamp
+        {
+            return Err(format!(
+                "Block #{} of graph '{}' does not match the receipt",
+                block.height, block.graph_id
+            ));
+        }
+        for (index, block) in path.iter().enumerate() {
+            if !block.is_valid() {
+                return Err(format!("Invalid block hash at height {}", block.height));
+            }
+            if index > 0
+                && (block.previous_hash != path[index - 1].hash
+                    || block.height != path[index - 1].height + 1)
+            {
+                return Err(format!(
+                    "Block at height {} is not linked to the path",
+                    block.height
+                ));
+            }
+        }
+        let tip = path.last().expect("path is not empty");
+        match trusted_tip {
+            Some(hash) if hash != tip.hash => {
+                Err(format!("Path ends at {}, not at the trusted tip", tip.hash))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Checks that the anchor block is in `anchor_chain` and records the tip
+    /// the receipt names, which must be in `chain` below the receipted block
+    pub fn verify_anchor(&self, chain: &[Block], anchor_chain: &[Block]) -> Result<(), String> {
+        let anchor = match &self.anchor {
+            Some(anchor) => anchor,
+            None => return Ok(()),
+        };
+        let anchored = GraphAnchor {
+            graph_id: self.graph_id.clone(),
+            height: anchor.anchored_height,
+            hash: anchor.anchored_hash.clone(),
+        };
+        let recorded = anchor_chain
+            .get(anchor.height as usize)
+            .filter(|block| block.hash == anchor.hash)
+            .and_then(|block| parse_anchors(&block.data))
+            .is_some_and(|tips| tips.contains(&anchored));
+        if !recorded {
+            return Err(format!(
+                "Anchor #{} of '{}' does not record the receipt's graph",
+                anchor.height, anchor.graph_id
+            ));
+        }
+        let in_history = anchored.height < self.height
+            && chain
+                .get(anchored.height as usize)
+                .is_some_and(|block| block.hash == anchored.hash);
+        if !in_history {
+            return Err(format!(
+                "Anchored tip #{} is not in the history of the block",
+                anchored.height
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Verifies a receipt offline against exported graphs (see
+/// `ImportGraphsUseCase::read_archive`): the signature, the hash path from
+/// the block to `trusted_tip` (or the graph's tip) and, when the anchor
+/// graph is among `graphs`, the anchor
+pub fn verify_receipt(
+    receipt: &Receipt,
+    graphs: &[BlockchainGraph],
+    trusted_signer: Option<&str>,
+    trusted_tip: Option<&str>,
+) -> Result<(), String> {
+    receipt.verify_signature(trusted_signer)?;
+
+    let graph = graphs
+        .iter()
+        .find(|graph| graph.id == receipt.graph_id)
+        .ok_or_else(|| format!("Graph '{}' is not in the export", receipt.graph_id))?;
+    let start = receipt.height as usize;
+    let end = match trusted_tip {
+        Some(hash) => graph
+            .chain
+            .iter()
+            .skip(start)
+            .position(|block| block.hash == hash)
+            .map(|offset| start + offset)
+            .ok_or_else(|| format!("Trusted tip {} is not at or above the block", hash))?,
+        None => graph.chain.len().saturating_sub(1),
+    };
+    let path = graph.chain.get(start..=end).unwrap_or_default();
+    receipt.verify_path(path, trusted_tip)?;
+
+    if let Some(anchor) = &receipt.anchor {
+        if let Some(anchors) = graphs.iter().find(|graph| graph.id == anchor.graph_id) {
+            receipt.verify_anchor(&graph.chain, &anchors.chain)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::consensus::generate_signing_key;
+    use crate::domain::GraphType;
+
+    fn graph_with_blocks(count: u64) -> BlockchainGraph {
+        let mut graph =
+            BlockchainGraph::new("docs".to_string(), GraphType::Custom, String::new(), 1);
+        for i in 0..count {
+            let tip = graph.get_latest_block().unwrap().clone();
+            let block = Block::new(
+                tip.hash,
+                format!("doc {}", i),
+                "docs".to_string(),
+                tip.height + 1,
+                vec![],
+            );
+            graph.add_block(block).unwrap();
+        }
+        graph
+    }
+
+    #[test]
+    fn test_receipt_verifies_against_chain_and_tip() {
+        let key = generate_signing_key();
+        let mut graph = graph_with_blocks(1);
+        let receipt = Receipt::issue(&key, &graph.chain[1], None);
+        receipt
+            .verify_signature(Some(&public_key_hex(&key)))
+            .unwrap();
+        verify_receipt(
+            &receipt,
+            std::slice::from_ref(&graph),
+            None,
+            Some(&receipt.block_hash),
+        )
+        .unwrap();
+
+        // Later blocks extend the path to the tip
+        graph = graph_with_blocks(3);
+        let receipt = Receipt::issue(&key, &graph.chain[1], None);
+        let tip = graph.chain[3].hash.clone();
+        verify_receipt(&receipt, std::slice::from_ref(&graph), None, Some(&tip)).unwrap();
+        assert!(verify_receipt(&receipt, std::slice::from_ref(&graph), None, Some("ff")).is_err());
+        assert!(receipt.verify_path(&graph.chain[2..], None).is_err());
+
+        let other = public_key_hex(&generate_signing_key());
+        assert!(receipt.verify_signature(Some(&other)).is_err());
+        let mut forged = receipt.clone();
+        forged.height = 2;
+        assert_eq!(
+            forged.verify_signature(None),
+            Err("Invalid receipt signature".to_string())
+        );
+    }
+
+    #[test]
+    fn test_receipt_rejects_rewritten_block() {
+        let key = generate_signing_key();
+        let mut graph = graph_with_blocks(2);
+        let receipt = Receipt::issue(&key, &graph.chain[1], None);
+
+        let genesis = graph.chain[0].hash.clone();
+        graph.chain.truncate(1);
+        graph
+            .add_block(Block::new(
+                genesis,
+                "other".to_string(),
+                "docs".to_string(),
+                1,
+                vec![],
+            ))
+            .unwrap();
+        let err = verify_receipt(&receipt, std::slice::from_ref(&graph), None, None).unwrap_err();
+        assert!(err.contains("does not match the receipt"), "{}", err);
+    }
+}
@@ -0,0 +1,625 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use super::GraphType;
+
+/// Key of the JSON object that changes a graph's data schema in block data,
+/// e.g. `{"schema_update": {"schema": {"type": "object"}}}`; a `null` schema removes it
+pub const SCHEMA_UPDATE_KEY: &str = "schema_update";
+
+/// Keywords that only annotate a schema
+const ANNOTATIONS: &[&str] = &[
+    "$schema",
+    "$id",
+    "$comment",
+    "title",
+    "description",
+    "default",
+    "examples",
+    "format",
+];
+
+/// Keywords this validator enforces; schemas using any other are refused
+const KEYWORDS: &[&str] = &[
+    "type",
+    "enum",
+    "const",
+    "properties",
+    "required",
+    "additionalProperties",
+    "minProperties",
+    "maxProperties",
+    "items",
+    "minItems",
+    "maxItems",
+    "uniqueItems",
+    "minLength",
+    "maxLength",
+    "minimum",
+    "maximum",
+    "exclusiveMinimum",
+    "exclusiveMaximum",
+    "multipleOf",
+    "allOf",
+    "anyOf",
+    "oneOf",
+    "not",
+];
+
+const TYPES: &[&str] = &[
+    "null", "boolean", "object", "array", "number", "integer", "string",
+];
+
+/// JSON Schema that block data of a graph must match. Supports the
+/// structural subset of draft 2020-12 listed in `KEYWORDS` (no `$ref` or
+/// `pattern`). Kept as JSON text so it encodes like the other graph metadata.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct DataSchema {
+    source: String,
+    root: Value,
+}
+
+impl DataSchema {
+    /// Parses and checks a schema
+    pub fn parse(source: &str) -> Result<Self, String> {
+        let root: Value =
+            serde_json::from_str(source).map_err(|e| format!("Schema is not valid JSON: {}", e))?;
+        Self::from_value(root)
+    }
+
+    pub fn from_value(root: Value) -> Result<Self, String> {
+        check_schema(&root, "")?;
+        Ok(Self {
+            source: root.to_string(),
+            root,
+        })
+    }
+
+    /// Built-in schema for graphs holding transactions, identity records or assets
+    pub fn default_for(graph_type: GraphType) -> Option<Self> {
+        let root = match graph_type {
+            GraphType::Transaction => serde_json::json!({
+                "title": "Transaction",
+                "type": "object",
+                "required": ["from", "to", "amount"],
+                "properties": {
+                    "id": {"type": "string"},
+                    "from": {"type": "string", "minLength": 1},
+                    "to": {"type": "string", "minLength": 1},
+                    "amount": {"type": "number", "exclusiveMinimum": 0},
+                    "timestamp": {"type": "integer"},
+                    "signature": {"type": ["string", "null"]},
+                    "metadata": {"type": ["string", "null"]}
+                }
+            }),
+            GraphType::Identity => serde_json::json!({
+                "title": "Identity record",
+                "type": "object",
+                "required": ["id"],
+                "properties": {
+                    "id": {"type": "string", "minLength": 1},
+                    "name": {"type": "string"},
+                    "public_key": {"type": "string"},
+                    "attributes": {"type": "object"}
+                }
+            }),
+            GraphType::Asset => serde_json::json!({
+                "title": "Asset",
+                "type": "object",
+                "required": ["asset_id"],
+                "properties": {
+                    "asset_id": {"type": "string", "minLength": 1},
+                    "owner": {"type": "string"},
+                    "quantity": {"type": "number", "minimum": 0},
+                    "metadata": {"type": "object"}
+                }
+            }),
+            GraphType::Audit | GraphType::Custom => return None,
+        };
+        Self::from_value(root).ok()
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.source
+    }
+
+    /// Checks block data against the schema
+    pub fn validate_data(&self, data: &str) -> Result<(), String> {
+        let value: Value = serde_json::from_str(data)
+            .map_err(|e| format!("Block data is not valid JSON: {}", e))?;
+        validate(&self.root, &value, "")
+            .map_err(|e| format!("Block data does not match the schema: {}", e))
+    }
+}
+
+impl TryFrom<String> for DataSchema {
+    type Error = String;
+
+    fn try_from(source: String) -> Result<Self, String> {
+        Self::parse(&source)
+    }
+}
+
+impl From<DataSchema> for String {
+    fn from(schema: DataSchema) -> String {
+        schema.source
+    }
+}
+
+/// Block data replacing (or with `None`, removing) a graph's schema
+pub fn schema_update_data(schema: Option<&DataSchema>) -> String {
+    let schema = schema.map(|s| s.root.clone()).unwrap_or(Value::Null);
+    serde_json::json!({ SCHEMA_UPDATE_KEY: { "schema": schema } }).to_string()
+}
+
+/// Reads a schema update from block data: `Some(None)` removes the schema
+pub fn parse_schema_update(data: &str) -> Option<Option<DataSchema>> {
+    if !data.contains(SCHEMA_UPDATE_KEY) {
+        return None;
+    }
+    let value: Value = serde_json::from_str(data).ok()?;
+    match value.get(SCHEMA_UPDATE_KEY)?.get("schema")? {
+        Value::Null => Some(None),
+        schema => DataSchema::from_value(schema.clone()).ok().map(Some),
+    }
+}
+
+fn check_schema(schema: &Value, path: &str) -> Result<(), String> {
+    let object = match schema {
+        Value::Bool(_) => return Ok(()),
+        Value::Object(object) => object,
+        _ => {
+            return Err(format!(
+                "{}: a schema must be an object or a boolean",
+                pointer(path)
+            ))
+        }
+    };
+
+    for (keyword, value) in object {
+        let at = format!("{}/{}", path, keyword);
+        if ANNOTATIONS.contains(&keyword.as_str()) {
+            continue;
+        }
+        if !KEYWORDS.contains(&keyword.as_str()) {
+            return Err(format!(
+                "{}: unsupported keyword '{}'",
+                pointer(path),
+                keyword
+            ));
+        }
+        match keyword.as_str() {
+            "type" => {
+                let names: Vec<&Value> = match value {
+                    Value::Array(names) => names.iter().collect(),
+                    name => vec![name],
+                };
+                for name in names {
+                    if !name.as_str().is_some_and(|n| TYPES.contains(&n)) {
+                        return Err(format!("{}: unknown type {}", pointer(&at), name));
+                    }
+                }
+            }
+            "enum" if !value.is_array() => {
+                return Err(format!("{}: must be an array", pointer(&at)))
+            }
+            "properties" => {
+                let properties = value
+                    .as_object()
+                    .ok_or_else(|| format!("{}: must be an object", pointer(&at)))?;
+                for (name, property) in properties {
+                    check_schema(property, &format!("{}/{}", at, name))?;
+                }
+            }
+            "required"
+                if !value
+                    .as_array()
+                    .is_some_and(|names| names.iter().all(Value::is_string)) =>
+            {
+                return Err(format!("{}: must be an array of strings", pointer(&at)))
+            }
+            "additionalProperties" | "items" | "not" => check_schema(value, &at)?,
+            "allOf" | "anyOf" | "oneOf" => match value.as_array() {
+                Some(schemas) if !schemas.is_empty() => {
+                    for (i, schema) in schemas.iter().enumerate() {
+                        check_schema(schema, &format!("{}/{}", at, i))?;
+                    }
+                }
+                _ => return Err(format!("{}: must be a non-empty array", pointer(&at))),
+            },
+            "minProperties" | "maxProperties" | "minItems" | "maxItems" | "minLength"
+            | "maxLength"
+                if !value.is_u64() =>
+            {
+                return Err(format!("{}: must be a non-negative integer", pointer(&at)))
+            }
+            "minimum" | "maximum" | "exclusiveMinimum" | "exclusiveMaximum"
+                if !value.is_number() =>
+            {
+                return Err(format!("{}: must be a number", pointer(&at)))
+            }
+            "multipleOf" if !value.as_f64().is_some_and(|n| n > 0.0) => {
+                return Err(format!("{}: must be a positive number", pointer(&at)))
+            }
+            "uniqueItems" if !value.is_boolean() => {
+                return Err(format!("{}: must be a boolean", pointer(&at)))
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+fn validate(schema: &Value, value: &Value, path: &str) -> Result<(), String> {
+    let object = match schema {
+        Value::Bool(true) => return Ok(()),
+        Value::Bool(false) => return Err(format!("{}: no value is allowed", pointer(path))),
+        Value::Object(object) => object,
+        _ => return Ok(()),
+    };
+
+    if let Some(types) = object.get("type") {
+        let allowed = match types {
+            Value::Array(names) => names
+                .iter()
+                .filter_map(Value::as_str)
+                .any(|t| has_type(value, t)),
+            name => name.as_str().is_some_and(|t| has_type(value, t)),
+        };
+        if !allowed {
+            return Err(format!(
+                "{}: expected type {}, found {}",
+                pointer(path),
+                types,
+                type_name(value)
+            ));
+        }
+    }
+    if let Some(Value::Array(options)) = object.get("enum") {
+        if !options.iter().any(|option| json_equal(option, value)) {
+            return Err(format!(
+                "{}: {} is not one of {}",
+                pointer(path),
+                value,
+                Value::Array(options.clone())
+            ));
+        }
+    }
+    if let Some(expected) = object.get("const") {
+        if !json_equal(expected, value) {
+            return Err(format!("{}: expected {}", pointer(path), expected));
+        }
+    }
+
+    match value {
+        Value::Object(fields) => validate_object(object, fields, path)?,
+        Value::Array(items) => validate_array(object, items, path)?,
+        Value::String(text) => {
+            let length = text.chars().count() as u64;
+            if let Some(min) = object.get("minLength").and_then(Value::as_u64) {
+                if length < min {
+                    return Err(format!(
+                        "{}: shorter than {} characters",
+                        pointer(path),
+                        min
+                    ));
+                }
+            }
+            if let Some(max) = object.get("maxLength").and_then(Value::as_u64) {
+                if length > max {
+                    return Err(format!("{}: longer than {} characters", pointer(path), max));
+                }
+            }
+        }
+        Value::Number(number) => {
+            validate_number(object, number.as_f64().unwrap_or_default(), path)?
+        }
+        _ => {}
+    }
+
+    if let Some(Value::Array(schemas)) = object.get("allOf") {
+        for schema in schemas {
+            validate(schema, value, path)?;
+        }
+    }
+    if let Some(Value::Array(schemas)) = object.get("anyOf") {
+        if !schemas
+            .iter()
+            .any(|schema| validate(schema, value, path).is_ok())
+        {
+            return Err(format!(
+                "{}: does not match any schema in anyOf",
+                pointer(path)
+            ));
+        }
+    }
+    if let Some(Value::Array(schemas)) = object.get("oneOf") {
+        let matches = schemas
+            .iter()
+            .filter(|schema| validate(schema, value, path).is_ok())
+            .count();
+        if matches != 1 {
+            return Err(format!(
+                "{}: matches {} schemas in oneOf, expected exactly 1",
+                pointer(path),
+                matches
+            ));
+        }
+    }
+    if let Some(schema) = object.get("not") {
+        if validate(schema, value, path).is_ok() {
+            return Err(format!(
+                "{}: must not match the schema in not",
+                pointer(path)
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn validate_object(
+    schema: &Map<String, Value>,
+    fields: &Map<String, Value>,
+    path: &str,
+) -> Result<(), String> {
+    if let Some(Value::Array(required)) = schema.get("required") {
+        for name in required.iter().filter_map(Value::as_str) {
+            if !fields.contains_key(name) {
+                return Err(format!(
+                    "{}: missing required property '{}'",
+                    pointer(path),
+                    name
+                ));
+            }
+        }
+    }
+    if let Some(min) = schema.get("minProperties").and_then(Value::as_u64) {
+        if (fields.len() as u64) < min {
+            return Err(format!("{}: fewer than {} properties", pointer(path), min));
+        }
+    }
+    if let Some(max) = schema.get("maxProperties").and_then(Value::as_u64) {
+        if fields.len() as u64 > max {
+            return Err(format!("{}: more than {} properties", pointer(path), max));
+        }
+    }
+
+    let properties = schema.get("properties").and_then(Value::as_object);
+    for (name, field) in fields {
+        let at = format!("{}/{}", path, escape(name));
+        match properties.and_then(|p| p.get(name)) {
+            Some(property) => validate(property, field, &at)?,
+            None => {
+                if let Some(additional) = schema.get("additionalProperties") {
+                    if additional == &Value::Bool(false) {
+                        return Err(format!(
+                            "{}: property '{}' is not allowed",
+                            pointer(path),
+                            name
+                        ));
+                    }
+                    validate(additional, field, &at)?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn validate_array(schema: &Map<String, Value>, items: &[Value], path: &str) -> Result<(), String> {
+    if let Some(min) = schema.get("minItems").and_then(Value::as_u64) {
+        if (items.len() as u64) < min {
+            return Err(format!("{}: fewer than {} items", pointer(path), min));
+        }
+    }
+    if let Some(max) = schema.get("maxItems").and_then(Value::as_u64) {
+        if items.len() as u64 > max {
+            return Err(format!("{}: more than {} items", pointer(path), max));
+        }
+    }
+    if schema.get("uniqueItems") == Some(&Value::Bool(true)) {
+        for (i, item) in items.iter().enumerate() {
+            if items[..i].iter().any(|earlier| json_equal(earlier, item)) {
+                return Err(format!("{}: items are not unique", pointer(path)));
+            }
+        }
+    }
+    if let Some(item_schema) = schema.get("items") {
+        for (i, item) in items.iter().enumerate() {
+            validate(item_schema, item, &format!("{}/{}", path, i))?;
+        }
+    }
+    Ok(())
+}
+
+fn validate_number(schema: &Map<String, Value>, number: f64, path: &str) -> Result<(), String> {
+    let bound = |keyword: &str| schema.get(keyword).and_then(Value::as_f64);
+    if let Some(min) = bound("minimum") {
+        if number < min {
+            return Err(format!(
+                "{}: {} is less than {}",
+                pointer(path),
+                number,
+                min
+            ));
+        }
+    }
+    if let Some(max) = bound("maximum") {
+        if number > max {
+            return Err(format!(
+                "{}: {} is greater than {}",
+                pointer(path),
+                number,
+                max
+            ));
+        }
+    }
+    if let Some(min) = bound("exclusiveMinimum") {
+        if number <= min {
+            return Err(format!(
+                "{}: {} is not greater than {}",
+                pointer(path),
+                number,
+                min
+            ));
+        }
+    }
+    if let Some(max) = bound("exclusiveMaximum") {
+        if number >= max {
+            return Err(format!(
+                "{}: {} is not less than {}",
+                pointer(path),
+                number,
+                max
+            ));
+        }
+    }
+    if let Some(divisor) = bound("multipleOf") {
+        let quotient = number / divisor;
+        if (quotient - quotient.round()).abs() > 1e-9 {
+            return Err(format!(
+                "{}: {} is not a multiple of {}",
+                pointer(path),
+                number,
+                divisor
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn has_type(value: &Value, name: &str) -> bool {
+    match name {
+        "integer" => value.as_f64().is_some_and(|n| n.fract() == 0.0),
+        "number" => value.is_number(),
+        other => type_name(value) == other,
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Object(_) => "object",
+        Value::Array(_) => "array",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+    }
+}
+
+/// Equality where 1 and 1.0 are the same number
+fn json_equal(a: &Value, b: &Value) -> bool {
+    match (a, b) {
+        (Value::Number(x), Value::Number(y)) => x.as_f64() == y.as_f64(),
+        (Value::Array(x), Value::Array(y)) => {
+            x.len() == y.len() && x.iter().zip(y).all(|(x, y)| json_equal(x, y))
+        }
+        (Value::Object(x), Value::Object(y)) => {
+            x.len() == y.len()
+                && x.iter()
+                    .all(|(k, v)| y.get(k).is_some_and(|w| json_equal(v, w)))
+        }
+        _ => a == b,
+    }
+}
+
+fn escape(name: &str) -> String {
+    name.replace('~', "~0").replace('/', "~1")
+}
+
+fn pointer(path: &str) -> &str {
+    if path.is_empty() {
+        "/"
+    } else {
+        path
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_transaction_schema() {
+        let schema = DataSchema::default_for(GraphType::Transaction).unwrap();
+        assert!(schema
+            .validate_data(r#"{"from":"alice","to":"bob","amount":10}"#)
+            .is_ok());
+
+        let err = schema
+            .validate_data(r#"{"from":"alice","to":"bob"}"#)
+            .unwrap_err();
+        assert!(
+            err.contains("missing required property 'amount'"),
+            "{}",
+            err
+        );
+        let err = schema
+            .validate_data(r#"{"from":"alice","to":"bob","amount":"10"}"#)
+            .unwrap_err();
+        assert!(err.contains("/amount: expected type"), "{}", err);
+        let err = schema
+            .validate_data(r#"{"from":"alice","to":"bob","amount":0}"#)
+            .unwrap_err();
+        assert!(err.contains("not greater than 0"), "{}", err);
+        assert!(schema
+            .validate_data("tx 1")
+            .unwrap_err()
+            .contains("not valid JSON"));
+
+        assert!(DataSchema::default_for(GraphType::Custom).is_none());
+    }
+
+    #[test]
+    fn test_nested_and_combined_keywords() {
+        let schema = DataSchema::parse(
+            r#"{"type": "object", "additionalProperties": false,
+                "properties": {
+                    "tags": {"type": "array", "items": {"enum": ["a", "b"]}, "uniqueItems": true, "maxItems": 2},
+                    "code": {"anyOf": [{"type": "integer", "multipleOf": 5}, {"const": "none"}]}
+                }}"#,
+        )
+        .unwrap();
+        assert!(schema
+            .validate_data(r#"{"tags": ["a", "b"], "code": 10}"#)
+            .is_ok());
+        assert!(schema.validate_data(r#"{"code": "none"}"#).is_ok());
+        assert!(schema
+            .validate_data(r#"{"tags": ["c"]}"#)
+            .unwrap_err()
+            .contains("/tags/0"));
+        assert!(schema.validate_data(r#"{"tags": ["a", "a"]}"#).is_err());
+        assert!(schema.validate_data(r#"{"code": 7}"#).is_err());
+        assert!(schema
+            .validate_data(r#"{"other": 1}"#)
+            .unwrap_err()
+            .contains("not allowed"));
+
+        let err = DataSchema::parse(r#"{"properties": {"id": {"pattern": "^a"}}}"#).unwrap_err();
+        assert!(
+            err.contains("/properties/id: unsupported keyword 'pattern'"),
+            "{}",
+            err
+        );
+        assert!(DataSchema::parse(r#"{"type": "text"}"#).is_err());
+    }
+
+    #[test]
+    fn test_schema_update_round_trip() {
+        let schema = DataSchema::parse(r#"{"type": "object"}"#).unwrap();
+        assert_eq!(
+            parse_schema_update(&schema_update_data(Some(&schema))),
+            Some(Some(schema.clone()))
+        );
+        assert_eq!(parse_schema_update(&schema_update_data(None)), Some(None));
+        assert_eq!(parse_schema_update("schema_update"), None);
+
+        // Schemas travel as JSON text inside graph metadata
+        let encoded = bincode::serialize(&Some(schema.clone())).unwrap();
+        assert_eq!(
+            bincode::deserialize::<Option<DataSchema>>(&encoded).unwrap(),
+            Some(schema)
+        );
+    }
+}
@@ -0,0 +1,206 @@
+use serde_json::Value;
+use std::collections::BTreeSet;
+
+/// Longest term kept by the tokenizer; longer runs (hashes, blobs) are skipped
+const MAX_TERM_LEN: usize = 64;
+
+/// Full-text query over block data. Clauses are separated by whitespace and
+/// must all match:
+///
+/// - `refund` matches the term anywhere in the data
+/// - `"card declined"` matches the terms next to each other, in order
+/// - `customer.name:alice` or `memo:"late fee"` only look inside that JSON
+///   field (dotted path from the root; arrays are looked through)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchQuery {
+    pub clauses: Vec<SearchClause>,
+}
+
+/// One query clause: a term, or a phrase when it has several terms
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchClause {
+    pub field: Option<String>,
+    pub terms: Vec<String>,
+}
+
+impl SearchQuery {
+    pub fn parse(query: &str) -> Result<Self, String> {
+        let mut clauses = Vec::new();
+        let mut chars = query.chars().peekable();
+        loop {
+            while chars.next_if(|c| c.is_whitespace()).is_some() {}
+            if chars.peek().is_none() {
+                break;
+            }
+
+            // Field prefix, if the clause has one before its text
+            let mut word = String::new();
+            let mut field = None;
+            while let Some(c) = chars.next_if(|c| !c.is_whitespace() && *c != '"') {
+                if c == ':' && field.is_none() && !word.is_empty() {
+                    field = Some(std::mem::take(&mut word));
+                } else {
+                    word.push(c);
+                }
+            }
+
+            let text = if word.is_empty() && chars.next_if_eq(&'"').is_some() {
+                let mut phrase = String::new();
+                let mut closed = false;
+                for c in chars.by_ref() {
+                    if c == '"' {
+                        closed = true;
+                        break;
+                    }
+                    phrase.push(c);
+                }
+                if !closed {
+                    return Err("Unterminated phrase in search query".to_string());
+                }
+                phrase
+            } else {
+                if chars.next_if_eq(&'"').is_some() {
+                    return Err(format!("Unexpected '\"' after '{}' in search query", word));
+                }
+                word
+            };
+
+            let terms = tokenize(&text);
+            if !terms.is_empty() {
+                clauses.push(SearchClause { field, terms });
+            }
+        }
+
+        if clauses.is_empty() {
+            return Err("Search query has no terms".to_string());
+        }
+        Ok(Self { clauses })
+    }
+
+    /// Index tokens every matching block has (see [`index_tokens`])
+    pub fn tokens(&self) -> BTreeSet<String> {
+        self.clauses
+            .iter()
+            .flat_map(|clause| {
+                clause
+                    .terms
+                    .iter()
+                    .map(move |term| token(clause.field.as_deref(), term))
+            })
+            .collect()
+    }
+
+    /// Whether block data satisfies every clause; phrases are checked here,
+    /// as the index only knows which terms a block has
+    pub fn matches(&self, data: &str) -> bool {
+        let texts = texts(data);
+        self.clauses.iter().all(|clause| {
+            texts
+                .iter()
+                .filter(|(path, _)| {
+                    clause.field.is_none() || clause.field.as_deref() == path.as_deref()
+                })
+                .any(|(_, text)| {
+                    tokenize(text)
+                        .windows(clause.terms.len())
+                        .any(|window| window == clause.terms.as_slice())
+                })
+        })
+    }
+}
+
+/// Lowercased runs of letters and digits
+pub fn tokenize(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|term| !term.is_empty() && term.chars().count() <= MAX_TERM_LEN)
+        .map(|term| term.to_lowercase())
+        .collect()
+}
+
+/// Tokens a block is indexed under: each term of its data, plus
+/// `field:term` for terms inside a JSON field
+pub fn index_tokens(data: &str) -> BTreeSet<String> {
+    let mut tokens = BTreeSet::new();
+    for (path, text) in texts(data) {
+        for term in tokenize(&text) {
+            if let Some(path) = &path {
+                tokens.insert(token(Some(path), &term));
+            }
+            tokens.insert(term);
+        }
+    }
+    tokens
+}
+
+fn token(field: Option<&str>, term: &str) -> String {
+    match field {
+        Some(field) => format!("{}:{}", field, term),
+        None => term.to_string(),
+    }
+}
+
+/// Searchable texts of block data with their JSON field paths; non-JSON
+/// data is one text without a field
+fn texts(data: &str) -> Vec<(Option<String>, String)> {
+    let mut texts = Vec::new();
+    match serde_json::from_str::<Value>(data) {
+        Ok(value) => collect_texts(&value, None, &mut texts),
+        Err(_) => texts.push((None, data.to_string())),
+    }
+    texts
+}
+
+fn collect_texts(value: &Value, path: Option<String>, texts: &mut Vec<(Option<String>, String)>) {
+    match value {
+        Value::String(text) => texts.push((path, text.clone())),
+        Value::Number(_) | Value::Bool(_) => texts.push((path, value.to_string())),
+        Value::Array(items) => {
+            for item in items {
+                collect_texts(item, path.clone(), texts);
+            }
+        }
+        Value::Object(fields) => {
+            for (key, item) in fields {
+                let path = match &path {
+                    Some(path) => format!("{}.{}", path, key),
+                    None => key.clone(),
+                };
+                collect_texts(item, Some(path), texts);
+            }
+        }
+        Value::Null => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_match() {
+        let query = SearchQuery::parse(r#"Refund customer.name:alice memo:"late fee""#).unwrap();
+        assert_eq!(query.clauses.len(), 3);
+        assert_eq!(query.clauses[2].field.as_deref(), Some("memo"));
+        assert_eq!(query.clauses[2].terms, vec!["late", "fee"]);
+        assert!(query.tokens().contains("customer.name:alice"));
+
+        let data =
+            r#"{"type": "refund", "customer": {"name": "Alice"}, "memo": "Late fee waived"}"#;
+        assert!(query.matches(data));
+        assert!(index_tokens(data).is_superset(&query.tokens()));
+        assert!(!query
+            .matches(r#"{"type": "refund", "customer": {"name": "Alice"}, "memo": "fee late"}"#));
+        assert!(!query.matches(r#"{"type": "refund", "owner": "alice", "memo": "late fee"}"#));
+
+        // Plain text data, and arrays looked through
+        assert!(SearchQuery::parse("\"card declined\"")
+            .unwrap()
+            .matches("Card declined at POS"));
+        assert!(SearchQuery::parse("tags:vip")
+            .unwrap()
+            .matches(r#"{"tags": ["new", "VIP"]}"#));
+
+        assert!(SearchQuery::parse("  ").is_err());
+        assert!(SearchQuery::parse("\"open phrase").is_err());
+    }
+}
@@ -1,38 +1,170 @@
 use async_trait::async_trait;
 use std::error::Error;
 
-use super::{Block, BlockchainGraph};
+use std::sync::Arc;
+
+use super::{
+    Block, BlockchainGraph, ContractDeployment, ContractOutcome, PayloadIndex, Proposal, Reorg,
+    SearchQuery, StateEntry, StateSwitch, StateWrites, VerifiedCheckpoint, WorldState,
+};
 
 /// Repository trait for blockchain persistence (Repository Pattern)
 /// Following Interface Segregation Principle
 #[async_trait]
 pub trait BlockchainRepository: Send + Sync {
     async fn save_block(&self, graph_id: &str, block: &Block) -> Result<(), Box<dyn Error>>;
+    /// Saves a block together with its world-state write set in one atomic write
+    async fn save_block_with_state(
+        &self,
+        graph_id: &str,
+        block: &Block,
+        writes: &StateWrites,
+    ) -> Result<(), Box<dyn Error>>;
     async fn get_block(&self, graph_id: &str, hash: &str) -> Result<Option<Block>, Box<dyn Error>>;
     async fn get_latest_block(&self, graph_id: &str) -> Result<Option<Block>, Box<dyn Error>>;
-    async fn get_block_by_height(&self, graph_id: &str, height: u64,) -> Result<Option<Block>, Box<dyn Error>>;
-    async fn get_blocks_range(&self, graph_id: &str, start: u64, end: u64,) -> Result<Vec<Block>, Box<dyn Error>>;
+    async fn get_block_by_height(
+        &self,
+        graph_id: &str,
+        height: u64,
+    ) -> Result<Option<Block>, Box<dyn Error>>;
+    async fn get_blocks_range(
+        &self,
+        graph_id: &str,
+        start: u64,
+        end: u64,
+    ) -> Result<Vec<Block>, Box<dyn Error>>;
     async fn save_graph(&self, graph: &BlockchainGraph) -> Result<(), Box<dyn Error>>;
     async fn get_graph(&self, graph_id: &str) -> Result<Option<BlockchainGraph>, Box<dyn Error>>;
     async fn list_graphs(&self) -> Result<Vec<BlockchainGraph>, Box<dyn Error>>;
     async fn graph_exists(&self, graph_id: &str) -> Result<bool, Box<dyn Error>>;
+    async fn save_side_block(&self, graph_id: &str, block: &Block) -> Result<(), Box<dyn Error>>;
+    async fn get_side_blocks(&self, graph_id: &str) -> Result<Vec<Block>, Box<dyn Error>>;
+    async fn delete_side_blocks(
+        &self,
+        graph_id: &str,
+        hashes: &[String],
+    ) -> Result<(), Box<dyn Error>>;
+    /// Switches the stored main chain to `reorg.connected` and the world
+    /// state to that of the new chain in one atomic write
+    async fn reorganize(
+        &self,
+        graph_id: &str,
+        reorg: &Reorg,
+        state: &StateSwitch,
+    ) -> Result<(), Box<dyn Error>>;
+    async fn get_state(
+        &self,
+        graph_id: &str,
+        key: &str,
+    ) -> Result<Option<StateEntry>, Box<dyn Error>>;
+    /// Every version of a key on the main chain, oldest first (deletes included)
+    async fn get_state_history(
+        &self,
+        graph_id: &str,
+        key: &str,
+    ) -> Result<Vec<StateEntry>, Box<dyn Error>>;
+    /// Write set of the block at `height`
+    async fn get_block_writes(
+        &self,
+        graph_id: &str,
+        height: u64,
+    ) -> Result<StateWrites, Box<dyn Error>>;
+    async fn save_proposal(&self, proposal: &Proposal) -> Result<(), Box<dyn Error>>;
+    async fn get_proposal(
+        &self,
+        graph_id: &str,
+        id: &str,
+    ) -> Result<Option<Proposal>, Box<dyn Error>>;
+    /// Proposals of a graph, oldest first
+    async fn list_proposals(&self, graph_id: &str) -> Result<Vec<Proposal>, Box<dyn Error>>;
+    /// Builds the timestamp index of blocks stored before it existed; a
+    /// no-op once it has run for the graph
+    async fn index_block_times(&self, graph_id: &str) -> Result<(), Box<dyn Error>>;
+    /// Heights of main-chain blocks with `from <= timestamp <= to`, ordered
+    /// by timestamp, then height
+    async fn get_heights_by_time(
+        &self,
+        graph_id: &str,
+        from: i64,
+        to: i64,
+    ) -> Result<Vec<u64>, Box<dyn Error>>;
+    /// Main-chain block with the latest timestamp at or before `at`
+    async fn get_block_at_time(
+        &self,
+        graph_id: &str,
+        at: i64,
+    ) -> Result<Option<Block>, Box<dyn Error>>;
+    /// Defines a payload index and indexes the main chain stored so far;
+    /// returns how many blocks have a value at its path
+    async fn create_index(
+        &self,
+        graph_id: &str,
+        index: &PayloadIndex,
+    ) -> Result<u64, Box<dyn Error>>;
+    /// Removes an index and its entries; false if it did not exist
+    async fn drop_index(&self, graph_id: &str, name: &str) -> Result<bool, Box<dyn Error>>;
+    async fn list_indexes(&self, graph_id: &str) -> Result<Vec<PayloadIndex>, Box<dyn Error>>;
+    /// Turns on the full-text index of a graph and indexes the main chain
+    /// stored so far; returns how many blocks have terms
+    async fn enable_search(&self, graph_id: &str) -> Result<u64, Box<dyn Error>>;
+    /// Turns off the full-text index and removes its entries; false if it
+    /// was not enabled
+    async fn disable_search(&self, graph_id: &str) -> Result<bool, Box<dyn Error>>;
+    async fn search_enabled(&self, graph_id: &str) -> Result<bool, Box<dyn Error>>;
+    /// Drops and rebuilds the full-text index from the stored main chain
+    async fn rebuild_search_index(&self, graph_id: &str) -> Result<u64, Box<dyn Error>>;
+    /// Main-chain blocks matching a full-text query, in height order,
+    /// starting at `from_height`; at most `limit`
+    async fn search_blocks(
+        &self,
+        graph_id: &str,
+        query: &SearchQuery,
+        from_height: u64,
+        limit: usize,
+    ) -> Result<Vec<Block>, Box<dyn Error>>;
+    /// Ids of the assets `owner` holds, in id order, starting at `from`;
+    /// at most `limit`
+    async fn list_assets_by_owner(
+        &self,
+        graph_id: &str,
+        owner: &str,
+        from: &str,
+        limit: usize,
+    ) -> Result<Vec<String>, Box<dyn Error>>;
+    /// Main-chain blocks whose indexed value is `value`, in height order,
+    /// starting at `from_height`; at most `limit`
+    async fn query_index(
+        &self,
+        graph_id: &str,
+        name: &str,
+        value: &str,
+        from_height: u64,
+        limit: usize,
+    ) -> Result<Vec<Block>, Box<dyn Error>>;
+    /// Checkpoint of the last successful verification of a graph
+    async fn get_verified_checkpoint(
+        &self,
+        graph_id: &str,
+    ) -> Result<Option<VerifiedCheckpoint>, Box<dyn Error>>;
+    async fn save_verified_checkpoint(
+        &self,
+        graph_id: &str,
+        checkpoint: &VerifiedCheckpoint,
+    ) -> Result<(), Box<dyn Error>>;
+    async fn delete_verified_checkpoint(&self, graph_id: &str) -> Result<(), Box<dyn Error>>;
 }
 
 /// Validation strategy trait (Strategy Pattern)
 #[async_trait]
 pub trait ValidationStrategy: Send + Sync {
-    async fn validate(&self, graph: &BlockchainGraph) -> Result<bool, Box<dyn Error>>;
+    /// Validates the blocks of `graph` above height `from` (0 for every block after genesis)
+    async fn validate(&self, graph: &BlockchainGraph, from: u64) -> Result<bool, Box<dyn Error>>;
 }
 
 /// Hash calculator trait (Strategy Pattern)
 pub trait HashCalculator: Send + Sync {
-    fn calculate_hash(
-        &self,
-        previous_hash: &str,
-        timestamp: i64,
-        data: &str,
-        nonce: u64,
-    ) -> String;
+    fn calculate_hash(&self, previous_hash: &str, timestamp: i64, data: &str, nonce: u64)
+        -> String;
 }
 
 /// Proof of Work trait
@@ -40,3 +172,18 @@ pub trait HashCalculator: Send + Sync {
 pub trait ProofOfWork: Send + Sync {
     async fn mine(&self, block: &mut Block, difficulty: usize) -> Result<(), Box<dyn Error>>;
 }
+
+/// Contract runtime trait (Strategy Pattern)
+/// Execution must be deterministic: every node running the same deployment
+/// on the same block and state reaches the same outcome.
+pub trait ContractRuntime: Send + Sync {
+    /// Checks that the deployment holds a loadable contract module
+    fn validate(&self, deployment: &ContractDeployment) -> Result<(), String>;
+    /// Runs the contract on a block payload against the graph's world state
+    fn execute(
+        &self,
+        deployment: &ContractDeployment,
+        state: Arc<WorldState>,
+        block: &Block,
+    ) -> ContractOutcome;
+}
@@ -0,0 +1,249 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use super::Block;
+
+/// Key of the JSON object that carries state operations in block data,
+/// e.g. `{"rwset": {"reads": [{"key": "a", "version": 3}], "writes": [{"key": "a", "value": "10"}, {"key": "b", "delete": true}]}}`
+pub const RWSET_KEY: &str = "rwset";
+
+/// A world-state value and the block that wrote it
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct StateEntry {
+    pub value: Vec<u8>,
+    /// Height of the block that wrote this version
+    pub version: u64,
+    pub block_hash: String,
+    /// Set on history records of deletes
+    pub deleted: bool,
+}
+
+/// Current key/value world state of a graph
+pub type WorldState = BTreeMap<String, StateEntry>;
+
+/// Writes of a block before it is sealed: the new value, or `None` to delete the key
+pub type StateChanges = BTreeMap<String, Option<Vec<u8>>>;
+
+/// Entries written by a committed block (its write set), deletes included
+pub type StateWrites = BTreeMap<String, StateEntry>;
+
+/// Difference between two world states; `None` removes the key
+pub type StateDiff = BTreeMap<String, Option<StateEntry>>;
+
+/// World-state side of a reorganization
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StateSwitch {
+    /// Turns the current state into the state of the new main chain
+    pub diff: StateDiff,
+    /// Write sets of the connected blocks, in height order
+    pub connected: Vec<StateWrites>,
+}
+
+/// Version of a key read by a block; 0 means the key must be unset
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct StateRead {
+    pub key: String,
+    pub version: u64,
+}
+
+/// A put (`value`) or delete carried in block data
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct StateWrite {
+    pub key: String,
+    #[serde(default)]
+    pub value: String,
+    #[serde(default)]
+    pub delete: bool,
+}
+
+/// State operations of a block payload. The block is rejected if any read
+/// version is no longer current when it is added (optimistic concurrency).
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ReadWriteSet {
+    #[serde(default)]
+    pub reads: Vec<StateRead>,
+    #[serde(default)]
+    pub writes: Vec<StateWrite>,
+}
+
+impl ReadWriteSet {
+    /// Checks the reads against `state` and returns the writes
+    pub fn check(&self, state: &WorldState) -> Result<StateChanges, String> {
+        for read in &self.reads {
+            let current = state.get(&read.key).map(|e| e.version).unwrap_or_default();
+            if current != read.version {
+                return Err(format!(
+                    "Read of '{}' at version {} conflicts with current version {}",
+                    read.key, read.version, current
+                ));
+            }
+        }
+        let mut changes = StateChanges::new();
+        for write in &self.writes {
+            if write.key.is_empty() {
+                return Err("State keys must not be empty".to_string());
+            }
+            let value = (!write.delete).then(|| write.value.as_bytes().to_vec());
+            changes.insert(write.key.clone(), value);
+        }
+        Ok(changes)
+    }
+}
+
+/// Block data carrying state operations
+pub fn rwset_data(rwset: &ReadWriteSet) -> String {
+    serde_json::json!({ RWSET_KEY: rwset }).to_string()
+}
+
+/// Reads the state operations of a payload. Payloads without an `rwset`
+/// object have none; a malformed one is an error.
+pub fn parse_rwset(data: &str) -> Result<Option<ReadWriteSet>, String> {
+    if !data.contains(RWSET_KEY) {
+        return Ok(None);
+    }
+    let value = match serde_json::from_str::<serde_json::Value>(data) {
+        Ok(value) => value,
+        Err(_) => return Ok(None),
+    };
+    match value.get(RWSET_KEY) {
+        Some(rwset) => serde_json::from_value(rwset.clone())
+            .map(Some)
+            .map_err(|e| format!("Invalid {}: {}", RWSET_KEY, e)),
+        None => Ok(None),
+    }
+}
+
+/// Versioned entries for the changes of a sealed block
+pub fn commit_changes(changes: &StateChanges, block: &Block) -> StateWrites {
+    changes
+        .iter()
+        .map(|(key, value)| {
+            let entry = StateEntry {
+                value: value.clone().unwrap_or_default(),
+                version: block.height,
+                block_hash: block.hash.clone(),
+                deleted: value.is_none(),
+            };
+            (key.clone(), entry)
+        })
+        .collect()
+}
+
+/// Applies a committed block's write set to a state
+pub fn apply_writes(state: &mut WorldState, writes: &StateWrites) {
+    for (key, entry) in writes {
+        if entry.deleted {
+            state.remove(key);
+        } else {
+            state.insert(key.clone(), entry.clone());
+        }
+    }
+}
+
+/// Changes that turn `old` into `new`
+pub fn state_diff(old: &WorldState, new: &WorldState) -> StateDiff {
+    let mut diff: StateDiff = old
+        .keys()
+        .filter(|key| !new.contains_key(*key))
+        .map(|key| (key.clone(), None))
+        .collect();
+    for (key, entry) in new {
+        if old.get(key) != Some(entry) {
+            diff.insert(key.clone(), Some(entry.clone()));
+        }
+    }
+    diff
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn block(height: u64) -> Block {
+        let mut block = Block::new(
+            "0".to_string(),
+            String::new(),
+            "g".to_string(),
+            height,
+            vec![],
+        );
+        block.hash = format!("hash{}", height);
+        block
+    }
+
+    fn commit(state: &mut WorldState, data: &str, height: u64) -> Result<(), String> {
+        let changes = parse_rwset(data)?.unwrap_or_default().check(state)?;
+        apply_writes(state, &commit_changes(&changes, &block(height)));
+        Ok(())
+    }
+
+    #[test]
+    fn test_reads_must_match_current_versions() {
+        let mut state = WorldState::new();
+        let put = |key: &str, value: &str, read: u64| {
+            rwset_data(&ReadWriteSet {
+                reads: vec![StateRead {
+                    key: key.to_string(),
+                    version: read,
+                }],
+                writes: vec![StateWrite {
+                    key: key.to_string(),
+                    value: value.to_string(),
+                    delete: false,
+                }],
+            })
+        };
+
+        commit(&mut state, &put("a", "1", 0), 1).unwrap();
+        assert_eq!(state["a"].value, b"1");
+        assert_eq!(state["a"].version, 1);
+
+        // A second writer that read the key before block 1 conflicts
+        let err = commit(&mut state, &put("a", "2", 0), 2).unwrap_err();
+        assert!(err.contains("conflicts"), "{}", err);
+        commit(&mut state, &put("a", "2", 1), 2).unwrap();
+        assert_eq!(state["a"].block_hash, "hash2");
+
+        commit(
+            &mut state,
+            r#"{"rwset": {"writes": [{"key": "a", "delete": true}]}}"#,
+            3,
+        )
+        .unwrap();
+        assert!(state.is_empty());
+
+        assert!(parse_rwset(r#"{"rwset": {"writes": "a"}}"#).is_err());
+        assert_eq!(parse_rwset("rwset as plain text"), Ok(None));
+    }
+
+    #[test]
+    fn test_state_diff_reproduces_new_state() {
+        let mut old = WorldState::new();
+        commit(
+            &mut old,
+            r#"{"rwset": {"writes": [{"key": "a", "value": "1"}, {"key": "b", "value": "2"}]}}"#,
+            1,
+        )
+        .unwrap();
+        let mut new = old.clone();
+        commit(
+            &mut new,
+            r#"{"rwset": {"writes": [{"key": "a", "delete": true}, {"key": "c", "value": "3"}]}}"#,
+            2,
+        )
+        .unwrap();
+
+        let diff = state_diff(&old, &new);
+        assert_eq!(diff.len(), 2);
+        assert_eq!(diff["a"], None);
+
+        let mut state = old.clone();
+        for (key, entry) in diff {
+            match entry {
+                Some(entry) => state.insert(key, entry),
+                None => state.remove(&key),
+            };
+        }
+        assert_eq!(state, new);
+    }
+}
@@ -0,0 +1,3 @@
+pub mod wasm_runtime;
+
+pub use wasm_runtime::WasmContractRuntime;
@@ -0,0 +1,436 @@
+use std::sync::Arc;
+
+use wasmi::core::TrapCode;
+use wasmi::{
+    Caller, Config, Engine, Error, Extern, Linker, Memory, Module, Store, StoreLimits,
+    StoreLimitsBuilder,
+};
+
+use crate::domain::traits::ContractRuntime;
+use crate::domain::{Block, ContractDeployment, ContractOutcome, StateChanges, WorldState};
+
+/// Module name of the host functions a contract imports
+pub const HOST_MODULE: &str = "env";
+/// Exported entry point: `execute() -> i32`, 0 accepts the block
+pub const ENTRY_POINT: &str = "execute";
+
+/// Largest linear memory a contract may use
+const MAX_MEMORY_BYTES: usize = 16 * 1024 * 1024;
+/// Limits on state keys (UTF-8) and values
+const MAX_KEY_BYTES: usize = 256;
+const MAX_VALUE_BYTES: usize = 64 * 1024;
+/// Fuel charged per host call, plus one unit per byte moved
+const HOST_CALL_FUEL: u64 = 100;
+/// Most fuel a deployment may ask for unless configured otherwise
+pub const DEFAULT_MAX_FUEL: u64 = 10_000_000;
+
+/// Data a running contract can reach through host functions
+struct HostState {
+    state: Arc<WorldState>,
+    changes: StateChanges,
+    input: Vec<u8>,
+    height: u64,
+    rejection: Option<String>,
+    limits: StoreLimits,
+}
+
+impl HostState {
+    fn read(&self, key: &str) -> Option<&[u8]> {
+        match self.changes.get(key) {
+            Some(value) => value.as_deref(),
+            None => self.state.get(key).map(|entry| entry.value.as_slice()),
+        }
+    }
+}
+
+/// Runs contracts with the wasmi interpreter. Float instructions are
+/// disabled and every instruction consumes fuel, so execution is
+/// deterministic and bounded by the deployment's fuel limit, itself capped
+/// by the runtime. Modules may not declare a start function, so no contract
+/// code runs outside `execute`.
+///
+/// Host functions (module `env`):
+/// - `input_len() -> i32`, `input_read(ptr)`: the block payload
+/// - `block_height() -> i64`
+/// - `state_get(key_ptr, key_len, out_ptr, out_cap) -> i32`: value length, or -1 if unset
+/// - `state_set(key_ptr, key_len, value_ptr, value_len)`, `state_delete(key_ptr, key_len)`
+/// - `reject(msg_ptr, msg_len)`: reason reported when `execute` returns non-zero
+pub struct WasmContractRuntime {
+    engine: Engine,
+    max_fuel: u64,
+}
+
+impl Default for WasmContractRuntime {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl WasmContractRuntime {
+    pub fn new() -> Self {
+        Self::with_max_fuel(DEFAULT_MAX_FUEL)
+    }
+
+    /// Runtime refusing deployments with a fuel limit above `max_fuel`
+    pub fn with_max_fuel(max_fuel: u64) -> Self {
+        let mut config = Config::default();
+        config.consume_fuel(true).floats(false);
+        Self {
+            engine: Engine::new(&config),
+            max_fuel,
+        }
+    }
+
+    fn linker(&self) -> Result<Linker<HostState>, Error> {
+        let mut linker = Linker::new(&self.engine);
+        linker.func_wrap(HOST_MODULE, "input_len", |caller: Caller<'_, HostState>| {
+            caller.data().input.len() as i32
+        })?;
+        linker.func_wrap(
+            HOST_MODULE,
+            "input_read",
+            |mut caller: Caller<'_, HostState>, ptr: i32| {
+                let input = caller.data().input.clone();
+                charge(&mut caller, input.len())?;
+                write_bytes(&mut caller, ptr, &input)
+            },
+        )?;
+        linker.func_wrap(
+            HOST_MODULE,
+            "block_height",
+            |caller: Caller<'_, HostState>| caller.data().height as i64,
+        )?;
+        linker.func_wrap(
+            HOST_MODULE,
+            "state_get",
+            |mut caller: Caller<'_, HostState>,
+             key_ptr: i32,
+             key_len: i32,
+             out_ptr: i32,
+             out_cap: i32| {
+                let key = read_key(&mut caller, key_ptr, key_len)?;
+                let value = match caller.data().read(&key) {
+                    Some(value) => value.to_vec(),
+                    None => return Ok(-1),
+                };
+                charge(&mut caller, value.len())?;
+                let copied = value.len().min(out_cap.max(0) as usize);
+                write_bytes(&mut caller, out_ptr, &value[..copied])?;
+                Ok(value.len() as i32)
+            },
+        )?;
+        linker.func_wrap(
+            HOST_MODULE,
+            "state_set",
+            |mut caller: Caller<'_, HostState>,
+             key_ptr: i32,
+             key_len: i32,
+             value_ptr: i32,
+             value_len: i32| {
+                let key = read_key(&mut caller, key_ptr, key_len)?;
+                let value = read_bytes(&mut caller, value_ptr, value_len, MAX_VALUE_BYTES)?;
+                caller.data_mut().changes.insert(key, Some(value));
+                Ok(())
+            },
+        )?;
+        linker.func_wrap(
+            HOST_MODULE,
+            "state_delete",
+            |mut caller: Caller<'_, HostState>, key_ptr: i32, key_len: i32| {
+                let key = read_key(&mut caller, key_ptr, key_len)?;
+                caller.data_mut().changes.insert(key, None);
+                Ok(())
+            },
+        )?;
+        linker.func_wrap(
+            HOST_MODULE,
+            "reject",
+            |mut caller: Caller<'_, HostState>, msg_ptr: i32, msg_len: i32| {
+                let message = read_bytes(&mut caller, msg_ptr, msg_len, MAX_VALUE_BYTES)?;
+                caller.data_mut().rejection = Some(String::from_utf8_lossy(&message).into_owned());
+                Ok(())
+            },
+        )?;
+        Ok(linker)
+    }
+
+    /// Instantiates the module in a fresh store holding `host`
+    fn instantiate(
+        &self,
+        deployment: &ContractDeployment,
+        host: HostState,
+    ) -> Result<(Store<HostState>, wasmi::TypedFunc<(), i32>), Error> {
+        let code = deployment.code_bytes().map_err(Error::new)?;
+        let module = Module::new(&self.engine, &code)?;
+        let mut store = Store::new(&self.engine, host);
+        store.limiter(|host| &mut host.limits);
+        store.set_fuel(deployment.fuel_limit)?;
+
+        let instance = self
+            .linker()?
+            .instantiate(&mut store, &module)?
+            .ensure_no_start(&mut store)
+            .map_err(|_| Error::new("Contract must not declare a start function"))?;
+        if instance.get_memory(&store, "memory").is_none() {
+            return Err(Error::new("Contract must export its memory as \"memory\""));
+        }
+        let entry = instance.get_typed_func::<(), i32>(&store, ENTRY_POINT)?;
+        Ok((store, entry))
+    }
+
+    fn run(
+        &self,
+        deployment: &ContractDeployment,
+        host: HostState,
+    ) -> Result<ContractOutcome, Error> {
+        let (mut store, entry) = self.instantiate(deployment, host)?;
+        let code = entry.call(&mut store, ())?;
+        let fuel_used = deployment.fuel_limit - store.get_fuel()?;
+        let host = store.into_data();
+
+        if code != 0 {
+            let reason = host
+                .rejection
+                .unwrap_or_else(|| format!("contract returned {}", code));
+            return Ok(ContractOutcome::Rejected(reason));
+        }
+        Ok(ContractOutcome::Accepted {
+            changes: host.changes,
+            fuel_used,
+        })
+    }
+
+    fn host_state(state: Arc<WorldState>, input: Vec<u8>, height: u64) -> HostState {
+        HostState {
+            state,
+            changes: StateChanges::new(),
+            input,
+            height,
+            rejection: None,
+            limits: StoreLimitsBuilder::new()
+                .memory_size(MAX_MEMORY_BYTES)
+                .build(),
+        }
+    }
+}
+
+impl ContractRuntime for WasmContractRuntime {
+    fn validate(&self, deployment: &ContractDeployment) -> Result<(), String> {
+        if deployment.fuel_limit == 0 {
+            return Err("Contract fuel limit must be greater than 0".to_string());
+        }
+        if deployment.fuel_limit > self.max_fuel {
+            return Err(format!(
+                "Contract fuel limit {} exceeds the maximum of {}",
+                deployment.fuel_limit, self.max_fuel
+            ));
+        }
+        let host = Self::host_state(Arc::default(), Vec::new(), 0);
+        self.instantiate(deployment, host)
+            .map(|_| ())
+            .map_err(|e| format!("Invalid contract module: {}", e))
+    }
+
+    fn execute(
+        &self,
+        deployment: &ContractDeployment,
+        state: Arc<WorldState>,
+        block: &Block,
+    ) -> ContractOutcome {
+        let host = Self::host_state(state, block.data.as_bytes().to_vec(), block.height);
+        match self.run(deployment, host) {
+            Ok(outcome) => outcome,
+            Err(e) if e.as_trap_code() == Some(TrapCode::OutOfFuel) => {
+                ContractOutcome::Rejected(format!("out of fuel (limit {})", deployment.fuel_limit))
+            }
+            Err(e) => ContractOutcome::Rejected(format!("contract failed: {}", e)),
+        }
+    }
+}
+
+/// Charges a host call against the remaining fuel
+fn charge(caller: &mut Caller<'_, HostState>, bytes: usize) -> Result<(), Error> {
+    let cost = HOST_CALL_FUEL + bytes as u64;
+    let fuel = caller.get_fuel()?;
+    if fuel < cost {
+        return Err(Error::from(TrapCode::OutOfFuel));
+    }
+    caller.set_fuel(fuel - cost)?;
+    Ok(())
+}
+
+fn memory(caller: &Caller<'_, HostState>) -> Result<Memory, Error> {
+    caller
+        .get_export("memory")
+        .and_then(Extern::into_memory)
+        .ok_or_else(|| Error::new("Contract does not export its memory"))
+}
+
+fn read_bytes(
+    caller: &mut Caller<'_, HostState>,
+    ptr: i32,
+    len: i32,
+    max: usize,
+) -> Result<Vec<u8>, Error> {
+    if ptr < 0 || len < 0 || len as usize > max {
+        return Err(Error::new(format!(
+            "Invalid buffer ({} bytes at {})",
+            len, ptr
+        )));
+    }
+    charge(caller, len as usize)?;
+    let mut buffer = vec![0; len as usize];
+    memory(caller)?
+        .read(&*caller, ptr as usize, &mut buffer)
+        .map_err(|e| Error::new(e.to_string()))?;
+    Ok(buffer)
+}
+
+fn read_key(caller: &mut Caller<'_, HostState>, ptr: i32, len: i32) -> Result<String, Error> {
+    let bytes = read_bytes(caller, ptr, len, MAX_KEY_BYTES)?;
+    String::from_utf8(bytes).map_err(|_| Error::new("State keys must be UTF-8"))
+}
+
+fn write_bytes(caller: &mut Caller<'_, HostState>, ptr: i32, bytes: &[u8]) -> Result<(), Error> {
+    if ptr < 0 {
+        return Err(Error::new(format!("Invalid pointer {}", ptr)));
+    }
+    memory(caller)?
+        .write(&mut *caller, ptr as usize, bytes)
+        .map_err(|e| Error::new(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::StateEntry;
+
+    /// Counter contract: payload "inc" increments `count`, "reset" deletes it,
+    /// anything else is rejected
+    const COUNTER: &str = r#"
+        (module
+          (import "env" "input_len" (func $input_len (result i32)))
+          (import "env" "input_read" (func $input_read (param i32)))
+          (import "env" "state_get" (func $state_get (param i32 i32 i32 i32) (result i32)))
+          (import "env" "state_set" (func $state_set (param i32 i32 i32 i32)))
+          (import "env" "state_delete" (func $state_delete (param i32 i32)))
+          (import "env" "reject" (func $reject (param i32 i32)))
+          (memory (export "memory") 1)
+          (data (i32.const 0) "count")
+          (data (i32.const 16) "unknown operation")
+          (func (export "execute") (result i32)
+            (call $input_read (i32.const 64))
+            (if (i32.eq (call $input_len) (i32.const 5))
+              (then
+                (call $state_delete (i32.const 0) (i32.const 5))
+                (return (i32.const 0))))
+            (if (i32.ne (call $input_len) (i32.const 3))
+              (then
+                (call $reject (i32.const 16) (i32.const 17))
+                (return (i32.const 1))))
+            (if (i32.lt_s (call $state_get (i32.const 0) (i32.const 5) (i32.const 32) (i32.const 1)) (i32.const 0))
+              (then (i32.store8 (i32.const 32) (i32.const 0))))
+            (i32.store8 (i32.const 32) (i32.add (i32.load8_u (i32.const 32)) (i32.const 1)))
+            (call $state_set (i32.const 0) (i32.const 5) (i32.const 32) (i32.const 1))
+            (i32.const 0)))
+    "#;
+
+    fn block(data: &str) -> Block {
+        Block::new(
+            "0".to_string(),
+            data.to_string(),
+            "g".to_string(),
+            1,
+            vec![],
+        )
+    }
+
+    #[test]
+    fn test_contract_reads_and_writes_state() {
+        let runtime = WasmContractRuntime::new();
+        let deployment = ContractDeployment::new(&wat::parse_str(COUNTER).unwrap(), 100_000);
+        runtime.validate(&deployment).unwrap();
+
+        let entry = StateEntry {
+            value: vec![41],
+            version: 1,
+            block_hash: "abc".to_string(),
+            deleted: false,
+        };
+        let state: WorldState = [("count".to_string(), entry)].into_iter().collect();
+        match runtime.execute(&deployment, Arc::new(state), &block("inc")) {
+            ContractOutcome::Accepted { changes, fuel_used } => {
+                assert_eq!(changes["count"], Some(vec![42]));
+                assert!(fuel_used > 0);
+            }
+            other => panic!("unexpected outcome {:?}", other),
+        }
+        match runtime.execute(&deployment, Arc::default(), &block("reset")) {
+            ContractOutcome::Accepted { changes, .. } => assert_eq!(changes["count"], None),
+            other => panic!("unexpected outcome {:?}", other),
+        }
+        assert_eq!(
+            runtime.execute(&deployment, Arc::default(), &block("boom")),
+            ContractOutcome::Rejected("unknown operation".to_string())
+        );
+    }
+
+    #[test]
+    fn test_execution_is_bounded_by_fuel() {
+        let runtime = WasmContractRuntime::new();
+        let looping = wat::parse_str(
+            r#"(module (memory (export "memory") 1)
+                 (func (export "execute") (result i32) (loop $l (br $l)) (i32.const 0)))"#,
+        )
+        .unwrap();
+        let deployment = ContractDeployment::new(&looping, 10_000);
+        match runtime.execute(&deployment, Arc::default(), &block("x")) {
+            ContractOutcome::Rejected(reason) => {
+                assert!(reason.contains("out of fuel"), "{}", reason)
+            }
+            other => panic!("unexpected outcome {:?}", other),
+        }
+
+        // Modules without the entry point or using floats are refused at deployment
+        let no_entry = wat::parse_str(r#"(module (memory (export "memory") 1))"#).unwrap();
+        assert!(runtime
+            .validate(&ContractDeployment::new(&no_entry, 1_000))
+            .is_err());
+        let floats = wat::parse_str(
+            r#"(module (memory (export "memory") 1)
+                 (func (export "execute") (result i32) (drop (f32.const 1)) (i32.const 0)))"#,
+        )
+        .unwrap();
+        assert!(runtime
+            .validate(&ContractDeployment::new(&floats, 1_000))
+            .is_err());
+    }
+
+    #[test]
+    fn test_validation_caps_fuel_and_refuses_start_functions() {
+        let runtime = WasmContractRuntime::with_max_fuel(5_000);
+        let accepting = wat::parse_str(
+            r#"(module (memory (export "memory") 1)
+                 (func (export "execute") (result i32) (i32.const 0)))"#,
+        )
+        .unwrap();
+        runtime
+            .validate(&ContractDeployment::new(&accepting, 5_000))
+            .unwrap();
+        assert!(runtime
+            .validate(&ContractDeployment::new(&accepting, u64::MAX))
+            .is_err());
+
+        // The start function would run on validation, outside any block
+        let starting = wat::parse_str(
+            r#"(module (memory (export "memory") 1)
+                 (func $init (loop $l (br $l)))
+                 (start $init)
+                 (func (export "execute") (result i32) (i32.const 0)))"#,
+        )
+        .unwrap();
+        assert!(runtime
+            .validate(&ContractDeployment::new(&starting, 1_000))
+            .is_err());
+    }
+}
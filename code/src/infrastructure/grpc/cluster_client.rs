@@ -0,0 +1,215 @@
+use async_trait::async_trait;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::time::Duration;
+use tonic::metadata::MetadataMap;
+use tonic::transport::{Channel, Endpoint};
+use tonic::Request;
+
+use crate::application::services::raft::RaftTransport;
+use crate::infrastructure::grpc::blockchain::cluster_service_client::ClusterServiceClient;
+use crate::infrastructure::grpc::blockchain::*;
+
+/// Metadata naming the member that sent a cluster RPC
+pub const CLUSTER_NODE_HEADER: &str = "x-cluster-node";
+/// Metadata carrying the shared cluster secret
+pub const CLUSTER_SECRET_HEADER: &str = "x-cluster-secret";
+
+/// Timeout for a single node-to-node RPC
+const RPC_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Turns `host:port` into a URL tonic can connect to
+pub fn endpoint_url(address: &str) -> String {
+    if address.contains("://") {
+        address.to_string()
+    } else {
+        format!("http://{}", address)
+    }
+}
+
+/// What a member presents on every cluster RPC: its node id and the shared
+/// cluster secret
+#[derive(Clone)]
+pub struct ClusterCredentials {
+    node_id: u64,
+    secret: String,
+}
+
+impl ClusterCredentials {
+    pub fn new(node_id: u64, secret: impl Into<String>) -> Self {
+        Self {
+            node_id,
+            secret: secret.into(),
+        }
+    }
+
+    pub fn node_id(&self) -> u64 {
+        self.node_id
+    }
+
+    /// Wraps `message` in a request carrying these credentials
+    pub fn request<T>(&self, message: T) -> Request<T> {
+        let mut request = Request::new(message);
+        let metadata = request.metadata_mut();
+        if let Ok(value) = self.node_id.to_string().parse() {
+            metadata.insert(CLUSTER_NODE_HEADER, value);
+        }
+        if let Ok(value) = self.secret.parse() {
+            metadata.insert(CLUSTER_SECRET_HEADER, value);
+        }
+        request
+    }
+
+    /// The member that sent a request, if it carries the right secret and
+    /// names a node other than this one. Secrets are compared through
+    /// their digests so the comparison takes the same time for any guess.
+    pub fn authenticate(&self, metadata: &MetadataMap) -> Option<u64> {
+        let secret = metadata.get(CLUSTER_SECRET_HEADER)?.as_bytes();
+        let expected = Sha256::digest(self.secret.as_bytes());
+        let presented = Sha256::digest(secret);
+        let difference = expected
+            .iter()
+            .zip(presented.iter())
+            .fold(0u8, |acc, (a, b)| acc | (a ^ b));
+        if difference != 0 {
+            return None;
+        }
+        let node_id = metadata
+            .get(CLUSTER_NODE_HEADER)?
+            .to_str()
+            .ok()?
+            .parse::<u64>()
+            .ok()?;
+        (node_id != self.node_id).then_some(node_id)
+    }
+}
+
+fn lazy_channel(address: &str, timeout: Duration) -> Result<Channel, Box<dyn std::error::Error>> {
+    Ok(Endpoint::from_shared(endpoint_url(address))?
+        .connect_timeout(timeout)
+        .timeout(timeout)
+        .connect_lazy())
+}
+
+/// Raft transport over gRPC; connections are opened lazily and re-established
+/// by tonic when a peer restarts
+pub struct GrpcRaftTransport {
+    clients: HashMap<u64, ClusterServiceClient<Channel>>,
+    credentials: ClusterCredentials,
+}
+
+impl GrpcRaftTransport {
+    pub fn new(
+        peers: &HashMap<u64, String>,
+        credentials: ClusterCredentials,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut clients = HashMap::new();
+        for (id, address) in peers {
+            clients.insert(
+                *id,
+                ClusterServiceClient::new(lazy_channel(address, RPC_TIMEOUT)?),
+            );
+        }
+        Ok(Self {
+            clients,
+            credentials,
+        })
+    }
+
+    fn client(&self, peer: u64) -> Result<ClusterServiceClient<Channel>, String> {
+        self.clients
+            .get(&peer)
+            .cloned()
+            .ok_or_else(|| format!("Unknown cluster member {}", peer))
+    }
+}
+
+#[async_trait]
+impl RaftTransport for GrpcRaftTransport {
+    async fn request_vote(
+        &self,
+        peer: u64,
+        request: RaftVoteRequest,
+    ) -> Result<RaftVoteResponse, String> {
+        self.client(peer)?
+            .request_vote(self.credentials.request(request))
+            .await
+            .map(|r| r.into_inner())
+            .map_err(|e| e.to_string())
+    }
+
+    async fn append_entries(
+        &self,
+        peer: u64,
+        request: RaftAppendRequest,
+    ) -> Result<RaftAppendResponse, String> {
+        self.client(peer)?
+            .append_entries(self.credentials.request(request))
+            .await
+            .map(|r| r.into_inner())
+            .map_err(|e| e.to_string())
+    }
+}
+
+async fn leader_client(address: &str) -> Result<ClusterServiceClient<Channel>, String> {
+    ClusterServiceClient::connect(endpoint_url(address))
+        .await
+        .map_err(|e| format!("Cannot reach leader at {}: {}", address, e))
+}
+
+/// Forwards a client's AddBlock to the leader, which checks it again
+pub async fn forward_add_block(
+    address: &str,
+    credentials: &ClusterCredentials,
+    request: AddBlockRequest,
+) -> Result<AddBlockResponse, String> {
+    leader_client(address)
+        .await?
+        .forward_add_block(credentials.request(request))
+        .await
+        .map(|r| r.into_inner())
+        .map_err(|e| e.to_string())
+}
+
+/// Forwards a client's CreateGraph to the leader
+pub async fn forward_create_graph(
+    address: &str,
+    credentials: &ClusterCredentials,
+    request: CreateGraphRequest,
+) -> Result<CreateGraphResponse, String> {
+    leader_client(address)
+        .await?
+        .forward_create_graph(credentials.request(request))
+        .await
+        .map(|r| r.into_inner())
+        .map_err(|e| e.to_string())
+}
+
+/// Forwards an endorsement to the leader, which holds the ordered proposals
+pub async fn forward_endorse_proposal(
+    address: &str,
+    credentials: &ClusterCredentials,
+    request: EndorseProposalRequest,
+) -> Result<ProposalResponse, String> {
+    leader_client(address)
+        .await?
+        .forward_endorse_proposal(credentials.request(request))
+        .await
+        .map(|r| r.into_inner())
+        .map_err(|e| e.to_string())
+}
+
+/// Hands a block this member wrote itself (audit batches, endorsed
+/// proposals) to the leader to be sealed and ordered
+pub async fn forward_commit(
+    address: &str,
+    credentials: &ClusterCredentials,
+    request: ForwardCommitRequest,
+) -> Result<AddBlockResponse, String> {
+    leader_client(address)
+        .await?
+        .forward_commit(credentials.request(request))
+        .await
+        .map(|r| r.into_inner())
+        .map_err(|e| e.to_string())
+}
@@ -0,0 +1,201 @@
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast::error::TryRecvError;
+use tonic::transport::Channel;
+
+use crate::application::services::blockchain_service::BlockchainServiceImpl;
+use crate::application::services::gossip::graph_from_tip;
+use crate::application::services::replication::block_from_proto;
+use crate::domain::Block;
+use crate::infrastructure::grpc::blockchain::blockchain_service_client::BlockchainServiceClient;
+use crate::infrastructure::grpc::blockchain::{
+    AnnounceTipsRequest, GetBlockRangeRequest, GraphTip,
+};
+use crate::infrastructure::grpc::cluster_client::endpoint_url;
+
+type SyncError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Announces local tips to a static list of peers and pulls the blocks they
+/// have and this node lacks, from genesis if needed. Every received block is
+/// verified by `ChainApplier` before it is persisted.
+pub struct GossipNode {
+    service: Arc<BlockchainServiceImpl>,
+    peers: Vec<String>,
+    /// Address peers can pull from after our announcements (may be empty)
+    advertise_address: String,
+    interval: Duration,
+    batch_size: u64,
+}
+
+impl GossipNode {
+    pub fn new(
+        service: Arc<BlockchainServiceImpl>,
+        peers: Vec<String>,
+        advertise_address: String,
+        interval: Duration,
+        batch_size: u64,
+    ) -> Self {
+        Self {
+            service,
+            peers,
+            advertise_address,
+            interval,
+            batch_size: batch_size.max(1),
+        }
+    }
+
+    /// Runs until the task is aborted: exchanges tips with every peer on each
+    /// interval and right after local commits, and pulls from peers that
+    /// announced newer tips
+    pub async fn run(self) {
+        let mut events = self.service.subscribe_events();
+        let mut ticker = tokio::time::interval(self.interval);
+
+        loop {
+            let peers = tokio::select! {
+                _ = ticker.tick() => self.peers.clone(),
+                _ = events.recv() => {
+                    // One announcement covers every commit made since
+                    while !matches!(events.try_recv(), Err(TryRecvError::Empty | TryRecvError::Closed)) {}
+                    self.peers.clone()
+                }
+                _ = self.service.gossip().sync_requested() => self.service.gossip().take_sync_requests(),
+            };
+
+            for peer in peers {
+                self.sync_logged(&peer).await;
+            }
+        }
+    }
+
+    async fn sync_logged(&self, peer: &str) {
+        match self.sync_with(peer).await {
+            Ok(received) => {
+                if received > 0 {
+                    tracing::info!("📥 Pulled {} block(s) from {}", received, peer);
+                }
+                self.service.gossip().record_success(peer, received);
+            }
+            Err(e) => {
+                tracing::warn!("⚠️  Gossip with {} failed: {}", peer, e);
+                self.service.gossip().record_failure(peer, e.to_string());
+            }
+        }
+    }
+
+    /// Announces our tips to `peer` and pulls every graph it is ahead on.
+    /// Returns the number of blocks received.
+    pub async fn sync_with(&self, peer: &str) -> Result<u64, SyncError> {
+        let mut client = BlockchainServiceClient::connect(endpoint_url(peer)).await?;
+        let response = client
+            .announce_tips(AnnounceTipsRequest {
+                origin: self.advertise_address.clone(),
+                tips: self.service.gossip_tips().await,
+            })
+            .await?
+            .into_inner();
+        if !response.success {
+            return Err(format!("Peer refused announcement: {}", response.message).into());
+        }
+
+        let mut received = 0;
+        let mut failures = Vec::new();
+        for tip in self.service.tips_ahead(response.tips).await {
+            match self.pull_graph(&mut client, &tip).await {
+                Ok(count) => received += count,
+                Err(e) => failures.push(e),
+            }
+        }
+
+        if failures.is_empty() {
+            Ok(received)
+        } else {
+            Err(failures.join("; ").into())
+        }
+    }
+
+    /// Fetches the blocks after our tip of `tip.graph_id` in batches
+    /// (GetBlockRange) and applies them in order. When the peer is on
+    /// another branch, fetching starts at the fork point instead.
+    async fn pull_graph(
+        &self,
+        client: &mut BlockchainServiceClient<Channel>,
+        tip: &GraphTip,
+    ) -> Result<u64, String> {
+        let applier = self.service.applier();
+        let mut next = match self.service.local_height(&tip.graph_id).await {
+            Some(height) => self.fork_start(client, tip, height + 1).await?,
+            None => 0,
+        };
+        let mut received = 0;
+
+        while next <= tip.height {
+            let end = (next + self.batch_size - 1).min(tip.height);
+            for block in self.fetch(client, tip, next, end).await? {
+                if block.graph_id != tip.graph_id || block.height != next {
+                    return Err(format!(
+                        "Peer returned block #{} of graph '{}', expected #{} of '{}'",
+                        block.height, block.graph_id, next, tip.graph_id
+                    ));
+                }
+                if next == 0 {
+                    applier.apply_graph(graph_from_tip(tip)?, block).await?;
+                    tracing::info!("🌱 Created graph '{}' from a peer", tip.graph_id);
+                } else {
+                    applier.apply_block(block).await?;
+                }
+                next += 1;
+                received += 1;
+            }
+        }
+
+        Ok(received)
+    }
+
+    /// Steps back from `start` one batch at a time until the peer's block at
+    /// that height builds on a block this node knows
+    async fn fork_start(
+        &self,
+        client: &mut BlockchainServiceClient<Channel>,
+        tip: &GraphTip,
+        mut start: u64,
+    ) -> Result<u64, String> {
+        let applier = self.service.applier();
+        while start > 0 {
+            let first = self.fetch(client, tip, start, start).await?.remove(0);
+            if applier
+                .knows_block(&tip.graph_id, &first.previous_hash)
+                .await
+            {
+                break;
+            }
+            start = start.saturating_sub(self.batch_size);
+        }
+        Ok(start)
+    }
+
+    async fn fetch(
+        &self,
+        client: &mut BlockchainServiceClient<Channel>,
+        tip: &GraphTip,
+        start: u64,
+        end: u64,
+    ) -> Result<Vec<Block>, String> {
+        let response = client
+            .get_block_range(GetBlockRangeRequest {
+                graph_id: tip.graph_id.clone(),
+                start_height: start,
+                end_height: end,
+            })
+            .await
+            .map_err(|e| e.to_string())?
+            .into_inner();
+        if !response.success || response.blocks.is_empty() {
+            return Err(format!(
+                "Peer returned no blocks {}..={} of graph '{}'",
+                start, end, tip.graph_id
+            ));
+        }
+        Ok(response.blocks.into_iter().map(block_from_proto).collect())
+    }
+}
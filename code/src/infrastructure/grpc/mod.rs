@@ -6,4 +6,7 @@ pub mod blockchain {
     tonic::include_proto!("blockchain");
 }
 
+pub mod cluster_client;
+pub mod gossip_client;
+pub mod replication_client;
 pub mod server;
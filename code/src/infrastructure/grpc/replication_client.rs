@@ -0,0 +1,68 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::application::services::blockchain_service::BlockchainServiceImpl;
+use crate::infrastructure::grpc::blockchain::blockchain_service_client::BlockchainServiceClient;
+use crate::infrastructure::grpc::blockchain::SubscribeReplicationRequest;
+use crate::infrastructure::grpc::cluster_client::endpoint_url;
+
+type SyncError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Keeps a follower in sync with its leader: subscribes from the local
+/// tips, applies every event and reconnects when the stream drops
+pub struct ReplicationFollower {
+    service: Arc<BlockchainServiceImpl>,
+    leader_address: String,
+    retry_interval: Duration,
+}
+
+impl ReplicationFollower {
+    pub fn new(
+        service: Arc<BlockchainServiceImpl>,
+        leader_address: String,
+        retry_interval: Duration,
+    ) -> Self {
+        Self {
+            service,
+            leader_address,
+            retry_interval,
+        }
+    }
+
+    /// Runs until the task is aborted
+    pub async fn run(self) {
+        loop {
+            match self.sync_once().await {
+                Ok(()) => tracing::warn!(
+                    "⚠️  Leader {} closed the replication stream",
+                    self.leader_address
+                ),
+                Err(e) => {
+                    tracing::warn!("⚠️  Replication from {} failed: {}", self.leader_address, e)
+                }
+            }
+            self.service.set_replication_connected(false);
+            tokio::time::sleep(self.retry_interval).await;
+        }
+    }
+
+    /// Subscribes once and applies events until the stream ends or fails
+    pub async fn sync_once(&self) -> Result<(), SyncError> {
+        let mut client =
+            BlockchainServiceClient::connect(endpoint_url(&self.leader_address)).await?;
+        let cursors = self.service.replication_cursors().await;
+        let mut stream = client
+            .subscribe_replication(SubscribeReplicationRequest { cursors })
+            .await?
+            .into_inner();
+
+        self.service.set_replication_connected(true);
+        tracing::info!("🔁 Replicating from leader {}", self.leader_address);
+
+        while let Some(event) = stream.message().await? {
+            self.service.apply_replication_event(event).await?;
+        }
+
+        Ok(())
+    }
+}
@@ -1,22 +1,64 @@
+use std::collections::HashSet;
 use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::oneshot;
+use tonic::service::interceptor::InterceptedService;
+use tonic::service::Interceptor;
 use tonic::{transport::Server, Request, Response, Status};
 
-use crate::application::services::blockchain_service::BlockchainServiceImpl;
+use crate::application::services::blockchain_service::{BlockStream, BlockchainServiceImpl};
+use crate::application::services::raft::RaftNode;
+use crate::application::services::replication::ReplicationStream;
+use crate::infrastructure::grpc::cluster_client::ClusterCredentials;
 
 // Use the generated protobuf module re-exported at `crate::infrastructure::grpc::blockchain`
-use crate::infrastructure::grpc::blockchain::blockchain_service_server::{BlockchainService, BlockchainServiceServer};
+use crate::infrastructure::grpc::blockchain::blockchain_service_server::{
+    BlockchainService, BlockchainServiceServer,
+};
+use crate::infrastructure::grpc::blockchain::cluster_service_server::{
+    ClusterService, ClusterServiceServer,
+};
 use crate::infrastructure::grpc::blockchain::*;
 
+/// Metadata naming the caller in the audit trail. Clients choose it
+/// freely, so it is recorded as a claim next to the peer address.
+pub const CALLER_HEADER: &str = "x-caller-id";
+
+/// Who sent `request`, as recorded in the audit trail: the peer address,
+/// preceded by the caller id the client claims when it sends one, e.g.
+/// `billing-api (unverified) via 10.0.0.7:51234`
+fn caller<T>(request: &Request<T>) -> String {
+    let address = match request.remote_addr() {
+        Some(addr) => addr.to_string(),
+        None => "unknown".to_string(),
+    };
+    match request
+        .metadata()
+        .get(CALLER_HEADER)
+        .and_then(|v| v.to_str().ok())
+    {
+        Some(claimed) => format!("{} (unverified) via {}", claimed, address),
+        None => address,
+    }
+}
+
 /// gRPC server implementation
 /// Delegates to application service layer
 #[tonic::async_trait]
 impl BlockchainService for BlockchainServiceImpl {
+    type SubscribeReplicationStream = ReplicationStream;
+    type GetBlocksByTimeStream = BlockStream;
+
     async fn add_block(
         &self,
         request: Request<AddBlockRequest>,
     ) -> Result<Response<AddBlockResponse>, Status> {
+        let caller = caller(&request);
         let req = request.into_inner();
-        self.handle_add_block(req).await
+        let graph_id = req.graph_id.clone();
+        let response = self.handle_add_block(req).await?;
+        self.record_audit("block_added", &caller, &graph_id, response.get_ref());
+        Ok(response)
     }
 
     async fn get_block(
@@ -47,16 +89,32 @@ impl BlockchainService for BlockchainServiceImpl {
         &self,
         request: Request<VerifyGraphRequest>,
     ) -> Result<Response<VerifyGraphResponse>, Status> {
+        let caller = caller(&request);
         let req = request.into_inner();
-        self.handle_verify_graph(req).await
+        let graph_id = req.graph_id.clone();
+        let response = self.handle_verify_graph(req).await?;
+        if response.get_ref().success && !response.get_ref().is_valid {
+            self.record_audit(
+                "verification_failed",
+                &caller,
+                &graph_id,
+                response.get_ref(),
+            );
+        }
+        Ok(response)
     }
 
     async fn cross_validate_graphs(
         &self,
         request: Request<CrossValidateRequest>,
     ) -> Result<Response<CrossValidateResponse>, Status> {
-        let _req = request.into_inner();
-        self.handle_cross_validate().await
+        let caller = caller(&request);
+        let req = request.into_inner();
+        let response = self.handle_cross_validate(req).await?;
+        if !response.get_ref().all_valid {
+            self.record_audit("verification_failed", &caller, "", response.get_ref());
+        }
+        Ok(response)
     }
 
     async fn list_graphs(
@@ -71,8 +129,12 @@ impl BlockchainService for BlockchainServiceImpl {
         &self,
         request: Request<CreateGraphRequest>,
     ) -> Result<Response<CreateGraphResponse>, Status> {
+        let caller = caller(&request);
         let req = request.into_inner();
-        self.handle_create_graph(req).await
+        let graph_id = req.graph_id.clone();
+        let response = self.handle_create_graph(req).await?;
+        self.record_audit("graph_created", &caller, &graph_id, response.get_ref());
+        Ok(response)
     }
 
     async fn get_block_range(
@@ -82,21 +144,540 @@ impl BlockchainService for BlockchainServiceImpl {
         let req = request.into_inner();
         self.handle_get_block_range(req).await
     }
+
+    async fn create_backup(
+        &self,
+        request: Request<CreateBackupRequest>,
+    ) -> Result<Response<CreateBackupResponse>, Status> {
+        let caller = caller(&request);
+        let _req = request.into_inner();
+        let response = self.handle_create_backup().await?;
+        self.record_audit("backup_created", &caller, "", response.get_ref());
+        Ok(response)
+    }
+
+    async fn list_backups(
+        &self,
+        request: Request<ListBackupsRequest>,
+    ) -> Result<Response<ListBackupsResponse>, Status> {
+        let _req = request.into_inner();
+        self.handle_list_backups().await
+    }
+
+    async fn subscribe_replication(
+        &self,
+        request: Request<SubscribeReplicationRequest>,
+    ) -> Result<Response<Self::SubscribeReplicationStream>, Status> {
+        let req = request.into_inner();
+        self.handle_subscribe_replication(req).await
+    }
+
+    async fn get_replication_status(
+        &self,
+        request: Request<GetReplicationStatusRequest>,
+    ) -> Result<Response<GetReplicationStatusResponse>, Status> {
+        let _req = request.into_inner();
+        self.handle_get_replication_status().await
+    }
+
+    async fn get_cluster_status(
+        &self,
+        request: Request<GetClusterStatusRequest>,
+    ) -> Result<Response<GetClusterStatusResponse>, Status> {
+        let _req = request.into_inner();
+        self.handle_get_cluster_status().await
+    }
+
+    async fn announce_tips(
+        &self,
+        request: Request<AnnounceTipsRequest>,
+    ) -> Result<Response<AnnounceTipsResponse>, Status> {
+        let req = request.into_inner();
+        self.handle_announce_tips(req).await
+    }
+
+    async fn get_gossip_status(
+        &self,
+        request: Request<GetGossipStatusRequest>,
+    ) -> Result<Response<GetGossipStatusResponse>, Status> {
+        let _req = request.into_inner();
+        self.handle_get_gossip_status().await
+    }
+
+    async fn update_authorities(
+        &self,
+        request: Request<UpdateAuthoritiesRequest>,
+    ) -> Result<Response<AddBlockResponse>, Status> {
+        let caller = caller(&request);
+        let req = request.into_inner();
+        let graph_id = req.graph_id.clone();
+        let response = self.handle_update_authorities(req).await?;
+        self.record_audit(
+            "authorities_updated",
+            &caller,
+            &graph_id,
+            response.get_ref(),
+        );
+        Ok(response)
+    }
+
+    async fn endorse_proposal(
+        &self,
+        request: Request<EndorseProposalRequest>,
+    ) -> Result<Response<ProposalResponse>, Status> {
+        let caller = caller(&request);
+        let req = request.into_inner();
+        let graph_id = req.graph_id.clone();
+        let response = self.handle_endorse_proposal(req).await?;
+        self.record_audit("proposal_endorsed", &caller, &graph_id, response.get_ref());
+        Ok(response)
+    }
+
+    async fn get_proposal(
+        &self,
+        request: Request<GetProposalRequest>,
+    ) -> Result<Response<ProposalResponse>, Status> {
+        self.handle_get_proposal(request.into_inner()).await
+    }
+
+    async fn list_proposals(
+        &self,
+        request: Request<ListProposalsRequest>,
+    ) -> Result<Response<ListProposalsResponse>, Status> {
+        self.handle_list_proposals(request.into_inner()).await
+    }
+
+    async fn deploy_contract(
+        &self,
+        request: Request<DeployContractRequest>,
+    ) -> Result<Response<AddBlockResponse>, Status> {
+        let caller = caller(&request);
+        let req = request.into_inner();
+        let graph_id = req.graph_id.clone();
+        let response = self.handle_deploy_contract(req).await?;
+        self.record_audit("contract_deployed", &caller, &graph_id, response.get_ref());
+        Ok(response)
+    }
+
+    async fn update_schema(
+        &self,
+        request: Request<UpdateSchemaRequest>,
+    ) -> Result<Response<AddBlockResponse>, Status> {
+        let caller = caller(&request);
+        let req = request.into_inner();
+        let graph_id = req.graph_id.clone();
+        let response = self.handle_update_schema(req).await?;
+        self.record_audit("schema_updated", &caller, &graph_id, response.get_ref());
+        Ok(response)
+    }
+
+    async fn get_state(
+        &self,
+        request: Request<GetStateRequest>,
+    ) -> Result<Response<GetStateResponse>, Status> {
+        self.handle_get_state(request.into_inner()).await
+    }
+
+    async fn get_state_history(
+        &self,
+        request: Request<GetStateHistoryRequest>,
+    ) -> Result<Response<StateEntriesResponse>, Status> {
+        self.handle_get_state_history(request.into_inner()).await
+    }
+
+    async fn range_state(
+        &self,
+        request: Request<RangeStateRequest>,
+    ) -> Result<Response<StateEntriesResponse>, Status> {
+        self.handle_range_state(request.into_inner()).await
+    }
+
+    async fn create_index(
+        &self,
+        request: Request<CreateIndexRequest>,
+    ) -> Result<Response<IndexResponse>, Status> {
+        let caller = caller(&request);
+        let req = request.into_inner();
+        let graph_id = req.graph_id.clone();
+        let response = self.handle_create_index(req).await?;
+        self.record_audit("index_created", &caller, &graph_id, response.get_ref());
+        Ok(response)
+    }
+
+    async fn drop_index(
+        &self,
+        request: Request<DropIndexRequest>,
+    ) -> Result<Response<IndexResponse>, Status> {
+        let caller = caller(&request);
+        let req = request.into_inner();
+        let graph_id = req.graph_id.clone();
+        let response = self.handle_drop_index(req).await?;
+        self.record_audit("index_dropped", &caller, &graph_id, response.get_ref());
+        Ok(response)
+    }
+
+    async fn list_indexes(
+        &self,
+        request: Request<ListIndexesRequest>,
+    ) -> Result<Response<ListIndexesResponse>, Status> {
+        self.handle_list_indexes(request.into_inner()).await
+    }
+
+    async fn query_blocks(
+        &self,
+        request: Request<QueryBlocksRequest>,
+    ) -> Result<Response<QueryBlocksResponse>, Status> {
+        self.handle_query_blocks(request.into_inner()).await
+    }
+
+    async fn get_blocks_by_time(
+        &self,
+        request: Request<GetBlocksByTimeRequest>,
+    ) -> Result<Response<Self::GetBlocksByTimeStream>, Status> {
+        self.handle_get_blocks_by_time(request.into_inner()).await
+    }
+
+    async fn get_block_at_time(
+        &self,
+        request: Request<GetBlockAtTimeRequest>,
+    ) -> Result<Response<GetBlockResponse>, Status> {
+        self.handle_get_block_at_time(request.into_inner()).await
+    }
+
+    async fn configure_search(
+        &self,
+        request: Request<ConfigureSearchRequest>,
+    ) -> Result<Response<IndexResponse>, Status> {
+        let caller = caller(&request);
+        let req = request.into_inner();
+        let graph_id = req.graph_id.clone();
+        let response = self.handle_configure_search(req).await?;
+        self.record_audit("search_configured", &caller, &graph_id, response.get_ref());
+        Ok(response)
+    }
+
+    async fn rebuild_search_index(
+        &self,
+        request: Request<RebuildSearchIndexRequest>,
+    ) -> Result<Response<IndexResponse>, Status> {
+        let caller = caller(&request);
+        let req = request.into_inner();
+        let graph_id = req.graph_id.clone();
+        let response = self.handle_rebuild_search_index(req).await?;
+        self.record_audit(
+            "search_index_rebuilt",
+            &caller,
+            &graph_id,
+            response.get_ref(),
+        );
+        Ok(response)
+    }
+
+    async fn search_blocks(
+        &self,
+        request: Request<SearchBlocksRequest>,
+    ) -> Result<Response<SearchBlocksResponse>, Status> {
+        self.handle_search_blocks(request.into_inner()).await
+    }
+
+    async fn get_asset(
+        &self,
+        request: Request<GetAssetRequest>,
+    ) -> Result<Response<AssetResponse>, Status> {
+        self.handle_get_asset(request.into_inner()).await
+    }
+
+    async fn get_asset_history(
+        &self,
+        request: Request<GetAssetRequest>,
+    ) -> Result<Response<AssetHistoryResponse>, Status> {
+        self.handle_get_asset_history(request.into_inner()).await
+    }
+
+    async fn list_assets_by_owner(
+        &self,
+        request: Request<ListAssetsByOwnerRequest>,
+    ) -> Result<Response<ListAssetsResponse>, Status> {
+        self.handle_list_assets_by_owner(request.into_inner()).await
+    }
+
+    async fn update_asset_policy(
+        &self,
+        request: Request<UpdateAssetPolicyRequest>,
+    ) -> Result<Response<AddBlockResponse>, Status> {
+        let caller = caller(&request);
+        let req = request.into_inner();
+        let graph_id = req.graph_id.clone();
+        let response = self.handle_update_asset_policy(req).await?;
+        self.record_audit(
+            "asset_policy_updated",
+            &caller,
+            &graph_id,
+            response.get_ref(),
+        );
+        Ok(response)
+    }
+
+    async fn resolve_identity(
+        &self,
+        request: Request<ResolveIdentityRequest>,
+    ) -> Result<Response<IdentityResponse>, Status> {
+        self.handle_resolve_identity(request.into_inner()).await
+    }
+
+    async fn get_scrub_status(
+        &self,
+        request: Request<GetScrubStatusRequest>,
+    ) -> Result<Response<GetScrubStatusResponse>, Status> {
+        let _req = request.into_inner();
+        self.handle_get_scrub_status().await
+    }
 }
 
-/// Starts the gRPC server
-pub async fn start_grpc_server(
+/// Cluster member that sent a request, set by [`ClusterAuth`]
+#[derive(Debug, Clone, Copy)]
+struct ClusterPeer(u64);
+
+/// Admits a cluster RPC only when it carries the cluster secret and names a
+/// configured member
+#[derive(Clone)]
+pub struct ClusterAuth {
+    credentials: ClusterCredentials,
+    members: HashSet<u64>,
+}
+
+impl ClusterAuth {
+    pub fn new(credentials: ClusterCredentials, members: HashSet<u64>) -> Self {
+        Self {
+            credentials,
+            members,
+        }
+    }
+}
+
+impl Interceptor for ClusterAuth {
+    fn call(&mut self, mut request: Request<()>) -> Result<Request<()>, Status> {
+        match self.credentials.authenticate(request.metadata()) {
+            Some(node_id) if self.members.contains(&node_id) => {
+                request.extensions_mut().insert(ClusterPeer(node_id));
+                Ok(request)
+            }
+            _ => {
+                tracing::warn!(
+                    "⚠️  Refused cluster RPC from {}",
+                    request
+                        .remote_addr()
+                        .map_or("unknown".to_string(), |addr| addr.to_string())
+                );
+                Err(Status::unauthenticated("Not a cluster member"))
+            }
+        }
+    }
+}
+
+/// Node id of the member that sent an authenticated request
+fn cluster_peer<T>(request: &Request<T>) -> Option<u64> {
+    request.extensions().get::<ClusterPeer>().map(|peer| peer.0)
+}
+
+fn not_a_member() -> Status {
+    Status::unauthenticated("Not a cluster member")
+}
+
+/// gRPC adapter for node-to-node cluster RPCs: Raft and the writes other
+/// members forward to the leader
+pub struct ClusterServer {
+    node: Arc<RaftNode>,
+    service: Arc<BlockchainServiceImpl>,
+}
+
+impl ClusterServer {
+    pub fn new(node: Arc<RaftNode>, service: Arc<BlockchainServiceImpl>) -> Self {
+        Self { node, service }
+    }
+}
+
+#[tonic::async_trait]
+impl ClusterService for ClusterServer {
+    async fn request_vote(
+        &self,
+        request: Request<RaftVoteRequest>,
+    ) -> Result<Response<RaftVoteResponse>, Status> {
+        let sender = cluster_peer(&request).ok_or_else(not_a_member)?;
+        let req = request.into_inner();
+        if req.candidate_id != sender {
+            return Err(Status::permission_denied(
+                "Candidate id does not match the sender",
+            ));
+        }
+        Ok(Response::new(self.node.handle_request_vote(req)))
+    }
+
+    async fn append_entries(
+        &self,
+        request: Request<RaftAppendRequest>,
+    ) -> Result<Response<RaftAppendResponse>, Status> {
+        let sender = cluster_peer(&request).ok_or_else(not_a_member)?;
+        let req = request.into_inner();
+        if req.leader_id != sender {
+            return Err(Status::permission_denied(
+                "Leader id does not match the sender",
+            ));
+        }
+        Ok(Response::new(self.node.handle_append_entries(req)))
+    }
+
+    async fn forward_add_block(
+        &self,
+        request: Request<AddBlockRequest>,
+    ) -> Result<Response<AddBlockResponse>, Status> {
+        cluster_peer(&request).ok_or_else(not_a_member)?;
+        self.service
+            .handle_forwarded_add_block(request.into_inner())
+            .await
+    }
+
+    async fn forward_create_graph(
+        &self,
+        request: Request<CreateGraphRequest>,
+    ) -> Result<Response<CreateGraphResponse>, Status> {
+        cluster_peer(&request).ok_or_else(not_a_member)?;
+        self.service
+            .handle_forwarded_create_graph(request.into_inner())
+            .await
+    }
+
+    async fn forward_commit(
+        &self,
+        request: Request<ForwardCommitRequest>,
+    ) -> Result<Response<AddBlockResponse>, Status> {
+        cluster_peer(&request).ok_or_else(not_a_member)?;
+        self.service
+            .handle_forwarded_commit(request.into_inner())
+            .await
+    }
+
+    async fn forward_endorse_proposal(
+        &self,
+        request: Request<EndorseProposalRequest>,
+    ) -> Result<Response<ProposalResponse>, Status> {
+        cluster_peer(&request).ok_or_else(not_a_member)?;
+        self.service
+            .handle_forwarded_endorse_proposal(request.into_inner())
+            .await
+    }
+}
+
+/// Cluster service for a clustered member, with authentication in front
+pub fn cluster_service(
+    service: &Arc<BlockchainServiceImpl>,
+) -> Option<InterceptedService<ClusterServiceServer<ClusterServer>, ClusterAuth>> {
+    let node = service.cluster()?;
+    let credentials = service.cluster_credentials()?.clone();
+    let members = node.peers().keys().copied().collect();
+    Some(ClusterServiceServer::with_interceptor(
+        ClusterServer::new(node, service.clone()),
+        ClusterAuth::new(credentials, members),
+    ))
+}
+
+/// Serves the cluster service on its own listener, apart from clients,
+/// until the task is aborted
+pub async fn start_cluster_server(
     service: Arc<BlockchainServiceImpl>,
     addr: String,
 ) -> Result<(), Box<dyn std::error::Error>> {
     let addr = addr.parse()?;
+    let cluster = cluster_service(&service).ok_or("Clustered mode is not enabled")?;
+
+    tracing::info!("🤝 Starting cluster listener on {}", addr);
+    Server::builder().add_service(cluster).serve(addr).await?;
+    Ok(())
+}
+
+/// Starts the gRPC server and runs it until a shutdown signal arrives.
+/// On SIGINT/SIGTERM new writes are rejected and in-flight requests get
+/// up to `shutdown_timeout` to finish before the server stops.
+/// Returns whether every in-flight request finished in time.
+pub async fn start_grpc_server(
+    service: Arc<BlockchainServiceImpl>,
+    addr: String,
+    shutdown_timeout: Duration,
+) -> Result<bool, Box<dyn std::error::Error>> {
+    let addr = addr.parse()?;
 
     tracing::info!("🚀 Starting gRPC server on {}", addr);
 
-    Server::builder()
-        .add_service(BlockchainServiceServer::from_arc(service))
-        .serve(addr)
-        .await?;
+    let (stop_tx, stop_rx) = oneshot::channel::<()>();
+    let server = Server::builder()
+        .add_service(BlockchainServiceServer::from_arc(service.clone()))
+        .serve_with_shutdown(addr, async {
+            let _ = stop_rx.await;
+        });
+    tokio::pin!(server);
 
-    Ok(())
+    tokio::select! {
+        result = &mut server => {
+            result?;
+            service.begin_shutdown();
+            return Ok(service.drain_in_flight(shutdown_timeout).await);
+        }
+        _ = shutdown_signal() => {}
+    }
+
+    tracing::info!("🛑 Shutdown signal received, draining in-flight requests...");
+    service.begin_shutdown();
+    let _ = stop_tx.send(());
+
+    let drained = async {
+        let mining_done = service.drain_in_flight(shutdown_timeout).await;
+        server.await?;
+        Ok::<bool, tonic::transport::Error>(mining_done)
+    };
+
+    match tokio::time::timeout(shutdown_timeout, drained).await {
+        Ok(Ok(true)) => {
+            tracing::info!("✅ All in-flight requests finished");
+            Ok(true)
+        }
+        Ok(Ok(false)) | Err(_) => {
+            tracing::warn!(
+                "⚠️  Shutdown timeout of {:?} elapsed with requests still in flight",
+                shutdown_timeout
+            );
+            Ok(false)
+        }
+        Ok(Err(e)) => Err(e.into()),
+    }
+}
+
+/// Resolves when the process receives Ctrl+C or (on Unix) SIGTERM
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            tracing::error!("Failed to listen for Ctrl+C: {}", e);
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(e) => {
+                tracing::error!("Failed to listen for SIGTERM: {}", e);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {}
+        _ = terminate => {}
+    }
 }
@@ -0,0 +1,456 @@
+//! GraphQL schema over graphs, blocks and cross-references. Resolvers are
+//! adapters over the gRPC handlers, like the server in `grpc::server`.
+//!
+//! ```graphql
+//! type Query {
+//!   graphs: [Graph!]!
+//!   graph(id: String!): Graph
+//!   block(graphId: String!, height: Int, hash: String): Block
+//! }
+//! type Graph {
+//!   id: String!  type: String!  description: String!  blockCount: Int!
+//!   tip: Block
+//!   block(height: Int, hash: String): Block
+//!   blocks(first: Int, after: Int): BlockConnection!
+//! }
+//! type BlockConnection { nodes: [Block!]!  hasNextPage: Boolean!  endCursor: Int }
+//! type Block {
+//!   hash: String!  previousHash: String!  height: Int!  timestamp: Int!  nonce: Int!
+//!   data: String!  graphId: String!  signer: String!  signature: String!
+//!   graph: Graph!  previous: Block  crossReferences: [CrossReference!]!
+//! }
+//! type CrossReference { hash: String!  block: Block  graph: Graph }
+//! ```
+use futures::future::BoxFuture;
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+use std::sync::Arc;
+use tokio::sync::OnceCell;
+
+use super::query::{self, Field};
+use crate::application::services::blockchain_service::BlockchainServiceImpl;
+use crate::infrastructure::grpc::blockchain::{
+    Block, GetBlockRangeRequest, GetBlockRequest, GraphInfo, GraphType,
+};
+
+/// Deepest selection nesting accepted unless configured otherwise
+pub const DEFAULT_MAX_DEPTH: usize = 8;
+
+/// Most fields a query may select unless configured otherwise
+pub const DEFAULT_MAX_FIELDS: usize = 200;
+
+/// Largest `blocks(first:)` page unless configured otherwise
+pub const DEFAULT_MAX_PAGE_SIZE: u64 = 100;
+
+/// Page size of `blocks` when `first` is omitted
+const DEFAULT_BLOCK_PAGE: u64 = 20;
+
+/// Body of a GraphQL request (POST /graphql)
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GraphQlRequest {
+    pub query: String,
+    #[serde(default)]
+    pub variables: Option<Map<String, Value>>,
+    #[serde(default)]
+    pub operation_name: Option<String>,
+}
+
+/// Executes GraphQL queries against the blockchain service
+pub struct GraphQlExecutor {
+    service: Arc<BlockchainServiceImpl>,
+    limits: query::Limits,
+    max_page_size: u64,
+}
+
+/// Object a selection set is resolved against
+enum Node {
+    Query,
+    Graph(GraphInfo),
+    Block(Block),
+    Connection {
+        nodes: Vec<Block>,
+        has_next_page: bool,
+    },
+    CrossReference(String),
+}
+
+/// State of one execution
+struct Context {
+    variables: Map<String, Value>,
+    /// Graph list, fetched once per query when first needed
+    graphs: OnceCell<Vec<GraphInfo>>,
+}
+
+impl GraphQlExecutor {
+    pub fn new(service: Arc<BlockchainServiceImpl>) -> Self {
+        Self {
+            service,
+            limits: query::Limits {
+                max_depth: DEFAULT_MAX_DEPTH,
+                max_fields: DEFAULT_MAX_FIELDS,
+            },
+            max_page_size: DEFAULT_MAX_PAGE_SIZE,
+        }
+    }
+
+    pub fn with_limits(mut self, max_depth: usize, max_fields: usize, max_page_size: u64) -> Self {
+        self.limits = query::Limits {
+            max_depth,
+            max_fields,
+        };
+        self.max_page_size = max_page_size;
+        self
+    }
+
+    /// Runs a query; the result is a GraphQL response with `data`, or
+    /// `errors` when the query is rejected or a resolver fails
+    pub async fn execute(&self, request: GraphQlRequest) -> Value {
+        match self.run(request).await {
+            Ok(data) => json!({ "data": data }),
+            Err(message) => json!({ "data": null, "errors": [{ "message": message }] }),
+        }
+    }
+
+    async fn run(&self, request: GraphQlRequest) -> Result<Value, String> {
+        let operation = query::parse(&request.query, self.limits)?;
+
+        let mut variables = operation.variable_defaults.clone();
+        variables.extend(request.variables.unwrap_or_default());
+        let context = Context {
+            variables,
+            graphs: OnceCell::new(),
+        };
+        self.resolve_selection(&context, Node::Query, &operation.selection)
+            .await
+    }
+
+    fn resolve_selection<'a>(
+        &'a self,
+        context: &'a Context,
+        node: Node,
+        selection: &'a [Field],
+    ) -> BoxFuture<'a, Result<Value, String>> {
+        Box::pin(async move {
+            let mut object = Map::new();
+            for field in selection {
+                let value = self.resolve_field(context, &node, field).await?;
+                object.insert(field.response_key().to_string(), value);
+            }
+            Ok(Value::Object(object))
+        })
+    }
+
+    async fn resolve_field(
+        &self,
+        context: &Context,
+        node: &Node,
+        field: &Field,
+    ) -> Result<Value, String> {
+        if field.name == "__typename" {
+            return Ok(json!(node.type_name()));
+        }
+
+        let child = match (node, field.name.as_str()) {
+            (Node::Query, "graphs") => {
+                let graphs = self
+                    .graphs(context)
+                    .await?
+                    .iter()
+                    .cloned()
+                    .map(Node::Graph)
+                    .collect();
+                return self.resolve_list(context, graphs, field).await;
+            }
+            (Node::Query, "graph") => {
+                let id = required_string(field, context, "id")?;
+                self.graph(context, &id).await?.map(Node::Graph)
+            }
+            (Node::Query, "block") => {
+                let graph_id = required_string(field, context, "graphId")?;
+                self.block_at(context, &graph_id, field)
+                    .await?
+                    .map(Node::Block)
+            }
+
+            (Node::Graph(graph), "id") => return Ok(json!(graph.graph_id)),
+            (Node::Graph(graph), "type") => {
+                let name = GraphType::try_from(graph.graph_type).unwrap_or(GraphType::Custom);
+                return Ok(json!(name.as_str_name()));
+            }
+            (Node::Graph(graph), "description") => return Ok(json!(graph.description)),
+            (Node::Graph(graph), "blockCount") => return Ok(json!(graph.total_blocks)),
+            (Node::Graph(graph), "tip") => match graph.total_blocks.checked_sub(1) {
+                Some(height) => self
+                    .block_by_height(&graph.graph_id, height)
+                    .await?
+                    .map(Node::Block),
+                None => None,
+            },
+            (Node::Graph(graph), "block") => self
+                .block_at(context, &graph.graph_id, field)
+                .await?
+                .map(Node::Block),
+            (Node::Graph(graph), "blocks") => Some(self.blocks(context, graph, field).await?),
+
+            (Node::Connection { nodes, .. }, "nodes") => {
+                let blocks = nodes.iter().cloned().map(Node::Block).collect();
+                return self.resolve_list(context, blocks, field).await;
+            }
+            (Node::Connection { has_next_page, .. }, "hasNextPage") => {
+                return Ok(json!(has_next_page))
+            }
+            (Node::Connection { nodes, .. }, "endCursor") => {
+                return Ok(json!(nodes.last().map(|b| b.height)))
+            }
+
+            (Node::Block(block), "hash") => return Ok(json!(block.hash)),
+            (Node::Block(block), "previousHash") => return Ok(json!(block.previous_hash)),
+            (Node::Block(block), "height") => return Ok(json!(block.height)),
+            (Node::Block(block), "timestamp") => return Ok(json!(block.timestamp)),
+            (Node::Block(block), "nonce") => return Ok(json!(block.nonce)),
+            (Node::Block(block), "data") => return Ok(json!(block.data)),
+            (Node::Block(block), "graphId") => return Ok(json!(block.graph_id)),
+            (Node::Block(block), "signer") => return Ok(json!(block.signer)),
+            (Node::Block(block), "signature") => return Ok(json!(block.signature)),
+            (Node::Block(block), "graph") => {
+                self.graph(context, &block.graph_id).await?.map(Node::Graph)
+            }
+            (Node::Block(block), "previous") => match block.height.checked_sub(1) {
+                Some(height) => self
+                    .block_by_height(&block.graph_id, height)
+                    .await?
+                    .map(Node::Block),
+                None => None,
+            },
+            (Node::Block(block), "crossReferences") => {
+                let references = block
+                    .cross_references
+                    .iter()
+                    .filter(|hash| !hash.is_empty())
+                    .cloned()
+                    .map(Node::CrossReference)
+                    .collect();
+                return self.resolve_list(context, references, field).await;
+            }
+
+            (Node::CrossReference(hash), "hash") => return Ok(json!(hash)),
+            (Node::CrossReference(hash), "block") => {
+                self.referenced_block(context, hash).await?.map(Node::Block)
+            }
+            (Node::CrossReference(hash), "graph") => {
+                match self.referenced_block(context, hash).await? {
+                    Some(block) => self.graph(context, &block.graph_id).await?.map(Node::Graph),
+                    None => None,
+                }
+            }
+
+            (node, name) => {
+                return Err(format!(
+                    "Unknown field '{}' on type '{}'",
+                    name,
+                    node.type_name()
+                ))
+            }
+        };
+
+        match child {
+            Some(child) => self.resolve_object(context, child, field).await,
+            None => Ok(Value::Null),
+        }
+    }
+
+    async fn resolve_object(
+        &self,
+        context: &Context,
+        node: Node,
+        field: &Field,
+    ) -> Result<Value, String> {
+        if field.selection.is_empty() {
+            return Err(format!(
+                "Field '{}' of type '{}' needs a selection of subfields",
+                field.name,
+                node.type_name()
+            ));
+        }
+        self.resolve_selection(context, node, &field.selection)
+            .await
+    }
+
+    async fn resolve_list(
+        &self,
+        context: &Context,
+        nodes: Vec<Node>,
+        field: &Field,
+    ) -> Result<Value, String> {
+        let mut items = Vec::with_capacity(nodes.len());
+        for node in nodes {
+            items.push(self.resolve_object(context, node, field).await?);
+        }
+        Ok(Value::Array(items))
+    }
+
+    async fn graphs<'a>(&self, context: &'a Context) -> Result<&'a Vec<GraphInfo>, String> {
+        context
+            .graphs
+            .get_or_try_init(|| async {
+                let mut graphs = self
+                    .service
+                    .handle_list_graphs()
+                    .await
+                    .map_err(|status| status.message().to_string())?
+                    .into_inner()
+                    .graphs;
+                graphs.sort_by(|a, b| a.graph_id.cmp(&b.graph_id));
+                Ok(graphs)
+            })
+            .await
+    }
+
+    async fn graph(&self, context: &Context, id: &str) -> Result<Option<GraphInfo>, String> {
+        Ok(self
+            .graphs(context)
+            .await?
+            .iter()
+            .find(|g| g.graph_id == id)
+            .cloned())
+    }
+
+    async fn block_by_height(&self, graph_id: &str, height: u64) -> Result<Option<Block>, String> {
+        let response = self
+            .service
+            .handle_get_block_range(GetBlockRangeRequest {
+                graph_id: graph_id.to_string(),
+                start_height: height,
+                end_height: height,
+            })
+            .await
+            .map_err(|status| status.message().to_string())?
+            .into_inner();
+        Ok(response.blocks.into_iter().next())
+    }
+
+    async fn block_by_hash(&self, graph_id: &str, hash: &str) -> Result<Option<Block>, String> {
+        let response = self
+            .service
+            .handle_get_block(GetBlockRequest {
+                graph_id: graph_id.to_string(),
+                hash: hash.to_string(),
+            })
+            .await
+            .map_err(|status| status.message().to_string())?
+            .into_inner();
+        Ok(response.block)
+    }
+
+    /// Block selected by a `height` or `hash` argument
+    async fn block_at(
+        &self,
+        context: &Context,
+        graph_id: &str,
+        field: &Field,
+    ) -> Result<Option<Block>, String> {
+        let height = optional_u64(field, context, "height")?;
+        let hash = field.argument("hash", &context.variables)?;
+        match (height, hash) {
+            (Some(height), None | Some(Value::Null)) => {
+                self.block_by_height(graph_id, height).await
+            }
+            (None, Some(Value::String(hash))) => self.block_by_hash(graph_id, &hash).await,
+            _ => Err(format!(
+                "Field '{}' needs either a height or a hash",
+                field.name
+            )),
+        }
+    }
+
+    /// Page of main-chain blocks after the `after` height cursor
+    async fn blocks(
+        &self,
+        context: &Context,
+        graph: &GraphInfo,
+        field: &Field,
+    ) -> Result<Node, String> {
+        let first = optional_u64(field, context, "first")?.unwrap_or(DEFAULT_BLOCK_PAGE);
+        if first == 0 || first > self.max_page_size {
+            return Err(format!(
+                "'first' must be between 1 and {}",
+                self.max_page_size
+            ));
+        }
+        let start = match optional_u64(field, context, "after")? {
+            Some(after) => after + 1,
+            None => 0,
+        };
+        if start >= graph.total_blocks {
+            return Ok(Node::Connection {
+                nodes: Vec::new(),
+                has_next_page: false,
+            });
+        }
+
+        let end = (start + first - 1).min(graph.total_blocks - 1);
+        let response = self
+            .service
+            .handle_get_block_range(GetBlockRangeRequest {
+                graph_id: graph.graph_id.clone(),
+                start_height: start,
+                end_height: end,
+            })
+            .await
+            .map_err(|status| status.message().to_string())?
+            .into_inner();
+        Ok(Node::Connection {
+            nodes: response.blocks,
+            has_next_page: end + 1 < graph.total_blocks,
+        })
+    }
+
+    /// Block of any graph with the referenced hash
+    async fn referenced_block(
+        &self,
+        context: &Context,
+        hash: &str,
+    ) -> Result<Option<Block>, String> {
+        for graph in self.graphs(context).await? {
+            if let Some(block) = self.block_by_hash(&graph.graph_id, hash).await? {
+                return Ok(Some(block));
+            }
+        }
+        Ok(None)
+    }
+}
+
+impl Node {
+    fn type_name(&self) -> &'static str {
+        match self {
+            Node::Query => "Query",
+            Node::Graph(_) => "Graph",
+            Node::Block(_) => "Block",
+            Node::Connection { .. } => "BlockConnection",
+            Node::CrossReference(_) => "CrossReference",
+        }
+    }
+}
+
+fn required_string(field: &Field, context: &Context, name: &str) -> Result<String, String> {
+    match field.argument(name, &context.variables)? {
+        Some(Value::String(value)) => Ok(value),
+        _ => Err(format!(
+            "Field '{}' needs a String argument '{}'",
+            field.name, name
+        )),
+    }
+}
+
+fn optional_u64(field: &Field, context: &Context, name: &str) -> Result<Option<u64>, String> {
+    match field.argument(name, &context.variables)? {
+        None | Some(Value::Null) => Ok(None),
+        Some(value) => value.as_u64().map(Some).ok_or_else(|| {
+            format!(
+                "Argument '{}' of '{}' must be a non-negative Int",
+                name, field.name
+            )
+        }),
+    }
+}
@@ -0,0 +1,83 @@
+use std::fmt::Write;
+
+use crate::application::services::IntegrityScrubber;
+
+/// Renders the scrubber gauges in the Prometheus text exposition format
+pub fn render(scrubber: Option<&IntegrityScrubber>) -> String {
+    let mut out = String::new();
+    let scrubber = match scrubber {
+        Some(scrubber) => scrubber,
+        None => return out,
+    };
+    let report = scrubber.last_report().unwrap_or_default();
+    let gauges = [
+        (
+            "blockchain_scrub_running",
+            "Whether an integrity scrub is in progress",
+            scrubber.is_running() as i64,
+        ),
+        (
+            "blockchain_scrub_last_finished_timestamp_ms",
+            "End of the last finished scrub, in milliseconds since the epoch",
+            report.finished_at_ms,
+        ),
+        (
+            "blockchain_scrub_graphs_checked",
+            "Graphs checked by the last scrub",
+            report.graphs_checked as i64,
+        ),
+        (
+            "blockchain_scrub_blocks_checked",
+            "Blocks checked by the last scrub",
+            report.blocks_checked as i64,
+        ),
+        (
+            "blockchain_scrub_issues",
+            "Inconsistencies found by the last scrub",
+            report.issues.len() as i64,
+        ),
+    ];
+    for (name, help, value) in gauges {
+        let _ = writeln!(
+            out,
+            "# HELP {} {}\n# TYPE {} gauge\n{} {}",
+            name, help, name, name, value
+        );
+    }
+    let _ = writeln!(
+        out,
+        "# HELP blockchain_scrubs_completed_total Scrubs finished since the node started\n\
+         # TYPE blockchain_scrubs_completed_total counter\n\
+         blockchain_scrubs_completed_total {}",
+        scrubber.scrubs_completed()
+    );
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infrastructure::persistence::{BlockchainRepositoryImpl, RocksDbAdapter};
+    use std::sync::Arc;
+    use tempfile::tempdir;
+
+    #[tokio::test]
+    async fn test_render_scrub_metrics() {
+        assert_eq!(render(None), "");
+
+        let dir = tempdir().unwrap();
+        let repository = Arc::new(BlockchainRepositoryImpl::new(Arc::new(
+            RocksDbAdapter::new(dir.path()).unwrap(),
+        )));
+        let scrubber = IntegrityScrubber::new(repository, 1000);
+        scrubber.scrub().await;
+        let text = render(Some(&scrubber));
+        assert!(text.contains("blockchain_scrub_running 0\n"), "{}", text);
+        assert!(text.contains("blockchain_scrub_issues 0\n"), "{}", text);
+        assert!(
+            text.contains("blockchain_scrubs_completed_total 1\n"),
+            "{}",
+            text
+        );
+    }
+}
@@ -0,0 +1,54 @@
+pub mod graphql;
+pub mod metrics;
+pub mod query;
+
+use axum::extract::{DefaultBodyLimit, State};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use serde_json::Value;
+use std::sync::Arc;
+
+use crate::application::services::IntegrityScrubber;
+use graphql::{GraphQlExecutor, GraphQlRequest};
+
+/// HTTP routes served next to gRPC: `POST /graphql`, whose body may not
+/// exceed `max_body_bytes`, and `GET /metrics`
+pub fn router(
+    executor: Arc<GraphQlExecutor>,
+    scrubber: Option<Arc<IntegrityScrubber>>,
+    max_body_bytes: usize,
+) -> Router {
+    Router::new()
+        .route("/graphql", post(graphql))
+        .layer(DefaultBodyLimit::max(max_body_bytes))
+        .with_state(executor)
+        .merge(
+            Router::new()
+                .route("/metrics", get(metrics))
+                .with_state(scrubber),
+        )
+}
+
+async fn graphql(
+    State(executor): State<Arc<GraphQlExecutor>>,
+    Json(request): Json<GraphQlRequest>,
+) -> Json<Value> {
+    Json(executor.execute(request).await)
+}
+
+async fn metrics(State(scrubber): State<Option<Arc<IntegrityScrubber>>>) -> String {
+    metrics::render(scrubber.as_deref())
+}
+
+/// Serves the HTTP routes until the task is aborted
+pub async fn start_http_server(
+    executor: Arc<GraphQlExecutor>,
+    scrubber: Option<Arc<IntegrityScrubber>>,
+    addr: String,
+    max_body_bytes: usize,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let listener = tokio::net::TcpListener::bind(&addr).await?;
+    tracing::info!("🌐 Serving GraphQL on http://{}/graphql", addr);
+    axum::serve(listener, router(executor, scrubber, max_body_bytes)).await?;
+    Ok(())
+}
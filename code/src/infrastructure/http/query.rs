@@ -0,0 +1,523 @@
+//! Parser for the subset of the GraphQL query language served by the
+//! endpoint: one query operation with variables, aliases, arguments and
+//! nested selections. Mutations, fragments and directives are rejected.
+use serde_json::{Map, Value};
+use std::iter::Peekable;
+use std::str::Chars;
+
+/// Bounds enforced while parsing, so an oversized query fails before it is
+/// built and deep nesting cannot exhaust the stack
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Limits {
+    /// Deepest nesting of selections, and of list and object values
+    pub max_depth: usize,
+    /// Most fields selected by the whole query, aliases included
+    pub max_fields: usize,
+}
+
+/// A parsed query operation
+#[derive(Debug, Clone, PartialEq)]
+pub struct Operation {
+    pub selection: Vec<Field>,
+    /// Declared variables with their default values (null without one)
+    pub variable_defaults: Map<String, Value>,
+}
+
+/// A selected field, possibly with arguments and a nested selection
+#[derive(Debug, Clone, PartialEq)]
+pub struct Field {
+    pub alias: Option<String>,
+    pub name: String,
+    pub arguments: Vec<(String, InputValue)>,
+    pub selection: Vec<Field>,
+}
+
+/// Argument value as written in the query
+#[derive(Debug, Clone, PartialEq)]
+pub enum InputValue {
+    Null,
+    Int(i64),
+    Float(f64),
+    String(String),
+    Boolean(bool),
+    Enum(String),
+    List(Vec<InputValue>),
+    Object(Vec<(String, InputValue)>),
+    Variable(String),
+}
+
+impl Operation {
+    /// Deepest nesting of selections (a query selecting only scalars of
+    /// the root has depth 1)
+    pub fn depth(&self) -> usize {
+        self.selection
+            .iter()
+            .map(Field::depth)
+            .max()
+            .unwrap_or_default()
+    }
+}
+
+impl Field {
+    /// Key of the field in the response
+    pub fn response_key(&self) -> &str {
+        self.alias.as_deref().unwrap_or(&self.name)
+    }
+
+    fn depth(&self) -> usize {
+        1 + self
+            .selection
+            .iter()
+            .map(Field::depth)
+            .max()
+            .unwrap_or_default()
+    }
+
+    /// Argument value with variables substituted
+    pub fn argument(
+        &self,
+        name: &str,
+        variables: &Map<String, Value>,
+    ) -> Result<Option<Value>, String> {
+        match self.arguments.iter().find(|(arg, _)| arg == name) {
+            Some((_, value)) => value.resolve(variables).map(Some),
+            None => Ok(None),
+        }
+    }
+}
+
+impl InputValue {
+    fn resolve(&self, variables: &Map<String, Value>) -> Result<Value, String> {
+        Ok(match self {
+            InputValue::Null => Value::Null,
+            InputValue::Int(value) => Value::from(*value),
+            InputValue::Float(value) => Value::from(*value),
+            InputValue::String(value) | InputValue::Enum(value) => Value::String(value.clone()),
+            InputValue::Boolean(value) => Value::Bool(*value),
+            InputValue::List(items) => Value::Array(
+                items
+                    .iter()
+                    .map(|item| item.resolve(variables))
+                    .collect::<Result<_, _>>()?,
+            ),
+            InputValue::Object(fields) => Value::Object(
+                fields
+                    .iter()
+                    .map(|(key, value)| Ok((key.clone(), value.resolve(variables)?)))
+                    .collect::<Result<_, String>>()?,
+            ),
+            InputValue::Variable(name) => variables
+                .get(name)
+                .cloned()
+                .ok_or_else(|| format!("Variable '${}' is not defined", name))?,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Punct(char),
+    Spread,
+    Name(String),
+    Int(i64),
+    Float(f64),
+    String(String),
+}
+
+/// Parses a query document holding a single query operation
+pub fn parse(source: &str, limits: Limits) -> Result<Operation, String> {
+    let mut parser = Parser {
+        tokens: tokenize(source)?,
+        position: 0,
+        limits,
+        depth: 0,
+        value_depth: 0,
+        fields: 0,
+    };
+    let operation = parser.operation()?;
+    if let Some(token) = parser.peek() {
+        return Err(match token {
+            Token::Name(name) if name == "fragment" => "Fragments are not supported".to_string(),
+            _ => "Only one operation per document is supported".to_string(),
+        });
+    }
+    Ok(operation)
+}
+
+fn tokenize(source: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = source.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() || c == ',' || c == '\u{feff}' => {
+                chars.next();
+            }
+            '#' => while chars.next_if(|c| *c != '\n').is_some() {},
+            '{' | '}' | '(' | ')' | '[' | ']' | ':' | '!' | '$' | '=' | '@' => {
+                chars.next();
+                tokens.push(Token::Punct(c));
+            }
+            '.' => {
+                if chars.by_ref().take(3).collect::<String>() != "..." {
+                    return Err("Unexpected '.' in query".to_string());
+                }
+                tokens.push(Token::Spread);
+            }
+            '"' => {
+                chars.next();
+                tokens.push(Token::String(string(&mut chars)?));
+            }
+            c if c == '-' || c.is_ascii_digit() => tokens.push(number(&mut chars)?),
+            c if c == '_' || c.is_ascii_alphabetic() => {
+                let mut name = String::new();
+                while let Some(c) = chars.next_if(|c| *c == '_' || c.is_ascii_alphanumeric()) {
+                    name.push(c);
+                }
+                tokens.push(Token::Name(name));
+            }
+            other => return Err(format!("Unexpected character '{}' in query", other)),
+        }
+    }
+    Ok(tokens)
+}
+
+fn string(chars: &mut Peekable<Chars>) -> Result<String, String> {
+    let mut text = String::new();
+    loop {
+        match chars.next() {
+            Some('"') => return Ok(text),
+            Some('\\') => match chars.next() {
+                Some('n') => text.push('\n'),
+                Some('t') => text.push('\t'),
+                Some('r') => text.push('\r'),
+                Some('b') => text.push('\u{8}'),
+                Some('f') => text.push('\u{c}'),
+                Some('u') => {
+                    let code: String = chars.by_ref().take(4).collect();
+                    let c = u32::from_str_radix(&code, 16)
+                        .ok()
+                        .and_then(char::from_u32)
+                        .ok_or_else(|| format!("Invalid escape '\\u{}' in string", code))?;
+                    text.push(c);
+                }
+                Some(c @ ('"' | '\\' | '/')) => text.push(c),
+                _ => return Err("Invalid escape in string".to_string()),
+            },
+            Some('\n') | None => return Err("Unterminated string in query".to_string()),
+            Some(c) => text.push(c),
+        }
+    }
+}
+
+fn number(chars: &mut Peekable<Chars>) -> Result<Token, String> {
+    let mut text = String::new();
+    while let Some(c) =
+        chars.next_if(|c| c.is_ascii_digit() || matches!(c, '-' | '+' | '.' | 'e' | 'E'))
+    {
+        text.push(c);
+    }
+    if text.contains(['.', 'e', 'E']) {
+        text.parse()
+            .map(Token::Float)
+            .map_err(|_| format!("Invalid number '{}'", text))
+    } else {
+        text.parse()
+            .map(Token::Int)
+            .map_err(|_| format!("Invalid number '{}'", text))
+    }
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+    limits: Limits,
+    /// Selection sets currently open
+    depth: usize,
+    /// Lists, input objects and list types currently open
+    value_depth: usize,
+    /// Fields parsed so far
+    fields: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(&Token::Punct(c)) {
+            self.position += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), String> {
+        if self.eat(c) {
+            Ok(())
+        } else {
+            Err(format!("Expected '{}' in query", c))
+        }
+    }
+
+    /// Opens a list, input object or list type
+    fn enter_value(&mut self) -> Result<(), String> {
+        self.value_depth += 1;
+        if self.value_depth > self.limits.max_depth {
+            return Err(format!(
+                "Value nesting exceeds the limit of {}",
+                self.limits.max_depth
+            ));
+        }
+        Ok(())
+    }
+
+    fn name(&mut self) -> Result<String, String> {
+        match self.next() {
+            Some(Token::Name(name)) => Ok(name),
+            _ => Err("Expected a name in query".to_string()),
+        }
+    }
+
+    fn operation(&mut self) -> Result<Operation, String> {
+        let mut variable_defaults = Map::new();
+        if let Some(Token::Name(keyword)) = self.peek() {
+            match keyword.as_str() {
+                "query" => self.position += 1,
+                "mutation" | "subscription" => {
+                    return Err(format!("{} operations are not supported", keyword))
+                }
+                "fragment" => return Err("Fragments are not supported".to_string()),
+                _ => return Err(format!("Unexpected '{}' in query", keyword)),
+            }
+            if let Some(Token::Name(_)) = self.peek() {
+                self.position += 1;
+            }
+            if self.eat('(') {
+                while !self.eat(')') {
+                    self.expect('$')?;
+                    let name = self.name()?;
+                    self.expect(':')?;
+                    self.type_reference()?;
+                    let default = if self.eat('=') {
+                        self.value()?.resolve(&Map::new())?
+                    } else {
+                        Value::Null
+                    };
+                    variable_defaults.insert(name, default);
+                }
+            }
+        }
+        if self.peek() == Some(&Token::Punct('@')) {
+            return Err("Directives are not supported".to_string());
+        }
+        Ok(Operation {
+            selection: self.selection_set()?,
+            variable_defaults,
+        })
+    }
+
+    /// Skips a variable type such as `[String!]!`; values are checked by
+    /// the resolvers
+    fn type_reference(&mut self) -> Result<(), String> {
+        if self.eat('[') {
+            self.enter_value()?;
+            self.type_reference()?;
+            self.expect(']')?;
+            self.value_depth -= 1;
+        } else {
+            self.name()?;
+        }
+        self.eat('!');
+        Ok(())
+    }
+
+    fn selection_set(&mut self) -> Result<Vec<Field>, String> {
+        self.expect('{')?;
+        self.depth += 1;
+        if self.depth > self.limits.max_depth {
+            return Err(format!(
+                "Query depth {} exceeds the limit of {}",
+                self.depth, self.limits.max_depth
+            ));
+        }
+        let mut fields = Vec::new();
+        while !self.eat('}') {
+            match self.peek() {
+                Some(Token::Spread) => return Err("Fragments are not supported".to_string()),
+                None => return Err("Unterminated selection set in query".to_string()),
+                _ => fields.push(self.field()?),
+            }
+        }
+        if fields.is_empty() {
+            return Err("Selection sets must not be empty".to_string());
+        }
+        self.depth -= 1;
+        Ok(fields)
+    }
+
+    fn field(&mut self) -> Result<Field, String> {
+        self.fields += 1;
+        if self.fields > self.limits.max_fields {
+            return Err(format!(
+                "Query selects more than {} fields",
+                self.limits.max_fields
+            ));
+        }
+        let mut name = self.name()?;
+        let mut alias = None;
+        if self.eat(':') {
+            alias = Some(name);
+            name = self.name()?;
+        }
+
+        let mut arguments = Vec::new();
+        if self.eat('(') {
+            while !self.eat(')') {
+                let argument = self.name()?;
+                self.expect(':')?;
+                arguments.push((argument, self.value()?));
+            }
+        }
+        if self.peek() == Some(&Token::Punct('@')) {
+            return Err("Directives are not supported".to_string());
+        }
+
+        let selection = if self.peek() == Some(&Token::Punct('{')) {
+            self.selection_set()?
+        } else {
+            Vec::new()
+        };
+        Ok(Field {
+            alias,
+            name,
+            arguments,
+            selection,
+        })
+    }
+
+    fn value(&mut self) -> Result<InputValue, String> {
+        match self.next() {
+            Some(Token::Punct('$')) => Ok(InputValue::Variable(self.name()?)),
+            Some(Token::Int(value)) => Ok(InputValue::Int(value)),
+            Some(Token::Float(value)) => Ok(InputValue::Float(value)),
+            Some(Token::String(value)) => Ok(InputValue::String(value)),
+            Some(Token::Name(name)) => Ok(match name.as_str() {
+                "true" => InputValue::Boolean(true),
+                "false" => InputValue::Boolean(false),
+                "null" => InputValue::Null,
+                _ => InputValue::Enum(name),
+            }),
+            Some(Token::Punct('[')) => {
+                self.enter_value()?;
+                let mut items = Vec::new();
+                while !self.eat(']') {
+                    items.push(self.value()?);
+                }
+                self.value_depth -= 1;
+                Ok(InputValue::List(items))
+            }
+            Some(Token::Punct('{')) => {
+                self.enter_value()?;
+                let mut fields = Vec::new();
+                while !self.eat('}') {
+                    let name = self.name()?;
+                    self.expect(':')?;
+                    fields.push((name, self.value()?));
+                }
+                self.value_depth -= 1;
+                Ok(InputValue::Object(fields))
+            }
+            _ => Err("Expected a value in query".to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LIMITS: Limits = Limits {
+        max_depth: 8,
+        max_fields: 20,
+    };
+
+    #[test]
+    fn test_parse_query() {
+        let operation = parse(
+            r#"
+            # Tip of the payments graph and what it references
+            query Tip($id: String! = "payments", $first: Int) {
+                tip: graph(id: $id) {
+                    blocks(first: $first, after: 10) { nodes { hash } }
+                    description
+                }
+            }"#,
+            LIMITS,
+        )
+        .unwrap();
+        assert_eq!(operation.depth(), 4);
+        assert_eq!(operation.variable_defaults["id"], "payments");
+        assert_eq!(operation.variable_defaults["first"], Value::Null);
+
+        let field = &operation.selection[0];
+        assert_eq!(field.response_key(), "tip");
+        assert_eq!(field.name, "graph");
+        assert_eq!(
+            field
+                .argument("id", &operation.variable_defaults)
+                .unwrap()
+                .unwrap(),
+            "payments"
+        );
+        assert_eq!(
+            field.selection[0]
+                .argument("after", &Map::new())
+                .unwrap()
+                .unwrap(),
+            10
+        );
+        assert!(field.selection[0].argument("first", &Map::new()).is_err());
+        assert_eq!(
+            field.selection[0]
+                .argument("first", &operation.variable_defaults)
+                .unwrap(),
+            Some(Value::Null)
+        );
+
+        assert!(parse("{ graphs { id } }", LIMITS).is_ok());
+        assert!(parse("mutation { addBlock }", LIMITS).is_err());
+        assert!(parse("{ graphs { ...Fields } }", LIMITS).is_err());
+        assert!(parse("{ graphs { id }", LIMITS).is_err());
+        assert!(parse("{ graph(id: \"open) { id } }", LIMITS).is_err());
+    }
+
+    #[test]
+    fn test_limits_are_checked_while_parsing() {
+        // Nesting far beyond the stack is refused at the first level too deep
+        let deep = format!("{}{}", "{ a ".repeat(100_000), "}".repeat(100_000));
+        let err = parse(&deep, LIMITS).unwrap_err();
+        assert_eq!(err, "Query depth 9 exceeds the limit of 8");
+        let nested = format!("{{ a(x: {}) }}", "[".repeat(100_000));
+        let err = parse(&nested, LIMITS).unwrap_err();
+        assert!(err.contains("Value nesting"), "{}", err);
+        let typed = format!("query Q($x: {}) {{ a }}", "[".repeat(100_000));
+        assert!(parse(&typed, LIMITS).unwrap_err().contains("Value nesting"));
+
+        // Aliases count as separate fields
+        let wide = format!("{{ {} }}", "x: graphs { id } ".repeat(10));
+        assert!(parse(&wide, LIMITS).is_ok());
+        let wider = format!("{{ {} }}", "x: graphs { id } ".repeat(11));
+        assert_eq!(
+            parse(&wider, LIMITS).unwrap_err(),
+            "Query selects more than 20 fields"
+        );
+    }
+}
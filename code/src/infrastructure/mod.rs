@@ -1,2 +1,4 @@
+pub mod contracts;
 pub mod grpc;
+pub mod http;
 pub mod persistence;
@@ -0,0 +1,177 @@
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use super::rocksdb_adapter::RocksDbAdapter;
+
+/// Prefix of every backup directory name
+const BACKUP_PREFIX: &str = "backup-";
+
+/// A backup directory produced by `BackupManager`
+#[derive(Debug, Clone, PartialEq)]
+pub struct BackupInfo {
+    pub backup_id: String,
+    pub path: PathBuf,
+    /// Unix timestamp in milliseconds
+    pub created_at_ms: i64,
+}
+
+/// Creates consistent online backups from RocksDB checkpoints and
+/// applies the retention rule (keep the newest `max_backups`)
+pub struct BackupManager {
+    db: Arc<RocksDbAdapter>,
+    backup_dir: PathBuf,
+    max_backups: usize,
+}
+
+impl BackupManager {
+    pub fn new<P: AsRef<Path>>(db: Arc<RocksDbAdapter>, backup_dir: P, max_backups: usize) -> Self {
+        Self {
+            db,
+            backup_dir: backup_dir.as_ref().to_path_buf(),
+            max_backups,
+        }
+    }
+
+    /// Creates a checkpoint of the live database and prunes old backups
+    pub fn create_backup(&self) -> Result<BackupInfo, Box<dyn Error>> {
+        fs::create_dir_all(&self.backup_dir)?;
+
+        let mut created_at_ms = chrono::Utc::now().timestamp_millis();
+        let mut path = self.backup_dir.join(Self::backup_id(created_at_ms));
+        while path.exists() {
+            created_at_ms += 1;
+            path = self.backup_dir.join(Self::backup_id(created_at_ms));
+        }
+
+        self.db.create_checkpoint(&path)?;
+        tracing::info!("💾 Backup created at {}", path.display());
+
+        let info = BackupInfo {
+            backup_id: Self::backup_id(created_at_ms),
+            path,
+            created_at_ms,
+        };
+
+        self.apply_retention()?;
+        Ok(info)
+    }
+
+    /// Lists available backups, oldest first
+    pub fn list_backups(&self) -> Result<Vec<BackupInfo>, Box<dyn Error>> {
+        list_backups_in(&self.backup_dir)
+    }
+
+    /// Deletes the oldest backups beyond `max_backups` (0 keeps everything)
+    pub fn apply_retention(&self) -> Result<Vec<BackupInfo>, Box<dyn Error>> {
+        let backups = self.list_backups()?;
+        if self.max_backups == 0 || backups.len() <= self.max_backups {
+            return Ok(Vec::new());
+        }
+
+        let excess = backups.len() - self.max_backups;
+        let removed: Vec<BackupInfo> = backups.into_iter().take(excess).collect();
+        for backup in &removed {
+            fs::remove_dir_all(&backup.path)?;
+            tracing::info!("🗑️  Removed old backup {}", backup.backup_id);
+        }
+
+        Ok(removed)
+    }
+
+    fn backup_id(created_at_ms: i64) -> String {
+        format!("{}{:015}", BACKUP_PREFIX, created_at_ms)
+    }
+}
+
+/// Lists backups found in a directory, oldest first
+pub fn list_backups_in<P: AsRef<Path>>(backup_dir: P) -> Result<Vec<BackupInfo>, Box<dyn Error>> {
+    let backup_dir = backup_dir.as_ref();
+    if !backup_dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut backups = Vec::new();
+    for entry in fs::read_dir(backup_dir)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().to_string();
+        let created_at_ms = match name
+            .strip_prefix(BACKUP_PREFIX)
+            .and_then(|t| t.parse().ok())
+        {
+            Some(ts) => ts,
+            None => continue,
+        };
+
+        if entry.file_type()?.is_dir() {
+            backups.push(BackupInfo {
+                backup_id: name,
+                path: entry.path(),
+                created_at_ms,
+            });
+        }
+    }
+
+    backups.sort_by_key(|b| b.created_at_ms);
+    Ok(backups)
+}
+
+/// Copies a backup into `data_dir`. Fails if `data_dir` already exists.
+pub fn restore_backup<P: AsRef<Path>, Q: AsRef<Path>>(
+    backup_path: P,
+    data_dir: Q,
+) -> Result<(), Box<dyn Error>> {
+    let data_dir = data_dir.as_ref();
+    if data_dir.exists() {
+        return Err(format!("Target directory {} already exists", data_dir.display()).into());
+    }
+
+    copy_dir_all(backup_path.as_ref(), data_dir)?;
+    Ok(())
+}
+
+fn copy_dir_all(from: &Path, to: &Path) -> Result<(), Box<dyn Error>> {
+    fs::create_dir_all(to)?;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        let target = to.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_dir_all(&entry.path(), &target)?;
+        } else {
+            fs::copy(entry.path(), target)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_backup_retention_and_restore() {
+        let dir = tempdir().unwrap();
+        let db = Arc::new(RocksDbAdapter::new(dir.path().join("db")).unwrap());
+        let manager = BackupManager::new(db.clone(), dir.path().join("backups"), 2);
+
+        db.put("key", b"v1").unwrap();
+        let first = manager.create_backup().unwrap();
+        db.put("key", b"v2").unwrap();
+        manager.create_backup().unwrap();
+        let last = manager.create_backup().unwrap();
+
+        let backups = manager.list_backups().unwrap();
+        assert_eq!(backups.len(), 2);
+        assert!(!backups.contains(&first));
+        assert_eq!(backups.last(), Some(&last));
+
+        let restored_dir = dir.path().join("restored");
+        restore_backup(&last.path, &restored_dir).unwrap();
+        assert!(restore_backup(&last.path, &restored_dir).is_err());
+
+        let restored = RocksDbAdapter::new(&restored_dir).unwrap();
+        assert_eq!(restored.get("key").unwrap(), Some(b"v2".to_vec()));
+    }
+}
@@ -1,5 +1,9 @@
+pub mod backup;
+pub mod raft_store;
 pub mod repository;
 pub mod rocksdb_adapter;
 
+pub use backup::BackupManager;
+pub use raft_store::RaftStore;
 pub use repository::BlockchainRepositoryImpl;
 pub use rocksdb_adapter::RocksDbAdapter;
@@ -0,0 +1,178 @@
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::sync::Arc;
+
+use super::rocksdb_adapter::RocksDbAdapter;
+
+const HARD_STATE_KEY: &str = "raft:state";
+const APPLIED_KEY: &str = "raft:applied";
+const COMPACTED_KEY: &str = "raft:compacted";
+const LOG_PREFIX: &str = "raft:log:";
+
+/// One entry of the replicated Raft log
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RaftLogEntry {
+    pub term: u64,
+    /// Encoded state machine command (empty for a leader's no-op)
+    pub command: Vec<u8>,
+}
+
+/// Raft state that must survive restarts before answering any RPC
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RaftHardState {
+    pub term: u64,
+    pub voted_for: Option<u64>,
+}
+
+/// Last entry dropped from the front of the log once applied; the chains
+/// hold its effect, so only its index and term are kept
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct RaftCompaction {
+    pub index: u64,
+    pub term: u64,
+}
+
+/// Durable Raft log and hard state, stored under `raft:` keys
+pub struct RaftStore {
+    db: Arc<RocksDbAdapter>,
+}
+
+impl RaftStore {
+    pub fn new(db: Arc<RocksDbAdapter>) -> Self {
+        Self { db }
+    }
+
+    fn log_key(index: u64) -> String {
+        format!("{}{:020}", LOG_PREFIX, index)
+    }
+
+    pub fn load_hard_state(&self) -> Result<RaftHardState, Box<dyn Error>> {
+        match self.db.get(HARD_STATE_KEY)? {
+            Some(bytes) => Ok(bincode::deserialize(&bytes)?),
+            None => Ok(RaftHardState::default()),
+        }
+    }
+
+    pub fn save_hard_state(&self, state: &RaftHardState) -> Result<(), Box<dyn Error>> {
+        self.db.put(HARD_STATE_KEY, &bincode::serialize(state)?)
+    }
+
+    pub fn load_compaction(&self) -> Result<RaftCompaction, Box<dyn Error>> {
+        match self.db.get(COMPACTED_KEY)? {
+            Some(bytes) => Ok(bincode::deserialize(&bytes)?),
+            None => Ok(RaftCompaction::default()),
+        }
+    }
+
+    /// Loads the log kept after `compacted`; entry `i` of the result has
+    /// Raft index `compacted.index + i + 1`
+    pub fn load_log(
+        &self,
+        compacted: &RaftCompaction,
+    ) -> Result<Vec<RaftLogEntry>, Box<dyn Error>> {
+        let mut entries = Vec::new();
+        for (key, value) in self.db.get_entries_with_prefix(LOG_PREFIX)? {
+            let index: u64 = key[LOG_PREFIX.len()..]
+                .parse()
+                .map_err(|_| format!("Invalid Raft log key '{}'", key))?;
+            if index != compacted.index + entries.len() as u64 + 1 {
+                return Err(format!("Raft log has a gap before index {}", index).into());
+            }
+            entries.push(bincode::deserialize(&value)?);
+        }
+        Ok(entries)
+    }
+
+    /// Atomically replaces the log from `first_index` on: entries up to
+    /// `old_last_index` are removed and `entries` are written in their place
+    pub fn replace_log_from(
+        &self,
+        first_index: u64,
+        entries: &[RaftLogEntry],
+        old_last_index: u64,
+    ) -> Result<(), Box<dyn Error>> {
+        let deletes = (first_index..=old_last_index).map(Self::log_key).collect();
+        let mut puts = Vec::with_capacity(entries.len());
+        for (offset, entry) in entries.iter().enumerate() {
+            puts.push((
+                Self::log_key(first_index + offset as u64),
+                bincode::serialize(entry)?,
+            ));
+        }
+        self.db.batch_write(puts, deletes)
+    }
+
+    /// Atomically drops the entries after `previous` up to and including
+    /// `compaction.index`, and records the compaction
+    pub fn compact_log(
+        &self,
+        compaction: &RaftCompaction,
+        previous: u64,
+    ) -> Result<(), Box<dyn Error>> {
+        let deletes = (previous + 1..=compaction.index)
+            .map(Self::log_key)
+            .collect();
+        let puts = vec![(COMPACTED_KEY.to_string(), bincode::serialize(compaction)?)];
+        self.db.batch_write(puts, deletes)
+    }
+
+    pub fn load_applied(&self) -> Result<u64, Box<dyn Error>> {
+        match self.db.get(APPLIED_KEY)? {
+            Some(bytes) => Ok(u64::from_le_bytes(
+                bytes.try_into().map_err(|_| "Invalid Raft applied index")?,
+            )),
+            None => Ok(0),
+        }
+    }
+
+    pub fn save_applied(&self, index: u64) -> Result<(), Box<dyn Error>> {
+        self.db.put(APPLIED_KEY, &index.to_le_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn entry(term: u64, command: &str) -> RaftLogEntry {
+        RaftLogEntry {
+            term,
+            command: command.as_bytes().to_vec(),
+        }
+    }
+
+    #[test]
+    fn test_log_truncation_and_reload() {
+        let dir = tempdir().unwrap();
+        let db = Arc::new(RocksDbAdapter::new(dir.path()).unwrap());
+        let store = RaftStore::new(db);
+
+        store
+            .replace_log_from(1, &[entry(1, "a"), entry(1, "b"), entry(1, "c")], 0)
+            .unwrap();
+        // A new leader overwrites the conflicting suffix
+        store.replace_log_from(2, &[entry(2, "x")], 3).unwrap();
+        assert_eq!(
+            store.load_log(&RaftCompaction::default()).unwrap(),
+            vec![entry(1, "a"), entry(2, "x")]
+        );
+
+        let hard_state = RaftHardState {
+            term: 2,
+            voted_for: Some(3),
+        };
+        store.save_hard_state(&hard_state).unwrap();
+        store.save_applied(2).unwrap();
+        assert_eq!(store.load_hard_state().unwrap(), hard_state);
+        assert_eq!(store.load_applied().unwrap(), 2);
+
+        // Applied entries are dropped from the front; the log reloads after them
+        store.replace_log_from(3, &[entry(2, "y")], 2).unwrap();
+        let compaction = RaftCompaction { index: 2, term: 2 };
+        store.compact_log(&compaction, 0).unwrap();
+        assert_eq!(store.load_compaction().unwrap(), compaction);
+        assert_eq!(store.load_log(&compaction).unwrap(), vec![entry(2, "y")]);
+        assert!(store.load_log(&RaftCompaction::default()).is_err());
+    }
+}
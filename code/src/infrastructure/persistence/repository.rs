@@ -1,12 +1,237 @@
 use async_trait::async_trait;
+use std::collections::{BTreeMap, BTreeSet};
 use std::error::Error;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
+use serde::Deserialize;
+
+use crate::domain::asset::ASSET_KEY_PREFIX;
+use crate::domain::search::index_tokens;
 use crate::domain::traits::BlockchainRepository;
-use crate::domain::{Block, BlockchainGraph};
+use crate::domain::{
+    AssetRecord, Block, BlockchainGraph, Consensus, GraphType, PayloadIndex, Proposal,
+    ProposalStatus, Reorg, SearchQuery, StateEntry, StateSwitch, StateWrites, VerifiedCheckpoint,
+    WorldState,
+};
+
+use super::rocksdb_adapter::{KeyValue, RocksDbAdapter};
+
+/// A graph id with its metadata, or why the stored record could not be decoded
+pub type VerifiedGraphRead = (String, Result<BlockchainGraph, String>);
+
+/// Index entries written per batch while backfilling a payload, time or
+/// full-text index
+const INDEX_BACKFILL_BATCH: usize = 1000;
+
+/// Maps a timestamp to a `u64` with the same order (negative ones included)
+fn time_order(timestamp: i64) -> u64 {
+    (timestamp as u64) ^ (1 << 63)
+}
+
+/// Block layout stored before blocks carried an authority signature
+#[derive(Deserialize)]
+struct LegacyBlock {
+    hash: String,
+    previous_hash: String,
+    timestamp: i64,
+    data: String,
+    nonce: u64,
+    height: u64,
+    graph_id: String,
+    cross_references: Vec<String>,
+}
+
+/// Block layout stored before blocks carried endorsements
+#[derive(Deserialize)]
+struct SignedBlock {
+    hash: String,
+    previous_hash: String,
+    timestamp: i64,
+    data: String,
+    nonce: u64,
+    height: u64,
+    graph_id: String,
+    cross_references: Vec<String>,
+    signer: String,
+    signature: String,
+}
+
+/// Decodes a stored block, accepting the layouts without endorsements or
+/// signer fields
+fn decode_block(bytes: &[u8]) -> Result<Block, Box<dyn Error>> {
+    if let Ok(block) = bincode::deserialize::<Block>(bytes) {
+        return Ok(block);
+    }
+    if let Ok(signed) = bincode::deserialize::<SignedBlock>(bytes) {
+        return Ok(Block {
+            hash: signed.hash,
+            previous_hash: signed.previous_hash,
+            timestamp: signed.timestamp,
+            data: signed.data,
+            nonce: signed.nonce,
+            height: signed.height,
+            graph_id: signed.graph_id,
+            cross_references: signed.cross_references,
+            signer: signed.signer,
+            signature: signed.signature,
+            endorsements: None,
+        });
+    }
+    let legacy: LegacyBlock = bincode::deserialize(bytes)?;
+    Ok(Block {
+        hash: legacy.hash,
+        previous_hash: legacy.previous_hash,
+        timestamp: legacy.timestamp,
+        data: legacy.data,
+        nonce: legacy.nonce,
+        height: legacy.height,
+        graph_id: legacy.graph_id,
+        cross_references: legacy.cross_references,
+        signer: String::new(),
+        signature: String::new(),
+        endorsements: None,
+    })
+}
+
+/// Proposal layout stored before proposals carried a nonce
+#[derive(Deserialize)]
+struct LegacyProposal {
+    id: String,
+    graph_id: String,
+    data: String,
+    cross_references: Vec<String>,
+    created_at_ms: i64,
+    expires_at_ms: i64,
+    endorsements: BTreeMap<String, String>,
+    status: ProposalStatus,
+    block_hash: String,
+    message: String,
+}
+
+/// Decodes a stored proposal; older ones get an empty nonce and can no
+/// longer be committed
+fn decode_proposal(bytes: &[u8]) -> Result<Proposal, Box<dyn Error>> {
+    if let Ok(proposal) = bincode::deserialize::<Proposal>(bytes) {
+        return Ok(proposal);
+    }
+    let legacy: LegacyProposal = bincode::deserialize(bytes)?;
+    Ok(Proposal {
+        id: legacy.id,
+        graph_id: legacy.graph_id,
+        data: legacy.data,
+        cross_references: legacy.cross_references,
+        created_at_ms: legacy.created_at_ms,
+        expires_at_ms: legacy.expires_at_ms,
+        endorsements: legacy.endorsements,
+        status: legacy.status,
+        block_hash: legacy.block_hash,
+        message: legacy.message,
+        nonce: String::new(),
+    })
+}
+
+/// Graph layout stored with bincode before metadata moved to JSON
+#[derive(Deserialize)]
+struct BincodeGraph {
+    id: String,
+    graph_type: GraphType,
+    description: String,
+    created_at: i64,
+    difficulty: usize,
+    consensus: Consensus,
+}
 
-use super::rocksdb_adapter::RocksDbAdapter;
+/// Graph layout stored before graphs had a consensus mode
+#[derive(Deserialize)]
+struct LegacyGraph {
+    id: String,
+    graph_type: GraphType,
+    description: String,
+    created_at: i64,
+    difficulty: usize,
+}
+
+/// Decodes stored graph metadata. Graphs are stored as JSON so optional
+/// fields can be added; older bincode records are still read (graphs saved
+/// before consensus modes existed use proof of work).
+fn decode_graph(bytes: &[u8]) -> Result<BlockchainGraph, Box<dyn Error>> {
+    if let Ok(graph) = serde_json::from_slice::<BlockchainGraph>(bytes) {
+        return Ok(graph);
+    }
+    let old = match bincode::deserialize::<BincodeGraph>(bytes) {
+        Ok(graph) => graph,
+        Err(_) => {
+            let legacy: LegacyGraph = bincode::deserialize(bytes)?;
+            BincodeGraph {
+                id: legacy.id,
+                graph_type: legacy.graph_type,
+                description: legacy.description,
+                created_at: legacy.created_at,
+                difficulty: legacy.difficulty,
+                consensus: Consensus::ProofOfWork,
+            }
+        }
+    };
+    let mut graph = BlockchainGraph::new(old.id, old.graph_type, old.description, old.difficulty)
+        .with_consensus(old.consensus);
+    graph.created_at = old.created_at;
+    graph.chain.clear();
+    Ok(graph)
+}
+
+/// Key/value counts for one key prefix
+#[derive(Debug, Clone, Default)]
+pub struct PrefixStats {
+    pub keys: u64,
+    pub bytes: u64,
+}
+
+/// Storage statistics gathered by scanning the whole database
+#[derive(Debug, Clone, Default)]
+pub struct StorageStats {
+    pub total_keys: u64,
+    pub total_bytes: u64,
+    /// Stats per key prefix (`block`, `block_hash`, `latest`, ...)
+    pub prefixes: BTreeMap<String, PrefixStats>,
+    /// Number of stored blocks per graph
+    pub blocks_per_graph: BTreeMap<String, u64>,
+    pub estimated_live_data_size: Option<u64>,
+    pub total_sst_files_size: Option<u64>,
+}
+
+/// Outcome of rebuilding `graph_list` and `latest:` keys from block data
+#[derive(Debug, Clone, Default)]
+pub struct RepairReport {
+    /// Graph list before and after the repair
+    pub previous_graph_list: Vec<String>,
+    pub graph_list: Vec<String>,
+    /// Latest pointers that were changed: (graph_id, old height, new height)
+    pub latest_fixed: Vec<(String, Option<u64>, u64)>,
+    /// Graphs whose stored heights are not contiguous: (graph_id, first missing height)
+    pub gaps: Vec<(String, u64)>,
+    /// Graph ids that have blocks but no `graph:` metadata
+    pub orphan_block_graphs: Vec<String>,
+    pub dry_run: bool,
+}
+
+/// A stored main-chain block read back by the integrity scrubber
+#[derive(Debug, Clone)]
+pub struct StoredBlock {
+    /// Height in the block's `block:` key
+    pub key_height: u64,
+    /// The decoded block, or why it could not be decoded
+    pub block: Result<Block, String>,
+    /// Height the block's `block_hash:` entry points to
+    pub indexed_height: Option<u64>,
+}
+
+impl RepairReport {
+    /// Returns true if the repair changed (or would change) anything
+    pub fn has_changes(&self) -> bool {
+        self.previous_graph_list != self.graph_list || !self.latest_fixed.is_empty()
+    }
+}
 
 /// Repository implementation using RocksDB (Repository Pattern)
 /// Provides abstraction over data storage
@@ -38,6 +263,216 @@ impl BlockchainRepositoryImpl {
         format!("latest:{}", graph_id)
     }
 
+    /// Generates key for a side-branch block
+    fn side_block_key(graph_id: &str, hash: &str) -> String {
+        format!("side:{}:{}", graph_id, hash)
+    }
+
+    /// Generates key for an endorsement proposal
+    fn proposal_key(graph_id: &str, id: &str) -> String {
+        format!("proposal:{}:{}", graph_id, id)
+    }
+
+    /// Generates key for a world-state entry
+    fn state_key(graph_id: &str, key: &str) -> String {
+        format!("state:{}:{}", graph_id, key)
+    }
+
+    /// Generates key for one version of a world-state entry
+    fn state_history_key(graph_id: &str, key: &str, height: u64) -> String {
+        format!("state_history:{}:{}:{:020}", graph_id, key, height)
+    }
+
+    /// Generates key for the write set of a block
+    fn block_writes_key(graph_id: &str, height: u64) -> String {
+        format!("state_writes:{}:{:020}", graph_id, height)
+    }
+
+    /// Puts for a block's write set: its history versions and the write set itself
+    fn write_set_puts(
+        graph_id: &str,
+        height: u64,
+        writes: &StateWrites,
+    ) -> Result<Vec<KeyValue>, Box<dyn Error>> {
+        let mut puts = Vec::new();
+        if writes.is_empty() {
+            return Ok(puts);
+        }
+        for (key, entry) in writes {
+            puts.push((
+                Self::state_history_key(graph_id, key, height),
+                bincode::serialize(entry)?,
+            ));
+        }
+        puts.push((
+            Self::block_writes_key(graph_id, height),
+            bincode::serialize(writes)?,
+        ));
+        Ok(puts)
+    }
+
+    /// Reads the whole world state of a graph
+    fn load_state(&self, graph_id: &str) -> Result<WorldState, Box<dyn Error>> {
+        let prefix = format!("state:{}:", graph_id);
+        let mut state = WorldState::new();
+        for (key, value) in self.db.get_entries_with_prefix(&prefix)? {
+            if let Some(key) = key.strip_prefix(&prefix) {
+                state.insert(key.to_string(), bincode::deserialize(&value)?);
+            }
+        }
+        Ok(state)
+    }
+
+    /// Generates key for the timestamp index, which orders blocks by time,
+    /// then height
+    fn time_key(graph_id: &str, timestamp: i64, height: u64) -> String {
+        format!(
+            "time:{}:{:020}:{:020}",
+            graph_id,
+            time_order(timestamp),
+            height
+        )
+    }
+
+    /// Generates key marking a graph's timestamp index as built
+    fn time_indexed_key(graph_id: &str) -> String {
+        format!("time_indexed:{}", graph_id)
+    }
+
+    /// Height at the end of a `time:` key
+    fn time_key_height(key: &str) -> Result<u64, Box<dyn Error>> {
+        let (_, height) = key.rsplit_once(':').ok_or("Invalid time index key")?;
+        Ok(height.parse()?)
+    }
+
+    /// Generates key marking a graph's full-text index as enabled
+    fn search_enabled_key(graph_id: &str) -> String {
+        format!("search_enabled:{}", graph_id)
+    }
+
+    /// Prefix of the full-text entries of one token (hex encoded, like
+    /// payload index values)
+    fn search_token_prefix(graph_id: &str, token: &str) -> String {
+        format!("search:{}:{}:", graph_id, hex::encode(token))
+    }
+
+    /// Keys of the full-text entries of a block
+    fn search_entry_keys(graph_id: &str, block: &Block) -> Vec<String> {
+        index_tokens(&block.data)
+            .iter()
+            .map(|token| {
+                format!(
+                    "{}{:020}",
+                    Self::search_token_prefix(graph_id, token),
+                    block.height
+                )
+            })
+            .collect()
+    }
+
+    /// Writes the full-text entries of the stored main chain
+    async fn backfill_search(&self, graph_id: &str) -> Result<u64, Box<dyn Error>> {
+        let latest = self
+            .read_height(&Self::latest_block_key(graph_id))?
+            .unwrap_or_default();
+        let mut indexed = 0;
+        let mut puts = Vec::new();
+        for height in 0..=latest {
+            let block = match self.get_block_by_height(graph_id, height).await? {
+                Some(block) => block,
+                None => continue,
+            };
+            let keys = Self::search_entry_keys(graph_id, &block);
+            if !keys.is_empty() {
+                indexed += 1;
+            }
+            puts.extend(keys.into_iter().map(|key| (key, Vec::new())));
+            if puts.len() >= INDEX_BACKFILL_BATCH {
+                self.db.batch_write(std::mem::take(&mut puts), Vec::new())?;
+            }
+        }
+        self.db.batch_write(puts, Vec::new())?;
+        Ok(indexed)
+    }
+
+    /// Prefix of the ownership index entries of one owner; asset ids are
+    /// hex encoded, so they may contain any character
+    fn asset_owner_prefix(graph_id: &str, owner: &str) -> String {
+        format!("asset_owner:{}:{}:", graph_id, owner)
+    }
+
+    /// Moves the ownership index entry of an asset whose record changes
+    /// to `entry` (`None` removes it): the previous holder's entry is
+    /// deleted and the new holder's written
+    fn asset_owner_updates(
+        &self,
+        graph_id: &str,
+        key: &str,
+        entry: Option<&StateEntry>,
+        puts: &mut Vec<KeyValue>,
+        deletes: &mut Vec<String>,
+    ) -> Result<(), Box<dyn Error>> {
+        let asset_id = match key.strip_prefix(ASSET_KEY_PREFIX) {
+            Some(asset_id) => hex::encode(asset_id),
+            None => return Ok(()),
+        };
+        let holder = |value: &[u8]| {
+            AssetRecord::from_bytes(value).and_then(|r| r.holder().map(str::to_string))
+        };
+        if let Some(bytes) = self.db.get(&Self::state_key(graph_id, key))? {
+            let previous: StateEntry = bincode::deserialize(&bytes)?;
+            if let Some(owner) = holder(&previous.value) {
+                deletes.push(format!(
+                    "{}{}",
+                    Self::asset_owner_prefix(graph_id, &owner),
+                    asset_id
+                ));
+            }
+        }
+        if let Some(owner) = entry.filter(|e| !e.deleted).and_then(|e| holder(&e.value)) {
+            puts.push((
+                format!("{}{}", Self::asset_owner_prefix(graph_id, &owner), asset_id),
+                Vec::new(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Generates key for a payload index definition
+    fn index_def_key(graph_id: &str, name: &str) -> String {
+        format!("index_def:{}:{}", graph_id, name)
+    }
+
+    /// Prefix of the entries of a payload index for one value (hex encoded,
+    /// so values may contain any character)
+    fn index_value_prefix(graph_id: &str, name: &str, value: &str) -> String {
+        format!("index:{}:{}:{}:", graph_id, name, hex::encode(value))
+    }
+
+    /// Keys of the index entries of a block
+    fn index_entry_keys(graph_id: &str, indexes: &[PayloadIndex], block: &Block) -> Vec<String> {
+        let mut keys = Vec::new();
+        for index in indexes {
+            for value in index.values(&block.data) {
+                keys.push(format!(
+                    "{}{:020}",
+                    Self::index_value_prefix(graph_id, &index.name, &value),
+                    block.height
+                ));
+            }
+        }
+        keys
+    }
+
+    fn load_indexes(&self, graph_id: &str) -> Result<Vec<PayloadIndex>, Box<dyn Error>> {
+        let prefix = format!("index_def:{}:", graph_id);
+        let mut indexes = Vec::new();
+        for value in self.db.get_values_with_prefix(&prefix)? {
+            indexes.push(bincode::deserialize::<PayloadIndex>(&value)?);
+        }
+        Ok(indexes)
+    }
+
     /// Generates key for graph metadata
     fn graph_key(graph_id: &str) -> String {
         format!("graph:{}", graph_id)
@@ -47,24 +482,281 @@ impl BlockchainRepositoryImpl {
     fn graph_list_key() -> String {
         "graph_list".to_string()
     }
+
+    fn verified_checkpoint_key(graph_id: &str) -> String {
+        format!("verified:{}", graph_id)
+    }
+
+    /// Key of the last integrity scrub report
+    fn scrub_report_key() -> String {
+        "scrub_report".to_string()
+    }
+
+    /// Parses a `block:{graph_id}:{height:020}` key into its graph id and height
+    fn parse_block_key(key: &str) -> Option<(&str, u64)> {
+        let rest = key.strip_prefix("block:")?;
+        let (graph_id, height) = rest.rsplit_once(':')?;
+        if height.len() != 20 {
+            return None;
+        }
+        Some((graph_id, height.parse().ok()?))
+    }
+
+    /// Reads the stored graph list directly from the database
+    fn read_graph_list(&self) -> Result<Vec<String>, Box<dyn Error>> {
+        match self.db.get(&Self::graph_list_key())? {
+            Some(bytes) => Ok(bincode::deserialize(&bytes).unwrap_or_default()),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// Reads a stored height pointer (`latest:` or `block_hash:` value)
+    fn read_height(&self, key: &str) -> Result<Option<u64>, Box<dyn Error>> {
+        match self.db.get(key)? {
+            Some(bytes) => Ok(Some(u64::from_le_bytes(
+                bytes.try_into().map_err(|_| "Invalid height")?,
+            ))),
+            None => Ok(None),
+        }
+    }
+
+    /// Scans the whole database and summarizes key and byte counts
+    pub fn storage_stats(&self) -> Result<StorageStats, Box<dyn Error>> {
+        let mut stats = StorageStats::default();
+
+        for (key, value) in self.db.get_entries_with_prefix("")? {
+            let bytes = (key.len() + value.len()) as u64;
+            stats.total_keys += 1;
+            stats.total_bytes += bytes;
+
+            let prefix = key.split(':').next().unwrap_or_default().to_string();
+            let entry = stats.prefixes.entry(prefix).or_default();
+            entry.keys += 1;
+            entry.bytes += bytes;
+
+            if let Some((graph_id, _)) = Self::parse_block_key(&key) {
+                *stats
+                    .blocks_per_graph
+                    .entry(graph_id.to_string())
+                    .or_default() += 1;
+            }
+        }
+
+        stats.estimated_live_data_size = self.db.property_int("rocksdb.estimate-live-data-size")?;
+        stats.total_sst_files_size = self.db.property_int("rocksdb.total-sst-files-size")?;
+
+        Ok(stats)
+    }
+
+    /// Rebuilds `graph_list` from `graph:` metadata keys and every `latest:`
+    /// pointer from the highest contiguous stored block height.
+    /// With `dry_run` nothing is written.
+    pub async fn repair_indexes(&self, dry_run: bool) -> Result<RepairReport, Box<dyn Error>> {
+        let mut report = RepairReport {
+            previous_graph_list: self.read_graph_list()?,
+            dry_run,
+            ..Default::default()
+        };
+
+        // Graph ids come from metadata keys, keeping the previous order where possible
+        let mut graph_ids: Vec<String> = self
+            .db
+            .get_keys_with_prefix("graph:")?
+            .into_iter()
+            .filter_map(|key| key.strip_prefix("graph:").map(str::to_string))
+            .collect();
+        graph_ids.sort_by_key(|id| {
+            report
+                .previous_graph_list
+                .iter()
+                .position(|existing| existing == id)
+                .unwrap_or(usize::MAX)
+        });
+        report.graph_list = graph_ids;
+
+        // Collect stored heights per graph from block keys
+        let mut heights: BTreeMap<String, Vec<u64>> = BTreeMap::new();
+        for key in self.db.get_keys_with_prefix("block:")? {
+            if let Some((graph_id, height)) = Self::parse_block_key(&key) {
+                heights
+                    .entry(graph_id.to_string())
+                    .or_default()
+                    .push(height);
+            }
+        }
+
+        let mut writes = Vec::new();
+        for (graph_id, mut graph_heights) in heights {
+            if !report.graph_list.contains(&graph_id) {
+                report.orphan_block_graphs.push(graph_id.clone());
+                continue;
+            }
+
+            graph_heights.sort_unstable();
+            let mut tip = None;
+            for (expected, height) in graph_heights.iter().enumerate() {
+                if *height != expected as u64 {
+                    report.gaps.push((graph_id.clone(), expected as u64));
+                    break;
+                }
+                tip = Some(*height);
+            }
+
+            let tip = match tip {
+                Some(tip) => tip,
+                None => continue,
+            };
+
+            let latest_key = Self::latest_block_key(&graph_id);
+            let current = self.read_height(&latest_key)?;
+            if current != Some(tip) {
+                report.latest_fixed.push((graph_id.clone(), current, tip));
+                writes.push((latest_key, tip.to_le_bytes().to_vec()));
+            }
+        }
+
+        if report.previous_graph_list != report.graph_list {
+            writes.push((
+                Self::graph_list_key(),
+                bincode::serialize(&report.graph_list)?,
+            ));
+        }
+
+        if !dry_run && !writes.is_empty() {
+            self.db.batch_put(writes)?;
+            self.cache.write().await.clear();
+        }
+
+        Ok(report)
+    }
+
+    /// Metadata of every stored graph, read from disk with RocksDB checksums
+    /// verified and without blocks or the cache; undecodable records are
+    /// returned as errors
+    pub fn read_graphs_verified(&self) -> Result<Vec<VerifiedGraphRead>, Box<dyn Error>> {
+        let entries = self.db.scan_prefix_verified("graph:", "", usize::MAX)?;
+        Ok(entries
+            .into_iter()
+            .map(|(key, value)| {
+                let graph_id = key.strip_prefix("graph:").unwrap_or_default().to_string();
+                (graph_id, decode_graph(&value).map_err(|e| e.to_string()))
+            })
+            .collect())
+    }
+
+    /// Up to `limit` stored main-chain blocks of a graph from height `start`,
+    /// read with RocksDB checksums verified, each with its hash index entry
+    pub fn read_blocks_verified(
+        &self,
+        graph_id: &str,
+        start: u64,
+        limit: usize,
+    ) -> Result<Vec<StoredBlock>, Box<dyn Error>> {
+        let prefix = format!("block:{}:", graph_id);
+        let entries =
+            self.db
+                .scan_prefix_verified(&prefix, &Self::block_key(graph_id, start), limit)?;
+        let mut blocks = Vec::with_capacity(entries.len());
+        for (key, value) in entries {
+            let key_height = match Self::parse_block_key(&key) {
+                Some((id, height)) if id == graph_id => height,
+                _ => continue,
+            };
+            let block = decode_block(&value).map_err(|e| e.to_string());
+            let indexed_height = match &block {
+                Ok(block) => match self
+                    .db
+                    .get_verified(&Self::block_hash_key(graph_id, &block.hash))?
+                {
+                    Some(bytes) => bytes.try_into().ok().map(u64::from_le_bytes),
+                    None => None,
+                },
+                Err(_) => None,
+            };
+            blocks.push(StoredBlock {
+                key_height,
+                block,
+                indexed_height,
+            });
+        }
+        Ok(blocks)
+    }
+
+    /// Height the `latest:` pointer of a graph names
+    pub fn stored_tip_height(&self, graph_id: &str) -> Result<Option<u64>, Box<dyn Error>> {
+        self.read_height(&Self::latest_block_key(graph_id))
+    }
+
+    /// Stores the JSON report of the last integrity scrub
+    pub fn save_scrub_report(&self, report: &[u8]) -> Result<(), Box<dyn Error>> {
+        self.db.put(&Self::scrub_report_key(), report)
+    }
+
+    /// JSON report of the last integrity scrub, if one ran
+    pub fn load_scrub_report(&self) -> Result<Option<Vec<u8>>, Box<dyn Error>> {
+        self.db.get(&Self::scrub_report_key())
+    }
 }
 
 #[async_trait]
 impl BlockchainRepository for BlockchainRepositoryImpl {
     async fn save_block(&self, graph_id: &str, block: &Block) -> Result<(), Box<dyn Error>> {
-        let serialized = bincode::serialize(block)?;
+        self.save_block_with_state(graph_id, block, &StateWrites::new())
+            .await
+    }
 
-        // Save block by height
-        let block_key = Self::block_key(graph_id, block.height);
-        self.db.put(&block_key, &serialized)?;
+    async fn save_block_with_state(
+        &self,
+        graph_id: &str,
+        block: &Block,
+        writes: &StateWrites,
+    ) -> Result<(), Box<dyn Error>> {
+        let serialized = bincode::serialize(block)?;
+        let mut puts = Self::write_set_puts(graph_id, block.height, writes)?;
+        let indexes = self.load_indexes(graph_id)?;
+        for key in Self::index_entry_keys(graph_id, &indexes, block) {
+            puts.push((key, Vec::new()));
+        }
+        if self.db.exists(&Self::search_enabled_key(graph_id))? {
+            for key in Self::search_entry_keys(graph_id, block) {
+                puts.push((key, Vec::new()));
+            }
+        }
+        let mut deletes = Vec::new();
+        for (key, entry) in writes {
+            self.asset_owner_updates(graph_id, key, Some(entry), &mut puts, &mut deletes)?;
+            if entry.deleted {
+                deletes.push(Self::state_key(graph_id, key));
+            } else {
+                puts.push((Self::state_key(graph_id, key), bincode::serialize(entry)?));
+            }
+        }
 
-        // Save hash index for quick lookup
-        let hash_key = Self::block_hash_key(graph_id, &block.hash);
-        self.db.put(&hash_key, &block.height.to_le_bytes())?;
+        puts.push((
+            Self::time_key(graph_id, block.timestamp, block.height),
+            Vec::new(),
+        ));
 
-        // Update latest block pointer
-        let latest_key = Self::latest_block_key(graph_id);
-        self.db.put(&latest_key, &block.height.to_le_bytes())?;
+        // Block, hash index, latest pointer, world state and index entries
+        // are written in one batch so an interrupted write never
+        // leaves them out of sync
+        puts.extend([
+            // Save block by height
+            (Self::block_key(graph_id, block.height), serialized),
+            // Save hash index for quick lookup
+            (
+                Self::block_hash_key(graph_id, &block.hash),
+                block.height.to_le_bytes().to_vec(),
+            ),
+            // Update latest block pointer
+            (
+                Self::latest_block_key(graph_id),
+                block.height.to_le_bytes().to_vec(),
+            ),
+        ]);
+        self.db.batch_write(puts, deletes)?;
+        // The cached copy no longer has the whole chain
+        self.cache.write().await.remove(graph_id);
 
         Ok(())
     }
@@ -105,8 +797,7 @@ impl BlockchainRepository for BlockchainRepositoryImpl {
             None => return Ok(None),
         };
 
-        let block: Block = bincode::deserialize(&data)?;
-        Ok(Some(block))
+        Ok(Some(decode_block(&data)?))
     }
 
     async fn get_blocks_range(
@@ -129,7 +820,7 @@ impl BlockchainRepository for BlockchainRepositoryImpl {
     async fn save_graph(&self, graph: &BlockchainGraph) -> Result<(), Box<dyn Error>> {
         tracing::info!("Starting save_graph for graph_id: {}", graph.id);
 
-        let serialized = bincode::serialize(graph)?;
+        let serialized = serde_json::to_vec(graph)?;
         tracing::info!("Serialized graph metadata for {}", graph.id);
 
         let graph_key = Self::graph_key(&graph.id);
@@ -151,7 +842,10 @@ impl BlockchainRepository for BlockchainRepositoryImpl {
             match bincode::deserialize(&bytes) {
                 Ok(ids) => ids,
                 Err(e) => {
-                    tracing::error!("Failed to deserialize graph list, resetting list. Error: {}", e);
+                    tracing::error!(
+                        "Failed to deserialize graph list, resetting list. Error: {}",
+                        e
+                    );
                     Vec::new()
                 }
             }
@@ -192,15 +886,20 @@ impl BlockchainRepository for BlockchainRepositoryImpl {
             None => return Ok(None),
         };
 
-        let mut graph: BlockchainGraph = bincode::deserialize(&data)?;
+        let mut graph = decode_graph(&data)?;
 
         // Load all blocks into graph
         let latest_block = self.get_latest_block(graph_id).await?;
         if let Some(latest) = latest_block {
-            tracing::trace!("get_graph: loading blocks 0..={} for {}", latest.height, graph_id);
+            tracing::trace!(
+                "get_graph: loading blocks 0..={} for {}",
+                latest.height,
+                graph_id
+            );
             let blocks = self.get_blocks_range(graph_id, 0, latest.height).await?;
             graph.load_blocks(blocks);
         }
+        graph.state = Arc::new(self.load_state(graph_id)?);
 
         // Update cache
         let mut cache = self.cache.write().await;
@@ -225,14 +924,21 @@ impl BlockchainRepository for BlockchainRepositoryImpl {
                 Vec::new()
             }
         };
-        tracing::debug!("list_graphs: found {} ids: {:?}", graph_ids.len(), graph_ids);
+        tracing::debug!(
+            "list_graphs: found {} ids: {:?}",
+            graph_ids.len(),
+            graph_ids
+        );
         let mut graphs = Vec::new();
 
         for id in graph_ids {
             if let Some(graph) = self.get_graph(&id).await? {
                 graphs.push(graph);
             } else {
-                tracing::warn!("list_graphs: id {} present in list but graph not found in DB", id);
+                tracing::warn!(
+                    "list_graphs: id {} present in list but graph not found in DB",
+                    id
+                );
             }
         }
 
@@ -245,4 +951,515 @@ impl BlockchainRepository for BlockchainRepositoryImpl {
         tracing::debug!("graph_exists: key {} exists={} ", graph_key, exists);
         Ok(exists)
     }
+
+    async fn save_side_block(&self, graph_id: &str, block: &Block) -> Result<(), Box<dyn Error>> {
+        let serialized = bincode::serialize(block)?;
+        self.db
+            .put(&Self::side_block_key(graph_id, &block.hash), &serialized)
+    }
+
+    async fn get_side_blocks(&self, graph_id: &str) -> Result<Vec<Block>, Box<dyn Error>> {
+        let prefix = format!("side:{}:", graph_id);
+        let mut blocks = Vec::new();
+        for value in self.db.get_values_with_prefix(&prefix)? {
+            blocks.push(decode_block(&value)?);
+        }
+        Ok(blocks)
+    }
+
+    async fn delete_side_blocks(
+        &self,
+        graph_id: &str,
+        hashes: &[String],
+    ) -> Result<(), Box<dyn Error>> {
+        let deletes = hashes
+            .iter()
+            .map(|hash| Self::side_block_key(graph_id, hash))
+            .collect();
+        self.db.batch_write(Vec::new(), deletes)
+    }
+
+    async fn reorganize(
+        &self,
+        graph_id: &str,
+        reorg: &Reorg,
+        state: &StateSwitch,
+    ) -> Result<(), Box<dyn Error>> {
+        let new_tip = reorg
+            .connected
+            .last()
+            .ok_or("Reorganization without blocks")?;
+        let mut puts = Vec::new();
+        let mut deletes = Vec::new();
+
+        // History of the old branch is dropped, the new branch's is written
+        for block in &reorg.disconnected {
+            for key in self.get_block_writes(graph_id, block.height).await?.keys() {
+                deletes.push(Self::state_history_key(graph_id, key, block.height));
+            }
+            deletes.push(Self::block_writes_key(graph_id, block.height));
+        }
+        for (block, writes) in reorg.connected.iter().zip(&state.connected) {
+            puts.extend(Self::write_set_puts(graph_id, block.height, writes)?);
+        }
+        for (key, entry) in &state.diff {
+            self.asset_owner_updates(graph_id, key, entry.as_ref(), &mut puts, &mut deletes)?;
+            match entry {
+                Some(entry) => {
+                    puts.push((Self::state_key(graph_id, key), bincode::serialize(entry)?))
+                }
+                None => deletes.push(Self::state_key(graph_id, key)),
+            }
+        }
+
+        // Payload, time and full-text index entries follow the main chain
+        let indexes = self.load_indexes(graph_id)?;
+        let search = self.db.exists(&Self::search_enabled_key(graph_id))?;
+        for block in &reorg.disconnected {
+            deletes.extend(Self::index_entry_keys(graph_id, &indexes, block));
+            deletes.push(Self::time_key(graph_id, block.timestamp, block.height));
+            if search {
+                deletes.extend(Self::search_entry_keys(graph_id, block));
+            }
+        }
+        for block in &reorg.connected {
+            puts.extend(
+                Self::index_entry_keys(graph_id, &indexes, block)
+                    .into_iter()
+                    .map(|key| (key, Vec::new())),
+            );
+            puts.push((
+                Self::time_key(graph_id, block.timestamp, block.height),
+                Vec::new(),
+            ));
+            if search {
+                puts.extend(
+                    Self::search_entry_keys(graph_id, block)
+                        .into_iter()
+                        .map(|key| (key, Vec::new())),
+                );
+            }
+        }
+
+        // The old branch leaves the height and hash indexes and becomes a side branch
+        for block in &reorg.disconnected {
+            deletes.push(Self::block_hash_key(graph_id, &block.hash));
+            if block.height > new_tip.height {
+                deletes.push(Self::block_key(graph_id, block.height));
+            }
+            puts.push((
+                Self::side_block_key(graph_id, &block.hash),
+                bincode::serialize(block)?,
+            ));
+        }
+        for block in &reorg.connected {
+            deletes.push(Self::side_block_key(graph_id, &block.hash));
+            puts.push((
+                Self::block_key(graph_id, block.height),
+                bincode::serialize(block)?,
+            ));
+            puts.push((
+                Self::block_hash_key(graph_id, &block.hash),
+                block.height.to_le_bytes().to_vec(),
+            ));
+        }
+        puts.push((
+            Self::latest_block_key(graph_id),
+            new_tip.height.to_le_bytes().to_vec(),
+        ));
+
+        self.db.batch_write(puts, deletes)?;
+        self.cache.write().await.remove(graph_id);
+        Ok(())
+    }
+
+    async fn get_state(
+        &self,
+        graph_id: &str,
+        key: &str,
+    ) -> Result<Option<StateEntry>, Box<dyn Error>> {
+        match self.db.get(&Self::state_key(graph_id, key))? {
+            Some(bytes) => Ok(Some(bincode::deserialize(&bytes)?)),
+            None => Ok(None),
+        }
+    }
+
+    async fn get_state_history(
+        &self,
+        graph_id: &str,
+        key: &str,
+    ) -> Result<Vec<StateEntry>, Box<dyn Error>> {
+        let prefix = format!("state_history:{}:{}:", graph_id, key);
+        let mut history = Vec::new();
+        for (stored_key, value) in self.db.get_entries_with_prefix(&prefix)? {
+            // Skip longer keys sharing the prefix (e.g. "a:b" when reading "a")
+            let height = &stored_key[prefix.len()..];
+            if height.len() == 20 && height.bytes().all(|b| b.is_ascii_digit()) {
+                history.push(bincode::deserialize::<StateEntry>(&value)?);
+            }
+        }
+        history.sort_by_key(|entry| entry.version);
+        Ok(history)
+    }
+
+    async fn get_block_writes(
+        &self,
+        graph_id: &str,
+        height: u64,
+    ) -> Result<StateWrites, Box<dyn Error>> {
+        match self.db.get(&Self::block_writes_key(graph_id, height))? {
+            Some(bytes) => Ok(bincode::deserialize(&bytes)?),
+            None => Ok(StateWrites::new()),
+        }
+    }
+
+    async fn save_proposal(&self, proposal: &Proposal) -> Result<(), Box<dyn Error>> {
+        let serialized = bincode::serialize(proposal)?;
+        self.db.put(
+            &Self::proposal_key(&proposal.graph_id, &proposal.id),
+            &serialized,
+        )
+    }
+
+    async fn get_proposal(
+        &self,
+        graph_id: &str,
+        id: &str,
+    ) -> Result<Option<Proposal>, Box<dyn Error>> {
+        match self.db.get(&Self::proposal_key(graph_id, id))? {
+            Some(bytes) => Ok(Some(decode_proposal(&bytes)?)),
+            None => Ok(None),
+        }
+    }
+
+    async fn list_proposals(&self, graph_id: &str) -> Result<Vec<Proposal>, Box<dyn Error>> {
+        let prefix = format!("proposal:{}:", graph_id);
+        let mut proposals = Vec::new();
+        for value in self.db.get_values_with_prefix(&prefix)? {
+            proposals.push(decode_proposal(&value)?);
+        }
+        proposals.sort_by_key(|p| p.created_at_ms);
+        Ok(proposals)
+    }
+
+    async fn index_block_times(&self, graph_id: &str) -> Result<(), Box<dyn Error>> {
+        let marker = Self::time_indexed_key(graph_id);
+        if self.db.exists(&marker)? {
+            return Ok(());
+        }
+
+        let latest = self.read_height(&Self::latest_block_key(graph_id))?;
+        let mut puts = Vec::new();
+        for height in 0..=latest.unwrap_or_default() {
+            if let Some(block) = self.get_block_by_height(graph_id, height).await? {
+                puts.push((
+                    Self::time_key(graph_id, block.timestamp, height),
+                    Vec::new(),
+                ));
+            }
+            if puts.len() >= INDEX_BACKFILL_BATCH {
+                self.db.batch_write(std::mem::take(&mut puts), Vec::new())?;
+            }
+        }
+        puts.push((marker, Vec::new()));
+        self.db.batch_write(puts, Vec::new())?;
+        if let Some(latest) = latest {
+            tracing::info!(
+                "🕒 Indexed timestamps of {} block(s) in '{}'",
+                latest + 1,
+                graph_id
+            );
+        }
+        Ok(())
+    }
+
+    async fn get_heights_by_time(
+        &self,
+        graph_id: &str,
+        from: i64,
+        to: i64,
+    ) -> Result<Vec<u64>, Box<dyn Error>> {
+        if from > to {
+            return Ok(Vec::new());
+        }
+        let start = Self::time_key(graph_id, from, 0);
+        let end = Self::time_key(graph_id, to, u64::MAX);
+        let mut heights = Vec::new();
+        for (key, _) in self.db.scan_range(&start, &end)? {
+            heights.push(Self::time_key_height(&key)?);
+        }
+        Ok(heights)
+    }
+
+    async fn get_block_at_time(
+        &self,
+        graph_id: &str,
+        at: i64,
+    ) -> Result<Option<Block>, Box<dyn Error>> {
+        let prefix = format!("time:{}:", graph_id);
+        let height = match self
+            .db
+            .last_at_or_before(&prefix, &Self::time_key(graph_id, at, u64::MAX))?
+        {
+            Some((key, _)) => Self::time_key_height(&key)?,
+            None => return Ok(None),
+        };
+        self.get_block_by_height(graph_id, height).await
+    }
+
+    async fn create_index(
+        &self,
+        graph_id: &str,
+        index: &PayloadIndex,
+    ) -> Result<u64, Box<dyn Error>> {
+        let def_key = Self::index_def_key(graph_id, &index.name);
+        if self.db.exists(&def_key)? {
+            return Err(format!("Index '{}' already exists", index.name).into());
+        }
+        self.db.put(&def_key, &bincode::serialize(index)?)?;
+
+        // Backfill the main chain in batches
+        let latest = self
+            .read_height(&Self::latest_block_key(graph_id))?
+            .unwrap_or_default();
+        let mut indexed = 0;
+        let mut puts = Vec::new();
+        for height in 0..=latest {
+            let block = match self.get_block_by_height(graph_id, height).await? {
+                Some(block) => block,
+                None => continue,
+            };
+            let keys = Self::index_entry_keys(graph_id, std::slice::from_ref(index), &block);
+            if !keys.is_empty() {
+                indexed += 1;
+            }
+            puts.extend(keys.into_iter().map(|key| (key, Vec::new())));
+            if puts.len() >= INDEX_BACKFILL_BATCH {
+                self.db.batch_write(std::mem::take(&mut puts), Vec::new())?;
+            }
+        }
+        self.db.batch_write(puts, Vec::new())?;
+        Ok(indexed)
+    }
+
+    async fn drop_index(&self, graph_id: &str, name: &str) -> Result<bool, Box<dyn Error>> {
+        let def_key = Self::index_def_key(graph_id, name);
+        if !self.db.exists(&def_key)? {
+            return Ok(false);
+        }
+        let mut deletes = self
+            .db
+            .get_keys_with_prefix(&format!("index:{}:{}:", graph_id, name))?;
+        deletes.push(def_key);
+        self.db.batch_write(Vec::new(), deletes)?;
+        Ok(true)
+    }
+
+    async fn list_indexes(&self, graph_id: &str) -> Result<Vec<PayloadIndex>, Box<dyn Error>> {
+        self.load_indexes(graph_id)
+    }
+
+    async fn enable_search(&self, graph_id: &str) -> Result<u64, Box<dyn Error>> {
+        let enabled_key = Self::search_enabled_key(graph_id);
+        if self.db.exists(&enabled_key)? {
+            return Err(format!("Search is already enabled for graph '{}'", graph_id).into());
+        }
+        self.db.put(&enabled_key, &[])?;
+        self.backfill_search(graph_id).await
+    }
+
+    async fn disable_search(&self, graph_id: &str) -> Result<bool, Box<dyn Error>> {
+        let enabled_key = Self::search_enabled_key(graph_id);
+        if !self.db.exists(&enabled_key)? {
+            return Ok(false);
+        }
+        let mut deletes = self
+            .db
+            .get_keys_with_prefix(&format!("search:{}:", graph_id))?;
+        deletes.push(enabled_key);
+        self.db.batch_write(Vec::new(), deletes)?;
+        Ok(true)
+    }
+
+    async fn search_enabled(&self, graph_id: &str) -> Result<bool, Box<dyn Error>> {
+        self.db.exists(&Self::search_enabled_key(graph_id))
+    }
+
+    async fn rebuild_search_index(&self, graph_id: &str) -> Result<u64, Box<dyn Error>> {
+        if !self.db.exists(&Self::search_enabled_key(graph_id))? {
+            return Err(format!("Search is not enabled for graph '{}'", graph_id).into());
+        }
+        let stale = self
+            .db
+            .get_keys_with_prefix(&format!("search:{}:", graph_id))?;
+        self.db.batch_write(Vec::new(), stale)?;
+        self.backfill_search(graph_id).await
+    }
+
+    async fn search_blocks(
+        &self,
+        graph_id: &str,
+        query: &SearchQuery,
+        from_height: u64,
+        limit: usize,
+    ) -> Result<Vec<Block>, Box<dyn Error>> {
+        if !self.db.exists(&Self::search_enabled_key(graph_id))? {
+            return Err(format!("Search is not enabled for graph '{}'", graph_id).into());
+        }
+
+        // Blocks holding every term are candidates; phrases and fields are
+        // then checked against the block data
+        let mut candidates: Option<BTreeSet<u64>> = None;
+        for token in query.tokens() {
+            let prefix = Self::search_token_prefix(graph_id, &token);
+            let start = format!("{}{:020}", prefix, from_height);
+            let entries = self.db.scan_prefix(&prefix, &start, usize::MAX)?;
+            let mut heights = BTreeSet::new();
+            for (key, _) in entries {
+                heights.insert(key[prefix.len()..].parse::<u64>()?);
+            }
+            candidates = Some(match candidates {
+                Some(candidates) => candidates.intersection(&heights).copied().collect(),
+                None => heights,
+            });
+        }
+
+        let mut blocks = Vec::new();
+        for height in candidates.unwrap_or_default() {
+            if blocks.len() >= limit {
+                break;
+            }
+            if let Some(block) = self.get_block_by_height(graph_id, height).await? {
+                if query.matches(&block.data) {
+                    blocks.push(block);
+                }
+            }
+        }
+        Ok(blocks)
+    }
+
+    async fn list_assets_by_owner(
+        &self,
+        graph_id: &str,
+        owner: &str,
+        from: &str,
+        limit: usize,
+    ) -> Result<Vec<String>, Box<dyn Error>> {
+        let prefix = Self::asset_owner_prefix(graph_id, owner);
+        let start = format!("{}{}", prefix, hex::encode(from));
+        let mut asset_ids = Vec::new();
+        for (key, _) in self.db.scan_prefix(&prefix, &start, limit)? {
+            asset_ids.push(String::from_utf8(hex::decode(&key[prefix.len()..])?)?);
+        }
+        Ok(asset_ids)
+    }
+
+    async fn query_index(
+        &self,
+        graph_id: &str,
+        name: &str,
+        value: &str,
+        from_height: u64,
+        limit: usize,
+    ) -> Result<Vec<Block>, Box<dyn Error>> {
+        if !self.db.exists(&Self::index_def_key(graph_id, name))? {
+            return Err(format!("Index '{}' not found", name).into());
+        }
+        let prefix = Self::index_value_prefix(graph_id, name, value);
+        let start = format!("{}{:020}", prefix, from_height);
+        let entries = self.db.scan_prefix(&prefix, &start, limit)?;
+        let mut blocks = Vec::new();
+        for (key, _) in entries {
+            let height: u64 = key[prefix.len()..].parse()?;
+            if let Some(block) = self.get_block_by_height(graph_id, height).await? {
+                blocks.push(block);
+            }
+        }
+        Ok(blocks)
+    }
+
+    async fn get_verified_checkpoint(
+        &self,
+        graph_id: &str,
+    ) -> Result<Option<VerifiedCheckpoint>, Box<dyn Error>> {
+        match self.db.get(&Self::verified_checkpoint_key(graph_id))? {
+            Some(bytes) => Ok(Some(serde_json::from_slice(&bytes)?)),
+            None => Ok(None),
+        }
+    }
+
+    async fn save_verified_checkpoint(
+        &self,
+        graph_id: &str,
+        checkpoint: &VerifiedCheckpoint,
+    ) -> Result<(), Box<dyn Error>> {
+        self.db.put(
+            &Self::verified_checkpoint_key(graph_id),
+            &serde_json::to_vec(checkpoint)?,
+        )
+    }
+
+    async fn delete_verified_checkpoint(&self, graph_id: &str) -> Result<(), Box<dyn Error>> {
+        self.db.delete(&Self::verified_checkpoint_key(graph_id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_graph_metadata_from_older_layouts() {
+        let legacy =
+            bincode::serialize(&("people", GraphType::Identity, "Test", 42i64, 3usize)).unwrap();
+        let graph = decode_graph(&legacy).unwrap();
+        assert_eq!(graph.id, "people");
+        assert_eq!(graph.created_at, 42);
+        assert_eq!(graph.consensus, Consensus::ProofOfWork);
+        assert!(graph.endorsement.is_none() && graph.chain.is_empty());
+
+        let current = BlockchainGraph::new(
+            "people".to_string(),
+            GraphType::Identity,
+            "Test".to_string(),
+            3,
+        );
+        let decoded = decode_graph(&serde_json::to_vec(&current).unwrap()).unwrap();
+        assert_eq!(decoded.created_at, current.created_at);
+
+        let block = current.chain[0].clone();
+        let legacy_block = bincode::serialize(&(
+            &block.hash,
+            &block.previous_hash,
+            block.timestamp,
+            &block.data,
+            block.nonce,
+            block.height,
+            &block.graph_id,
+            &block.cross_references,
+        ))
+        .unwrap();
+        assert_eq!(decode_block(&legacy_block).unwrap(), block);
+
+        let signed_block = bincode::serialize(&(
+            &block.hash,
+            &block.previous_hash,
+            block.timestamp,
+            &block.data,
+            block.nonce,
+            block.height,
+            &block.graph_id,
+            &block.cross_references,
+            "signer",
+            "signature",
+        ))
+        .unwrap();
+        let decoded = decode_block(&signed_block).unwrap();
+        assert_eq!(decoded.signer, "signer");
+        assert!(decoded.endorsements.is_none());
+        assert_eq!(
+            decode_block(&bincode::serialize(&block).unwrap()).unwrap(),
+            block
+        );
+    }
 }
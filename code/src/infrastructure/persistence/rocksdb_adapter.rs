@@ -1,8 +1,15 @@
-use rocksdb::{IteratorMode, Options, DB};
+use rocksdb::checkpoint::Checkpoint;
+use rocksdb::{Direction, IteratorMode, Options, ReadOptions, DB};
 use std::error::Error;
 use std::path::Path;
 use std::sync::Arc;
 
+/// Key written on clean shutdown and consumed on the next startup
+const CLEAN_SHUTDOWN_KEY: &str = "clean_shutdown";
+
+/// A raw key-value pair read from the database
+pub type KeyValue = (String, Vec<u8>);
+
 /// Low-level RocksDB adapter (Adapter Pattern)
 /// Encapsulates RocksDB operations
 pub struct RocksDbAdapter {
@@ -21,6 +28,16 @@ impl RocksDbAdapter {
         Ok(Self { db: Arc::new(db) })
     }
 
+    /// Opens an existing database without creating it (used by offline tools)
+    pub fn open_existing<P: AsRef<Path>>(path: P) -> Result<Self, Box<dyn Error>> {
+        let mut opts = Options::default();
+        opts.create_if_missing(false);
+
+        let db = DB::open(&opts, path)?;
+
+        Ok(Self { db: Arc::new(db) })
+    }
+
     /// Puts a key-value pair
     pub fn put(&self, key: &str, value: &[u8]) -> Result<(), Box<dyn Error>> {
         self.db.put(key.as_bytes(), value)?;
@@ -48,36 +65,129 @@ impl RocksDbAdapter {
 
     /// Gets all keys with a given prefix
     pub fn get_keys_with_prefix(&self, prefix: &str) -> Result<Vec<String>, Box<dyn Error>> {
-        let mut keys = Vec::new();
-        let iter = self.db.iterator(IteratorMode::Start);
+        let entries = self.scan_prefix(prefix, prefix, usize::MAX)?;
+        Ok(entries.into_iter().map(|(key, _)| key).collect())
+    }
 
-        for item in iter {
-            let (key, _) = item?;
-            let key_str = String::from_utf8(key.to_vec())?;
+    /// Gets all values with a given key prefix
+    pub fn get_values_with_prefix(&self, prefix: &str) -> Result<Vec<Vec<u8>>, Box<dyn Error>> {
+        let entries = self.scan_prefix(prefix, prefix, usize::MAX)?;
+        Ok(entries.into_iter().map(|(_, value)| value).collect())
+    }
+
+    /// Gets all key-value pairs with a given key prefix
+    pub fn get_entries_with_prefix(&self, prefix: &str) -> Result<Vec<KeyValue>, Box<dyn Error>> {
+        self.scan_prefix(prefix, prefix, usize::MAX)
+    }
+
+    /// Gets up to `limit` key-value pairs with a given prefix, in key order,
+    /// starting at `start` (inclusive)
+    pub fn scan_prefix(
+        &self,
+        prefix: &str,
+        start: &str,
+        limit: usize,
+    ) -> Result<Vec<KeyValue>, Box<dyn Error>> {
+        self.scan_prefix_opt(prefix, start, limit, ReadOptions::default())
+    }
+
+    /// Like [`Self::scan_prefix`], but every block read from disk is checked
+    /// against its RocksDB checksum and kept out of the block cache (for scrubbing)
+    pub fn scan_prefix_verified(
+        &self,
+        prefix: &str,
+        start: &str,
+        limit: usize,
+    ) -> Result<Vec<KeyValue>, Box<dyn Error>> {
+        self.scan_prefix_opt(prefix, start, limit, Self::verified_read_options())
+    }
+
+    /// Gets a value by key, checked against its RocksDB checksum
+    pub fn get_verified(&self, key: &str) -> Result<Option<Vec<u8>>, Box<dyn Error>> {
+        Ok(self
+            .db
+            .get_opt(key.as_bytes(), &Self::verified_read_options())?)
+    }
 
-            if key_str.starts_with(prefix) {
-                keys.push(key_str);
+    fn verified_read_options() -> ReadOptions {
+        let mut opts = ReadOptions::default();
+        opts.set_verify_checksums(true);
+        opts.fill_cache(false);
+        opts
+    }
+
+    fn scan_prefix_opt(
+        &self,
+        prefix: &str,
+        start: &str,
+        limit: usize,
+        opts: ReadOptions,
+    ) -> Result<Vec<KeyValue>, Box<dyn Error>> {
+        let mut entries = Vec::new();
+        let from = start.max(prefix);
+        let iter = self.db.iterator_opt(
+            IteratorMode::From(from.as_bytes(), Direction::Forward),
+            opts,
+        );
+
+        // Keys sharing a prefix are contiguous, so the scan stops at the first other key
+        for item in iter {
+            if entries.len() >= limit {
+                break;
             }
+            let (key, value) = item?;
+            if !key.starts_with(prefix.as_bytes()) {
+                break;
+            }
+            entries.push((String::from_utf8(key.to_vec())?, value.to_vec()));
         }
 
-        Ok(keys)
+        Ok(entries)
     }
 
-    /// Gets all values with a given key prefix
-    pub fn get_values_with_prefix(&self, prefix: &str) -> Result<Vec<Vec<u8>>, Box<dyn Error>> {
-        let mut values = Vec::new();
-        let iter = self.db.iterator(IteratorMode::Start);
+    /// Gets the key-value pairs from `start` to `end` (both inclusive), in key order
+    pub fn scan_range(&self, start: &str, end: &str) -> Result<Vec<KeyValue>, Box<dyn Error>> {
+        let mut entries = Vec::new();
+        let iter = self
+            .db
+            .iterator(IteratorMode::From(start.as_bytes(), Direction::Forward));
 
         for item in iter {
             let (key, value) = item?;
-            let key_str = String::from_utf8(key.to_vec())?;
+            if key.as_ref() > end.as_bytes() {
+                break;
+            }
+            entries.push((String::from_utf8(key.to_vec())?, value.to_vec()));
+        }
+
+        Ok(entries)
+    }
 
-            if key_str.starts_with(prefix) {
-                values.push(value.to_vec());
+    /// Gets the last key-value pair with a given prefix that sorts at or before `end`
+    pub fn last_at_or_before(
+        &self,
+        prefix: &str,
+        end: &str,
+    ) -> Result<Option<KeyValue>, Box<dyn Error>> {
+        let mut iter = self
+            .db
+            .iterator(IteratorMode::From(end.as_bytes(), Direction::Reverse));
+
+        match iter.next() {
+            Some(item) => {
+                let (key, value) = item?;
+                if !key.starts_with(prefix.as_bytes()) {
+                    return Ok(None);
+                }
+                Ok(Some((String::from_utf8(key.to_vec())?, value.to_vec())))
             }
+            None => Ok(None),
         }
+    }
 
-        Ok(values)
+    /// Reads an integer RocksDB property (e.g. `rocksdb.estimate-num-keys`)
+    pub fn property_int(&self, name: &str) -> Result<Option<u64>, Box<dyn Error>> {
+        Ok(self.db.property_int_value(name)?)
     }
 
     /// Performs a batch write operation
@@ -91,6 +201,63 @@ impl RocksDbAdapter {
         self.db.write(batch)?;
         Ok(())
     }
+
+    /// Atomically writes `puts` and removes `deletes`
+    pub fn batch_write(
+        &self,
+        puts: Vec<KeyValue>,
+        deletes: Vec<String>,
+    ) -> Result<(), Box<dyn Error>> {
+        let mut batch = rocksdb::WriteBatch::default();
+
+        for key in deletes {
+            batch.delete(key.as_bytes());
+        }
+        for (key, value) in puts {
+            batch.put(key.as_bytes(), &value);
+        }
+
+        self.db.write(batch)?;
+        Ok(())
+    }
+
+    /// Flushes memtables to SST files and syncs the WAL to disk
+    pub fn flush(&self) -> Result<(), Box<dyn Error>> {
+        self.db.flush()?;
+        self.db.flush_wal(true)?;
+        Ok(())
+    }
+
+    /// Creates a consistent point-in-time checkpoint of the database at `path`.
+    /// The target directory must not exist yet.
+    pub fn create_checkpoint<P: AsRef<Path>>(&self, path: P) -> Result<(), Box<dyn Error>> {
+        let checkpoint = Checkpoint::new(&self.db)?;
+        checkpoint.create_checkpoint(path)?;
+        Ok(())
+    }
+
+    /// Records that the database was closed cleanly (stores the unix timestamp)
+    pub fn mark_clean_shutdown(&self) -> Result<(), Box<dyn Error>> {
+        let timestamp = chrono::Utc::now().timestamp();
+        self.put(CLEAN_SHUTDOWN_KEY, &timestamp.to_le_bytes())?;
+        self.flush()
+    }
+
+    /// Reads and clears the clean-shutdown marker left by the previous run.
+    /// Returns the shutdown timestamp, or None if the marker is missing.
+    pub fn take_clean_shutdown_marker(&self) -> Result<Option<i64>, Box<dyn Error>> {
+        let timestamp = match self.get(CLEAN_SHUTDOWN_KEY)? {
+            Some(bytes) => i64::from_le_bytes(
+                bytes
+                    .try_into()
+                    .map_err(|_| "Invalid clean shutdown marker")?,
+            ),
+            None => return Ok(None),
+        };
+
+        self.delete(CLEAN_SHUTDOWN_KEY)?;
+        Ok(Some(timestamp))
+    }
 }
 
 #[cfg(test)]
@@ -128,5 +295,36 @@ mod tests {
 
         let keys = adapter.get_keys_with_prefix("prefix_").unwrap();
         assert_eq!(keys.len(), 2);
+
+        let page = adapter.scan_prefix("prefix_", "prefix_key2", 10).unwrap();
+        assert_eq!(page, vec![("prefix_key2".to_string(), b"value2".to_vec())]);
+        assert_eq!(adapter.scan_prefix("prefix_", "", 1).unwrap().len(), 1);
+
+        let range = adapter.scan_range("other_key", "prefix_key1").unwrap();
+        assert_eq!(range.len(), 2);
+        let last = adapter
+            .last_at_or_before("prefix_", "prefix_key9")
+            .unwrap()
+            .unwrap();
+        assert_eq!(last.0, "prefix_key2");
+        assert_eq!(
+            adapter.last_at_or_before("prefix_", "prefix_").unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_clean_shutdown_marker() {
+        let dir = tempdir().unwrap();
+
+        {
+            let adapter = RocksDbAdapter::new(dir.path()).unwrap();
+            assert_eq!(adapter.take_clean_shutdown_marker().unwrap(), None);
+            adapter.mark_clean_shutdown().unwrap();
+        }
+
+        let adapter = RocksDbAdapter::new(dir.path()).unwrap();
+        assert!(adapter.take_clean_shutdown_marker().unwrap().is_some());
+        assert_eq!(adapter.take_clean_shutdown_marker().unwrap(), None);
     }
 }
@@ -1,10 +1,76 @@
 use std::sync::Arc;
+use std::time::Duration;
 
+use clap::Parser;
+
+use blockchain_grpc::application::services::{IntegrityScrubber, RaftConfig, RaftNode};
+use blockchain_grpc::config::SettingsOverrides;
+use blockchain_grpc::domain::consensus::{public_key_hex, signing_key_from_hex};
+use blockchain_grpc::infrastructure::grpc::cluster_client::{
+    ClusterCredentials, GrpcRaftTransport,
+};
+use blockchain_grpc::infrastructure::grpc::gossip_client::GossipNode;
+use blockchain_grpc::infrastructure::grpc::replication_client::ReplicationFollower;
+use blockchain_grpc::infrastructure::grpc::server::start_cluster_server;
+use blockchain_grpc::infrastructure::http::graphql::GraphQlExecutor;
+use blockchain_grpc::infrastructure::http::start_http_server;
+use blockchain_grpc::infrastructure::persistence::{
+    BackupManager, BlockchainRepositoryImpl, RaftStore, RocksDbAdapter,
+};
 use blockchain_grpc::{BlockchainServiceImpl, Settings};
-use blockchain_grpc::infrastructure::persistence::{BlockchainRepositoryImpl, RocksDbAdapter};
+
+/// Command-line flags; they override the config file and `BLOCKCHAIN_*` env vars
+#[derive(Parser)]
+#[command(
+    name = "blockchain-grpc",
+    about = "Multi-graph blockchain gRPC service"
+)]
+struct Cli {
+    /// JSON configuration file (defaults to ./config.json if present)
+    #[arg(long)]
+    config: Option<String>,
+
+    /// gRPC listen host (IP address)
+    #[arg(long)]
+    host: Option<String>,
+
+    /// gRPC listen port
+    #[arg(long)]
+    port: Option<u16>,
+
+    /// RocksDB data directory
+    #[arg(long)]
+    data_dir: Option<String>,
+
+    /// Proof-of-work difficulty for new graphs
+    #[arg(long)]
+    difficulty: Option<usize>,
+
+    /// Run as a read-only follower replicating from this leader (host:port)
+    #[arg(long, value_name = "LEADER")]
+    follow: Option<String>,
+
+    /// Raft node id of this member (clustered mode)
+    #[arg(long)]
+    node_id: Option<u64>,
+
+    /// Gossip with this peer (host:port); repeat for several peers
+    #[arg(long = "peer", value_name = "PEER")]
+    peers: Vec<String>,
+
+    /// File with this node's authority key (proof-of-authority graphs)
+    #[arg(long, value_name = "FILE")]
+    authority_key: Option<String>,
+
+    /// File with the key this node signs notarization receipts with
+    #[arg(long, value_name = "FILE")]
+    receipt_key: Option<String>,
+}
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let cli = Cli::parse();
+
     // Initialize tracing
     tracing_subscriber::fmt()
         .with_env_filter(
@@ -15,8 +81,19 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     tracing::info!("🔗 Starting Blockchain gRPC Service");
 
-    // Load settings
-    let settings = Settings::load("config.json")?;
+    // Load settings: defaults < config file < environment < command line
+    let overrides = SettingsOverrides {
+        host: cli.host,
+        port: cli.port,
+        data_dir: cli.data_dir,
+        default_difficulty: cli.difficulty,
+        leader_address: cli.follow,
+        node_id: cli.node_id,
+        gossip_peers: (!cli.peers.is_empty()).then_some(cli.peers),
+        authority_key_file: cli.authority_key,
+        receipt_key_file: cli.receipt_key,
+    };
+    let settings = Settings::load_layered(cli.config.as_deref(), &overrides)?;
     tracing::info!("⚙️  Configuration loaded");
 
     // Initialize storage
@@ -24,18 +101,242 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let db = Arc::new(RocksDbAdapter::new(&settings.storage.data_dir)?);
     tracing::info!("💾 Storage initialized at {}", settings.storage.data_dir);
 
+    match db.take_clean_shutdown_marker()? {
+        Some(timestamp) => tracing::info!("🧹 Previous run shut down cleanly (at {})", timestamp),
+        None => {
+            tracing::warn!("⚠️  No clean-shutdown marker found (first run or unclean shutdown)")
+        }
+    }
+
     // Initialize repository
-    let repository = Arc::new(BlockchainRepositoryImpl::new(db));
+    let repository = Arc::new(BlockchainRepositoryImpl::new(db.clone()));
 
     // Initialize service
-    let service = Arc::new(BlockchainServiceImpl::new(repository));
+    let backups = Arc::new(BackupManager::new(
+        db.clone(),
+        &settings.storage.backup_dir,
+        settings.storage.max_backups,
+    ));
+    let scrubber = settings.scrub.enabled.then(|| {
+        Arc::new(IntegrityScrubber::new(
+            repository.clone(),
+            settings.scrub.max_blocks_per_sec,
+        ))
+    });
+    let mut service = BlockchainServiceImpl::new(repository)
+        .with_default_difficulty(settings.blockchain.default_difficulty)
+        .with_proposal_ttl(settings.proposal_ttl())
+        .with_max_contract_fuel(settings.contracts.max_fuel)
+        .with_backup_manager(backups);
+    if let Some(leader) = settings.leader_address() {
+        service = service.with_leader(leader.to_string());
+    }
+    if settings.gossip.enabled {
+        service = service.with_gossip(&settings.gossip.peers);
+    }
+    if settings.audit.enabled {
+        service = service.with_audit(&settings.audit.graph_id, settings.audit.max_batch);
+    }
+    if settings.anchoring.enabled {
+        service = service.with_anchoring(&settings.anchoring.graph_id);
+    }
+    if let Some(scrubber) = &scrubber {
+        service = service.with_scrubber(scrubber.clone());
+    }
+    if let Some(path) = settings.authority_key_file() {
+        let seed = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read authority key '{}': {}", path, e))?;
+        let key = signing_key_from_hex(&seed)?;
+        tracing::info!(
+            "🔑 Signing proof-of-authority blocks as {}",
+            public_key_hex(&key)
+        );
+        service = service.with_authority_key(key);
+    }
+    if let Some(path) = settings.receipt_key_file() {
+        let seed = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read receipt key '{}': {}", path, e))?;
+        let key = signing_key_from_hex(&seed)?;
+        tracing::info!(
+            "🧾 Signing notarization receipts as {}",
+            public_key_hex(&key)
+        );
+        service = service.with_receipt_key(key);
+    }
+
+    // Clustered mode: writes are ordered through Raft before being applied
+    let raft = if settings.cluster.enabled {
+        let peers = settings.cluster_peers();
+        let credentials =
+            ClusterCredentials::new(settings.cluster.node_id, settings.cluster.secret.clone());
+        let config = RaftConfig {
+            node_id: settings.cluster.node_id,
+            peers: peers.clone(),
+            election_timeout_min: Duration::from_millis(settings.cluster.election_timeout_min_ms),
+            election_timeout_max: Duration::from_millis(settings.cluster.election_timeout_max_ms),
+            heartbeat_interval: Duration::from_millis(settings.cluster.heartbeat_interval_ms),
+            retained_log_entries: settings.cluster.retained_log_entries,
+        };
+        let node = Arc::new(RaftNode::new(
+            config,
+            RaftStore::new(db.clone()),
+            Arc::new(GrpcRaftTransport::new(&peers, credentials.clone())?),
+            Arc::new(service.applier()),
+        )?);
+        service = service.with_cluster(node.clone(), credentials);
+        Some(node)
+    } else {
+        None
+    };
+
+    let service = Arc::new(service);
     service.initialize().await?;
 
+    // Members talk to each other on their own authenticated listener
+    let cluster_listener = match settings.cluster_address().filter(|_| raft.is_some()) {
+        Some(addr) => {
+            let service = service.clone();
+            Some(tokio::spawn(async move {
+                if let Err(e) = start_cluster_server(service, addr).await {
+                    tracing::error!("❌ Cluster listener stopped: {}", e);
+                }
+            }))
+        }
+        None => None,
+    };
+
+    // Committed entries not yet applied are replayed once the node starts
+    let raft_task = raft.as_ref().map(|node| node.start());
+
     tracing::info!("✅ Service initialized successfully");
 
+    // Followers replicate from the leader in the background
+    let follower = settings.leader_address().map(|leader| {
+        tracing::info!("🔁 Running as read-only follower of {}", leader);
+        let follower = ReplicationFollower::new(
+            service.clone(),
+            leader.to_string(),
+            settings.replication_retry_interval(),
+        );
+        tokio::spawn(follower.run())
+    });
+
+    // Gossip nodes exchange tips with their peers and catch up from them
+    let gossip = settings.gossip.enabled.then(|| {
+        tracing::info!("📣 Gossiping with {} peer(s)", settings.gossip.peers.len());
+        let node = GossipNode::new(
+            service.clone(),
+            settings.gossip.peers.clone(),
+            settings.gossip.advertise_address.clone(),
+            settings.gossip_interval(),
+            settings.gossip.batch_size,
+        );
+        tokio::spawn(node.run())
+    });
+
+    // GraphQL over HTTP, next to the gRPC server
+    let http = settings.http.enabled.then(|| {
+        let executor = GraphQlExecutor::new(service.clone()).with_limits(
+            settings.http.graphql_max_depth,
+            settings.http.graphql_max_fields,
+            settings.http.graphql_max_page_size,
+        );
+        let addr = settings.http_address();
+        let scrubber = scrubber.clone();
+        let max_body_bytes = settings.http.max_body_bytes;
+        tokio::spawn(async move {
+            let executor = Arc::new(executor);
+            if let Err(e) = start_http_server(executor, scrubber, addr, max_body_bytes).await {
+                tracing::error!("❌ HTTP server stopped: {}", e);
+            }
+        })
+    });
+
+    // Audit events are appended to the audit graph in batches
+    let audit = settings.audit.enabled.then(|| {
+        tracing::info!(
+            "🧾 Recording an audit trail into graph '{}'",
+            settings.audit.graph_id
+        );
+        tokio::spawn(
+            service
+                .clone()
+                .run_audit_writer(settings.audit_flush_interval()),
+        )
+    });
+
+    // Tips of every graph are anchored periodically
+    let anchoring = settings.anchoring.enabled.then(|| {
+        tracing::info!(
+            "⚓ Anchoring graph tips into '{}'",
+            settings.anchoring.graph_id
+        );
+        tokio::spawn(
+            service
+                .clone()
+                .run_anchor_writer(settings.anchor_interval()),
+        )
+    });
+
+    // Verified checkpoints follow the graph tips
+    let checkpointing = tokio::spawn(
+        service
+            .clone()
+            .run_checkpoint_writer(settings.checkpoint_interval()),
+    );
+
+    // Storage is re-read and checked periodically
+    let scrubbing = scrubber.map(|scrubber| {
+        tracing::info!("🩺 Scrubbing storage every {:?}", settings.scrub_interval());
+        tokio::spawn(scrubber.run(settings.scrub_interval()))
+    });
+
     // Start gRPC server
     let addr = settings.server_address();
-    blockchain_grpc::start_grpc_server(service, addr).await?;
+    let drained =
+        blockchain_grpc::start_grpc_server(service.clone(), addr, settings.shutdown_timeout())
+            .await?;
+
+    if let Some(follower) = follower {
+        follower.abort();
+    }
+    if let Some(gossip) = gossip {
+        gossip.abort();
+    }
+    if let Some(http) = http {
+        http.abort();
+    }
+    if let Some(anchoring) = anchoring {
+        anchoring.abort();
+    }
+    if let Some(scrubbing) = scrubbing {
+        scrubbing.abort();
+    }
+    checkpointing.abort();
+    if let Some(audit) = audit {
+        audit.abort();
+        let written = service.flush_audit().await;
+        tracing::info!("🧾 Flushed {} pending audit event(s)", written);
+    }
+    if let Some(cluster_listener) = cluster_listener {
+        cluster_listener.abort();
+    }
+    if let (Some(raft), Some(raft_task)) = (raft, raft_task) {
+        raft.stop();
+        let _ = raft_task.await;
+    }
+
+    // Flush storage and leave a marker for the next startup, unless writes
+    // may still have been running when the server stopped
+    db.flush()?;
+    if drained {
+        db.mark_clean_shutdown()?;
+        tracing::info!("👋 Storage flushed, clean shutdown complete");
+    } else {
+        tracing::warn!(
+            "⚠️  Storage flushed; no clean-shutdown marker since writes were still in flight"
+        );
+    }
 
     Ok(())
 }
@@ -0,0 +1,90 @@
+// Helpers shared by the integration tests; each test crate uses a subset
+#![allow(dead_code)]
+
+use blockchain_grpc::infrastructure::grpc::blockchain::*;
+use blockchain_grpc::infrastructure::persistence::{BlockchainRepositoryImpl, RocksDbAdapter};
+use blockchain_grpc::BlockchainServiceImpl;
+use std::path::Path;
+use std::sync::Arc;
+
+/// Repository over a RocksDB store in `data_dir`
+pub fn repository(data_dir: &Path) -> Arc<BlockchainRepositoryImpl> {
+    Arc::new(BlockchainRepositoryImpl::new(Arc::new(
+        RocksDbAdapter::new(data_dir).unwrap(),
+    )))
+}
+
+/// Initialized service over `data_dir`, mining at difficulty 1
+pub async fn open_service(data_dir: &Path) -> BlockchainServiceImpl {
+    open_service_with(data_dir, |service| service).await
+}
+
+/// Like [`open_service`], with `configure` applied before initialization
+pub async fn open_service_with(
+    data_dir: &Path,
+    configure: impl FnOnce(BlockchainServiceImpl) -> BlockchainServiceImpl,
+) -> BlockchainServiceImpl {
+    start_service(repository(data_dir), configure).await
+}
+
+/// Initialized service over `repository`, for tests that also read storage
+pub async fn start_service(
+    repository: Arc<BlockchainRepositoryImpl>,
+    configure: impl FnOnce(BlockchainServiceImpl) -> BlockchainServiceImpl,
+) -> BlockchainServiceImpl {
+    let service = configure(BlockchainServiceImpl::new(repository).with_default_difficulty(1));
+    service.initialize().await.unwrap();
+    service
+}
+
+/// CreateGraph request for a plain graph; set other fields with struct update
+pub fn graph_request(graph_id: &str, graph_type: GraphType) -> CreateGraphRequest {
+    CreateGraphRequest {
+        graph_id: graph_id.to_string(),
+        graph_type: graph_type as i32,
+        ..Default::default()
+    }
+}
+
+/// Creates a plain graph, which must succeed
+pub async fn create_graph(service: &BlockchainServiceImpl, graph_id: &str, graph_type: GraphType) {
+    create_graph_with(service, graph_request(graph_id, graph_type)).await;
+}
+
+/// Sends `request` to CreateGraph, which must succeed
+pub async fn create_graph_with(service: &BlockchainServiceImpl, request: CreateGraphRequest) {
+    let response = service
+        .handle_create_graph(request)
+        .await
+        .unwrap()
+        .into_inner();
+    assert!(response.success, "{}", response.message);
+}
+
+/// AddBlock request without references or receipt
+pub fn block_request(graph_id: &str, data: &str) -> AddBlockRequest {
+    AddBlockRequest {
+        graph_id: graph_id.to_string(),
+        data: data.to_string(),
+        ..Default::default()
+    }
+}
+
+/// Sends `request` to AddBlock and returns the response, successful or not
+pub async fn try_add_block(
+    service: &BlockchainServiceImpl,
+    request: AddBlockRequest,
+) -> AddBlockResponse {
+    service
+        .handle_add_block(request)
+        .await
+        .unwrap()
+        .into_inner()
+}
+
+/// Adds a block, which must be committed, and returns it
+pub async fn add_block(service: &BlockchainServiceImpl, graph_id: &str, data: &str) -> Block {
+    let response = try_add_block(service, block_request(graph_id, data)).await;
+    assert!(response.success, "{}", response.message);
+    response.block.expect("committed block")
+}
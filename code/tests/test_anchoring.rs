@@ -0,0 +1,99 @@
+// Anchoring of graph tips and their verification by CrossValidateGraphs
+
+mod common;
+
+use blockchain_grpc::domain::anchor::parse_anchors;
+use blockchain_grpc::domain::traits::BlockchainRepository;
+use blockchain_grpc::domain::Block;
+use blockchain_grpc::infrastructure::grpc::blockchain::*;
+use blockchain_grpc::BlockchainServiceImpl;
+use common::{
+    add_block, block_request, create_graph, open_service_with, repository, try_add_block,
+};
+use std::path::Path;
+use tempfile::tempdir;
+
+async fn start(path: &Path) -> BlockchainServiceImpl {
+    open_service_with(path, |service| service.with_anchoring("anchors")).await
+}
+
+#[tokio::test]
+async fn test_anchors_detect_rewritten_history() {
+    let dir = tempdir().unwrap();
+    let service = start(dir.path()).await;
+    for graph_id in ["people", "payments"] {
+        create_graph(&service, graph_id, GraphType::Custom).await;
+    }
+    add_block(&service, "people", "alice").await;
+    add_block(&service, "people", "bob").await;
+
+    // A new anchor only once a tip moved
+    assert_eq!(service.write_anchor().await, Ok(Some(1)));
+    assert_eq!(service.write_anchor().await, Ok(None));
+    add_block(&service, "payments", "10").await;
+    assert_eq!(service.write_anchor().await, Ok(Some(2)));
+
+    let anchor = service
+        .handle_get_latest_block(GetLatestBlockRequest {
+            graph_id: "anchors".to_string(),
+        })
+        .await
+        .unwrap()
+        .into_inner()
+        .block
+        .unwrap();
+    let tips = parse_anchors(&anchor.data).unwrap();
+    let anchored: Vec<(&str, u64)> = tips
+        .iter()
+        .map(|t| (t.graph_id.as_str(), t.height))
+        .collect();
+    assert_eq!(anchored, vec![("payments", 1), ("people", 2)]);
+
+    let forged = try_add_block(&service, block_request("anchors", &anchor.data)).await;
+    assert!(!forged.success);
+
+    let validated = service
+        .handle_cross_validate(CrossValidateRequest { incremental: false })
+        .await
+        .unwrap()
+        .into_inner();
+    assert!(validated.all_valid, "{}", validated.message);
+
+    // Rewrite the anchored tip of "people" in storage: the chain itself stays valid
+    drop(service);
+    let repository = repository(dir.path());
+    let previous = repository
+        .get_block_by_height("people", 1)
+        .await
+        .unwrap()
+        .unwrap();
+    let mut rewritten = Block::new(
+        previous.hash,
+        "mallory".to_string(),
+        "people".to_string(),
+        2,
+        vec![],
+    );
+    rewritten.mine_block(1);
+    repository.save_block("people", &rewritten).await.unwrap();
+    drop(repository);
+
+    let service = start(dir.path()).await;
+    let people = service
+        .handle_verify_graph(VerifyGraphRequest {
+            graph_id: "people".to_string(),
+            full: false,
+        })
+        .await
+        .unwrap()
+        .into_inner();
+    assert!(people.is_valid);
+    let validated = service
+        .handle_cross_validate(CrossValidateRequest { incremental: false })
+        .await
+        .unwrap()
+        .into_inner();
+    assert!(!validated.all_valid);
+    assert!(!validated.graph_statuses["people"]);
+    assert!(validated.graph_statuses["payments"]);
+}
@@ -0,0 +1,309 @@
+// Asset operations, ownership index and payment cross-references on asset graphs
+
+mod common;
+
+use blockchain_grpc::domain::consensus::{generate_signing_key, public_key_hex, sign_message};
+use blockchain_grpc::domain::AssetOperation;
+use blockchain_grpc::infrastructure::grpc::blockchain::*;
+use blockchain_grpc::BlockchainServiceImpl;
+use common::{block_request, create_graph_with, graph_request, open_service, try_add_block};
+use ed25519_dalek::SigningKey;
+use serde_json::Map;
+use tempfile::tempdir;
+
+/// Creates a graph governed by a single "admin" org holding `admin`
+async fn create_graph(
+    service: &BlockchainServiceImpl,
+    graph_id: &str,
+    graph_type: GraphType,
+    admin: &SigningKey,
+) {
+    create_graph_with(
+        service,
+        CreateGraphRequest {
+            default_schema: graph_type == GraphType::Asset,
+            governance: Some(EndorsementPolicy {
+                required: 1,
+                endorsers: vec![Endorser {
+                    org: "admin".to_string(),
+                    public_key: public_key_hex(admin),
+                }],
+            }),
+            ..graph_request(graph_id, graph_type)
+        },
+    )
+    .await;
+}
+
+async fn add_block(
+    service: &BlockchainServiceImpl,
+    graph_id: &str,
+    data: &str,
+    references: Vec<String>,
+) -> AddBlockResponse {
+    try_add_block(
+        service,
+        AddBlockRequest {
+            cross_references: references,
+            ..block_request(graph_id, data)
+        },
+    )
+    .await
+}
+
+async fn get_asset(service: &BlockchainServiceImpl, asset_id: &str) -> Asset {
+    let response = service
+        .handle_get_asset(GetAssetRequest {
+            graph_id: "titles".to_string(),
+            asset_id: asset_id.to_string(),
+        })
+        .await
+        .unwrap()
+        .into_inner();
+    assert!(response.success, "{}", response.message);
+    response.asset.unwrap()
+}
+
+async fn owned(
+    service: &BlockchainServiceImpl,
+    owner: &SigningKey,
+    page_size: u32,
+    page_token: &str,
+) -> (Vec<String>, String) {
+    let response = service
+        .handle_list_assets_by_owner(ListAssetsByOwnerRequest {
+            graph_id: "titles".to_string(),
+            owner: public_key_hex(owner),
+            page_size,
+            page_token: page_token.to_string(),
+        })
+        .await
+        .unwrap()
+        .into_inner();
+    assert!(response.success, "{}", response.message);
+    let ids = response
+        .assets
+        .into_iter()
+        .map(|asset| asset.asset_id)
+        .collect();
+    (ids, response.next_page_token)
+}
+
+/// Proposes a payment graph for "titles"; the policy applies once `approver`
+/// endorses it, if given
+async fn set_payment_graph(
+    service: &BlockchainServiceImpl,
+    payment_graph: &str,
+    approver: Option<&SigningKey>,
+) -> ProposalResponse {
+    let proposed = service
+        .handle_update_asset_policy(UpdateAssetPolicyRequest {
+            graph_id: "titles".to_string(),
+            payment_graph: payment_graph.to_string(),
+        })
+        .await
+        .unwrap()
+        .into_inner();
+    let (proposal, key) = match (proposed.proposal, approver) {
+        (Some(proposal), Some(key)) => (proposal, key),
+        (proposal, _) => {
+            return ProposalResponse {
+                success: proposed.success,
+                message: proposed.message,
+                proposal,
+                block: proposed.block,
+            }
+        }
+    };
+    service
+        .handle_endorse_proposal(EndorseProposalRequest {
+            graph_id: "titles".to_string(),
+            signature: sign_message(key, proposal.proposal_id.as_bytes()),
+            proposal_id: proposal.proposal_id,
+            org: "admin".to_string(),
+        })
+        .await
+        .unwrap()
+        .into_inner()
+}
+
+fn mint(asset_id: &str, owner: &SigningKey) -> String {
+    AssetOperation::Mint {
+        asset_id: asset_id.to_string(),
+        owner: public_key_hex(owner),
+        metadata: Map::new(),
+    }
+    .to_data()
+}
+
+#[tokio::test]
+async fn test_asset_lifecycle_ownership_and_payments() {
+    let dir = tempdir().unwrap();
+    let service = open_service(dir.path()).await;
+
+    let admin = generate_signing_key();
+    create_graph(&service, "titles", GraphType::Asset, &admin).await;
+    create_graph(&service, "payments", GraphType::Transaction, &admin).await;
+    let alice = generate_signing_key();
+    let bob = generate_signing_key();
+
+    assert!(
+        add_block(&service, "titles", &mint("car", &alice), vec![])
+            .await
+            .success
+    );
+    assert!(
+        add_block(&service, "titles", &mint("bike", &alice), vec![])
+            .await
+            .success
+    );
+    let duplicate = add_block(&service, "titles", &mint("car", &bob), vec![]).await;
+    assert!(
+        duplicate.message.contains("already exists"),
+        "{}",
+        duplicate.message
+    );
+    let reserved = add_block(
+        &service,
+        "titles",
+        r#"{"asset_id": "car", "rwset": {"writes": [{"key": "asset/car", "value": "{}"}]}}"#,
+        vec![],
+    )
+    .await;
+    assert!(
+        reserved.message.contains("reserved"),
+        "{}",
+        reserved.message
+    );
+
+    // The ownership index pages by asset id
+    let (first, token) = owned(&service, &alice, 1, "").await;
+    assert_eq!(first, vec!["bike"]);
+    assert_eq!(
+        owned(&service, &alice, 1, &token).await,
+        (vec!["car".to_string()], String::new())
+    );
+
+    // Transfers are signed by the current owner and, once required, paid for
+    let transfer = AssetOperation::Transfer {
+        asset_id: "car".to_string(),
+        to: public_key_hex(&bob),
+        signature: String::new(),
+    };
+    let version = get_asset(&service, "car").await.version;
+    let forged = add_block(
+        &service,
+        "titles",
+        &transfer.clone().signed(&bob, "titles", version).to_data(),
+        vec![],
+    )
+    .await;
+    assert!(
+        forged.message.contains("Only the owner"),
+        "{}",
+        forged.message
+    );
+
+    // The payment requirement applies once the governance approves it
+    assert!(
+        !set_payment_graph(&service, "titles", Some(&admin))
+            .await
+            .success
+    );
+    let pending = set_payment_graph(&service, "payments", None).await;
+    assert!(pending.success && pending.block.is_none());
+    let outsider = generate_signing_key();
+    assert!(
+        !set_payment_graph(&service, "payments", Some(&outsider))
+            .await
+            .success
+    );
+    let approved = set_payment_graph(&service, "payments", Some(&admin)).await;
+    assert!(approved.success, "{}", approved.message);
+    assert!(approved.block.unwrap().endorsements.is_some());
+    let signed = transfer.signed(&alice, "titles", version).to_data();
+    let unpaid = add_block(&service, "titles", &signed, vec![]).await;
+    assert!(
+        unpaid.message.contains("payment block in graph 'payments'"),
+        "{}",
+        unpaid.message
+    );
+    let title_block = get_asset(&service, "bike").await.block_hash;
+    assert!(
+        !add_block(&service, "titles", &signed, vec![title_block])
+            .await
+            .success
+    );
+    let payment = add_block(&service, "payments", r#"{"amount": 12000}"#, vec![])
+        .await
+        .block
+        .unwrap()
+        .hash;
+    let transferred = add_block(&service, "titles", &signed, vec![payment]).await;
+    assert!(transferred.success, "{}", transferred.message);
+
+    let car = get_asset(&service, "car").await;
+    assert_eq!(car.owner, public_key_hex(&bob));
+    assert_eq!(owned(&service, &alice, 0, "").await.0, vec!["bike"]);
+    assert_eq!(owned(&service, &bob, 0, "").await.0, vec!["car"]);
+
+    // Metadata and burn by the new owner; history keeps every version
+    let metadata = AssetOperation::Metadata {
+        asset_id: "car".to_string(),
+        metadata: serde_json::json!({"color": "red"})
+            .as_object()
+            .unwrap()
+            .clone(),
+        signature: String::new(),
+    };
+    let response = add_block(
+        &service,
+        "titles",
+        &metadata.signed(&bob, "titles", car.version).to_data(),
+        vec![],
+    )
+    .await;
+    assert!(response.success, "{}", response.message);
+    let version = get_asset(&service, "car").await.version;
+    let burn = AssetOperation::Burn {
+        asset_id: "car".to_string(),
+        signature: String::new(),
+    };
+    let response = add_block(
+        &service,
+        "titles",
+        &burn.signed(&bob, "titles", version).to_data(),
+        vec![],
+    )
+    .await;
+    assert!(response.success, "{}", response.message);
+
+    let burned = get_asset(&service, "car").await;
+    assert!(burned.burned);
+    assert_eq!(burned.metadata, r#"{"color":"red"}"#);
+    assert!(owned(&service, &bob, 0, "").await.0.is_empty());
+    let history = service
+        .handle_get_asset_history(GetAssetRequest {
+            graph_id: "titles".to_string(),
+            asset_id: "car".to_string(),
+        })
+        .await
+        .unwrap()
+        .into_inner();
+    let operations: Vec<&str> = history
+        .versions
+        .iter()
+        .map(|v| v.operation.as_str())
+        .collect();
+    assert_eq!(operations, vec!["mint", "transfer", "metadata", "burn"]);
+
+    let not_asset = service
+        .handle_get_asset(GetAssetRequest {
+            graph_id: "payments".to_string(),
+            asset_id: "car".to_string(),
+        })
+        .await
+        .unwrap()
+        .into_inner();
+    assert!(not_asset.message.contains("not an asset graph"));
+}
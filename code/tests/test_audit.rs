@@ -0,0 +1,152 @@
+// Audit trail of node operations, batched into an audit graph
+
+mod common;
+
+use blockchain_grpc::application::services::audit::{parse_audit_batch, AuditEvent};
+use blockchain_grpc::infrastructure::grpc::blockchain::blockchain_service_server::BlockchainService;
+use blockchain_grpc::infrastructure::grpc::blockchain::*;
+use blockchain_grpc::infrastructure::grpc::server::CALLER_HEADER;
+use blockchain_grpc::BlockchainServiceImpl;
+use common::{block_request, graph_request, open_service_with};
+use tempfile::tempdir;
+use tonic::Request;
+
+fn from<T>(caller: &str, message: T) -> Request<T> {
+    let mut request = Request::new(message);
+    request
+        .metadata_mut()
+        .insert(CALLER_HEADER, caller.parse().unwrap());
+    request
+}
+
+async fn audit_events(service: &BlockchainServiceImpl) -> Vec<AuditEvent> {
+    let latest = service
+        .handle_get_latest_block(GetLatestBlockRequest {
+            graph_id: "audit".to_string(),
+        })
+        .await
+        .unwrap()
+        .into_inner()
+        .block
+        .unwrap();
+    let blocks = service
+        .handle_get_block_range(GetBlockRangeRequest {
+            graph_id: "audit".to_string(),
+            start_height: 1,
+            end_height: latest.height,
+        })
+        .await
+        .unwrap()
+        .into_inner()
+        .blocks;
+    blocks
+        .iter()
+        .flat_map(|b| parse_audit_batch(&b.data).unwrap())
+        .collect()
+}
+
+#[tokio::test]
+async fn test_operations_are_batched_into_a_valid_audit_graph() {
+    let dir = tempdir().unwrap();
+    let service = open_service_with(dir.path(), |service| service.with_audit("audit", 2)).await;
+
+    let request = graph_request("people", GraphType::Identity);
+    let created = BlockchainService::create_graph(&service, from("alice", request))
+        .await
+        .unwrap();
+    assert!(created.get_ref().success);
+
+    let added = BlockchainService::add_block(
+        &service,
+        from("alice", block_request("people", r#"{"name":"alice"}"#)),
+    )
+    .await
+    .unwrap()
+    .into_inner();
+    let missing = BlockchainService::add_block(&service, Request::new(block_request("nope", "x")))
+        .await
+        .unwrap();
+    assert!(!missing.get_ref().success);
+    // Clients cannot write the node's own audit graph
+    let forged = BlockchainService::add_block(
+        &service,
+        from("mallory", block_request("audit", r#"{"audit_events":[]}"#)),
+    )
+    .await
+    .unwrap();
+    assert!(
+        forged
+            .get_ref()
+            .message
+            .contains("written by the node only"),
+        "{}",
+        forged.get_ref().message
+    );
+
+    // Valid graphs and reads are not recorded
+    let verified = BlockchainService::verify_graph(
+        &service,
+        from(
+            "bob",
+            VerifyGraphRequest {
+                graph_id: "people".to_string(),
+                full: false,
+            },
+        ),
+    )
+    .await
+    .unwrap();
+    assert!(verified.get_ref().is_valid);
+
+    // Two events per block: four events take two blocks
+    assert_eq!(service.flush_audit().await, 4);
+    assert_eq!(service.flush_audit().await, 0);
+    let events = audit_events(&service).await;
+    let summary: Vec<(&str, &str, &str, bool)> = events
+        .iter()
+        .map(|e| {
+            (
+                e.action.as_str(),
+                e.caller.as_str(),
+                e.graph_id.as_str(),
+                e.success,
+            )
+        })
+        .collect();
+    assert_eq!(
+        summary,
+        vec![
+            (
+                "graph_created",
+                "alice (unverified) via unknown",
+                "people",
+                true
+            ),
+            (
+                "block_added",
+                "alice (unverified) via unknown",
+                "people",
+                true
+            ),
+            ("block_added", "unknown", "nope", false),
+            (
+                "block_added",
+                "mallory (unverified) via unknown",
+                "audit",
+                false
+            ),
+        ]
+    );
+    assert!(events[1].details.contains(&added.block.unwrap().hash));
+
+    let info = service
+        .handle_get_graph_info(GetGraphInfoRequest {
+            graph_id: "audit".to_string(),
+        })
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(info.graph_type, GraphType::Audit as i32);
+    assert_eq!(info.total_blocks, 3);
+    assert!(info.is_valid);
+}
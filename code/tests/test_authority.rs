@@ -0,0 +1,187 @@
+// Proof-of-authority graphs: signed blocks, rotation and on-chain key updates
+
+mod common;
+
+use blockchain_grpc::domain::consensus::{generate_signing_key, public_key_hex, sign_message};
+use blockchain_grpc::infrastructure::grpc::blockchain::*;
+use blockchain_grpc::BlockchainServiceImpl;
+use common::{block_request, graph_request, open_service_with, try_add_block};
+use ed25519_dalek::SigningKey;
+use std::path::Path;
+use tempfile::tempdir;
+
+async fn open_service(data_dir: &Path, key: Option<SigningKey>) -> BlockchainServiceImpl {
+    open_service_with(data_dir, |service| match key {
+        Some(key) => service.with_authority_key(key),
+        None => service,
+    })
+    .await
+}
+
+async fn create_graph(
+    service: &BlockchainServiceImpl,
+    authorities: Vec<String>,
+    rotation: AuthorityRotation,
+) -> CreateGraphResponse {
+    service
+        .handle_create_graph(CreateGraphRequest {
+            consensus: Some(ConsensusConfig {
+                mode: ConsensusMode::ProofOfAuthority as i32,
+                authorities,
+                rotation: rotation as i32,
+            }),
+            ..graph_request("members", GraphType::Identity)
+        })
+        .await
+        .unwrap()
+        .into_inner()
+}
+
+async fn add_block(service: &BlockchainServiceImpl, data: &str) -> AddBlockResponse {
+    try_add_block(service, block_request("members", data)).await
+}
+
+async fn graph_info(service: &BlockchainServiceImpl) -> GetGraphInfoResponse {
+    service
+        .handle_get_graph_info(GetGraphInfoRequest {
+            graph_id: "members".to_string(),
+        })
+        .await
+        .unwrap()
+        .into_inner()
+}
+
+#[tokio::test]
+async fn test_authority_signs_blocks_and_rotates_keys_on_chain() {
+    let dir = tempdir().unwrap();
+    let replica_dir = tempdir().unwrap();
+    let node_key = generate_signing_key();
+    let other_key = generate_signing_key();
+    let node_public = public_key_hex(&node_key);
+    let other_public = public_key_hex(&other_key);
+
+    let service = open_service(dir.path(), Some(node_key.clone())).await;
+    let mut events = service.subscribe_events();
+
+    // Invalid authority sets are refused
+    let response = create_graph(
+        &service,
+        vec!["not-a-key".to_string()],
+        AuthorityRotation::AnyOf,
+    )
+    .await;
+    assert!(!response.success);
+
+    let response = create_graph(
+        &service,
+        vec![node_public.clone(), other_public.clone()],
+        AuthorityRotation::AnyOf,
+    )
+    .await;
+    assert!(response.success, "{}", response.message);
+
+    // Blocks are signed instantly with the node key instead of being mined
+    let block = add_block(&service, "alice joined").await.block.unwrap();
+    assert_eq!(block.signer, node_public);
+    assert!(!block.signature.is_empty());
+
+    // Round robin over [other, node]: odd heights belong to this node, even ones to `other`.
+    // The update waits for one of the current authorities to approve it.
+    let update = service
+        .handle_update_authorities(UpdateAuthoritiesRequest {
+            graph_id: "members".to_string(),
+            authorities: vec![other_public.clone(), node_public.clone()],
+            rotation: AuthorityRotation::RoundRobin as i32,
+        })
+        .await
+        .unwrap()
+        .into_inner();
+    assert!(update.success, "{}", update.message);
+    assert!(update.block.is_none());
+    let proposal = update.proposal.unwrap();
+    assert_eq!(proposal.required, 1);
+    let endorse = |org: &str, key: &SigningKey| EndorseProposalRequest {
+        graph_id: "members".to_string(),
+        proposal_id: proposal.proposal_id.clone(),
+        org: org.to_string(),
+        signature: sign_message(key, proposal.proposal_id.as_bytes()),
+    };
+    let outsider = generate_signing_key();
+    let response = service
+        .handle_endorse_proposal(endorse(&public_key_hex(&outsider), &outsider))
+        .await
+        .unwrap()
+        .into_inner();
+    assert!(!response.success);
+    let response = service
+        .handle_endorse_proposal(endorse(&node_public, &node_key))
+        .await
+        .unwrap()
+        .into_inner();
+    assert!(response.success, "{}", response.message);
+    assert!(response.block.unwrap().endorsements.is_some());
+    let response = add_block(&service, "bob joined").await;
+    assert!(response.success, "{}", response.message);
+    let response = add_block(&service, "carol joined").await;
+    assert!(!response.success);
+    assert!(
+        response.message.contains("must be signed by"),
+        "{}",
+        response.message
+    );
+
+    let consensus = graph_info(&service).await.consensus.unwrap();
+    assert_eq!(consensus.mode, ConsensusMode::ProofOfAuthority as i32);
+    assert_eq!(
+        consensus.authorities,
+        vec![other_public.clone(), node_public.clone()]
+    );
+    assert_eq!(consensus.rotation, AuthorityRotation::RoundRobin as i32);
+
+    // Proof-of-work graphs have no authorities to update
+    let pow = service
+        .handle_create_graph(graph_request("payments", GraphType::Transaction))
+        .await
+        .unwrap()
+        .into_inner();
+    assert!(pow.success);
+    let update = service
+        .handle_update_authorities(UpdateAuthoritiesRequest {
+            graph_id: "payments".to_string(),
+            authorities: vec![node_public.clone()],
+            rotation: 0,
+        })
+        .await
+        .unwrap()
+        .into_inner();
+    assert!(!update.success);
+
+    // A node without the key verifies and applies the signed blocks
+    let replica = open_service(replica_dir.path(), None).await;
+    while let Ok(event) = events.try_recv() {
+        replica
+            .apply_replication_event(event.to_proto())
+            .await
+            .unwrap();
+    }
+    let info = graph_info(&replica).await;
+    assert_eq!(info.total_blocks, 4);
+    assert!(info.is_valid);
+    let response = add_block(&replica, "dave joined").await;
+    assert!(!response.success);
+    assert!(
+        response.message.contains("no authority key"),
+        "{}",
+        response.message
+    );
+
+    // The consensus mode and the updated set survive a restart
+    drop(service);
+    let reopened = open_service(dir.path(), None).await;
+    let info = graph_info(&reopened).await;
+    assert!(info.is_valid);
+    assert_eq!(
+        info.consensus.unwrap().authorities,
+        vec![other_public, node_public]
+    );
+}
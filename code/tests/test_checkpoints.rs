@@ -0,0 +1,105 @@
+// Verified-up-to checkpoints: routine checks resume from them, full checks do
+// not, and only the checkpoint writer moves them
+
+mod common;
+
+use blockchain_grpc::domain::traits::BlockchainRepository;
+use blockchain_grpc::infrastructure::grpc::blockchain::*;
+use blockchain_grpc::BlockchainServiceImpl;
+use common::{add_block, create_graph, open_service, repository};
+use tempfile::tempdir;
+
+async fn info(service: &BlockchainServiceImpl) -> GetGraphInfoResponse {
+    service
+        .handle_get_graph_info(GetGraphInfoRequest {
+            graph_id: "ledger".to_string(),
+        })
+        .await
+        .unwrap()
+        .into_inner()
+}
+
+async fn verify(service: &BlockchainServiceImpl, full: bool) -> VerifyGraphResponse {
+    service
+        .handle_verify_graph(VerifyGraphRequest {
+            graph_id: "ledger".to_string(),
+            full,
+        })
+        .await
+        .unwrap()
+        .into_inner()
+}
+
+#[tokio::test]
+async fn test_checkpoints_survive_restarts_and_full_checks_ignore_them() {
+    let dir = tempdir().unwrap();
+    let service = open_service(dir.path()).await;
+    create_graph(&service, "ledger", GraphType::Transaction).await;
+    add_block(&service, "ledger", "a").await;
+    add_block(&service, "ledger", "b").await;
+
+    // Reads report the stored checkpoint and leave it where it is
+    let before = info(&service).await;
+    assert!(before.is_valid);
+    assert_eq!(before.verified_height, 0);
+    assert_eq!(verify(&service, false).await.verified_height, 2);
+    assert_eq!(info(&service).await.verified_height, 0);
+
+    assert_eq!(service.advance_checkpoints().await, 1);
+    assert_eq!(info(&service).await.verified_height, 2);
+    add_block(&service, "ledger", "c").await;
+    assert_eq!(verify(&service, false).await.verified_height, 3);
+    assert_eq!(info(&service).await.verified_height, 2);
+    assert_eq!(service.advance_checkpoints().await, 1);
+    assert_eq!(service.advance_checkpoints().await, 0);
+    drop(service);
+
+    // Rewrite block 1 in storage without re-mining it: below the checkpoint
+    let repository = repository(dir.path());
+    assert_eq!(
+        repository
+            .get_verified_checkpoint("ledger")
+            .await
+            .unwrap()
+            .unwrap()
+            .height,
+        3
+    );
+    let mut block = repository
+        .get_block_by_height("ledger", 1)
+        .await
+        .unwrap()
+        .unwrap();
+    block.data = "forged".to_string();
+    repository.save_block("ledger", &block).await.unwrap();
+    let tip = repository
+        .get_block_by_height("ledger", 3)
+        .await
+        .unwrap()
+        .unwrap();
+    repository.save_block("ledger", &tip).await.unwrap();
+    drop(repository);
+
+    let service = open_service(dir.path()).await;
+    let routine = verify(&service, false).await;
+    assert!(routine.is_valid && routine.verified_height == 3);
+
+    let full = verify(&service, true).await;
+    assert!(!full.is_valid);
+    assert!(full.errors[0].starts_with("Block #1"), "{:?}", full.errors);
+
+    // Cross-validation checks from genesis unless asked to be incremental
+    let validated = service
+        .handle_cross_validate(CrossValidateRequest { incremental: false })
+        .await
+        .unwrap()
+        .into_inner();
+    assert!(!validated.graph_statuses["ledger"]);
+    let validated = service
+        .handle_cross_validate(CrossValidateRequest { incremental: true })
+        .await
+        .unwrap()
+        .into_inner();
+    assert!(validated.graph_statuses["ledger"]);
+    assert_eq!(info(&service).await.verified_height, 3);
+}
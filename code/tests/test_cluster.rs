@@ -0,0 +1,421 @@
+// Raft clustered mode with three nodes on localhost
+
+mod common;
+
+use blockchain_grpc::application::services::{RaftConfig, RaftNode};
+use blockchain_grpc::domain::consensus::{generate_signing_key, public_key_hex, sign_message};
+use blockchain_grpc::infrastructure::grpc::blockchain::cluster_service_client::ClusterServiceClient;
+use blockchain_grpc::infrastructure::grpc::blockchain::*;
+use blockchain_grpc::infrastructure::grpc::cluster_client::{
+    endpoint_url, ClusterCredentials, GrpcRaftTransport,
+};
+use blockchain_grpc::infrastructure::grpc::server::cluster_service;
+use blockchain_grpc::infrastructure::persistence::{
+    BlockchainRepositoryImpl, RaftStore, RocksDbAdapter,
+};
+use blockchain_grpc::BlockchainServiceImpl;
+use common::{block_request, graph_request, try_add_block};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tempfile::{tempdir, TempDir};
+use tokio::net::TcpListener;
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+use tokio_stream::wrappers::TcpListenerStream;
+use tonic::transport::Server;
+use tonic::Code;
+
+const SECRET: &str = "test-cluster-secret";
+
+struct Node {
+    service: Arc<BlockchainServiceImpl>,
+    raft: JoinHandle<()>,
+    stop: oneshot::Sender<()>,
+    server: JoinHandle<()>,
+}
+
+impl Node {
+    /// Simulates a crash: stops Raft and the server and releases the database
+    async fn kill(self) {
+        if let Some(raft) = self.service.cluster() {
+            raft.stop();
+        }
+        self.raft.await.unwrap();
+        let _ = self.stop.send(());
+        drop(self.service);
+        self.server.await.unwrap();
+    }
+}
+
+struct Cluster {
+    _dirs: Vec<TempDir>,
+    data_dirs: HashMap<u64, PathBuf>,
+    addresses: HashMap<u64, String>,
+    nodes: HashMap<u64, Node>,
+}
+
+impl Cluster {
+    async fn start(size: u64) -> Self {
+        let mut listeners = HashMap::new();
+        let mut addresses = HashMap::new();
+        for id in 1..=size {
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            addresses.insert(id, listener.local_addr().unwrap().to_string());
+            listeners.insert(id, listener);
+        }
+
+        let dirs: Vec<TempDir> = (0..size).map(|_| tempdir().unwrap()).collect();
+        let data_dirs: HashMap<u64, PathBuf> = (1..=size)
+            .map(|id| (id, dirs[id as usize - 1].path().to_path_buf()))
+            .collect();
+
+        let mut cluster = Self {
+            _dirs: dirs,
+            data_dirs,
+            addresses,
+            nodes: HashMap::new(),
+        };
+        for (id, listener) in listeners {
+            let node = cluster.start_node(id, listener).await;
+            cluster.nodes.insert(id, node);
+        }
+        cluster
+    }
+
+    async fn start_node(&self, id: u64, listener: TcpListener) -> Node {
+        let peers: HashMap<u64, String> = self
+            .addresses
+            .iter()
+            .filter(|(peer, _)| **peer != id)
+            .map(|(peer, address)| (*peer, address.clone()))
+            .collect();
+        open_node(&self.data_dirs[&id], id, peers, listener).await
+    }
+
+    async fn restart(&mut self, id: u64) {
+        let listener = TcpListener::bind(&self.addresses[&id]).await.unwrap();
+        let node = self.start_node(id, listener).await;
+        self.nodes.insert(id, node);
+    }
+
+    async fn kill(&mut self, id: u64) {
+        self.nodes.remove(&id).unwrap().kill().await;
+    }
+
+    fn service(&self, id: u64) -> Arc<BlockchainServiceImpl> {
+        self.nodes[&id].service.clone()
+    }
+
+    /// Waits until exactly one running node is leader and every running
+    /// node knows it, then returns its id
+    async fn wait_for_leader(&self) -> u64 {
+        for _ in 0..300 {
+            let mut leaders = Vec::new();
+            let mut known = Vec::new();
+            for (id, node) in &self.nodes {
+                let status = node
+                    .service
+                    .handle_get_cluster_status()
+                    .await
+                    .unwrap()
+                    .into_inner();
+                if status.role == "leader" {
+                    leaders.push(*id);
+                }
+                known.push(status.leader_id);
+            }
+            if leaders.len() == 1 && known.iter().all(|id| *id == leaders[0]) {
+                return leaders[0];
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        panic!("no leader elected");
+    }
+
+    /// Waits until every running node has the same tip for `graph_id`
+    async fn wait_for_tip(&self, graph_id: &str, height: u64) -> String {
+        for _ in 0..300 {
+            let mut tips = Vec::new();
+            for node in self.nodes.values() {
+                tips.push(latest(&node.service, graph_id).await);
+            }
+            let first = tips[0].clone();
+            if let Some((tip_height, hash)) = first {
+                if tip_height == height
+                    && tips
+                        .iter()
+                        .all(|t| t.as_ref() == Some(&(tip_height, hash.clone())))
+                {
+                    return hash;
+                }
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        panic!(
+            "nodes did not converge on height {} of '{}'",
+            height, graph_id
+        );
+    }
+}
+
+async fn open_node(
+    data_dir: &Path,
+    id: u64,
+    peers: HashMap<u64, String>,
+    listener: TcpListener,
+) -> Node {
+    let db = Arc::new(RocksDbAdapter::new(data_dir).unwrap());
+    let repository = Arc::new(BlockchainRepositoryImpl::new(db.clone()));
+    let service = BlockchainServiceImpl::new(repository).with_default_difficulty(1);
+
+    let config = RaftConfig {
+        node_id: id,
+        peers: peers.clone(),
+        election_timeout_min: Duration::from_millis(150),
+        election_timeout_max: Duration::from_millis(300),
+        heartbeat_interval: Duration::from_millis(30),
+        retained_log_entries: 1000,
+    };
+    let credentials = ClusterCredentials::new(id, SECRET);
+    let raft = Arc::new(
+        RaftNode::new(
+            config,
+            RaftStore::new(db),
+            Arc::new(GrpcRaftTransport::new(&peers, credentials.clone()).unwrap()),
+            Arc::new(service.applier()),
+        )
+        .unwrap(),
+    );
+    let service = Arc::new(service.with_cluster(raft.clone(), credentials));
+    service.initialize().await.unwrap();
+    let raft_task = raft.start();
+
+    let (stop, stopped) = oneshot::channel::<()>();
+    let server = Server::builder()
+        .add_service(cluster_service(&service).unwrap())
+        .serve_with_incoming_shutdown(TcpListenerStream::new(listener), async {
+            let _ = stopped.await;
+        });
+    let server = tokio::spawn(async move {
+        server.await.unwrap();
+    });
+
+    Node {
+        service,
+        raft: raft_task,
+        stop,
+        server,
+    }
+}
+
+async fn latest(service: &BlockchainServiceImpl, graph_id: &str) -> Option<(u64, String)> {
+    let response = service
+        .handle_get_latest_block(GetLatestBlockRequest {
+            graph_id: graph_id.to_string(),
+        })
+        .await
+        .unwrap()
+        .into_inner();
+    response.block.map(|b| (b.height, b.hash))
+}
+
+async fn add_block(service: &BlockchainServiceImpl, data: &str) -> AddBlockResponse {
+    try_add_block(service, block_request("payments", data)).await
+}
+
+/// Retries while a new leader is still being elected
+async fn add_block_eventually(service: &BlockchainServiceImpl, data: &str) -> AddBlockResponse {
+    for _ in 0..100 {
+        let response = add_block(service, data).await;
+        if response.success {
+            return response;
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    panic!("block '{}' was never committed", data);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_cluster_orders_writes_and_survives_node_crashes() {
+    let mut cluster = Cluster::start(3).await;
+    let leader = cluster.wait_for_leader().await;
+    let follower = (1..=3).find(|id| *id != leader).unwrap();
+
+    // Writes sent to a follower are forwarded to the leader
+    let created = cluster
+        .service(follower)
+        .handle_create_graph(graph_request("payments", GraphType::Transaction))
+        .await
+        .unwrap()
+        .into_inner();
+    assert!(created.success, "{}", created.message);
+    assert!(add_block(&cluster.service(follower), "tx 1").await.success);
+    assert!(add_block(&cluster.service(leader), "tx 2").await.success);
+    let tip = cluster.wait_for_tip("payments", 2).await;
+
+    // Crash a follower: the remaining majority keeps committing
+    cluster.kill(follower).await;
+    assert!(add_block(&cluster.service(leader), "tx 3").await.success);
+
+    // The restarted node keeps its blocks and catches up from the log
+    cluster.restart(follower).await;
+    let tip_after_restart = cluster.wait_for_tip("payments", 3).await;
+    assert_ne!(tip, tip_after_restart);
+
+    // Crash the leader: a new one is elected and nothing committed is lost
+    cluster.kill(leader).await;
+    let new_leader = cluster.wait_for_leader().await;
+    assert_ne!(new_leader, leader);
+    assert_eq!(
+        latest(&cluster.service(new_leader), "payments").await,
+        Some((3, tip_after_restart))
+    );
+    let any_node = *cluster.nodes.keys().next().unwrap();
+    add_block_eventually(&cluster.service(any_node), "tx 4").await;
+
+    cluster.restart(leader).await;
+    cluster.wait_for_tip("payments", 4).await;
+
+    let info = cluster
+        .service(leader)
+        .handle_get_graph_info(GetGraphInfoRequest {
+            graph_id: "payments".to_string(),
+        })
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(info.total_blocks, 5);
+    assert!(info.is_valid);
+}
+
+async fn get_proposal(service: &BlockchainServiceImpl, proposal_id: &str) -> ProposalResponse {
+    service
+        .handle_get_proposal(GetProposalRequest {
+            graph_id: "contracts".to_string(),
+            proposal_id: proposal_id.to_string(),
+        })
+        .await
+        .unwrap()
+        .into_inner()
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_proposals_are_shared_by_every_member() {
+    let cluster = Cluster::start(3).await;
+    let leader = cluster.wait_for_leader().await;
+    let followers: Vec<u64> = (1..=3).filter(|id| *id != leader).collect();
+    let key = generate_signing_key();
+
+    let created = cluster
+        .service(followers[0])
+        .handle_create_graph(CreateGraphRequest {
+            endorsement: Some(EndorsementPolicy {
+                required: 1,
+                endorsers: vec![Endorser {
+                    org: "orgA".to_string(),
+                    public_key: public_key_hex(&key),
+                }],
+            }),
+            ..graph_request("contracts", GraphType::Transaction)
+        })
+        .await
+        .unwrap()
+        .into_inner();
+    assert!(created.success, "{}", created.message);
+
+    // Proposed through one follower, visible on the other
+    let proposed = try_add_block(
+        &cluster.service(followers[0]),
+        block_request("contracts", "clause 1"),
+    )
+    .await;
+    assert!(proposed.success, "{}", proposed.message);
+    let proposal_id = proposed.proposal.unwrap().proposal_id;
+    for _ in 0..300 {
+        if get_proposal(&cluster.service(followers[1]), &proposal_id)
+            .await
+            .success
+        {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+
+    // Endorsed through the other follower, committed everywhere
+    let endorsed = cluster
+        .service(followers[1])
+        .handle_endorse_proposal(EndorseProposalRequest {
+            graph_id: "contracts".to_string(),
+            proposal_id: proposal_id.clone(),
+            org: "orgA".to_string(),
+            signature: sign_message(&key, proposal_id.as_bytes()),
+        })
+        .await
+        .unwrap()
+        .into_inner();
+    assert!(endorsed.success, "{}", endorsed.message);
+    let hash = cluster.wait_for_tip("contracts", 1).await;
+    assert_eq!(endorsed.block.unwrap().hash, hash);
+    for _ in 0..300 {
+        let stored = get_proposal(&cluster.service(followers[0]), &proposal_id).await;
+        if stored.proposal.unwrap().status == ProposalStatus::ProposalCommitted as i32 {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    panic!("the committed proposal did not reach every member");
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_cluster_listener_refuses_outsiders() {
+    let cluster = Cluster::start(3).await;
+    let leader = cluster.wait_for_leader().await;
+    let follower = (1..=3).find(|id| *id != leader).unwrap();
+    let mut client = ClusterServiceClient::connect(endpoint_url(&cluster.addresses[&follower]))
+        .await
+        .unwrap();
+    let append = RaftAppendRequest {
+        term: 100,
+        leader_id: leader,
+        prev_log_index: 0,
+        prev_log_term: 0,
+        entries: vec![],
+        leader_commit: 0,
+    };
+
+    // No secret, a wrong secret, or an id outside the membership
+    let refused = client
+        .append_entries(tonic::Request::new(append.clone()))
+        .await
+        .unwrap_err();
+    assert_eq!(refused.code(), Code::Unauthenticated);
+    let wrong = ClusterCredentials::new(leader, "not-the-cluster-secret");
+    let refused = client
+        .append_entries(wrong.request(append.clone()))
+        .await
+        .unwrap_err();
+    assert_eq!(refused.code(), Code::Unauthenticated);
+    let outsider = ClusterCredentials::new(9, SECRET);
+    let refused = client
+        .forward_commit(outsider.request(ForwardCommitRequest {
+            block: Some(block_request("payments", "")),
+            endorsements: None,
+        }))
+        .await
+        .unwrap_err();
+    assert_eq!(refused.code(), Code::Unauthenticated);
+
+    // A member cannot speak for another one
+    let other = (1..=3).find(|id| *id != leader && *id != follower).unwrap();
+    let impostor = ClusterCredentials::new(other, SECRET);
+    let refused = client
+        .append_entries(impostor.request(append))
+        .await
+        .unwrap_err();
+    assert_eq!(refused.code(), Code::PermissionDenied);
+
+    // None of it disturbed the cluster
+    assert_eq!(cluster.wait_for_leader().await, leader);
+}
@@ -0,0 +1,278 @@
+// WebAssembly contracts: deterministic execution, world state and metering
+
+mod common;
+
+use blockchain_grpc::domain::consensus::{generate_signing_key, public_key_hex, sign_message};
+use blockchain_grpc::domain::contract::contract_deploy_data;
+use blockchain_grpc::domain::traits::BlockchainRepository;
+use blockchain_grpc::domain::ContractDeployment;
+use blockchain_grpc::infrastructure::grpc::blockchain::*;
+use blockchain_grpc::infrastructure::persistence::BlockchainRepositoryImpl;
+use blockchain_grpc::BlockchainServiceImpl;
+use common::{block_request, graph_request, repository, start_service, try_add_block};
+use ed25519_dalek::SigningKey;
+use std::path::Path;
+use std::sync::Arc;
+use tempfile::tempdir;
+
+/// Payload "inc" increments `count` up to 3; anything else is rejected
+const COUNTER: &str = r#"
+    (module
+      (import "env" "input_len" (func $input_len (result i32)))
+      (import "env" "state_get" (func $state_get (param i32 i32 i32 i32) (result i32)))
+      (import "env" "state_set" (func $state_set (param i32 i32 i32 i32)))
+      (import "env" "reject" (func $reject (param i32 i32)))
+      (memory (export "memory") 1)
+      (data (i32.const 0) "count")
+      (data (i32.const 16) "unknown operation")
+      (data (i32.const 48) "supply exhausted")
+      (func (export "execute") (result i32)
+        (if (i32.ne (call $input_len) (i32.const 3))
+          (then
+            (call $reject (i32.const 16) (i32.const 17))
+            (return (i32.const 1))))
+        (if (i32.lt_s (call $state_get (i32.const 0) (i32.const 5) (i32.const 32) (i32.const 1)) (i32.const 0))
+          (then (i32.store8 (i32.const 32) (i32.const 0))))
+        (if (i32.ge_u (i32.load8_u (i32.const 32)) (i32.const 3))
+          (then
+            (call $reject (i32.const 48) (i32.const 16))
+            (return (i32.const 1))))
+        (i32.store8 (i32.const 32) (i32.add (i32.load8_u (i32.const 32)) (i32.const 1)))
+        (call $state_set (i32.const 0) (i32.const 5) (i32.const 32) (i32.const 1))
+        (i32.const 0)))
+"#;
+
+async fn open_service(data_dir: &Path) -> (BlockchainServiceImpl, Arc<BlockchainRepositoryImpl>) {
+    let repository = repository(data_dir);
+    let service = start_service(repository.clone(), |service| {
+        service.with_max_contract_fuel(1_000_000)
+    })
+    .await;
+    (service, repository)
+}
+
+async fn add_block(service: &BlockchainServiceImpl, data: &str) -> AddBlockResponse {
+    try_add_block(service, block_request("tokens", data)).await
+}
+
+async fn deploy(
+    service: &BlockchainServiceImpl,
+    code: Vec<u8>,
+    fuel_limit: u64,
+) -> AddBlockResponse {
+    service
+        .handle_deploy_contract(DeployContractRequest {
+            graph_id: "tokens".to_string(),
+            code,
+            fuel_limit,
+        })
+        .await
+        .unwrap()
+        .into_inner()
+}
+
+/// Governance of a single "admin" org holding `key`
+fn governance(key: &SigningKey) -> EndorsementPolicy {
+    EndorsementPolicy {
+        required: 1,
+        endorsers: vec![Endorser {
+            org: "admin".to_string(),
+            public_key: public_key_hex(key),
+        }],
+    }
+}
+
+/// Approves the settings change proposed in `response` as the admin org
+async fn approve(
+    service: &BlockchainServiceImpl,
+    response: AddBlockResponse,
+    key: &SigningKey,
+) -> ProposalResponse {
+    let proposal_id = response.proposal.unwrap().proposal_id;
+    service
+        .handle_endorse_proposal(EndorseProposalRequest {
+            graph_id: "tokens".to_string(),
+            signature: sign_message(key, proposal_id.as_bytes()),
+            proposal_id,
+            org: "admin".to_string(),
+        })
+        .await
+        .unwrap()
+        .into_inner()
+}
+
+async fn graph_info(service: &BlockchainServiceImpl) -> GetGraphInfoResponse {
+    service
+        .handle_get_graph_info(GetGraphInfoRequest {
+            graph_id: "tokens".to_string(),
+        })
+        .await
+        .unwrap()
+        .into_inner()
+}
+
+#[tokio::test]
+async fn test_contract_accepts_rejects_and_writes_state_with_blocks() {
+    let dir = tempdir().unwrap();
+    let replica_dir = tempdir().unwrap();
+    let (service, repository) = open_service(dir.path()).await;
+    let mut events = service.subscribe_events();
+    let admin = generate_signing_key();
+
+    let created = service
+        .handle_create_graph(CreateGraphRequest {
+            governance: Some(governance(&admin)),
+            ..graph_request("tokens", GraphType::Custom)
+        })
+        .await
+        .unwrap()
+        .into_inner();
+    assert!(created.success, "{}", created.message);
+
+    // Invalid modules and excessive fuel limits are refused
+    let response = deploy(&service, b"not wasm".to_vec(), 0).await;
+    assert!(!response.success);
+    let response = deploy(&service, wat::parse_str(COUNTER).unwrap(), 2_000_000).await;
+    assert!(!response.success);
+    assert!(
+        response.message.contains("exceeds the maximum"),
+        "{}",
+        response.message
+    );
+
+    // A deployment sent as plain block data bypasses nothing
+    let smuggled = ContractDeployment::new(&wat::parse_str(COUNTER).unwrap(), u64::MAX);
+    let response = add_block(&service, &contract_deploy_data(&smuggled)).await;
+    assert!(!response.success);
+    assert!(
+        response.message.contains("DeployContract"),
+        "{}",
+        response.message
+    );
+
+    // The deployment waits for the governance to approve it
+    let response = deploy(&service, wat::parse_str(COUNTER).unwrap(), 50_000).await;
+    assert!(response.success, "{}", response.message);
+    assert!(graph_info(&service).await.contract_hash.is_empty());
+    let outsider = generate_signing_key();
+    let approval = approve(&service, response.clone(), &outsider).await;
+    assert!(!approval.success);
+    let approval = approve(&service, response, &admin).await;
+    assert!(approval.success, "{}", approval.message);
+    assert!(approval.block.unwrap().endorsements.is_some());
+    assert!(!graph_info(&service).await.contract_hash.is_empty());
+
+    for _ in 0..2 {
+        let response = add_block(&service, "inc").await;
+        assert!(response.success, "{}", response.message);
+    }
+    assert_eq!(
+        repository
+            .get_state("tokens", "count")
+            .await
+            .unwrap()
+            .map(|e| e.value),
+        Some(vec![2])
+    );
+
+    // A rejected payload appends nothing and writes nothing
+    let response = add_block(&service, "steal").await;
+    assert!(!response.success);
+    assert!(
+        response.message.contains("unknown operation"),
+        "{}",
+        response.message
+    );
+    assert_eq!(graph_info(&service).await.total_blocks, 4);
+
+    // Followers re-run the contract and reach the same state
+    let (replica, replica_repository) = open_service(replica_dir.path()).await;
+    while let Ok(event) = events.try_recv() {
+        replica
+            .apply_replication_event(event.to_proto())
+            .await
+            .unwrap();
+    }
+    assert_eq!(graph_info(&replica).await.total_blocks, 4);
+    assert_eq!(
+        replica_repository
+            .get_state("tokens", "count")
+            .await
+            .unwrap()
+            .map(|e| e.value),
+        Some(vec![2])
+    );
+
+    // State and contract survive a restart
+    drop(service);
+    drop(repository);
+    let (reopened, repository) = open_service(dir.path()).await;
+    assert!(add_block(&reopened, "inc").await.success);
+    let response = add_block(&reopened, "inc").await;
+    assert!(
+        response.message.contains("supply exhausted"),
+        "{}",
+        response.message
+    );
+    assert_eq!(
+        repository
+            .get_state("tokens", "count")
+            .await
+            .unwrap()
+            .map(|e| e.value),
+        Some(vec![3])
+    );
+}
+
+#[tokio::test]
+async fn test_runaway_contract_runs_out_of_fuel() {
+    let dir = tempdir().unwrap();
+    let (service, _) = open_service(dir.path()).await;
+    let admin = generate_signing_key();
+    let create = |graph_id: &str, governance| CreateGraphRequest {
+        governance,
+        ..graph_request(graph_id, GraphType::Custom)
+    };
+    let looping = wat::parse_str(
+        r#"(module (memory (export "memory") 1)
+             (func (export "execute") (result i32) (loop $l (br $l)) (i32.const 0)))"#,
+    )
+    .unwrap();
+
+    // Without governance nobody may deploy to a graph
+    service
+        .handle_create_graph(create("fixed", None))
+        .await
+        .unwrap();
+    let response = service
+        .handle_deploy_contract(DeployContractRequest {
+            graph_id: "fixed".to_string(),
+            code: looping.clone(),
+            fuel_limit: 0,
+        })
+        .await
+        .unwrap()
+        .into_inner();
+    assert!(!response.success);
+    assert!(
+        response.message.contains("no governance"),
+        "{}",
+        response.message
+    );
+
+    service
+        .handle_create_graph(create("tokens", Some(governance(&admin))))
+        .await
+        .unwrap();
+    let response = deploy(&service, looping, 0).await;
+    assert!(approve(&service, response, &admin).await.success);
+
+    let response = add_block(&service, "anything").await;
+    assert!(!response.success);
+    assert!(
+        response.message.contains("out of fuel"),
+        "{}",
+        response.message
+    );
+    assert_eq!(graph_info(&service).await.total_blocks, 2);
+}
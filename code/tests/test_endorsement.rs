@@ -0,0 +1,185 @@
+// Endorsement policies: blocks are held as proposals until enough orgs sign them
+
+mod common;
+
+use blockchain_grpc::domain::consensus::{generate_signing_key, public_key_hex, sign_message};
+use blockchain_grpc::infrastructure::grpc::blockchain::*;
+use blockchain_grpc::BlockchainServiceImpl;
+use common::{block_request, create_graph_with, graph_request, open_service_with, try_add_block};
+use ed25519_dalek::SigningKey;
+use std::path::Path;
+use std::time::Duration;
+use tempfile::tempdir;
+
+async fn open_service(data_dir: &Path, ttl: Duration) -> BlockchainServiceImpl {
+    open_service_with(data_dir, |service| service.with_proposal_ttl(ttl)).await
+}
+
+async fn create_graph(service: &BlockchainServiceImpl, keys: &[SigningKey]) {
+    let endorsers = ["orgA", "orgB", "orgC"]
+        .iter()
+        .zip(keys)
+        .map(|(org, key)| Endorser {
+            org: org.to_string(),
+            public_key: public_key_hex(key),
+        })
+        .collect();
+    create_graph_with(
+        service,
+        CreateGraphRequest {
+            endorsement: Some(EndorsementPolicy {
+                required: 2,
+                endorsers,
+            }),
+            ..graph_request("payments", GraphType::Transaction)
+        },
+    )
+    .await;
+}
+
+async fn propose(service: &BlockchainServiceImpl, data: &str) -> Proposal {
+    let response = try_add_block(service, block_request("payments", data)).await;
+    assert!(response.success, "{}", response.message);
+    assert!(response.block.is_none());
+    response.proposal.unwrap()
+}
+
+async fn endorse(
+    service: &BlockchainServiceImpl,
+    proposal_id: &str,
+    org: &str,
+    key: &SigningKey,
+) -> ProposalResponse {
+    service
+        .handle_endorse_proposal(EndorseProposalRequest {
+            graph_id: "payments".to_string(),
+            proposal_id: proposal_id.to_string(),
+            org: org.to_string(),
+            signature: sign_message(key, proposal_id.as_bytes()),
+        })
+        .await
+        .unwrap()
+        .into_inner()
+}
+
+async fn total_blocks(service: &BlockchainServiceImpl) -> u64 {
+    service
+        .handle_get_graph_info(GetGraphInfoRequest {
+            graph_id: "payments".to_string(),
+        })
+        .await
+        .unwrap()
+        .into_inner()
+        .total_blocks
+}
+
+#[tokio::test]
+async fn test_block_is_appended_once_two_of_three_endorse() {
+    let dir = tempdir().unwrap();
+    let keys: Vec<SigningKey> = (0..3).map(|_| generate_signing_key()).collect();
+    let service = open_service(dir.path(), Duration::from_secs(60)).await;
+    create_graph(&service, &keys).await;
+
+    let proposal = propose(&service, r#"{"from":"alice","to":"bob","amount":10}"#).await;
+    assert_eq!(proposal.status, ProposalStatus::ProposalPending as i32);
+    assert_eq!(proposal.required, 2);
+    assert_eq!(total_blocks(&service).await, 1);
+
+    // A signature by the wrong key does not count
+    let response = endorse(&service, &proposal.proposal_id, "orgB", &keys[0]).await;
+    assert!(!response.success);
+    assert!(
+        response.message.contains("Invalid endorsement signature"),
+        "{}",
+        response.message
+    );
+
+    let response = endorse(&service, &proposal.proposal_id, "orgA", &keys[0]).await;
+    assert!(response.success, "{}", response.message);
+    assert!(response.block.is_none());
+    assert_eq!(
+        response.proposal.unwrap().endorsed_by,
+        vec!["orgA".to_string()]
+    );
+
+    // The second endorsement commits the block
+    let response = endorse(&service, &proposal.proposal_id, "orgC", &keys[2]).await;
+    assert!(response.success, "{}", response.message);
+    let block = response.block.unwrap();
+    assert_eq!(block.height, 1);
+    let committed = response.proposal.unwrap();
+    assert_eq!(committed.status, ProposalStatus::ProposalCommitted as i32);
+    assert_eq!(committed.block_hash, block.hash);
+    assert_eq!(total_blocks(&service).await, 2);
+
+    // Late endorsements are refused and nothing is appended twice
+    let response = endorse(&service, &proposal.proposal_id, "orgB", &keys[1]).await;
+    assert!(!response.success);
+    assert_eq!(total_blocks(&service).await, 2);
+
+    // Proposal state survives a restart
+    drop(service);
+    let reopened = open_service(dir.path(), Duration::from_secs(60)).await;
+    let stored = reopened
+        .handle_get_proposal(GetProposalRequest {
+            graph_id: "payments".to_string(),
+            proposal_id: proposal.proposal_id.clone(),
+        })
+        .await
+        .unwrap()
+        .into_inner()
+        .proposal
+        .unwrap();
+    assert_eq!(stored.status, ProposalStatus::ProposalCommitted as i32);
+    assert_eq!(
+        stored.endorsed_by,
+        vec!["orgA".to_string(), "orgC".to_string()]
+    );
+}
+
+#[tokio::test]
+async fn test_unendorsed_proposals_expire() {
+    let dir = tempdir().unwrap();
+    let keys: Vec<SigningKey> = (0..3).map(|_| generate_signing_key()).collect();
+    let service = open_service(dir.path(), Duration::from_millis(100)).await;
+    create_graph(&service, &keys).await;
+
+    let proposal = propose(&service, "tx 1").await;
+    endorse(&service, &proposal.proposal_id, "orgA", &keys[0]).await;
+    let list = |pending_only| ListProposalsRequest {
+        graph_id: "payments".to_string(),
+        pending_only,
+    };
+    let pending = service
+        .handle_list_proposals(list(true))
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(pending.proposals.len(), 1);
+
+    tokio::time::sleep(Duration::from_millis(150)).await;
+    let response = endorse(&service, &proposal.proposal_id, "orgB", &keys[1]).await;
+    assert!(!response.success);
+    assert_eq!(
+        response.proposal.unwrap().status,
+        ProposalStatus::ProposalExpired as i32
+    );
+    assert_eq!(total_blocks(&service).await, 1);
+
+    let pending = service
+        .handle_list_proposals(list(true))
+        .await
+        .unwrap()
+        .into_inner();
+    assert!(pending.proposals.is_empty());
+    let all = service
+        .handle_list_proposals(list(false))
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(all.proposals.len(), 1);
+    assert_eq!(
+        all.proposals[0].status,
+        ProposalStatus::ProposalExpired as i32
+    );
+}
@@ -0,0 +1,317 @@
+// Peer-to-peer gossip between nodes running on localhost
+
+mod common;
+
+use blockchain_grpc::application::services::ChainEvent;
+use blockchain_grpc::domain::traits::BlockchainRepository;
+use blockchain_grpc::infrastructure::grpc::blockchain::blockchain_service_server::BlockchainServiceServer;
+use blockchain_grpc::infrastructure::grpc::blockchain::*;
+use blockchain_grpc::infrastructure::grpc::gossip_client::GossipNode;
+use blockchain_grpc::infrastructure::persistence::BlockchainRepositoryImpl;
+use blockchain_grpc::BlockchainServiceImpl;
+use common::{add_block, create_graph, repository};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use tempfile::tempdir;
+use tokio::net::TcpListener;
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+use tokio_stream::wrappers::TcpListenerStream;
+use tonic::transport::Server;
+
+/// A node serving gRPC on a localhost port, optionally gossiping
+struct Node {
+    service: Arc<BlockchainServiceImpl>,
+    stop: oneshot::Sender<()>,
+    server: JoinHandle<()>,
+    gossip: Option<JoinHandle<()>>,
+}
+
+impl Node {
+    /// Stops gossip and the server and waits until the database is released
+    async fn shutdown(self) {
+        if let Some(gossip) = self.gossip {
+            gossip.abort();
+            let _ = gossip.await;
+        }
+        let _ = self.stop.send(());
+        drop(self.service);
+        self.server.await.unwrap();
+    }
+}
+
+fn open_service(data_dir: &Path) -> (BlockchainServiceImpl, Arc<BlockchainRepositoryImpl>) {
+    let repository = repository(data_dir);
+    let service = BlockchainServiceImpl::new(repository.clone()).with_default_difficulty(1);
+    (service, repository)
+}
+
+async fn serve(service: BlockchainServiceImpl, listener: TcpListener, peers: &[String]) -> Node {
+    let address = listener.local_addr().unwrap().to_string();
+    let service = Arc::new(if peers.is_empty() {
+        service
+    } else {
+        service.with_gossip(peers)
+    });
+    service.initialize().await.unwrap();
+
+    let (stop, stopped) = oneshot::channel::<()>();
+    let server = Server::builder()
+        .add_service(BlockchainServiceServer::from_arc(service.clone()))
+        .serve_with_incoming_shutdown(TcpListenerStream::new(listener), async {
+            let _ = stopped.await;
+        });
+    let server = tokio::spawn(async move {
+        server.await.unwrap();
+    });
+
+    let gossip = (!peers.is_empty()).then(|| {
+        let node = GossipNode::new(
+            service.clone(),
+            peers.to_vec(),
+            address,
+            Duration::from_millis(200),
+            2,
+        );
+        tokio::spawn(node.run())
+    });
+
+    Node {
+        service,
+        stop,
+        server,
+        gossip,
+    }
+}
+
+async fn tip(service: &BlockchainServiceImpl, graph_id: &str) -> Option<(u64, String)> {
+    service
+        .gossip_tips()
+        .await
+        .into_iter()
+        .find(|t| t.graph_id == graph_id)
+        .map(|t| (t.height, t.hash))
+}
+
+async fn wait_for_height(service: &BlockchainServiceImpl, graph_id: &str, height: u64) -> String {
+    for _ in 0..200 {
+        if let Some((tip_height, hash)) = tip(service, graph_id).await {
+            if tip_height == height {
+                return hash;
+            }
+        }
+        tokio::time::sleep(Duration::from_millis(25)).await;
+    }
+    panic!("graph '{}' never reached height {}", graph_id, height);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_blocks_spread_along_a_line_of_peers() {
+    let dirs: Vec<_> = (0..3).map(|_| tempdir().unwrap()).collect();
+    let mut listeners = Vec::new();
+    let mut addresses = Vec::new();
+    for _ in 0..3 {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        addresses.push(listener.local_addr().unwrap().to_string());
+        listeners.push(listener);
+    }
+
+    // A has history; C is fresh and only knows B, which is also fresh
+    let (a_service, _) = open_service(dirs[0].path());
+    let a_listener = listeners.remove(0);
+    let a = serve(a_service, a_listener, &[addresses[1].clone()]).await;
+    create_graph(&a.service, "payments", GraphType::Transaction).await;
+    create_graph(&a.service, "people", GraphType::Transaction).await;
+    for i in 1..=5 {
+        add_block(&a.service, "payments", &format!("tx {}", i)).await;
+    }
+
+    let (b_service, _) = open_service(dirs[1].path());
+    let b = serve(
+        b_service,
+        listeners.remove(0),
+        &[addresses[0].clone(), addresses[2].clone()],
+    )
+    .await;
+    let (c_service, _) = open_service(dirs[2].path());
+    let c = serve(c_service, listeners.remove(0), &[addresses[1].clone()]).await;
+
+    // C catches up from genesis through B, in batches of two blocks
+    let a_tip = tip(&a.service, "payments").await.unwrap().1;
+    assert_eq!(wait_for_height(&c.service, "payments", 5).await, a_tip);
+    wait_for_height(&c.service, "people", 0).await;
+
+    // A block written on C is announced and reaches A through B
+    add_block(&c.service, "people", "alice").await;
+    let c_tip = wait_for_height(&c.service, "people", 1).await;
+    assert_eq!(wait_for_height(&a.service, "people", 1).await, c_tip);
+
+    let status = b
+        .service
+        .handle_get_gossip_status()
+        .await
+        .unwrap()
+        .into_inner();
+    assert!(status.enabled);
+    assert_eq!(status.peers.len(), 2);
+    assert!(
+        status.peers.iter().all(|p| p.reachable),
+        "{:?}",
+        status.peers
+    );
+    assert!(status.peers.iter().map(|p| p.blocks_received).sum::<u64>() >= 8);
+
+    let info = c
+        .service
+        .handle_get_graph_info(GetGraphInfoRequest {
+            graph_id: "payments".to_string(),
+        })
+        .await
+        .unwrap()
+        .into_inner();
+    assert!(info.is_valid);
+
+    for node in [a, b, c] {
+        node.shutdown().await;
+    }
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_tampered_blocks_from_a_peer_are_rejected() {
+    let honest_dir = tempdir().unwrap();
+    let fresh_dir = tempdir().unwrap();
+
+    // The peer serves a block whose data was altered after mining
+    let (peer_service, repository) = open_service(honest_dir.path());
+    let peer_listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let peer_address = peer_listener.local_addr().unwrap().to_string();
+    let unreachable = TcpListener::bind("127.0.0.1:0")
+        .await
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .to_string();
+    let peer = serve(peer_service, peer_listener, &[unreachable]).await;
+    create_graph(&peer.service, "payments", GraphType::Transaction).await;
+    for i in 1..=3 {
+        add_block(&peer.service, "payments", &format!("tx {}", i)).await;
+    }
+    let mut block = repository
+        .get_block_by_height("payments", 2)
+        .await
+        .unwrap()
+        .unwrap();
+    block.data = "tx 2 (forged)".to_string();
+    repository.save_block("payments", &block).await.unwrap();
+
+    let (fresh_service, _) = open_service(fresh_dir.path());
+    let fresh = Arc::new(fresh_service.with_gossip(std::slice::from_ref(&peer_address)));
+    let node = GossipNode::new(
+        fresh.clone(),
+        vec![peer_address.clone()],
+        String::new(),
+        Duration::from_secs(60),
+        10,
+    );
+
+    let error = node.sync_with(&peer_address).await.unwrap_err().to_string();
+    assert!(error.contains("Rejected block #2"), "{}", error);
+    assert_eq!(fresh.local_height("payments").await, Some(1));
+
+    // Nothing was persisted past the last valid block
+    drop(node);
+    assert!(Arc::try_unwrap(fresh).is_ok());
+    let (reopened, _) = open_service(fresh_dir.path());
+    reopened.initialize().await.unwrap();
+    assert_eq!(reopened.local_height("payments").await, Some(1));
+
+    peer.shutdown().await;
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_competing_branches_converge_on_the_most_work() {
+    let dirs: Vec<_> = (0..2).map(|_| tempdir().unwrap()).collect();
+    let a_listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let a_address = a_listener.local_addr().unwrap().to_string();
+    let b_listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let b_address = b_listener.local_addr().unwrap().to_string();
+
+    // Both nodes gossip with an unreachable peer; syncs between them are driven by hand
+    let unreachable = TcpListener::bind("127.0.0.1:0")
+        .await
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .to_string();
+    let peers = [unreachable];
+    let a = serve(open_service(dirs[0].path()).0, a_listener, &peers).await;
+    let b = serve(open_service(dirs[1].path()).0, b_listener, &peers).await;
+    let sync = |service: &Arc<BlockchainServiceImpl>| {
+        GossipNode::new(
+            service.clone(),
+            vec![],
+            String::new(),
+            Duration::from_secs(60),
+            1,
+        )
+    };
+
+    create_graph(&a.service, "payments", GraphType::Transaction).await;
+    sync(&b.service).sync_with(&a_address).await.unwrap();
+
+    // The nodes mine competing branches on the shared genesis block
+    add_block(&a.service, "payments", "a1").await;
+    let a1 = tip(&a.service, "payments").await.unwrap().1;
+    add_block(&b.service, "payments", "b1").await;
+    add_block(&b.service, "payments", "b2").await;
+    let b2 = tip(&b.service, "payments").await.unwrap().1;
+
+    // B's branch has more work: A reorganizes onto it
+    let mut events = a.service.subscribe_events();
+    sync(&a.service).sync_with(&b_address).await.unwrap();
+    assert_eq!(tip(&a.service, "payments").await, Some((2, b2.clone())));
+    match events.recv().await.unwrap() {
+        ChainEvent::Reorganized { graph_id, reorg } => {
+            assert_eq!(graph_id, "payments");
+            assert_eq!(reorg.fork_height, 0);
+            assert_eq!(reorg.disconnected.len(), 1);
+            assert_eq!(reorg.connected.len(), 2);
+        }
+        other => panic!("expected a reorg event, got {:?}", other),
+    }
+
+    // The lighter branch is kept aside and no longer indexed on the main chain
+    let lookup = a
+        .service
+        .handle_get_block(GetBlockRequest {
+            graph_id: "payments".to_string(),
+            hash: a1.clone(),
+        })
+        .await
+        .unwrap()
+        .into_inner();
+    assert!(!lookup.success);
+    assert!(a.service.applier().knows_block("payments", &a1).await);
+
+    // B has nothing to pull back
+    assert_eq!(sync(&b.service).sync_with(&a_address).await.unwrap(), 0);
+
+    // The switch survives a restart
+    a.shutdown().await;
+    let (reopened, _) = open_service(dirs[0].path());
+    reopened.initialize().await.unwrap();
+    assert_eq!(tip(&reopened, "payments").await, Some((2, b2)));
+    assert!(reopened.applier().knows_block("payments", &a1).await);
+    let info = reopened
+        .handle_get_graph_info(GetGraphInfoRequest {
+            graph_id: "payments".to_string(),
+        })
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(info.total_blocks, 3);
+    assert!(info.is_valid);
+
+    b.shutdown().await;
+}
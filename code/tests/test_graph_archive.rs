@@ -0,0 +1,72 @@
+// Round-trip tests for graph export/import archives
+
+use blockchain_grpc::application::use_cases::graph_archive::{
+    create_archive_file, open_archive_file, ExportGraphsUseCase, ImportGraphsUseCase,
+};
+use blockchain_grpc::domain::traits::BlockchainRepository;
+use blockchain_grpc::domain::{Block, BlockchainGraph, GraphType};
+use blockchain_grpc::infrastructure::persistence::{BlockchainRepositoryImpl, RocksDbAdapter};
+use std::sync::Arc;
+use tempfile::tempdir;
+
+#[tokio::test]
+async fn test_export_import_round_trip() {
+    let source_dir = tempdir().unwrap();
+    let source = Arc::new(BlockchainRepositoryImpl::new(Arc::new(
+        RocksDbAdapter::new(source_dir.path()).unwrap(),
+    )));
+
+    let mut graph = BlockchainGraph::new(
+        "assets".to_string(),
+        GraphType::Asset,
+        "Asset registry".to_string(),
+        1,
+    );
+    source
+        .save_block("assets", graph.get_latest_block().unwrap())
+        .await
+        .unwrap();
+    for i in 0..3 {
+        let latest = graph.get_latest_block().unwrap().clone();
+        let block = Block::new(
+            latest.hash,
+            format!("asset {}", i),
+            "assets".to_string(),
+            latest.height + 1,
+            vec![],
+        );
+        let mined = graph.add_block(block).unwrap();
+        source.save_block("assets", &mined).await.unwrap();
+    }
+    source.save_graph(&graph).await.unwrap();
+
+    let archive_dir = tempdir().unwrap();
+    let archive_path = archive_dir.path().join("assets.jsonl.gz");
+    let writer = create_archive_file(&archive_path, true).unwrap();
+    let manifest = ExportGraphsUseCase::new(source)
+        .execute(&[], writer)
+        .await
+        .unwrap();
+    assert_eq!(manifest.graphs[0].block_count, 4);
+
+    let target_dir = tempdir().unwrap();
+    let target = Arc::new(BlockchainRepositoryImpl::new(Arc::new(
+        RocksDbAdapter::new(target_dir.path()).unwrap(),
+    )));
+    let importer = ImportGraphsUseCase::new(target.clone());
+    importer
+        .execute(open_archive_file(&archive_path).unwrap())
+        .await
+        .unwrap();
+
+    let imported = target.get_graph("assets").await.unwrap().unwrap();
+    assert_eq!(imported.chain, graph.chain);
+    assert_eq!(imported.graph_type, GraphType::Asset);
+    assert!(imported.is_valid());
+
+    // Importing the same archive again is rejected
+    let again = importer
+        .execute(open_archive_file(&archive_path).unwrap())
+        .await;
+    assert!(again.is_err());
+}
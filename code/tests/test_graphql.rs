@@ -0,0 +1,175 @@
+// GraphQL traversal of graphs, blocks and cross-references
+
+mod common;
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use blockchain_grpc::infrastructure::grpc::blockchain::*;
+use blockchain_grpc::infrastructure::http::graphql::{GraphQlExecutor, GraphQlRequest};
+use blockchain_grpc::infrastructure::http::router;
+use blockchain_grpc::BlockchainServiceImpl;
+use common::{block_request, create_graph, open_service, try_add_block};
+use serde_json::{json, Value};
+use std::sync::Arc;
+use tempfile::tempdir;
+use tower::ServiceExt;
+
+async fn add_block(
+    service: &BlockchainServiceImpl,
+    graph_id: &str,
+    data: &str,
+    references: Vec<String>,
+) -> String {
+    let request = AddBlockRequest {
+        cross_references: references,
+        ..block_request(graph_id, data)
+    };
+    let response = try_add_block(service, request).await;
+    assert!(response.success, "{}", response.message);
+    response.block.unwrap().hash
+}
+
+async fn run(executor: &GraphQlExecutor, query: &str, variables: Value) -> Value {
+    executor
+        .execute(GraphQlRequest {
+            query: query.to_string(),
+            variables: variables.as_object().cloned(),
+            operation_name: None,
+        })
+        .await
+}
+
+fn error(response: &Value) -> &str {
+    response["errors"][0]["message"]
+        .as_str()
+        .unwrap_or_default()
+}
+
+#[tokio::test]
+async fn test_nested_traversal_pagination_and_limits() {
+    let dir = tempdir().unwrap();
+    let service = Arc::new(open_service(dir.path()).await);
+
+    create_graph(&service, "identity", GraphType::Identity).await;
+    create_graph(&service, "transactions", GraphType::Transaction).await;
+    let alice = add_block(&service, "identity", r#"{"name":"alice"}"#, vec![]).await;
+    for i in 0..4 {
+        add_block(
+            &service,
+            "transactions",
+            &format!("payment {}", i),
+            vec![alice.clone()],
+        )
+        .await;
+    }
+    let executor = GraphQlExecutor::new(service.clone()).with_limits(6, 20, 3);
+
+    // block -> referenced identity block -> its graph
+    let response = run(
+        &executor,
+        r#"query Payment($height: Int!) {
+            block(graphId: "transactions", height: $height) {
+                data
+                previous { height }
+                crossReferences {
+                    hash
+                    block { data graph { id type blockCount } }
+                }
+            }
+        }"#,
+        json!({ "height": 2 }),
+    )
+    .await;
+    assert!(response.get("errors").is_none(), "{}", response);
+    let block = &response["data"]["block"];
+    assert_eq!(block["data"], "payment 1");
+    assert_eq!(block["previous"]["height"], 1);
+    let reference = &block["crossReferences"][0];
+    assert_eq!(reference["hash"], alice.as_str());
+    assert_eq!(reference["block"]["data"], r#"{"name":"alice"}"#);
+    assert_eq!(
+        reference["block"]["graph"],
+        json!({ "id": "identity", "type": "IDENTITY", "blockCount": 2 })
+    );
+
+    // Pages follow the height cursor
+    let page = |after: Value| {
+        run(
+            &executor,
+            r#"query Page($after: Int) {
+                graph(id: "transactions") {
+                    blocks(first: 3, after: $after) { nodes { height } hasNextPage endCursor }
+                }
+            }"#,
+            json!({ "after": after }),
+        )
+    };
+    let first = page(Value::Null).await;
+    let blocks = &first["data"]["graph"]["blocks"];
+    assert_eq!(
+        blocks["nodes"],
+        json!([{ "height": 0 }, { "height": 1 }, { "height": 2 }])
+    );
+    assert_eq!(blocks["hasNextPage"], true);
+    let second = page(blocks["endCursor"].clone()).await;
+    let blocks = &second["data"]["graph"]["blocks"];
+    assert_eq!(blocks["nodes"], json!([{ "height": 3 }, { "height": 4 }]));
+    assert_eq!(blocks["hasNextPage"], false);
+
+    // Aliases, unknown objects and the graph list
+    let response = run(
+        &executor,
+        r#"{ graphs { id } missing: graph(id: "nope") { id } tip: graph(id: "identity") { tip { hash } } }"#,
+        json!({}),
+    )
+    .await;
+    assert_eq!(
+        response["data"]["graphs"],
+        json!([{ "id": "identity" }, { "id": "transactions" }])
+    );
+    assert_eq!(response["data"]["missing"], Value::Null);
+    assert_eq!(response["data"]["tip"]["tip"]["hash"], alice.as_str());
+
+    // Limits and malformed queries are reported as errors
+    let deep = r#"{ graph(id: "transactions") { tip { previous { previous { previous { previous { height } } } } } } }"#;
+    assert!(
+        error(&run(&executor, deep, json!({})).await).contains("depth 7 exceeds the limit of 6")
+    );
+    let wide = format!("{{ {} }}", "g: graphs { id } ".repeat(11));
+    assert!(error(&run(&executor, &wide, json!({})).await).contains("more than 20 fields"));
+    let large = r#"{ graph(id: "transactions") { blocks(first: 50) { nodes { height } } } }"#;
+    assert!(error(&run(&executor, large, json!({})).await).contains("between 1 and 3"));
+    assert!(
+        error(&run(&executor, "{ graphs { owner } }", json!({})).await)
+            .contains("Unknown field 'owner'")
+    );
+    assert!(
+        error(&run(&executor, "{ graphs }", json!({})).await).contains("selection of subfields")
+    );
+    assert!(error(&run(&executor, "mutation { x }", json!({})).await).contains("not supported"));
+}
+
+#[tokio::test]
+async fn test_request_bodies_over_the_limit_are_refused() {
+    let dir = tempdir().unwrap();
+    let service = Arc::new(open_service(dir.path()).await);
+    let app = router(Arc::new(GraphQlExecutor::new(service)), None, 1024);
+
+    let post = |query: String| {
+        Request::post("/graphql")
+            .header("content-type", "application/json")
+            .body(Body::from(json!({ "query": query }).to_string()))
+            .unwrap()
+    };
+    let response = app
+        .clone()
+        .oneshot(post("{ graphs { id } }".to_string()))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = app
+        .oneshot(post(format!("{{ graphs {{ id }} }} #{}", "x".repeat(2048))))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+}
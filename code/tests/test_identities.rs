@@ -0,0 +1,195 @@
+// DID registry on identity graphs and identities signing data of other graphs
+
+mod common;
+
+use blockchain_grpc::domain::consensus::{generate_signing_key, public_key_hex};
+use blockchain_grpc::domain::identity::sign_as_identity;
+use blockchain_grpc::domain::traits::BlockchainRepository;
+use blockchain_grpc::domain::IdentityOperation;
+use blockchain_grpc::infrastructure::grpc::blockchain::*;
+use blockchain_grpc::BlockchainServiceImpl;
+use common::{
+    block_request, create_graph_with, graph_request, repository, start_service, try_add_block,
+};
+use tempfile::tempdir;
+
+const ALICE: &str = "did:example:alice";
+
+async fn create_graph(service: &BlockchainServiceImpl, graph_id: &str, graph_type: GraphType) {
+    create_graph_with(
+        service,
+        CreateGraphRequest {
+            default_schema: true,
+            ..graph_request(graph_id, graph_type)
+        },
+    )
+    .await;
+}
+
+async fn add_block(
+    service: &BlockchainServiceImpl,
+    graph_id: &str,
+    data: &str,
+) -> AddBlockResponse {
+    try_add_block(service, block_request(graph_id, data)).await
+}
+
+async fn resolve(service: &BlockchainServiceImpl, height: u64) -> IdentityResponse {
+    service
+        .handle_resolve_identity(ResolveIdentityRequest {
+            graph_id: "ids".to_string(),
+            subject_id: ALICE.to_string(),
+            height,
+        })
+        .await
+        .unwrap()
+        .into_inner()
+}
+
+#[tokio::test]
+async fn test_identity_lifecycle_resolution_and_signers() {
+    let dir = tempdir().unwrap();
+    let repository = repository(dir.path());
+    let service = start_service(repository.clone(), |service| service).await;
+
+    create_graph(&service, "ids", GraphType::Identity).await;
+    create_graph(&service, "payments", GraphType::Transaction).await;
+    let first = generate_signing_key();
+    let second = generate_signing_key();
+
+    // Registration proves possession of one of the keys
+    let register = IdentityOperation::Register {
+        subject_id: ALICE.to_string(),
+        public_keys: vec![public_key_hex(&first)],
+        attributes_hash: "9f86d081".to_string(),
+        signature: String::new(),
+    };
+    assert!(
+        !add_block(
+            &service,
+            "ids",
+            &register.clone().signed(&second, "ids", 0).to_data()
+        )
+        .await
+        .success
+    );
+    let registered = add_block(
+        &service,
+        "ids",
+        &register.signed(&first, "ids", 0).to_data(),
+    )
+    .await;
+    assert!(registered.success, "{}", registered.message);
+
+    // Payments signed by the identity are accepted; other keys and graphs are not
+    let payment = r#"{"from": "alice", "to": "bob", "amount": 10}"#;
+    let signed = |key, signer_graph: &str| {
+        sign_as_identity(payment, "payments", signer_graph, ALICE, key).unwrap()
+    };
+    let response = add_block(&service, "payments", &signed(&first, "ids")).await;
+    assert!(response.success, "{}", response.message);
+    assert!(add_block(&service, "payments", &signed(&second, "ids"))
+        .await
+        .message
+        .contains("active key"));
+    assert!(add_block(&service, "payments", &signed(&first, "payments"))
+        .await
+        .message
+        .contains("not an identity graph"));
+    let unknown =
+        sign_as_identity(payment, "payments", "ids", "did:example:carol", &first).unwrap();
+    assert!(add_block(&service, "payments", &unknown)
+        .await
+        .message
+        .contains("not registered"));
+
+    // Blocks arriving by replication, clustering or gossip get the same check
+    let tip = repository
+        .get_latest_block("payments")
+        .await
+        .unwrap()
+        .unwrap();
+    let mut forged = blockchain_grpc::domain::Block::new(
+        tip.hash,
+        unknown.clone(),
+        "payments".to_string(),
+        tip.height + 1,
+        vec![],
+    );
+    forged.mine_block(1);
+    let err = service.applier().apply_block(forged).await.unwrap_err();
+    assert!(err.to_string().contains("does not match a key"), "{}", err);
+
+    // Rotation moves signing to the new key; resolution follows the height
+    let rotate = IdentityOperation::Rotate {
+        subject_id: ALICE.to_string(),
+        public_keys: vec![public_key_hex(&second)],
+        attributes_hash: None,
+        signature: String::new(),
+    };
+    let rotated = add_block(&service, "ids", &rotate.signed(&first, "ids", 1).to_data()).await;
+    assert!(rotated.success, "{}", rotated.message);
+    assert!(
+        !add_block(&service, "payments", &signed(&first, "ids"))
+            .await
+            .success
+    );
+    assert!(
+        add_block(&service, "payments", &signed(&second, "ids"))
+            .await
+            .success
+    );
+
+    let before = resolve(&service, 1).await.document.unwrap();
+    assert_eq!(before.public_keys, vec![public_key_hex(&first)]);
+    assert_eq!(before.operation, "register");
+    let current = resolve(&service, 0).await.document.unwrap();
+    assert_eq!(current.public_keys, vec![public_key_hex(&second)]);
+    assert_eq!(current.attributes_hash, "9f86d081");
+    assert_eq!(current.version, 2);
+
+    // Revoked identities sign nothing and cannot change
+    let revoke = IdentityOperation::Revoke {
+        subject_id: ALICE.to_string(),
+        signature: String::new(),
+    };
+    let revoked = add_block(&service, "ids", &revoke.signed(&second, "ids", 2).to_data()).await;
+    assert!(revoked.success, "{}", revoked.message);
+    assert_eq!(
+        resolve(&service, 0).await.document.unwrap().status,
+        IdentityStatus::IdentityRevoked as i32
+    );
+    assert_eq!(
+        resolve(&service, 2).await.document.unwrap().status,
+        IdentityStatus::IdentityActive as i32
+    );
+    assert!(
+        !add_block(&service, "payments", &signed(&second, "ids"))
+            .await
+            .success
+    );
+    let rotate_again = IdentityOperation::Rotate {
+        subject_id: ALICE.to_string(),
+        public_keys: vec![public_key_hex(&first)],
+        attributes_hash: None,
+        signature: String::new(),
+    };
+    let response = add_block(
+        &service,
+        "ids",
+        &rotate_again.signed(&second, "ids", 3).to_data(),
+    )
+    .await;
+    assert!(response.message.contains("revoked"), "{}", response.message);
+
+    let missing = service
+        .handle_resolve_identity(ResolveIdentityRequest {
+            graph_id: "ids".to_string(),
+            subject_id: "did:example:carol".to_string(),
+            height: 0,
+        })
+        .await
+        .unwrap()
+        .into_inner();
+    assert!(!missing.success);
+}
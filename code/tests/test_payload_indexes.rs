@@ -0,0 +1,123 @@
+// Secondary indexes over JSON block data and QueryBlocks
+
+mod common;
+
+use blockchain_grpc::infrastructure::grpc::blockchain::*;
+use blockchain_grpc::BlockchainServiceImpl;
+use common::{add_block, create_graph, open_service};
+use tempfile::tempdir;
+
+async fn add_payment(service: &BlockchainServiceImpl, from: &str, to: &str, amount: u64) {
+    let data = format!(r#"{{"from":"{}","to":"{}","amount":{}}}"#, from, to, amount);
+    add_block(service, "payments", &data).await;
+}
+
+async fn create_index(service: &BlockchainServiceImpl, name: &str, path: &str) -> IndexResponse {
+    service
+        .handle_create_index(CreateIndexRequest {
+            graph_id: "payments".to_string(),
+            name: name.to_string(),
+            path: path.to_string(),
+        })
+        .await
+        .unwrap()
+        .into_inner()
+}
+
+async fn query(
+    service: &BlockchainServiceImpl,
+    index: &str,
+    value: &str,
+    page_size: u32,
+    page_token: &str,
+) -> QueryBlocksResponse {
+    service
+        .handle_query_blocks(QueryBlocksRequest {
+            graph_id: "payments".to_string(),
+            index: index.to_string(),
+            value: value.to_string(),
+            page_size,
+            page_token: page_token.to_string(),
+        })
+        .await
+        .unwrap()
+        .into_inner()
+}
+
+fn heights(response: &QueryBlocksResponse) -> Vec<u64> {
+    response.blocks.iter().map(|b| b.height).collect()
+}
+
+#[tokio::test]
+async fn test_indexes_are_backfilled_maintained_and_paged() {
+    let dir = tempdir().unwrap();
+    let service = open_service(dir.path()).await;
+    create_graph(&service, "payments", GraphType::Transaction).await;
+
+    add_payment(&service, "alice", "bob", 10).await;
+    add_payment(&service, "carol", "alice", 5).await;
+    add_payment(&service, "alice", "dave", 7).await;
+
+    // Existing blocks are indexed when the index is created
+    let response = create_index(&service, "from", "/from").await;
+    assert!(response.success, "{}", response.message);
+    assert_eq!(response.blocks_indexed, 3);
+    assert!(!create_index(&service, "from", "/from").await.success);
+    assert!(!create_index(&service, "bad name", "/from").await.success);
+    assert!(create_index(&service, "amount", "/amount").await.success);
+    assert_eq!(
+        heights(&query(&service, "from", "alice", 0, "").await),
+        vec![1, 3]
+    );
+
+    // New blocks are indexed on commit
+    add_payment(&service, "alice", "erin", 7).await;
+    add_payment(&service, "alice", "frank", 1).await;
+    assert_eq!(
+        heights(&query(&service, "amount", "7", 0, "").await),
+        vec![3, 4]
+    );
+
+    // Pages follow height order
+    let first = query(&service, "from", "alice", 2, "").await;
+    assert_eq!(heights(&first), vec![1, 3]);
+    let second = query(&service, "from", "alice", 2, &first.next_page_token).await;
+    assert_eq!(heights(&second), vec![4, 5]);
+    assert!(second.next_page_token.is_empty());
+
+    assert!(query(&service, "from", "nobody", 0, "")
+        .await
+        .blocks
+        .is_empty());
+    let missing = query(&service, "to", "bob", 0, "").await;
+    assert!(!missing.success);
+    assert!(missing.message.contains("not found"), "{}", missing.message);
+
+    // Definitions and entries survive a restart; dropping removes them
+    drop(service);
+    let reopened = open_service(dir.path()).await;
+    let listed = reopened
+        .handle_list_indexes(ListIndexesRequest {
+            graph_id: "payments".to_string(),
+        })
+        .await
+        .unwrap()
+        .into_inner();
+    let names: Vec<_> = listed.indexes.iter().map(|i| i.name.as_str()).collect();
+    assert_eq!(names, ["amount", "from"]);
+    assert_eq!(
+        heights(&query(&reopened, "from", "carol", 0, "").await),
+        vec![2]
+    );
+
+    let dropped = reopened
+        .handle_drop_index(DropIndexRequest {
+            graph_id: "payments".to_string(),
+            name: "from".to_string(),
+        })
+        .await
+        .unwrap()
+        .into_inner();
+    assert!(dropped.success);
+    assert!(!query(&reopened, "from", "alice", 0, "").await.success);
+}
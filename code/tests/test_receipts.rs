@@ -0,0 +1,137 @@
+// Notarization receipts returned by AddBlock and verified offline against an export
+
+mod common;
+
+use blockchain_grpc::application::use_cases::graph_archive::{
+    ExportGraphsUseCase, ImportGraphsUseCase,
+};
+use blockchain_grpc::domain::consensus::{generate_signing_key, public_key_hex};
+use blockchain_grpc::domain::receipt::{verify_receipt, Receipt, ReceiptAnchor};
+use blockchain_grpc::infrastructure::grpc::blockchain::*;
+use common::{
+    add_block, block_request, create_graph, create_graph_with, graph_request, open_service,
+    repository, start_service, try_add_block,
+};
+use tempfile::tempdir;
+
+fn receipt_request(graph_id: &str, data: &str) -> AddBlockRequest {
+    AddBlockRequest {
+        include_receipt: true,
+        ..block_request(graph_id, data)
+    }
+}
+
+fn receipt_from_proto(
+    receipt: blockchain_grpc::infrastructure::grpc::blockchain::Receipt,
+) -> Receipt {
+    Receipt {
+        graph_id: receipt.graph_id,
+        height: receipt.height,
+        block_hash: receipt.block_hash,
+        previous_hash: receipt.previous_hash,
+        timestamp: receipt.timestamp,
+        anchor: receipt.anchor.map(|anchor| ReceiptAnchor {
+            graph_id: anchor.graph_id,
+            height: anchor.height,
+            hash: anchor.hash,
+            anchored_height: anchor.anchored_height,
+            anchored_hash: anchor.anchored_hash,
+        }),
+        signer: receipt.signer,
+        signature: receipt.signature,
+    }
+}
+
+#[tokio::test]
+async fn test_receipt_verifies_against_exported_chain() {
+    let dir = tempdir().unwrap();
+    let repository = repository(dir.path());
+    let key = generate_signing_key();
+    let service = start_service(repository.clone(), |service| {
+        service
+            .with_receipt_key(key.clone())
+            .with_anchoring("anchors")
+    })
+    .await;
+    create_graph(&service, "contracts", GraphType::Custom).await;
+
+    // No receipt unless asked for
+    let plain = try_add_block(&service, block_request("contracts", "draft")).await;
+    assert!(plain.success && plain.receipt.is_none());
+    service.write_anchor().await.unwrap();
+
+    let signed = try_add_block(&service, receipt_request("contracts", "signed")).await;
+    assert!(signed.success, "{}", signed.message);
+    let receipt = receipt_from_proto(signed.receipt.unwrap());
+    assert_eq!(receipt.block_hash, signed.block.unwrap().hash);
+    let anchor = receipt.anchor.clone().unwrap();
+    assert_eq!(
+        (anchor.graph_id.as_str(), anchor.anchored_height),
+        ("anchors", 1)
+    );
+
+    add_block(&service, "contracts", "amended").await;
+
+    let mut archive = Vec::new();
+    ExportGraphsUseCase::new(repository)
+        .execute(&[], &mut archive)
+        .await
+        .unwrap();
+    let (_, graphs) = ImportGraphsUseCase::read_archive(archive.as_slice()).unwrap();
+
+    let node_key = public_key_hex(&key);
+    verify_receipt(&receipt, &graphs, Some(&node_key), None).unwrap();
+    verify_receipt(
+        &receipt,
+        &graphs,
+        Some(&node_key),
+        Some(&receipt.block_hash),
+    )
+    .unwrap();
+    let err = verify_receipt(
+        &receipt,
+        &graphs,
+        Some(&public_key_hex(&generate_signing_key())),
+        None,
+    )
+    .unwrap_err();
+    assert!(err.contains("not by the trusted key"), "{}", err);
+
+    // A correctly signed receipt naming a tip the anchor never recorded
+    let mut wrong_anchor = anchor;
+    wrong_anchor.anchored_hash = receipt.block_hash.clone();
+    let block = &graphs.iter().find(|g| g.id == "contracts").unwrap().chain[2];
+    let forged = Receipt::issue(&key, block, Some(wrong_anchor));
+    let err = verify_receipt(&forged, &graphs, None, None).unwrap_err();
+    assert!(err.contains("does not record"), "{}", err);
+
+    // Endorsed graphs commit later, so a receipt cannot be returned
+    create_graph_with(
+        &service,
+        CreateGraphRequest {
+            endorsement: Some(EndorsementPolicy {
+                required: 1,
+                endorsers: vec![Endorser {
+                    org: "notary".to_string(),
+                    public_key: node_key.clone(),
+                }],
+            }),
+            ..graph_request("escrow", GraphType::Custom)
+        },
+    )
+    .await;
+    let refused = try_add_block(&service, receipt_request("escrow", "deposit")).await;
+    assert!(!refused.success && refused.proposal.is_none());
+    assert!(
+        refused.message.contains("requires endorsement"),
+        "{}",
+        refused.message
+    );
+
+    // Receipts need a node key
+    let other_dir = tempdir().unwrap();
+    let unsigned = open_service(other_dir.path()).await;
+    let refused = try_add_block(&unsigned, receipt_request("contracts", "x")).await;
+    assert!(!refused.success);
+    assert!(refused.message.contains("no key to sign receipts"));
+}
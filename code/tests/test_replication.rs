@@ -0,0 +1,276 @@
+// Leader/follower replication between nodes running on localhost
+
+mod common;
+
+use blockchain_grpc::infrastructure::grpc::blockchain::blockchain_service_server::BlockchainServiceServer;
+use blockchain_grpc::infrastructure::grpc::blockchain::*;
+use blockchain_grpc::infrastructure::grpc::replication_client::ReplicationFollower;
+use blockchain_grpc::BlockchainServiceImpl;
+use common::{block_request, graph_request, open_service_with, try_add_block};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use tempfile::tempdir;
+use tokio::net::TcpListener;
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+use tokio_stream::wrappers::TcpListenerStream;
+use tonic::transport::Server;
+
+/// A node serving gRPC on an ephemeral localhost port
+struct Node {
+    service: Arc<BlockchainServiceImpl>,
+    addr: String,
+    stop: oneshot::Sender<()>,
+    server: JoinHandle<()>,
+}
+
+impl Node {
+    /// Stops the server and waits until the database is released
+    async fn shutdown(self) {
+        let _ = self.stop.send(());
+        drop(self.service);
+        self.server.await.unwrap();
+    }
+}
+
+async fn start_node(data_dir: &Path, leader: Option<String>) -> Node {
+    let service = Arc::new(
+        open_service_with(data_dir, |service| match leader {
+            Some(leader) => service.with_leader(leader),
+            None => service,
+        })
+        .await,
+    );
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap().to_string();
+    let (stop, stopped) = oneshot::channel::<()>();
+    let server = Server::builder()
+        .add_service(BlockchainServiceServer::from_arc(service.clone()))
+        .serve_with_incoming_shutdown(TcpListenerStream::new(listener), async {
+            let _ = stopped.await;
+        });
+    let server = tokio::spawn(async move {
+        server.await.unwrap();
+    });
+
+    Node {
+        service,
+        addr,
+        stop,
+        server,
+    }
+}
+
+/// Replicates `node` from `leader_addr` in the background
+fn follow(node: &Node, leader_addr: &str) -> JoinHandle<()> {
+    let follower = ReplicationFollower::new(
+        node.service.clone(),
+        leader_addr.to_string(),
+        Duration::from_millis(100),
+    );
+    tokio::spawn(follower.run())
+}
+
+async fn create_graph(service: &BlockchainServiceImpl, graph_id: &str) -> CreateGraphResponse {
+    service
+        .handle_create_graph(graph_request(graph_id, GraphType::Transaction))
+        .await
+        .unwrap()
+        .into_inner()
+}
+
+async fn add_block(
+    service: &BlockchainServiceImpl,
+    graph_id: &str,
+    data: &str,
+) -> AddBlockResponse {
+    try_add_block(service, block_request(graph_id, data)).await
+}
+
+async fn latest_hash(service: &BlockchainServiceImpl, graph_id: &str) -> Option<String> {
+    let response = service
+        .handle_get_latest_block(GetLatestBlockRequest {
+            graph_id: graph_id.to_string(),
+        })
+        .await
+        .unwrap()
+        .into_inner();
+    response.block.map(|b| b.hash)
+}
+
+/// Polls until the follower reports no lag and has the leader's tips
+async fn wait_for_sync(
+    leader: &BlockchainServiceImpl,
+    follower: &BlockchainServiceImpl,
+    graph_ids: &[&str],
+) {
+    for _ in 0..200 {
+        let status = follower
+            .handle_get_replication_status()
+            .await
+            .unwrap()
+            .into_inner();
+        let mut in_sync = status.connected;
+        for graph_id in graph_ids {
+            in_sync &= latest_hash(follower, graph_id).await == latest_hash(leader, graph_id).await;
+        }
+        if in_sync {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    panic!("follower did not catch up with the leader");
+}
+
+#[tokio::test]
+async fn test_follower_catches_up_and_streams_live_commits() {
+    let leader_dir = tempdir().unwrap();
+    let follower_dir = tempdir().unwrap();
+
+    let leader_node = start_node(leader_dir.path(), None).await;
+    let leader = leader_node.service.clone();
+    assert!(create_graph(&leader, "payments").await.success);
+    assert!(add_block(&leader, "payments", "tx 1").await.success);
+    assert!(add_block(&leader, "payments", "tx 2").await.success);
+
+    let follower_node = start_node(follower_dir.path(), Some(leader_node.addr.clone())).await;
+    let follower = follower_node.service.clone();
+    let sync = follow(&follower_node, &leader_node.addr);
+    wait_for_sync(&leader, &follower, &["payments"]).await;
+
+    // Live commits, including a graph created after the follower connected
+    assert!(add_block(&leader, "payments", "tx 3").await.success);
+    assert!(create_graph(&leader, "people").await.success);
+    assert!(add_block(&leader, "people", "alice").await.success);
+    wait_for_sync(&leader, &follower, &["payments", "people"]).await;
+
+    // Followers are read-only
+    let rejected = add_block(&follower, "payments", "tx 4").await;
+    assert!(!rejected.success);
+    assert!(rejected.message.contains("read-only"));
+    assert!(!create_graph(&follower, "other").await.success);
+
+    let info = follower
+        .handle_get_graph_info(GetGraphInfoRequest {
+            graph_id: "payments".to_string(),
+        })
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(info.total_blocks, 4);
+    assert!(info.is_valid);
+
+    let status = follower
+        .handle_get_replication_status()
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(status.role, "follower");
+
+    let leader_status = leader
+        .handle_get_replication_status()
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(leader_status.role, "leader");
+    assert_eq!(leader_status.connected_followers, 1);
+
+    sync.abort();
+}
+
+#[tokio::test]
+async fn test_follower_resumes_from_its_own_tips() {
+    let leader_dir = tempdir().unwrap();
+    let follower_dir = tempdir().unwrap();
+
+    let leader_node = start_node(leader_dir.path(), None).await;
+    let leader = leader_node.service.clone();
+    assert!(create_graph(&leader, "payments").await.success);
+    assert!(add_block(&leader, "payments", "tx 1").await.success);
+
+    let follower_node = start_node(follower_dir.path(), Some(leader_node.addr.clone())).await;
+    let sync = follow(&follower_node, &leader_node.addr);
+    wait_for_sync(&leader, &follower_node.service, &["payments"]).await;
+    sync.abort();
+    let _ = sync.await;
+    follower_node.shutdown().await;
+
+    // Commits made while the follower is offline are replayed on restart
+    assert!(add_block(&leader, "payments", "tx 2").await.success);
+
+    let follower_node = start_node(follower_dir.path(), Some(leader_node.addr.clone())).await;
+    let follower = follower_node.service.clone();
+    let sync = follow(&follower_node, &leader_node.addr);
+    wait_for_sync(&leader, &follower, &["payments"]).await;
+
+    let status = follower
+        .handle_get_replication_status()
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(status.graphs.len(), 1);
+    assert_eq!(status.graphs[0].local_height, 2);
+
+    sync.abort();
+}
+
+#[tokio::test]
+async fn test_follower_rejects_tampered_block() {
+    let leader_dir = tempdir().unwrap();
+    let follower_dir = tempdir().unwrap();
+
+    let leader = start_node(leader_dir.path(), None).await.service;
+    let follower = start_node(follower_dir.path(), Some("127.0.0.1:1".to_string()))
+        .await
+        .service;
+
+    assert!(create_graph(&leader, "payments").await.success);
+    let mut block = add_block(&leader, "payments", "tx 1").await.block.unwrap();
+    let genesis = leader
+        .handle_get_block_range(GetBlockRangeRequest {
+            graph_id: "payments".to_string(),
+            start_height: 0,
+            end_height: 0,
+        })
+        .await
+        .unwrap()
+        .into_inner()
+        .blocks
+        .remove(0);
+
+    let created = ReplicationEvent {
+        event: Some(replication_event::Event::GraphCreated(ReplicatedGraph {
+            graph_id: "payments".to_string(),
+            graph_type: 0,
+            description: "Replication test".to_string(),
+            created_at: 0,
+            difficulty: 1,
+            genesis: Some(genesis),
+            consensus: None,
+            endorsement: None,
+            schema: String::new(),
+            governance: None,
+        })),
+    };
+    follower.apply_replication_event(created).await.unwrap();
+
+    block.data = "tx 1 (tampered)".to_string();
+    let tampered = ReplicationEvent {
+        event: Some(replication_event::Event::Block(block)),
+    };
+    let err = follower
+        .apply_replication_event(tampered)
+        .await
+        .unwrap_err();
+    assert!(err.contains("Invalid block hash"));
+    let info = follower
+        .handle_get_graph_info(GetGraphInfoRequest {
+            graph_id: "payments".to_string(),
+        })
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(info.total_blocks, 1);
+}
@@ -0,0 +1,170 @@
+// JSON Schema validation of block data, versioned by height
+
+mod common;
+
+use blockchain_grpc::domain::consensus::{generate_signing_key, public_key_hex, sign_message};
+use blockchain_grpc::infrastructure::grpc::blockchain::*;
+use blockchain_grpc::BlockchainServiceImpl;
+use common::{block_request, graph_request, open_service, try_add_block};
+use ed25519_dalek::SigningKey;
+use tempfile::tempdir;
+
+async fn add_block(service: &BlockchainServiceImpl, data: &str) -> AddBlockResponse {
+    try_add_block(service, block_request("payments", data)).await
+}
+
+/// Governance of a single "admin" org holding `key`
+fn governance(key: &SigningKey) -> EndorsementPolicy {
+    EndorsementPolicy {
+        required: 1,
+        endorsers: vec![Endorser {
+            org: "admin".to_string(),
+            public_key: public_key_hex(key),
+        }],
+    }
+}
+
+/// Approves the settings change proposed in `response` as the admin org
+async fn approve(
+    service: &BlockchainServiceImpl,
+    response: AddBlockResponse,
+    key: &SigningKey,
+) -> ProposalResponse {
+    let proposal_id = response.proposal.unwrap().proposal_id;
+    service
+        .handle_endorse_proposal(EndorseProposalRequest {
+            graph_id: "payments".to_string(),
+            signature: sign_message(key, proposal_id.as_bytes()),
+            proposal_id,
+            org: "admin".to_string(),
+        })
+        .await
+        .unwrap()
+        .into_inner()
+}
+
+async fn graph_info(service: &BlockchainServiceImpl) -> GetGraphInfoResponse {
+    service
+        .handle_get_graph_info(GetGraphInfoRequest {
+            graph_id: "payments".to_string(),
+        })
+        .await
+        .unwrap()
+        .into_inner()
+}
+
+#[tokio::test]
+async fn test_block_data_follows_the_schema_in_force() {
+    let dir = tempdir().unwrap();
+    let replica_dir = tempdir().unwrap();
+    let service = open_service(dir.path()).await;
+    let mut events = service.subscribe_events();
+    let admin = generate_signing_key();
+
+    let create = |schema: &str, default_schema| CreateGraphRequest {
+        schema: schema.to_string(),
+        default_schema,
+        governance: Some(governance(&admin)),
+        ..graph_request("payments", GraphType::Transaction)
+    };
+    let response = service
+        .handle_create_graph(create(r#"{"type": "object", "pattern": "x"}"#, false))
+        .await
+        .unwrap()
+        .into_inner();
+    assert!(!response.success);
+    assert!(
+        response.message.contains("unsupported keyword 'pattern'"),
+        "{}",
+        response.message
+    );
+    let response = service
+        .handle_create_graph(create("", true))
+        .await
+        .unwrap()
+        .into_inner();
+    assert!(response.success, "{}", response.message);
+    assert!(graph_info(&service)
+        .await
+        .schema
+        .contains("\"Transaction\""));
+
+    // The built-in transaction schema is enforced before mining
+    assert!(
+        add_block(&service, r#"{"from":"alice","to":"bob","amount":10}"#)
+            .await
+            .success
+    );
+    let response = add_block(&service, r#"{"from":"alice","to":"bob","amount":-1}"#).await;
+    assert!(!response.success);
+    assert!(response.message.contains("/amount"), "{}", response.message);
+    assert!(!add_block(&service, "tx 1").await.success);
+    assert_eq!(graph_info(&service).await.total_blocks, 2);
+
+    // A custom schema applies from its own block on, once the governance approves it
+    let response = service
+        .handle_update_schema(UpdateSchemaRequest {
+            graph_id: "payments".to_string(),
+            schema: r#"{"type": "object", "required": ["invoice"]}"#.to_string(),
+            default_schema: false,
+        })
+        .await
+        .unwrap()
+        .into_inner();
+    assert!(response.success, "{}", response.message);
+    assert!(!graph_info(&service).await.schema.contains("invoice"));
+    let approval = approve(&service, response, &admin).await;
+    assert!(approval.success, "{}", approval.message);
+    let response = add_block(&service, r#"{"from":"alice","to":"bob","amount":10}"#).await;
+    assert!(
+        response
+            .message
+            .contains("missing required property 'invoice'"),
+        "{}",
+        response.message
+    );
+    assert!(add_block(&service, r#"{"invoice": "INV-1"}"#).await.success);
+
+    // Schema changes only go through UpdateSchema and its checks
+    let response = add_block(&service, r#"{"schema_update": {"schema": null}}"#).await;
+    assert!(!response.success);
+    assert!(
+        response.message.contains("UpdateSchema"),
+        "{}",
+        response.message
+    );
+    assert!(graph_info(&service).await.schema.contains("invoice"));
+
+    let verified = service
+        .handle_verify_graph(VerifyGraphRequest {
+            graph_id: "payments".to_string(),
+            full: false,
+        })
+        .await
+        .unwrap()
+        .into_inner();
+    assert!(verified.is_valid, "{}", verified.message);
+
+    // Followers learn the schema with the graph and its updates with the chain
+    let replica = open_service(replica_dir.path()).await;
+    while let Ok(event) = events.try_recv() {
+        replica
+            .apply_replication_event(event.to_proto())
+            .await
+            .unwrap();
+    }
+    let info = graph_info(&replica).await;
+    assert_eq!(info.total_blocks, 4);
+    assert!(info.is_valid);
+    assert!(info.schema.contains("invoice"));
+
+    // Schemas survive a restart
+    drop(service);
+    let reopened = open_service(dir.path()).await;
+    assert!(
+        !add_block(&reopened, r#"{"memo": "no invoice"}"#)
+            .await
+            .success
+    );
+    assert!(graph_info(&reopened).await.is_valid);
+}
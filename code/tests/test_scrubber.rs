@@ -0,0 +1,78 @@
+// Background integrity scrubber reported through GetScrubStatus
+
+mod common;
+
+use blockchain_grpc::application::services::IntegrityScrubber;
+use blockchain_grpc::domain::traits::BlockchainRepository;
+use blockchain_grpc::infrastructure::grpc::blockchain::*;
+use blockchain_grpc::BlockchainServiceImpl;
+use common::{add_block, create_graph, repository, start_service};
+use std::sync::Arc;
+use tempfile::tempdir;
+
+#[tokio::test]
+async fn test_scrub_status_reports_tampered_storage() {
+    let dir = tempdir().unwrap();
+    let repository = repository(dir.path());
+
+    let disabled = BlockchainServiceImpl::new(repository.clone());
+    let status = disabled
+        .handle_get_scrub_status()
+        .await
+        .unwrap()
+        .into_inner();
+    assert!(status.success && !status.enabled);
+
+    let scrubber = Arc::new(IntegrityScrubber::new(repository.clone(), 10_000));
+    let service = start_service(repository.clone(), |service| {
+        service.with_scrubber(scrubber.clone())
+    })
+    .await;
+    let status = service
+        .handle_get_scrub_status()
+        .await
+        .unwrap()
+        .into_inner();
+    assert!(status.enabled && status.last_finished_at_ms == 0);
+
+    create_graph(&service, "transactions", GraphType::Transaction).await;
+    for data in ["a", "b"] {
+        add_block(&service, "transactions", data).await;
+    }
+
+    assert!(scrubber.scrub().await.is_clean());
+    let status = service
+        .handle_get_scrub_status()
+        .await
+        .unwrap()
+        .into_inner();
+    assert!(status.issues.is_empty() && !status.running);
+    assert_eq!((status.graphs_checked, status.blocks_checked), (1, 3));
+
+    // Rewrite a stored block behind the service's back
+    let mut block = repository
+        .get_block_by_height("transactions", 1)
+        .await
+        .unwrap()
+        .unwrap();
+    block.data = "forged".to_string();
+    repository.save_block("transactions", &block).await.unwrap();
+    let tip = repository
+        .get_block_by_height("transactions", 2)
+        .await
+        .unwrap()
+        .unwrap();
+    repository.save_block("transactions", &tip).await.unwrap();
+
+    scrubber.scrub().await;
+    let status = service
+        .handle_get_scrub_status()
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(status.issues.len(), 1, "{:?}", status.issues);
+    assert_eq!(
+        (status.issues[0].graph_id.as_str(), status.issues[0].height),
+        ("transactions", 1)
+    );
+}
@@ -0,0 +1,158 @@
+// Full-text search over block data with SearchBlocks
+
+mod common;
+
+use blockchain_grpc::infrastructure::grpc::blockchain::*;
+use blockchain_grpc::BlockchainServiceImpl;
+use common::{add_block, create_graph, open_service};
+use tempfile::tempdir;
+
+async fn configure(
+    service: &BlockchainServiceImpl,
+    graph_id: &str,
+    enabled: bool,
+) -> IndexResponse {
+    service
+        .handle_configure_search(ConfigureSearchRequest {
+            graph_id: graph_id.to_string(),
+            enabled,
+        })
+        .await
+        .unwrap()
+        .into_inner()
+}
+
+async fn search(
+    service: &BlockchainServiceImpl,
+    graph_ids: &[&str],
+    query: &str,
+    page_size: u32,
+    page_token: &str,
+) -> SearchBlocksResponse {
+    service
+        .handle_search_blocks(SearchBlocksRequest {
+            graph_ids: graph_ids.iter().map(|id| id.to_string()).collect(),
+            query: query.to_string(),
+            page_size,
+            page_token: page_token.to_string(),
+        })
+        .await
+        .unwrap()
+        .into_inner()
+}
+
+fn hits(response: &SearchBlocksResponse) -> Vec<(String, u64)> {
+    assert!(response.success, "{}", response.message);
+    response
+        .hits
+        .iter()
+        .map(|hit| (hit.graph_id.clone(), hit.block.as_ref().unwrap().height))
+        .collect()
+}
+
+fn hit(graph_id: &str, height: u64) -> (String, u64) {
+    (graph_id.to_string(), height)
+}
+
+#[tokio::test]
+async fn test_search_terms_phrases_and_fields() {
+    let dir = tempdir().unwrap();
+    let service = open_service(dir.path()).await;
+    create_graph(&service, "support", GraphType::Transaction).await;
+    create_graph(&service, "billing", GraphType::Transaction).await;
+
+    add_block(
+        &service,
+        "support",
+        r#"{"customer": {"name": "Alice"}, "memo": "Card declined at checkout"}"#,
+    )
+    .await;
+    add_block(
+        &service,
+        "support",
+        r#"{"customer": {"name": "Bob"}, "memo": "Refund for Alice"}"#,
+    )
+    .await;
+
+    // Enabling indexes the stored chain; later commits are indexed too
+    let response = configure(&service, "support", true).await;
+    assert!(response.success, "{}", response.message);
+    assert_eq!(response.blocks_indexed, 3);
+    assert!(!configure(&service, "support", true).await.success);
+    assert!(configure(&service, "billing", true).await.success);
+    add_block(&service, "support", "declined card replaced for carol").await;
+    add_block(
+        &service,
+        "billing",
+        r#"{"memo": "Card declined, late fee"}"#,
+    )
+    .await;
+
+    assert_eq!(
+        hits(&search(&service, &["support"], "alice", 0, "").await),
+        vec![hit("support", 1), hit("support", 2)]
+    );
+    assert_eq!(
+        hits(&search(&service, &["support"], "customer.name:alice", 0, "").await),
+        vec![hit("support", 1)]
+    );
+    assert_eq!(
+        hits(&search(&service, &["support"], "\"card declined\"", 0, "").await),
+        vec![hit("support", 1)]
+    );
+    assert_eq!(
+        hits(&search(&service, &["support"], "CARD declined", 0, "").await),
+        vec![hit("support", 1), hit("support", 3)]
+    );
+
+    // Several graphs, in request order, paged across graph boundaries
+    let first = search(&service, &["billing", "support"], "declined", 2, "").await;
+    assert_eq!(hits(&first), vec![hit("billing", 1), hit("support", 1)]);
+    let second = search(
+        &service,
+        &["billing", "support"],
+        "declined",
+        2,
+        &first.next_page_token,
+    )
+    .await;
+    assert_eq!(hits(&second), vec![hit("support", 3)]);
+    assert!(second.next_page_token.is_empty());
+    assert_eq!(
+        hits(&search(&service, &[], "memo:\"late fee\"", 0, "").await),
+        vec![hit("billing", 1)]
+    );
+
+    let invalid = search(&service, &["support"], "\"unterminated", 0, "").await;
+    assert!(!invalid.success);
+
+    // Rebuilding reproduces the index; disabling removes it
+    drop(service);
+    let reopened = open_service(dir.path()).await;
+    let rebuilt = reopened
+        .handle_rebuild_search_index(RebuildSearchIndexRequest {
+            graph_id: "support".to_string(),
+        })
+        .await
+        .unwrap()
+        .into_inner();
+    assert!(rebuilt.success, "{}", rebuilt.message);
+    assert_eq!(rebuilt.blocks_indexed, 4);
+    assert_eq!(
+        hits(&search(&reopened, &["support"], "carol", 0, "").await),
+        vec![hit("support", 3)]
+    );
+
+    assert!(configure(&reopened, "support", false).await.success);
+    let disabled = search(&reopened, &["support"], "carol", 0, "").await;
+    assert!(!disabled.success);
+    assert!(
+        disabled.message.contains("not enabled"),
+        "{}",
+        disabled.message
+    );
+    assert_eq!(
+        hits(&search(&reopened, &[], "declined", 0, "").await),
+        vec![hit("billing", 1)]
+    );
+}
@@ -0,0 +1,91 @@
+// Tests for offline maintenance helpers used by the blockchain-admin tool
+
+use blockchain_grpc::domain::traits::BlockchainRepository;
+use blockchain_grpc::domain::{Block, BlockchainGraph, GraphType};
+use blockchain_grpc::infrastructure::persistence::{BlockchainRepositoryImpl, RocksDbAdapter};
+use std::sync::Arc;
+use tempfile::tempdir;
+
+async fn seed_graph(repository: &BlockchainRepositoryImpl, graph_id: &str, extra_blocks: u64) {
+    let mut graph = BlockchainGraph::new(
+        graph_id.to_string(),
+        GraphType::Transaction,
+        "Maintenance test".to_string(),
+        1,
+    );
+    repository
+        .save_block(graph_id, graph.get_latest_block().unwrap())
+        .await
+        .unwrap();
+
+    for i in 0..extra_blocks {
+        let latest = graph.get_latest_block().unwrap().clone();
+        let block = Block::new(
+            latest.hash,
+            format!("data {}", i),
+            graph_id.to_string(),
+            latest.height + 1,
+            vec![],
+        );
+        let mined = graph.add_block(block).unwrap();
+        repository.save_block(graph_id, &mined).await.unwrap();
+    }
+
+    repository.save_graph(&graph).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_repair_rebuilds_graph_list_and_latest() {
+    let temp_dir = tempdir().unwrap();
+    let db = Arc::new(RocksDbAdapter::new(temp_dir.path()).unwrap());
+    let repository = BlockchainRepositoryImpl::new(db.clone());
+
+    seed_graph(&repository, "payments", 3).await;
+    seed_graph(&repository, "people", 0).await;
+
+    // Simulate lost index keys
+    db.delete("graph_list").unwrap();
+    db.put("latest:payments", &1u64.to_le_bytes()).unwrap();
+
+    let dry_run = repository.repair_indexes(true).await.unwrap();
+    assert!(dry_run.has_changes());
+    assert!(!db.exists("graph_list").unwrap());
+
+    let report = repository.repair_indexes(false).await.unwrap();
+    assert_eq!(report.graph_list.len(), 2);
+    assert_eq!(
+        report.latest_fixed,
+        vec![("payments".to_string(), Some(1), 3)]
+    );
+    assert!(report.gaps.is_empty());
+
+    let latest = repository
+        .get_latest_block("payments")
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(latest.height, 3);
+    assert_eq!(repository.list_graphs().await.unwrap().len(), 2);
+
+    // A second pass finds nothing to do
+    assert!(!repository
+        .repair_indexes(false)
+        .await
+        .unwrap()
+        .has_changes());
+}
+
+#[tokio::test]
+async fn test_storage_stats_counts_blocks() {
+    let temp_dir = tempdir().unwrap();
+    let db = Arc::new(RocksDbAdapter::new(temp_dir.path()).unwrap());
+    let repository = BlockchainRepositoryImpl::new(db);
+
+    seed_graph(&repository, "payments", 2).await;
+
+    let stats = repository.storage_stats().unwrap();
+    assert_eq!(stats.blocks_per_graph.get("payments"), Some(&3));
+    assert_eq!(stats.prefixes.get("block").map(|p| p.keys), Some(3));
+    assert_eq!(stats.prefixes.get("block_hash").map(|p| p.keys), Some(3));
+    assert!(stats.total_bytes > 0);
+}
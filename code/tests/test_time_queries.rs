@@ -0,0 +1,125 @@
+// Timestamp index with GetBlocksByTime and GetBlockAtTime
+
+mod common;
+
+use blockchain_grpc::domain::traits::BlockchainRepository;
+use blockchain_grpc::domain::{Block, BlockchainGraph, GraphType};
+use blockchain_grpc::infrastructure::grpc::blockchain::*;
+use blockchain_grpc::infrastructure::persistence::{BlockchainRepositoryImpl, RocksDbAdapter};
+use blockchain_grpc::BlockchainServiceImpl;
+use common::{add_block, start_service};
+use std::sync::Arc;
+use tempfile::tempdir;
+use tokio_stream::StreamExt;
+
+/// Stores a graph whose blocks follow the genesis by the given offsets (seconds)
+async fn seed_graph(repository: &BlockchainRepositoryImpl, offsets: &[i64]) -> i64 {
+    let mut graph = BlockchainGraph::new(
+        "payments".to_string(),
+        GraphType::Transaction,
+        "Timed payments".to_string(),
+        1,
+    );
+    let start = graph.get_latest_block().unwrap().timestamp;
+    repository
+        .save_block("payments", graph.get_latest_block().unwrap())
+        .await
+        .unwrap();
+
+    for (i, offset) in offsets.iter().enumerate() {
+        let latest = graph.get_latest_block().unwrap().clone();
+        let mut block = Block::new(
+            latest.hash,
+            format!("payment {}", i + 1),
+            "payments".to_string(),
+            latest.height + 1,
+            vec![],
+        );
+        block.timestamp = start + offset;
+        block.hash = block.calculate_hash();
+        let mined = graph.add_block(block).unwrap();
+        repository.save_block("payments", &mined).await.unwrap();
+    }
+
+    repository.save_graph(&graph).await.unwrap();
+    start
+}
+
+async fn blocks_by_time(service: &BlockchainServiceImpl, from: i64, to: i64) -> Vec<u64> {
+    let mut stream = service
+        .handle_get_blocks_by_time(GetBlocksByTimeRequest {
+            graph_id: "payments".to_string(),
+            from,
+            to,
+        })
+        .await
+        .unwrap()
+        .into_inner();
+    let mut heights = Vec::new();
+    while let Some(block) = stream.next().await {
+        heights.push(block.unwrap().height);
+    }
+    heights
+}
+
+async fn block_at_time(service: &BlockchainServiceImpl, at: i64) -> Option<u64> {
+    let response = service
+        .handle_get_block_at_time(GetBlockAtTimeRequest {
+            graph_id: "payments".to_string(),
+            at,
+        })
+        .await
+        .unwrap()
+        .into_inner();
+    response.block.map(|b| b.height)
+}
+
+#[tokio::test]
+async fn test_blocks_are_found_by_time() {
+    let temp_dir = tempdir().unwrap();
+    let db = Arc::new(RocksDbAdapter::new(temp_dir.path()).unwrap());
+    let repository = Arc::new(BlockchainRepositoryImpl::new(db.clone()));
+    let start = seed_graph(&repository, &[10, 20, 20, 30]).await;
+
+    // Graphs stored before the index existed are indexed on startup
+    for key in db.get_keys_with_prefix("time:").unwrap() {
+        db.delete(&key).unwrap();
+    }
+    let service = start_service(repository, |service| service).await;
+
+    assert_eq!(
+        blocks_by_time(&service, start + 10, start + 20).await,
+        vec![1, 2, 3]
+    );
+    assert_eq!(blocks_by_time(&service, start + 15, 0).await, vec![2, 3, 4]);
+    assert_eq!(
+        blocks_by_time(&service, start + 31, 0).await,
+        Vec::<u64>::new()
+    );
+
+    assert_eq!(block_at_time(&service, start + 25).await, Some(3));
+    assert_eq!(block_at_time(&service, start + 30).await, Some(4));
+    assert_eq!(block_at_time(&service, start).await, Some(0));
+    assert_eq!(block_at_time(&service, start - 1).await, None);
+
+    // New blocks are indexed on commit
+    let now = add_block(&service, "payments", "payment 5").await.timestamp;
+    assert!(blocks_by_time(&service, now, now).await.contains(&5));
+
+    let invalid = service
+        .handle_get_blocks_by_time(GetBlocksByTimeRequest {
+            graph_id: "payments".to_string(),
+            from: start + 20,
+            to: start + 10,
+        })
+        .await;
+    assert!(invalid.is_err());
+    let missing = service
+        .handle_get_blocks_by_time(GetBlocksByTimeRequest {
+            graph_id: "unknown".to_string(),
+            from: 0,
+            to: 0,
+        })
+        .await;
+    assert!(missing.is_err());
+}
@@ -0,0 +1,186 @@
+// World state: versioned keys written by read/write sets in block payloads
+
+mod common;
+
+use blockchain_grpc::domain::traits::BlockchainRepository;
+use blockchain_grpc::infrastructure::grpc::blockchain::*;
+use blockchain_grpc::infrastructure::persistence::BlockchainRepositoryImpl;
+use blockchain_grpc::BlockchainServiceImpl;
+use common::{block_request, create_graph, repository, start_service, try_add_block};
+use std::path::Path;
+use std::sync::Arc;
+use tempfile::tempdir;
+
+async fn open_service(data_dir: &Path) -> (BlockchainServiceImpl, Arc<BlockchainRepositoryImpl>) {
+    let repository = repository(data_dir);
+    let service = start_service(repository.clone(), |service| service).await;
+    (service, repository)
+}
+
+async fn add_block(service: &BlockchainServiceImpl, data: &str) -> AddBlockResponse {
+    try_add_block(service, block_request("ledger", data)).await
+}
+
+async fn get_state(service: &BlockchainServiceImpl, key: &str) -> GetStateResponse {
+    service
+        .handle_get_state(GetStateRequest {
+            graph_id: "ledger".to_string(),
+            key: key.to_string(),
+        })
+        .await
+        .unwrap()
+        .into_inner()
+}
+
+async fn history(service: &BlockchainServiceImpl, key: &str) -> Vec<StateEntry> {
+    service
+        .handle_get_state_history(GetStateHistoryRequest {
+            graph_id: "ledger".to_string(),
+            key: key.to_string(),
+        })
+        .await
+        .unwrap()
+        .into_inner()
+        .entries
+}
+
+#[tokio::test]
+async fn test_rwset_blocks_write_versioned_state() {
+    let dir = tempdir().unwrap();
+    let (service, _) = open_service(dir.path()).await;
+    create_graph(&service, "ledger", GraphType::Transaction).await;
+
+    let first = add_block(
+        &service,
+        r#"{"rwset": {"reads": [{"key": "alice", "version": 0}], "writes": [{"key": "alice", "value": "100"}, {"key": "bob", "value": "5"}]}}"#,
+    )
+    .await;
+    assert!(first.success, "{}", first.message);
+    let first = first.block.unwrap();
+
+    let entry = get_state(&service, "alice").await.entry.unwrap();
+    assert_eq!(entry.value, b"100");
+    assert_eq!(entry.version, 1);
+    assert_eq!(entry.block_hash, first.hash);
+
+    // A transaction that read alice before block 1 is rejected
+    let stale = add_block(
+        &service,
+        r#"{"rwset": {"reads": [{"key": "alice", "version": 0}], "writes": [{"key": "alice", "value": "0"}]}}"#,
+    )
+    .await;
+    assert!(!stale.success);
+    assert!(stale.message.contains("conflicts"), "{}", stale.message);
+
+    let update = add_block(
+        &service,
+        r#"{"rwset": {"reads": [{"key": "alice", "version": 1}], "writes": [{"key": "alice", "value": "90"}, {"key": "bob", "delete": true}]}}"#,
+    )
+    .await;
+    assert!(update.success, "{}", update.message);
+    assert!(!get_state(&service, "bob").await.success);
+
+    // Plain payloads leave the state alone
+    assert!(add_block(&service, "memo").await.success);
+    let malformed = add_block(&service, r#"{"rwset": {"writes": "alice"}}"#).await;
+    assert!(!malformed.success);
+
+    let bob = history(&service, "bob").await;
+    assert_eq!(bob.len(), 2);
+    assert!(!bob[0].deleted && bob[1].deleted);
+    assert_eq!(bob[1].version, 2);
+
+    let range = |start: &str, end: &str, limit| RangeStateRequest {
+        graph_id: "ledger".to_string(),
+        start_key: start.to_string(),
+        end_key: end.to_string(),
+        limit,
+    };
+    add_block(
+        &service,
+        r#"{"rwset": {"writes": [{"key": "carol", "value": "1"}, {"key": "dave", "value": "2"}]}}"#,
+    )
+    .await;
+    let page = service
+        .handle_range_state(range("", "", 2))
+        .await
+        .unwrap()
+        .into_inner();
+    let keys: Vec<_> = page.entries.iter().map(|e| e.key.as_str()).collect();
+    assert_eq!(keys, ["alice", "carol"]);
+    assert_eq!(page.next_key, "dave");
+    let page = service
+        .handle_range_state(range("b", "d", 0))
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(page.entries.len(), 1);
+    assert!(page.next_key.is_empty());
+
+    // State and history survive a restart
+    drop(service);
+    let (reopened, repository) = open_service(dir.path()).await;
+    assert_eq!(
+        get_state(&reopened, "alice").await.entry.unwrap().value,
+        b"90"
+    );
+    assert_eq!(history(&reopened, "alice").await.len(), 2);
+    assert_eq!(
+        repository
+            .get_block_writes("ledger", 2)
+            .await
+            .unwrap()
+            .len(),
+        2
+    );
+}
+
+#[tokio::test]
+async fn test_reorganization_switches_state_to_the_new_branch() {
+    let dirs: Vec<_> = (0..2).map(|_| tempdir().unwrap()).collect();
+    let (a, _) = open_service(dirs[0].path()).await;
+    let (b, b_repository) = open_service(dirs[1].path()).await;
+
+    let mut events = a.subscribe_events();
+    create_graph(&a, "ledger", GraphType::Transaction).await;
+    while let Ok(event) = events.try_recv() {
+        b.apply_replication_event(event.to_proto()).await.unwrap();
+    }
+
+    // Competing branches on the shared genesis block
+    assert!(add_block(&a, r#"{"rwset": {"writes": [{"key": "x", "value": "a1"}, {"key": "only_a", "value": "1"}]}}"#).await.success);
+    assert!(
+        add_block(
+            &b,
+            r#"{"rwset": {"writes": [{"key": "x", "value": "b1"}]}}"#
+        )
+        .await
+        .success
+    );
+    assert!(add_block(&b, r#"{"rwset": {"reads": [{"key": "x", "version": 1}], "writes": [{"key": "y", "value": "b2"}]}}"#).await.success);
+
+    // B's longer branch replaces A's block and its writes
+    let applier = a.applier();
+    for height in 1..=2 {
+        let block = b_repository
+            .get_block_by_height("ledger", height)
+            .await
+            .unwrap()
+            .unwrap();
+        applier.apply_block(block).await.unwrap();
+    }
+
+    assert_eq!(get_state(&a, "x").await.entry.unwrap().value, b"b1");
+    assert_eq!(get_state(&a, "y").await.entry.unwrap().version, 2);
+    assert!(!get_state(&a, "only_a").await.success);
+    assert!(history(&a, "only_a").await.is_empty());
+    let x = history(&a, "x").await;
+    assert_eq!(x.len(), 1);
+    assert_eq!(x[0].value, b"b1");
+
+    // The switched state is what a restart loads
+    drop((a, applier));
+    let (reopened, _) = open_service(dirs[0].path()).await;
+    assert!(!get_state(&reopened, "only_a").await.success);
+    assert_eq!(get_state(&reopened, "y").await.entry.unwrap().value, b"b2");
+}